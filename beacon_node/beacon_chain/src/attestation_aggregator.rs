@@ -8,6 +8,9 @@ use types::{
 
 const PHASE_0_CUSTODY_BIT: bool = false;
 
+/// The number of attestations stored by an `AttestationAggregator` created with `new()`.
+pub const DEFAULT_MAX_ATTESTATIONS: usize = 16_384;
+
 /// Provides the functionality to:
 ///
 ///  - Recieve a `FreeAttestation` and aggregate it into an `Attestation` (or create a new if it
@@ -16,18 +19,21 @@ const PHASE_0_CUSTODY_BIT: bool = false;
 ///  - Produce a list of attestations that would be valid for inclusion in some `BeaconState` (and
 ///  therefore valid for inclusion in a `BeaconBlock`.
 ///
-///  Note: `Attestations` are stored in memory and never deleted. This is not scalable and must be
-///  rectified in a future revision.
-#[derive(Default)]
+///  Attestations are stored in memory. Those which may no longer be included in a block are
+///  removed by `prune`, and at most `max_attestations` are stored, evicting the attestations with
+///  the earliest slot first.
 pub struct AttestationAggregator {
     store: HashMap<Vec<u8>, Attestation>,
+    max_attestations: usize,
 }
 
+#[derive(Debug, PartialEq)]
 pub struct Outcome {
     pub valid: bool,
     pub message: Message,
 }
 
+#[derive(Debug, PartialEq)]
 pub enum Message {
     /// The free attestation was added to an existing attestation.
     Aggregated,
@@ -45,8 +51,15 @@ pub enum Message {
     /// The given `shard` does not match the validators committee assignment, or is not included in
     /// a committee for the given slot.
     BadShard,
-    /// Attestation is from the epoch prior to this, ignoring.
+    /// The attestation is from prior to the previous epoch, or attests to a block prior to the
+    /// previous epoch.
     TooOld,
+    /// The attestation is for a slot which has not yet been reached.
+    FutureSlot,
+    /// The `beacon_block_root` of the attestation is not a known block.
+    UnknownBeaconBlock,
+    /// The committees for the attestation epoch are not available in the supplied state.
+    MissingEpochCache,
 }
 
 macro_rules! valid_outcome {
@@ -68,16 +81,57 @@ macro_rules! invalid_outcome {
 }
 
 impl AttestationAggregator {
-    /// Instantiates a new AttestationAggregator with an empty database.
+    /// Instantiates a new AttestationAggregator with an empty database, storing at most
+    /// `DEFAULT_MAX_ATTESTATIONS`.
     pub fn new() -> Self {
+        Self::with_max_attestations(DEFAULT_MAX_ATTESTATIONS)
+    }
+
+    /// Instantiates a new AttestationAggregator with an empty database, storing at most
+    /// `max_attestations`.
+    pub fn with_max_attestations(max_attestations: usize) -> Self {
         Self {
             store: HashMap::new(),
+            max_attestations,
+        }
+    }
+
+    /// Set the maximum number of stored attestations, evicting attestations if there are now too
+    /// many.
+    pub fn set_max_attestations(&mut self, max_attestations: usize) {
+        self.max_attestations = max_attestations;
+        while self.store.len() > self.max_attestations {
+            self.evict_earliest();
         }
     }
 
+    /// Returns the number of stored attestations.
+    pub fn len(&self) -> usize {
+        self.store.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.store.is_empty()
+    }
+
+    /// Removes all attestations prior to the previous epoch of `state`, as they may no longer be
+    /// included in a block built upon `state`.
+    ///
+    /// Returns the number of attestations removed.
+    pub fn prune(&mut self, state: &BeaconState, spec: &ChainSpec) -> usize {
+        let previous_epoch_start_slot = state.previous_epoch_start_slot(spec);
+        let len = self.store.len();
+        self.store
+            .retain(|_, attestation| attestation.data.slot >= previous_epoch_start_slot);
+        len - self.store.len()
+    }
+
     /// Accepts some `FreeAttestation`, validates it and either aggregates it upon some existing
     /// `Attestation` or produces a new `Attestation`.
     ///
+    /// `state` must be the state of the attested block, transitioned to an epoch with the same
+    /// committees as the attestation epoch and with its current epoch cache built.
+    ///
     /// The "validation" provided is not complete, instead the following points are checked:
     ///  - The given `validator_index` is in the committee for the given `shard` for the given
     ///  `slot`.
@@ -88,12 +142,17 @@ impl AttestationAggregator {
         free_attestation: &FreeAttestation,
         spec: &ChainSpec,
     ) -> Result<Outcome, BeaconStateError> {
+        let attestation_epoch = free_attestation.data.slot.epoch(spec.epoch_length);
+        if cached_state.current_epoch(spec) != attestation_epoch {
+            invalid_outcome!(Message::MissingEpochCache);
+        }
+
         let attestation_duties = match cached_state.attestation_slot_and_shard_for_validator(
             free_attestation.validator_index as usize,
             spec,
         ) {
-            Err(BeaconStateError::EpochCacheUninitialized(e)) => {
-                panic!("Attempted to access unbuilt cache {:?}.", e)
+            Err(BeaconStateError::EpochCacheUninitialized(_)) => {
+                invalid_outcome!(Message::MissingEpochCache)
             }
             Err(BeaconStateError::EpochOutOfBounds) => invalid_outcome!(Message::TooOld),
             Err(BeaconStateError::ShardOutOfBounds) => invalid_outcome!(Message::BadShard),
@@ -119,26 +178,14 @@ impl AttestationAggregator {
             invalid_outcome!(Message::BadShard);
         }
 
-        let signable_message = free_attestation.data.signable_message(PHASE_0_CUSTODY_BIT);
-
-        let validator_record = match cached_state
-            .validator_registry
-            .get(free_attestation.validator_index as usize)
-        {
+        match verify_free_attestation_signature(cached_state, free_attestation, spec) {
             None => invalid_outcome!(Message::BadValidatorIndex),
-            Some(validator_record) => validator_record,
-        };
-
-        if !free_attestation.signature.verify(
-            &signable_message,
-            cached_state
-                .fork
-                .get_domain(cached_state.current_epoch(spec), spec.domain_attestation),
-            &validator_record.pubkey,
-        ) {
-            invalid_outcome!(Message::BadSignature);
+            Some(false) => invalid_outcome!(Message::BadSignature),
+            Some(true) => {}
         }
 
+        let signable_message = free_attestation.data.signable_message(PHASE_0_CUSTODY_BIT);
+
         if let Some(existing_attestation) = self.store.get(&signable_message) {
             if let Some(updated_attestation) = aggregate_attestation(
                 existing_attestation,
@@ -161,7 +208,7 @@ impl AttestationAggregator {
                 custody_bitfield: Bitfield::new(),
                 aggregate_signature,
             };
-            self.store.insert(signable_message, new_attestation);
+            self.insert(signable_message, new_attestation);
             valid_outcome!(Message::NewAttestationCreated);
         }
     }
@@ -194,6 +241,62 @@ impl AttestationAggregator {
             })
             .collect()
     }
+
+    /// Store a new attestation, first evicting attestations if the store is full.
+    fn insert(&mut self, signable_message: Vec<u8>, attestation: Attestation) {
+        while self.store.len() >= self.max_attestations && !self.store.is_empty() {
+            self.evict_earliest();
+        }
+        self.store.insert(signable_message, attestation);
+    }
+
+    /// Remove the attestation with the earliest slot, which is the first to become too old for
+    /// inclusion in a block.
+    fn evict_earliest(&mut self) {
+        let earliest = self
+            .store
+            .iter()
+            .min_by_key(|(_, attestation)| attestation.data.slot)
+            .map(|(key, _)| key.clone());
+        if let Some(key) = earliest {
+            trace!(
+                "Evicting attestation for slot {}",
+                self.store[&key].data.slot
+            );
+            self.store.remove(&key);
+        }
+    }
+}
+
+impl Default for AttestationAggregator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Verifies the signature of `free_attestation` against the public key of its validator in
+/// `state`.
+///
+/// Returns `None` if the validator is not in the registry of `state`.
+pub fn verify_free_attestation_signature(
+    state: &BeaconState,
+    free_attestation: &FreeAttestation,
+    spec: &ChainSpec,
+) -> Option<bool> {
+    let validator_record = state
+        .validator_registry
+        .get(free_attestation.validator_index as usize)?;
+    let attestation_epoch = free_attestation.data.slot.epoch(spec.epoch_length);
+
+    Some(
+        free_attestation.signature.verify(
+            &free_attestation.data.signable_message(PHASE_0_CUSTODY_BIT),
+            state
+                .fork
+                .get_domain(attestation_epoch, spec.domain_attestation),
+            &validator_record.pubkey,
+        ),
+    )
 }
 
 /// Produces a new `Attestation` where:
@@ -225,3 +328,66 @@ fn aggregate_attestation(
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::test_utils::{SeedableRng, TestRandom, XorShiftRng};
+    use types::Slot;
+
+    fn attestation_at_slot(rng: &mut XorShiftRng, slot: u64) -> (Vec<u8>, Attestation) {
+        let mut attestation = Attestation::random_for_test(rng);
+        attestation.data.slot = Slot::new(slot);
+        (
+            attestation.data.signable_message(PHASE_0_CUSTODY_BIT),
+            attestation,
+        )
+    }
+
+    fn stored_slots(aggregator: &AttestationAggregator) -> Vec<u64> {
+        let mut slots: Vec<u64> = aggregator
+            .store
+            .values()
+            .map(|attestation| attestation.data.slot.as_u64())
+            .collect();
+        slots.sort();
+        slots
+    }
+
+    #[test]
+    fn test_prune() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let spec = ChainSpec::few_validators();
+        let mut aggregator = AttestationAggregator::new();
+        for slot in 0..spec.epoch_length * 4 {
+            let (key, attestation) = attestation_at_slot(&mut rng, slot);
+            aggregator.insert(key, attestation);
+        }
+
+        let mut state = BeaconState::random_for_test(&mut rng);
+        state.slot = Slot::new(spec.epoch_length * 3 + 1);
+
+        assert_eq!(
+            aggregator.prune(&state, &spec),
+            spec.epoch_length as usize * 2
+        );
+        assert_eq!(aggregator.len(), spec.epoch_length as usize * 2);
+        assert!(stored_slots(&aggregator)
+            .iter()
+            .all(|slot| *slot >= spec.epoch_length * 2));
+    }
+
+    #[test]
+    fn test_evicts_earliest_when_full() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let mut aggregator = AttestationAggregator::with_max_attestations(3);
+        for slot in &[5, 2, 7, 9] {
+            let (key, attestation) = attestation_at_slot(&mut rng, *slot);
+            aggregator.insert(key, attestation);
+        }
+        assert_eq!(stored_slots(&aggregator), vec![5, 7, 9]);
+
+        aggregator.set_max_attestations(1);
+        assert_eq!(stored_slots(&aggregator), vec![9]);
+    }
+}
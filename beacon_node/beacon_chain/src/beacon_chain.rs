@@ -1,25 +1,42 @@
-use crate::attestation_aggregator::{AttestationAggregator, Outcome as AggregationOutcome};
+use crate::attestation_aggregator::{
+    verify_free_attestation_signature, AttestationAggregator, Message as AggregationMessage,
+    Outcome as AggregationOutcome,
+};
 use crate::checkpoint::CheckPoint;
+use crate::future_block_queue::FutureBlockQueue;
+use crate::operation_pool::{BlockOperations, OperationPool};
+use crate::orphan_pool::OrphanPool;
+use crate::slasher::Slasher;
 use db::{
-    stores::{BeaconBlockStore, BeaconStateStore},
+    stores::{AttestationTarget, BeaconBlockStore, BeaconStateStore, ChainStore},
     ClientDB, DBError,
 };
+use eth1::Eth1Follower;
 use fork_choice::{ForkChoice, ForkChoiceError};
-use log::{debug, trace};
+use log::{debug, trace, warn};
 use parking_lot::{RwLock, RwLockReadGuard};
 use slot_clock::SlotClock;
 use ssz::ssz_encode;
 use state_processing::{
     BlockProcessable, BlockProcessingError, SlotProcessable, SlotProcessingError,
 };
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
 use std::sync::Arc;
 use types::{
     readers::{BeaconBlockReader, BeaconStateReader},
-    AttestationData, BeaconBlock, BeaconBlockBody, BeaconState, BeaconStateError, ChainSpec,
-    Crosslink, Deposit, Epoch, Eth1Data, FreeAttestation, Hash256, PublicKey, RelativeEpoch,
-    Signature, Slot,
+    AttestationData, AttesterSlashing, BeaconBlock, BeaconBlockBody, BeaconState, BeaconStateError,
+    ChainSpec, Crosslink, Deposit, Epoch, Eth1Data, Exit, FreeAttestation, Hash256,
+    ProposerSlashing, PublicKey, RelativeEpoch, Signature, Slot,
 };
 
+/// After finalization, the states of canonical blocks prior to the finalized block are pruned,
+/// except for the state of the last block in each interval of this many slots.
+const STATE_SNAPSHOT_INTERVAL: u64 = 256;
+/// The maximum number of re-orgs queued for a subscriber which has not received them. Further
+/// re-orgs are dropped for that subscriber.
+pub const MAX_QUEUED_REORGS: usize = 16;
+
 #[derive(Debug, PartialEq)]
 pub enum Error {
     InsufficientValidators,
@@ -30,6 +47,7 @@ pub enum Error {
     ForkChoiceError(ForkChoiceError),
     MissingBeaconBlock(Hash256),
     MissingBeaconState(Hash256),
+    SlotProcessingError(SlotProcessingError),
 }
 
 #[derive(Debug, PartialEq)]
@@ -46,7 +64,10 @@ pub enum InvalidBlock {
     StateRootMismatch,
     /// The blocks parent_root is unknown.
     ParentUnknown,
-    /// There was an error whilst advancing the parent state to the present slot. This condition
+    /// The block, or its parent, is not later than the finalized block (and the parent is not the
+    /// finalized block itself), so the block may never become canonical.
+    ConflictsWithFinalized,
+    /// There was an error whilst advancing the parent state to the block slot. This condition
     /// should not occur, it likely represents an internal error.
     SlotProcessingError(SlotProcessingError),
     /// The block could not be applied to the state, it is invalid.
@@ -61,16 +82,51 @@ pub enum BlockProcessingOutcome {
     InvalidBlock(InvalidBlock),
 }
 
+/// A change of the canonical head to a block which does not descend from the previous head.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Reorg {
+    pub old_head_root: Hash256,
+    pub old_head_slot: Slot,
+    pub new_head_root: Hash256,
+    pub new_head_slot: Slot,
+}
+
+/// The state used to validate a free attestation which is not to the present head.
+enum AttestationState {
+    /// The state of the attested block, transitioned to the attestation slot.
+    Built(BeaconState),
+    /// The state could not be built, as the attestation is invalid.
+    Invalid(AggregationMessage),
+}
+
 pub struct BeaconChain<T: ClientDB + Sized, U: SlotClock, F: ForkChoice> {
     pub block_store: Arc<BeaconBlockStore<T>>,
     pub state_store: Arc<BeaconStateStore<T>>,
+    pub chain_store: Arc<ChainStore<T>>,
     pub slot_clock: U,
     pub attestation_aggregator: RwLock<AttestationAggregator>,
+    pub operation_pool: RwLock<OperationPool>,
+    pub slasher: RwLock<Slasher>,
+    /// Blocks whose parent is unknown, processed once their parent has been.
+    pub orphan_pool: RwLock<OrphanPool>,
+    /// Blocks from slots shortly after the present slot, processed once the slot is reached.
+    pub future_blocks: RwLock<FutureBlockQueue>,
+    /// Provides the `Eth1Data` vote and deposits for produced blocks, if set. It is not updated by
+    /// the chain, see `Eth1Follower::update`.
+    pub eth1_follower: RwLock<Option<Arc<Eth1Follower>>>,
     canonical_head: RwLock<CheckPoint>,
+    justified_head: RwLock<CheckPoint>,
     finalized_head: RwLock<CheckPoint>,
     pub state: RwLock<BeaconState>,
     pub spec: ChainSpec,
     pub fork_choice: RwLock<F>,
+    /// The roots of all blocks supplied to the fork choice, persisted so the fork choice may be
+    /// rebuilt after a restart.
+    fork_choice_blocks: RwLock<Vec<Hash256>>,
+    /// The latest attestation target of each validator, persisted alongside `fork_choice_blocks`.
+    attestation_targets: RwLock<HashMap<u64, AttestationTarget>>,
+    /// Notified of each re-org, see `subscribe_reorgs`.
+    reorg_subscribers: RwLock<Vec<SyncSender<Reorg>>>,
 }
 
 impl<T, U, F> BeaconChain<T, U, F>
@@ -83,6 +139,7 @@ where
     pub fn genesis(
         state_store: Arc<BeaconStateStore<T>>,
         block_store: Arc<BeaconBlockStore<T>>,
+        chain_store: Arc<ChainStore<T>>,
         slot_clock: U,
         genesis_time: u64,
         latest_eth1_data: Eth1Data,
@@ -94,12 +151,39 @@ where
             return Err(Error::InsufficientValidators);
         }
 
-        let mut genesis_state = BeaconState::genesis(
+        let genesis_state = BeaconState::genesis(
             genesis_time,
             initial_validator_deposits,
             latest_eth1_data,
             &spec,
         )?;
+
+        Self::from_genesis_state(
+            state_store,
+            block_store,
+            chain_store,
+            slot_clock,
+            genesis_state,
+            spec,
+            fork_choice,
+        )
+    }
+
+    /// Instantiate a new Beacon Chain from an existing genesis state (e.g., one read from a
+    /// genesis state file).
+    pub fn from_genesis_state(
+        state_store: Arc<BeaconStateStore<T>>,
+        block_store: Arc<BeaconBlockStore<T>>,
+        chain_store: Arc<ChainStore<T>>,
+        slot_clock: U,
+        mut genesis_state: BeaconState,
+        spec: ChainSpec,
+        fork_choice: F,
+    ) -> Result<Self, Error> {
+        if genesis_state.validator_registry.is_empty() {
+            return Err(Error::InsufficientValidators);
+        }
+
         let state_root = genesis_state.canonical_root();
         state_store.put(&state_root, &ssz_encode(&genesis_state)[..])?;
 
@@ -107,6 +191,18 @@ where
         let block_root = genesis_block.canonical_root();
         block_store.put(&block_root, &ssz_encode(&genesis_block)[..])?;
 
+        chain_store.put_canonical_head(&block_root)?;
+        chain_store.put_justified_head(&block_root)?;
+        chain_store.put_finalized_head(&block_root)?;
+        chain_store.clear_fork_choice()?;
+
+        let justified_head = RwLock::new(CheckPoint::new(
+            genesis_block.clone(),
+            block_root,
+            // TODO: this is a memory waste; remove full clone.
+            genesis_state.clone(),
+            state_root,
+        ));
         let finalized_head = RwLock::new(CheckPoint::new(
             genesis_block.clone(),
             block_root,
@@ -130,16 +226,98 @@ where
         Ok(Self {
             block_store,
             state_store,
+            chain_store,
             slot_clock,
             attestation_aggregator,
+            operation_pool: RwLock::new(OperationPool::new()),
+            slasher: RwLock::new(Slasher::new()),
+            orphan_pool: RwLock::new(OrphanPool::new()),
+            future_blocks: RwLock::new(FutureBlockQueue::new()),
+            eth1_follower: RwLock::new(None),
             state: RwLock::new(genesis_state),
+            justified_head,
             finalized_head,
             canonical_head,
             spec,
             fork_choice: RwLock::new(fork_choice),
+            fork_choice_blocks: RwLock::new(vec![]),
+            attestation_targets: RwLock::new(HashMap::new()),
+            reorg_subscribers: RwLock::new(vec![]),
         })
     }
 
+    /// Instantiate a Beacon Chain from the heads and fork choice information stored by a previous
+    /// instance.
+    ///
+    /// Returns `Ok(None)` if the `chain_store` is empty, in which case the chain should be started
+    /// from genesis.
+    pub fn from_store(
+        state_store: Arc<BeaconStateStore<T>>,
+        block_store: Arc<BeaconBlockStore<T>>,
+        chain_store: Arc<ChainStore<T>>,
+        slot_clock: U,
+        spec: ChainSpec,
+        mut fork_choice: F,
+    ) -> Result<Option<Self>, Error> {
+        let (canonical_root, finalized_root) = match (
+            chain_store.get_canonical_head()?,
+            chain_store.get_finalized_head()?,
+        ) {
+            (Some(canonical_root), Some(finalized_root)) => (canonical_root, finalized_root),
+            _ => return Ok(None),
+        };
+
+        let canonical_head = load_checkpoint(&block_store, &state_store, canonical_root)?;
+        let finalized_head = load_checkpoint(&block_store, &state_store, finalized_root)?;
+        let justified_head = match chain_store.get_justified_head()? {
+            Some(justified_root) => load_checkpoint(&block_store, &state_store, justified_root)?,
+            None => finalized_head.clone(),
+        };
+
+        // Rebuild the fork choice by replaying the blocks and attestations it was supplied, starting
+        // from the finalized block.
+        fork_choice.prune(&finalized_root, &spec)?;
+        let fork_choice_blocks = chain_store.get_fork_choice_blocks()?;
+        for block_root in &fork_choice_blocks {
+            let block = block_store
+                .get_deserialized(block_root)?
+                .ok_or(Error::MissingBeaconBlock(*block_root))?;
+            fork_choice.add_block(&block, block_root, &spec)?;
+        }
+        let mut attestation_targets = HashMap::new();
+        for target in chain_store.get_attestation_targets()? {
+            fork_choice.add_attestation(target.validator_index, &target.block_root, &spec)?;
+            attestation_targets.insert(target.validator_index, target);
+        }
+
+        let mut state = canonical_head.beacon_state.clone();
+        state.build_epoch_cache(RelativeEpoch::Previous, &spec)?;
+        state.build_epoch_cache(RelativeEpoch::Current, &spec)?;
+        state.build_epoch_cache(RelativeEpoch::Next, &spec)?;
+
+        Ok(Some(Self {
+            block_store,
+            state_store,
+            chain_store,
+            slot_clock,
+            attestation_aggregator: RwLock::new(AttestationAggregator::new()),
+            operation_pool: RwLock::new(OperationPool::new()),
+            slasher: RwLock::new(Slasher::new()),
+            orphan_pool: RwLock::new(OrphanPool::new()),
+            future_blocks: RwLock::new(FutureBlockQueue::new()),
+            eth1_follower: RwLock::new(None),
+            state: RwLock::new(state),
+            justified_head: RwLock::new(justified_head),
+            finalized_head: RwLock::new(finalized_head),
+            canonical_head: RwLock::new(canonical_head),
+            spec,
+            fork_choice: RwLock::new(fork_choice),
+            fork_choice_blocks: RwLock::new(fork_choice_blocks),
+            attestation_targets: RwLock::new(attestation_targets),
+            reorg_subscribers: RwLock::new(vec![]),
+        }))
+    }
+
     /// Update the canonical head to some new values.
     pub fn update_canonical_head(
         &self,
@@ -147,11 +325,13 @@ where
         new_beacon_block_root: Hash256,
         new_beacon_state: BeaconState,
         new_beacon_state_root: Hash256,
-    ) {
+    ) -> Result<(), Error> {
         debug!(
             "Updating canonical head with block at slot: {}",
             new_beacon_block.slot
         );
+        self.chain_store
+            .put_canonical_head(&new_beacon_block_root)?;
         let mut head = self.canonical_head.write();
         head.update(
             new_beacon_block,
@@ -159,6 +339,7 @@ where
             new_beacon_state,
             new_beacon_state_root,
         );
+        Ok(())
     }
 
     /// Returns a read-lock guarded `CheckPoint` struct for reading the head (as chosen by the
@@ -172,13 +353,41 @@ where
     }
 
     /// Update the justified head to some new values.
+    pub fn update_justified_head(
+        &self,
+        new_beacon_block: BeaconBlock,
+        new_beacon_block_root: Hash256,
+        new_beacon_state: BeaconState,
+        new_beacon_state_root: Hash256,
+    ) -> Result<(), Error> {
+        self.chain_store
+            .put_justified_head(&new_beacon_block_root)?;
+        let mut justified_head = self.justified_head.write();
+        justified_head.update(
+            new_beacon_block,
+            new_beacon_block_root,
+            new_beacon_state,
+            new_beacon_state_root,
+        );
+        Ok(())
+    }
+
+    /// Returns a read-lock guarded `CheckPoint` struct for reading the justified head (the block
+    /// at the start of the `justified_epoch` of the canonical head state).
+    pub fn justified_head(&self) -> RwLockReadGuard<CheckPoint> {
+        self.justified_head.read()
+    }
+
+    /// Update the finalized head to some new values.
     pub fn update_finalized_head(
         &self,
         new_beacon_block: BeaconBlock,
         new_beacon_block_root: Hash256,
         new_beacon_state: BeaconState,
         new_beacon_state_root: Hash256,
-    ) {
+    ) -> Result<(), Error> {
+        self.chain_store
+            .put_finalized_head(&new_beacon_block_root)?;
         let mut finalized_head = self.finalized_head.write();
         finalized_head.update(
             new_beacon_block,
@@ -186,14 +395,103 @@ where
             new_beacon_state,
             new_beacon_state_root,
         );
+        Ok(())
     }
 
-    /// Returns a read-lock guarded `CheckPoint` struct for reading the justified head (as chosen,
-    /// indirectly,  by the fork-choice rule).
+    /// Returns a read-lock guarded `CheckPoint` struct for reading the finalized head (the block
+    /// at the start of the `finalized_epoch` of the canonical head state).
     pub fn finalized_head(&self) -> RwLockReadGuard<CheckPoint> {
         self.finalized_head.read()
     }
 
+    /// Returns the root of the genesis block, which identifies the chain.
+    ///
+    /// The blocks from the finalized head back to genesis are read from the store, so this should
+    /// not be called often.
+    pub fn genesis_block_root(&self) -> Result<Hash256, Error> {
+        let (mut block_root, mut parent_root) = {
+            let finalized_head = self.finalized_head();
+            (
+                finalized_head.beacon_block_root,
+                finalized_head.beacon_block.parent_root,
+            )
+        };
+
+        while parent_root != self.spec.zero_hash {
+            let block = self
+                .block_store
+                .get_deserialized(&parent_root)?
+                .ok_or(Error::MissingBeaconBlock(parent_root))?;
+            block_root = parent_root;
+            parent_root = block.parent_root;
+        }
+
+        Ok(block_root)
+    }
+
+    /// Returns the canonical blocks with slots from `start_slot` up to, but excluding,
+    /// `start_slot + count`, in ascending slot order.
+    ///
+    /// The roots of the blocks are read from the `latest_block_roots` of the canonical head state.
+    /// Blocks older than those roots are found by walking back from the oldest of them.
+    pub fn blocks_by_range(&self, start_slot: Slot, count: u64) -> Result<Vec<BeaconBlock>, Error> {
+        let end_slot = start_slot + count;
+        let head = self.head();
+        let state = &head.beacon_state;
+        let oldest_slot = std::cmp::max(
+            self.spec.genesis_slot,
+            state
+                .slot
+                .saturating_sub(self.spec.latest_block_roots_length as u64),
+        );
+
+        // The root at a slot without a block is that of the preceding block, so consecutive
+        // duplicates are skipped.
+        let mut roots: Vec<Hash256> = vec![];
+        if start_slot < oldest_slot && oldest_slot < state.slot {
+            let mut root = state
+                .get_block_root(oldest_slot, &self.spec)
+                .cloned()
+                .unwrap_or(self.spec.zero_hash);
+            while root != self.spec.zero_hash {
+                let block = self
+                    .block_store
+                    .get_deserialized(&root)?
+                    .ok_or(Error::MissingBeaconBlock(root))?;
+                if block.slot < start_slot {
+                    break;
+                }
+                roots.push(root);
+                root = block.parent_root;
+            }
+            roots.reverse();
+        }
+        let mut slot = std::cmp::max(start_slot, oldest_slot);
+        while slot < std::cmp::min(end_slot, state.slot) {
+            if let Some(root) = state.get_block_root(slot, &self.spec) {
+                if roots.last() != Some(root) {
+                    roots.push(*root);
+                }
+            }
+            slot += 1;
+        }
+        if head.beacon_block.slot >= start_slot && head.beacon_block.slot < end_slot {
+            roots.push(head.beacon_block_root);
+        }
+
+        let mut blocks = vec![];
+        for root in roots {
+            let block = self
+                .block_store
+                .get_deserialized(&root)?
+                .ok_or(Error::MissingBeaconBlock(root))?;
+            if block.slot >= start_slot && block.slot < end_slot {
+                blocks.push(block);
+            }
+        }
+        Ok(blocks)
+    }
+
     /// Advance the `self.state` `BeaconState` to the supplied slot.
     ///
     /// This will perform per_slot and per_epoch processing as required.
@@ -204,15 +502,109 @@ where
     /// It is important to note that this is _not_ the state corresponding to the canonical head
     /// block, instead it is that state which may or may not have had additional per slot/epoch
     /// processing applied to it.
-    pub fn advance_state(&self, slot: Slot) -> Result<(), SlotProcessingError> {
-        let state_slot = self.state.read().slot;
+    ///
+    /// Returns the queued blocks from the slots reached which were imported, e.g., so they may be
+    /// gossiped.
+    pub fn advance_state(&self, slot: Slot) -> Result<Vec<BeaconBlock>, SlotProcessingError> {
         let head_block_root = self.head().beacon_block_root;
-        for _ in state_slot.as_u64()..slot.as_u64() {
-            self.state
-                .write()
-                .per_slot_processing(head_block_root, &self.spec)?;
+        // Hold the lock throughout, so concurrent calls may not both advance the state.
+        let state_slot = {
+            let mut state = self.state.write();
+            let state_slot = state.slot;
+            for _ in state_slot.as_u64()..slot.as_u64() {
+                state.per_slot_processing(head_block_root, &self.spec)?;
+            }
+            state_slot
+        };
+        if slot > state_slot {
+            self.prune_attestations();
+            self.prune_orphans();
+            return Ok(self.process_future_blocks(slot));
         }
-        Ok(())
+        Ok(vec![])
+    }
+
+    /// Transition the state to the present slot of the slot clock, processing any queued blocks
+    /// from the slots which have been reached.
+    ///
+    /// Returns the queued blocks which were imported.
+    pub fn catch_up_with_slot_clock(&self) -> Result<Vec<BeaconBlock>, SlotProcessingError> {
+        match self.read_slot_clock() {
+            Some(slot) => self.advance_state(slot),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Process the queued blocks at or prior to `present_slot`, returning those which were
+    /// imported.
+    fn process_future_blocks(&self, present_slot: Slot) -> Vec<BeaconBlock> {
+        let blocks = self.future_blocks.write().remove_ready(present_slot);
+        let mut imported = vec![];
+        for block in blocks {
+            let slot = block.slot;
+            match self.process_block(block.clone()) {
+                Ok(BlockProcessingOutcome::ValidBlock(ValidBlock::Processed)) => {
+                    debug!("Processed queued block at slot {}", slot);
+                    imported.push(block);
+                }
+                Ok(outcome) => debug!("Dropped queued block at slot {}: {:?}", slot, outcome),
+                Err(e) => warn!("Unable to process queued block at slot {}: {:?}", slot, e),
+            }
+        }
+        imported
+    }
+
+    /// Returns the number of attestations held by the `attestation_aggregator`.
+    pub fn attestation_pool_size(&self) -> usize {
+        self.attestation_aggregator.read().len()
+    }
+
+    /// Remove the attestations which may no longer be included in a block built upon
+    /// `self.state`.
+    fn prune_attestations(&self) {
+        let pruned = self
+            .attestation_aggregator
+            .write()
+            .prune(&self.state.read(), &self.spec);
+        if pruned > 0 {
+            debug!(
+                "Pruned {} attestations, {} remain",
+                pruned,
+                self.attestation_pool_size()
+            );
+        }
+    }
+
+    /// Remove the orphan blocks which are not later than the finalized block, as they may never
+    /// become canonical.
+    fn prune_orphans(&self) {
+        let finalized_slot = self.finalized_head().beacon_block.slot;
+        let pruned = self.orphan_pool.write().prune(finalized_slot);
+        if pruned > 0 {
+            debug!("Pruned {} orphan blocks", pruned);
+        }
+    }
+
+    /// Returns the roots of the blocks which must be fetched (e.g., from peers) before the blocks
+    /// in the `orphan_pool` may be processed.
+    pub fn missing_block_roots(&self) -> Vec<Hash256> {
+        self.orphan_pool.read().missing_roots()
+    }
+
+    /// Returns the root of the block which must be fetched before the orphan with `block_root`
+    /// may be processed, or `None` if the block is not in the `orphan_pool`.
+    pub fn missing_ancestor_root(&self, block_root: &Hash256) -> Option<Hash256> {
+        self.orphan_pool.read().missing_root(block_root)
+    }
+
+    /// Returns the maximum number of blocks held in the `orphan_pool`.
+    pub fn max_orphans(&self) -> usize {
+        self.orphan_pool.read().max_orphans()
+    }
+
+    /// Returns `true` if the block with `block_root` is in the `orphan_pool`.
+    pub fn is_orphan(&self, block_root: &Hash256) -> bool {
+        self.orphan_pool.read().contains(block_root)
     }
 
     /// Returns the validator index (if any) for the given public key.
@@ -341,33 +733,158 @@ where
     ///
     /// - Create a new `Attestation`.
     /// - Aggregate it to an existing `Attestation`.
+    ///
+    /// The attestation is validated against the state of the attested block, transitioned to the
+    /// attestation slot, so attestations to competing forks or from the previous epoch are
+    /// judged by the committees they were produced for.
     pub fn process_free_attestation(
         &self,
         free_attestation: FreeAttestation,
     ) -> Result<AggregationOutcome, Error> {
-        let aggregation_outcome = self
-            .attestation_aggregator
-            .write()
-            .process_free_attestation(&self.state.read(), &free_attestation, &self.spec)?;
+        let data = &free_attestation.data;
+        let attestation_epoch = data.slot.epoch(self.spec.epoch_length);
+
+        if data.slot > self.present_slot() {
+            return Ok(invalid_attestation(AggregationMessage::FutureSlot));
+        }
+        if attestation_epoch < self.state.read().previous_epoch(&self.spec) {
+            return Ok(invalid_attestation(AggregationMessage::TooOld));
+        }
+
+        // `self.state` is built upon the head block, so it may be used for attestations to the
+        // head in the present epoch, saving a state transition.
+        let is_present_head = {
+            let head = self.head();
+            data.beacon_block_root == head.beacon_block_root && head.beacon_block.slot <= data.slot
+        } && self.state.read().current_epoch(&self.spec) == attestation_epoch;
+
+        let aggregation_outcome = if is_present_head {
+            self.attestation_aggregator
+                .write()
+                .process_free_attestation(&self.state.read(), &free_attestation, &self.spec)?
+        } else {
+            // Public keys in the registry never change, so a bad signature may be detected with
+            // `self.state` before building the state of the attested block.
+            let signature_is_valid = verify_free_attestation_signature(
+                &self.state.read(),
+                &free_attestation,
+                &self.spec,
+            );
+            if signature_is_valid == Some(false) {
+                return Ok(invalid_attestation(AggregationMessage::BadSignature));
+            }
+
+            match self.attestation_state(data)? {
+                AttestationState::Built(state) => self
+                    .attestation_aggregator
+                    .write()
+                    .process_free_attestation(&state, &free_attestation, &self.spec)?,
+                AttestationState::Invalid(message) => return Ok(invalid_attestation(message)),
+            }
+        };
 
         // return if the attestation is invalid
         if !aggregation_outcome.valid {
             return Ok(aggregation_outcome);
         }
 
+        let attester_slashings = self
+            .slasher
+            .write()
+            .observe_free_attestation(&free_attestation, &self.spec);
+        self.pool_slashings(vec![], attester_slashings);
+
         // valid attestation, proceed with fork-choice logic
         self.fork_choice.write().add_attestation(
             free_attestation.validator_index,
             &free_attestation.data.beacon_block_root,
             &self.spec,
         )?;
+        self.record_attestation_target(
+            free_attestation.validator_index,
+            free_attestation.data.beacon_block_root,
+        )?;
+
+        // The attestation may have changed the head.
+        self.fork_choice()?;
+
         Ok(aggregation_outcome)
     }
 
+    /// Returns the state of the block attested to by `data`, transitioned to the attestation slot
+    /// with its current epoch cache built.
+    ///
+    /// Blocks prior to the start of the previous epoch are rejected, so at most two epochs of
+    /// slots are processed.
+    fn attestation_state(&self, data: &AttestationData) -> Result<AttestationState, Error> {
+        let block = match self.block_store.get_deserialized(&data.beacon_block_root)? {
+            Some(block) => block,
+            None => {
+                return Ok(AttestationState::Invalid(
+                    AggregationMessage::UnknownBeaconBlock,
+                ))
+            }
+        };
+        if block.slot > data.slot {
+            return Ok(AttestationState::Invalid(AggregationMessage::BadSlot));
+        }
+        let previous_epoch_start_slot = self
+            .state
+            .read()
+            .previous_epoch(&self.spec)
+            .start_slot(self.spec.epoch_length);
+        if block.slot < previous_epoch_start_slot {
+            return Ok(AttestationState::Invalid(AggregationMessage::TooOld));
+        }
+
+        // The states of blocks prior to the finalized block may have been pruned.
+        let mut state = match self.state_store.get_deserialized(&block.state_root)? {
+            Some(state) => state,
+            None => return Ok(AttestationState::Invalid(AggregationMessage::TooOld)),
+        };
+        for _ in state.slot.as_u64()..data.slot.as_u64() {
+            state.per_slot_processing(data.beacon_block_root, &self.spec)?;
+        }
+        state.build_epoch_cache(RelativeEpoch::Current, &self.spec)?;
+
+        Ok(AttestationState::Built(state))
+    }
+
+    /// Persist the latest attestation target of a validator, so it may be supplied to the fork
+    /// choice after a restart.
+    ///
+    /// As in the fork choice, a target only replaces an existing target with a lower slot.
+    fn record_attestation_target(
+        &self,
+        validator_index: u64,
+        block_root: Hash256,
+    ) -> Result<(), Error> {
+        let slot = self
+            .block_store
+            .get_deserialized(&block_root)?
+            .ok_or(Error::MissingBeaconBlock(block_root))?
+            .slot;
+
+        let mut targets = self.attestation_targets.write();
+        if let Some(existing) = targets.get(&validator_index) {
+            if existing.block_root == block_root || existing.slot >= slot {
+                return Ok(());
+            }
+        }
+        let target = AttestationTarget {
+            validator_index,
+            block_root,
+            slot,
+        };
+        self.chain_store.put_attestation_target(&target)?;
+        targets.insert(validator_index, target);
+        Ok(())
+    }
+
     /// Dumps the entire canonical chain, from the head to genesis to a vector for analysis.
     ///
     /// This could be a very expensive operation and should only be done in testing/analysis
-    /// activities.
+    /// activities. It fails once the states prior to the finalized block have been pruned.
     pub fn chain_dump(&self) -> Result<Vec<CheckPoint>, Error> {
         let mut dump = vec![];
 
@@ -415,10 +932,82 @@ where
         Ok(dump)
     }
 
+    /// Validate an `Exit` against the present state and store it for inclusion in a block.
+    pub fn process_exit(&self, exit: Exit) -> Result<(), BlockProcessingError> {
+        self.operation_pool
+            .write()
+            .insert_exit(exit, &self.state.read(), &self.spec)
+    }
+
+    /// Validate a `ProposerSlashing` against the present state and store it for inclusion in a
+    /// block.
+    pub fn process_proposer_slashing(
+        &self,
+        proposer_slashing: ProposerSlashing,
+    ) -> Result<(), BlockProcessingError> {
+        self.operation_pool.write().insert_proposer_slashing(
+            proposer_slashing,
+            &self.state.read(),
+            &self.spec,
+        )
+    }
+
+    /// Validate an `AttesterSlashing` against the present state and store it for inclusion in a
+    /// block.
+    pub fn process_attester_slashing(
+        &self,
+        attester_slashing: AttesterSlashing,
+    ) -> Result<(), BlockProcessingError> {
+        self.operation_pool.write().insert_attester_slashing(
+            attester_slashing,
+            &self.state.read(),
+            &self.spec,
+        )
+    }
+
+    /// Validate a `Deposit` against the present state and store it for inclusion in a block.
+    pub fn process_deposit(&self, deposit: Deposit) -> Result<(), BlockProcessingError> {
+        self.operation_pool
+            .write()
+            .insert_deposit(deposit, &self.state.read(), &self.spec)
+    }
+
     /// Accept some block and attempt to add it to block DAG.
     ///
     /// Will accept blocks from prior slots, however it will reject any block from a future slot.
+    ///
+    /// Blocks with an unknown parent are stored in the `orphan_pool` (returning `ParentUnknown`)
+    /// and processed once their parent has been. Likewise, blocks from a future slot within the
+    /// tolerance of the `future_blocks` queue, whose proposer signature is not known to be
+    /// invalid, are processed once the state reaches their slot (returning `FutureSlot`).
     pub fn process_block(&self, block: BeaconBlock) -> Result<BlockProcessingOutcome, Error> {
+        let block_root = block.canonical_root();
+        let outcome = self.process_single_block(block)?;
+        if outcome == BlockProcessingOutcome::ValidBlock(ValidBlock::Processed) {
+            self.process_orphans(block_root);
+        }
+        Ok(outcome)
+    }
+
+    /// Process the orphans descending from a newly processed block, dropping any which are
+    /// invalid.
+    fn process_orphans(&self, block_root: Hash256) {
+        let mut parent_roots = vec![block_root];
+        while let Some(parent_root) = parent_roots.pop() {
+            let children = self.orphan_pool.write().remove_children(&parent_root);
+            for block in children {
+                let block_root = block.canonical_root();
+                let slot = block.slot;
+                match self.process_single_block(block) {
+                    Ok(BlockProcessingOutcome::ValidBlock(_)) => parent_roots.push(block_root),
+                    outcome => debug!("Dropped orphan block at slot {}: {:?}", slot, outcome),
+                }
+            }
+        }
+    }
+
+    /// Process a block, without processing its orphaned descendants.
+    fn process_single_block(&self, block: BeaconBlock) -> Result<BlockProcessingOutcome, Error> {
         debug!("Processing block with slot {}...", block.slot());
 
         let block_root = block.canonical_root();
@@ -426,27 +1015,66 @@ where
         let present_slot = self.present_slot();
 
         if block.slot > present_slot {
+            // Blocks whose proposer is known from the present state must be signed by it, so the
+            // queue may not be filled with unsigned blocks.
+            let signature_valid =
+                self.verify_proposer_signature(&block, &mut self.state.read().clone());
+            if signature_valid == Some(false) {
+                return Ok(BlockProcessingOutcome::InvalidBlock(
+                    InvalidBlock::PerBlockProcessingError(BlockProcessingError::BadBlockSignature),
+                ));
+            }
+
+            // The tolerance is from the slot clock, which the state may lag behind.
+            let clock_slot = self
+                .read_slot_clock()
+                .map_or(present_slot, |slot| slot.max(present_slot));
+            self.future_blocks
+                .write()
+                .insert(block, clock_slot, signature_valid == Some(true));
             return Ok(BlockProcessingOutcome::InvalidBlock(
                 InvalidBlock::FutureSlot,
             ));
         }
 
+        let (finalized_root, finalized_slot) = {
+            let finalized_head = self.finalized_head();
+            (
+                finalized_head.beacon_block_root,
+                finalized_head.beacon_block.slot,
+            )
+        };
+        if block.slot <= finalized_slot {
+            return Ok(BlockProcessingOutcome::InvalidBlock(
+                InvalidBlock::ConflictsWithFinalized,
+            ));
+        }
+
         // Load the blocks parent block from the database, returning invalid if that block is not
         // found.
         let parent_block_root = block.parent_root;
         let parent_block = match self.block_store.get_reader(&parent_block_root)? {
             Some(parent_root) => parent_root,
             None => {
+                self.orphan_pool.write().insert(block);
                 return Ok(BlockProcessingOutcome::InvalidBlock(
                     InvalidBlock::ParentUnknown,
                 ));
             }
         };
 
+        // The states of blocks prior to the finalized block may have been pruned, and blocks
+        // building upon them may never become canonical.
+        if parent_block.slot() <= finalized_slot && parent_block_root != finalized_root {
+            return Ok(BlockProcessingOutcome::InvalidBlock(
+                InvalidBlock::ConflictsWithFinalized,
+            ));
+        }
+
         // Load the parent blocks state from the database, returning an error if it is not found.
         // It is an error because if know the parent block we should also know the parent state.
         let parent_state_root = parent_block.state_root();
-        let parent_state = self
+        let mut parent_state = self
             .state_store
             .get_reader(&parent_state_root)?
             .ok_or_else(|| Error::DBInconsistent(format!("Missing state {}", parent_state_root)))?
@@ -455,12 +1083,17 @@ where
                 Error::DBInconsistent(format!("State SSZ invalid {}", parent_state_root))
             })?;
 
-        // TODO: check the block proposer signature BEFORE doing a state transition. This will
-        // significantly lower exposure surface to DoS attacks.
+        // Check the block proposer signature before doing a state transition, where possible, so
+        // unsigned blocks may not cause many slots to be processed.
+        if self.verify_proposer_signature(&block, &mut parent_state) == Some(false) {
+            return Ok(BlockProcessingOutcome::InvalidBlock(
+                InvalidBlock::PerBlockProcessingError(BlockProcessingError::BadBlockSignature),
+            ));
+        }
 
-        // Transition the parent state to the present slot.
+        // Transition the parent state to the block slot.
         let mut state = parent_state;
-        for _ in state.slot.as_u64()..present_slot.as_u64() {
+        for _ in state.slot.as_u64()..block.slot.as_u64() {
             if let Err(e) = state.per_slot_processing(parent_block_root, &self.spec) {
                 return Ok(BlockProcessingOutcome::InvalidBlock(
                     InvalidBlock::SlotProcessingError(e),
@@ -488,24 +1121,121 @@ where
         self.block_store.put(&block_root, &ssz_encode(&block)[..])?;
         self.state_store.put(&state_root, &ssz_encode(&state)[..])?;
 
+        self.observe_block(&block, &state);
+
         // run the fork_choice add_block logic
         self.fork_choice
             .write()
             .add_block(&block, &block_root, &self.spec)?;
-
-        // If the parent block was the parent_block, automatically update the canonical head.
-        //
-        // TODO: this is a first-in-best-dressed scenario that is not ideal; fork_choice should be
-        // run instead.
-        if self.head().beacon_block_root == parent_block_root {
-            self.update_canonical_head(block.clone(), block_root, state.clone(), state_root);
-            // Update the local state variable.
-            *self.state.write() = state.clone();
+        {
+            let mut fork_choice_blocks = self.fork_choice_blocks.write();
+            fork_choice_blocks.push(block_root);
+            self.chain_store.add_fork_choice_block(&block_root)?;
         }
 
+        // The block may have changed the head.
+        self.fork_choice()?;
+
         Ok(BlockProcessingOutcome::ValidBlock(ValidBlock::Processed))
     }
 
+    /// Record the proposal and attestations of a processed `block` in the slasher, pooling any
+    /// slashings which are detected. `state` is the state produced by `block`.
+    fn observe_block(&self, block: &BeaconBlock, state: &BeaconState) {
+        let mut proposer_slashings = vec![];
+        let mut attester_slashings = vec![];
+        {
+            let mut slasher = self.slasher.write();
+            if let Ok(proposer_index) = state.get_beacon_proposer_index(block.slot, &self.spec) {
+                proposer_slashings.extend(slasher.observe_block(
+                    block,
+                    proposer_index as u64,
+                    &self.spec,
+                ));
+            }
+            for attestation in &block.body.attestations {
+                match slasher.observe_attestation(attestation, state, &self.spec) {
+                    Ok(slashings) => attester_slashings.extend(slashings),
+                    Err(e) => warn!("Unable to observe attestation in slasher: {:?}", e),
+                }
+            }
+        }
+        self.pool_slashings(proposer_slashings, attester_slashings);
+    }
+
+    /// Add slashings detected by the slasher to the operation pool, so they may be included in a
+    /// produced block.
+    fn pool_slashings(
+        &self,
+        proposer_slashings: Vec<ProposerSlashing>,
+        attester_slashings: Vec<AttesterSlashing>,
+    ) {
+        for proposer_slashing in proposer_slashings {
+            warn!(
+                "Detected a double proposal from validator {} at slot {}",
+                proposer_slashing.proposer_index, proposer_slashing.proposal_data_1.slot
+            );
+            if let Err(e) = self.process_proposer_slashing(proposer_slashing) {
+                debug!("Detected proposer slashing is not includable: {:?}", e);
+            }
+        }
+        for attester_slashing in attester_slashings {
+            warn!(
+                "Detected slashable attestations from validators {:?}",
+                attester_slashing.slashable_attestation_2.validator_indices
+            );
+            if let Err(e) = self.process_attester_slashing(attester_slashing) {
+                debug!("Detected attester slashing is not includable: {:?}", e);
+            }
+        }
+    }
+
+    /// Verify the proposer signature of `block` using committees which are already known, without
+    /// transitioning any state.
+    ///
+    /// The committees for the block epoch are known if either:
+    ///
+    /// - `parent_state` is in the block epoch, or
+    /// - `self.state` is in the block epoch and the canonical chain at the start of that epoch is
+    /// the chain of the block parent, so the shuffling is shared.
+    ///
+    /// Returns `None` if the proposer may not be determined without a state transition.
+    fn verify_proposer_signature(
+        &self,
+        block: &BeaconBlock,
+        parent_state: &mut BeaconState,
+    ) -> Option<bool> {
+        let epoch = block.slot.epoch(self.spec.epoch_length);
+        let verify = |state: &BeaconState| {
+            let proposer_index = state
+                .get_beacon_proposer_index(block.slot, &self.spec)
+                .ok()?;
+            let proposer = state.validator_registry.get(proposer_index)?;
+            Some(block.signature.verify(
+                &block.proposal_root(&self.spec)[..],
+                state.fork.get_domain(epoch, self.spec.domain_proposal),
+                &proposer.pubkey,
+            ))
+        };
+
+        if parent_state.current_epoch(&self.spec) == epoch {
+            parent_state
+                .build_epoch_cache(RelativeEpoch::Current, &self.spec)
+                .ok()?;
+            return verify(parent_state);
+        }
+
+        let state = self.state.read();
+        let boundary_slot = epoch.start_slot(self.spec.epoch_length) - 1;
+        if state.current_epoch(&self.spec) == epoch
+            && state.get_block_root(boundary_slot, &self.spec) == Some(&block.parent_root)
+        {
+            verify(&state)
+        } else {
+            None
+        }
+    }
+
     /// Produce a new block at the present slot.
     ///
     /// The produced block will not be inherently valid, it must be signed by a block producer.
@@ -527,6 +1257,26 @@ where
             attestations.len()
         );
 
+        let (eth1_data, eth1_deposits) = match self.eth1_follower.read().as_ref() {
+            Some(eth1_follower) => (
+                eth1_follower.eth1_data_vote(&state),
+                eth1_follower.get_deposits(&state, &self.spec),
+            ),
+            None => (None, vec![]),
+        };
+        // Without a newer eth1 block to vote for, vote for the present eth1 data.
+        let eth1_data = eth1_data.unwrap_or_else(|| state.latest_eth1_data.clone());
+
+        let BlockOperations {
+            proposer_slashings,
+            attester_slashings,
+            deposits,
+            exits,
+        } = self
+            .operation_pool
+            .read()
+            .get_block_operations(&state, &eth1_deposits, &self.spec);
+
         let parent_root = *state.get_block_root(state.slot.saturating_sub(1_u64), &self.spec)?;
 
         let mut block = BeaconBlock {
@@ -534,18 +1284,14 @@ where
             parent_root,
             state_root: Hash256::zero(), // Updated after the state is calculated.
             randao_reveal,
-            eth1_data: Eth1Data {
-                // TODO: replace with real data
-                deposit_root: Hash256::zero(),
-                block_hash: Hash256::zero(),
-            },
+            eth1_data,
             signature: self.spec.empty_signature.clone(), // To be completed by a validator.
             body: BeaconBlockBody {
-                proposer_slashings: vec![],
-                attester_slashings: vec![],
+                proposer_slashings,
+                attester_slashings,
                 attestations,
-                deposits: vec![],
-                exits: vec![],
+                deposits,
+                exits,
             },
         };
 
@@ -568,33 +1314,345 @@ where
         Some((block, state))
     }
 
-    // TODO: Left this as is, modify later
+    /// Run the fork choice rule from the justified head, updating the canonical head and
+    /// `self.state` if the head has changed.
+    ///
+    /// A new head which does not descend from the previous head is a re-org.
     pub fn fork_choice(&self) -> Result<(), Error> {
-        let present_head = self.finalized_head().beacon_block_root;
+        // Hold the fork choice lock until the head is updated, so concurrent calls may not apply
+        // stale heads.
+        let mut fork_choice = self.fork_choice.write();
 
-        let new_head = self
-            .fork_choice
-            .write()
-            .find_head(&present_head, &self.spec)?;
+        let present_head = self.head().beacon_block_root;
+        let new_head =
+            fork_choice.find_head(&self.justified_head().beacon_block_root, &self.spec)?;
 
         if new_head != present_head {
-            let block = self
+            let present_head_slot = self.head().beacon_block.slot;
+            let checkpoint = load_checkpoint(&self.block_store, &self.state_store, new_head)?;
+
+            let reorg =
+                if self.is_ancestor(present_head, present_head_slot, &checkpoint.beacon_block)? {
+                    None
+                } else {
+                    warn!(
+                        "Beacon chain re-org from block {} (slot {}) to block {} (slot {})",
+                        present_head, present_head_slot, new_head, checkpoint.beacon_block.slot
+                    );
+                    Some(Reorg {
+                        old_head_root: present_head,
+                        old_head_slot: present_head_slot,
+                        new_head_root: new_head,
+                        new_head_slot: checkpoint.beacon_block.slot,
+                    })
+                };
+
+            let state = checkpoint.beacon_state.clone();
+            self.update_canonical_head(
+                checkpoint.beacon_block,
+                checkpoint.beacon_block_root,
+                checkpoint.beacon_state,
+                checkpoint.beacon_state_root,
+            )?;
+            self.update_state(state)?;
+            self.update_finality(&mut fork_choice)?;
+
+            if let Some(reorg) = reorg {
+                self.notify_reorg(reorg);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns a receiver of each subsequent re-org of the canonical head, which is notified once
+    /// the new head has been applied.
+    ///
+    /// At most `MAX_QUEUED_REORGS` are queued for a receiver which is not read.
+    pub fn subscribe_reorgs(&self) -> Receiver<Reorg> {
+        let (sender, receiver) = sync_channel(MAX_QUEUED_REORGS);
+        self.reorg_subscribers.write().push(sender);
+        receiver
+    }
+
+    /// Send `reorg` to each subscriber, forgetting those whose receiver has been dropped.
+    fn notify_reorg(&self, reorg: Reorg) {
+        self.reorg_subscribers
+            .write()
+            .retain(|sender| match sender.try_send(reorg.clone()) {
+                Err(TrySendError::Disconnected(_)) => false,
+                Err(TrySendError::Full(_)) => {
+                    debug!("Dropped re-org for a subscriber with a full queue");
+                    true
+                }
+                Ok(()) => true,
+            });
+    }
+
+    /// Move the justified and finalized heads forward to the blocks at the start of the
+    /// `justified_epoch` and `finalized_epoch` of the canonical head state.
+    ///
+    /// The heads are never moved to an earlier block, e.g., after a re-org to a chain which has
+    /// justified an earlier epoch. When the finalized head moves the database and `fork_choice`
+    /// are pruned.
+    fn update_finality(&self, fork_choice: &mut F) -> Result<(), Error> {
+        let (justified_root, finalized_root) = {
+            let head = self.head();
+            let state = &head.beacon_state;
+            (
+                epoch_boundary_root(&head, state.justified_epoch, &self.spec)?,
+                epoch_boundary_root(&head, state.finalized_epoch, &self.spec)?,
+            )
+        };
+
+        let justified_head = self.justified_head().clone();
+        if justified_root != justified_head.beacon_block_root {
+            let checkpoint = load_checkpoint(&self.block_store, &self.state_store, justified_root)?;
+            if checkpoint.beacon_block.slot > justified_head.beacon_block.slot {
+                debug!(
+                    "Justified block {} at slot {}",
+                    justified_root, checkpoint.beacon_block.slot
+                );
+                self.update_justified_head(
+                    checkpoint.beacon_block,
+                    checkpoint.beacon_block_root,
+                    checkpoint.beacon_state,
+                    checkpoint.beacon_state_root,
+                )?;
+            }
+        }
+
+        let finalized_head = self.finalized_head().clone();
+        if finalized_root != finalized_head.beacon_block_root {
+            let checkpoint = load_checkpoint(&self.block_store, &self.state_store, finalized_root)?;
+            if checkpoint.beacon_block.slot > finalized_head.beacon_block.slot {
+                debug!(
+                    "Finalized block {} at slot {}",
+                    finalized_root, checkpoint.beacon_block.slot
+                );
+                self.update_finalized_head(
+                    checkpoint.beacon_block,
+                    checkpoint.beacon_block_root,
+                    checkpoint.beacon_state,
+                    checkpoint.beacon_state_root,
+                )?;
+                self.prune(fork_choice, &finalized_head, finalized_root)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Delete the blocks and states which are no longer required after the finalized head moved
+    /// from `old_finalized_head` to the block with `finalized_root`:
+    ///
+    /// - Blocks (and their states) which do not descend from the finalized block, as they may
+    ///   never become canonical.
+    /// - States of canonical blocks prior to the finalized block, except for genesis and one
+    ///   snapshot in each `STATE_SNAPSHOT_INTERVAL` slots.
+    ///
+    /// The fork choice and slasher are pruned likewise.
+    fn prune(
+        &self,
+        fork_choice: &mut F,
+        old_finalized_head: &CheckPoint,
+        finalized_root: Hash256,
+    ) -> Result<(), Error> {
+        let mut pruned_blocks = 0;
+        let mut pruned_states = 0;
+
+        // Walk the canonical chain back from the new finalized block to the previous one.
+        let mut canonical = HashSet::new();
+        canonical.insert(finalized_root);
+        let mut block_root = finalized_root;
+        let mut block = self
+            .block_store
+            .get_deserialized(&block_root)?
+            .ok_or(Error::MissingBeaconBlock(block_root))?;
+        while block_root != old_finalized_head.beacon_block_root
+            && block.slot > old_finalized_head.beacon_block.slot
+        {
+            let parent_root = block.parent_root;
+            let parent = self
                 .block_store
-                .get_deserialized(&new_head)?
-                .ok_or_else(|| Error::MissingBeaconBlock(new_head))?;
-            let block_root = block.canonical_root();
+                .get_deserialized(&parent_root)?
+                .ok_or(Error::MissingBeaconBlock(parent_root))?;
+
+            let is_snapshot = parent.slot.as_u64() / STATE_SNAPSHOT_INTERVAL
+                != block.slot.as_u64() / STATE_SNAPSHOT_INTERVAL;
+            let is_genesis = parent.parent_root == self.spec.zero_hash;
+            if !is_snapshot && !is_genesis {
+                self.state_store.delete(&parent.state_root)?;
+                pruned_states += 1;
+            }
 
-            let state = self
-                .state_store
-                .get_deserialized(&block.state_root)?
-                .ok_or_else(|| Error::MissingBeaconState(block.state_root))?;
-            let state_root = state.canonical_root();
+            canonical.insert(parent_root);
+            block_root = parent_root;
+            block = parent;
+        }
+
+        // Blocks are imported after their parent, so a single pass over the imported blocks finds
+        // all descendants of the finalized block.
+        let mut descendants = HashSet::new();
+        descendants.insert(finalized_root);
+        {
+            let mut fork_choice_blocks = self.fork_choice_blocks.write();
+            let mut retained = vec![];
+            for block_root in fork_choice_blocks.iter() {
+                if canonical.contains(block_root) {
+                    self.chain_store.remove_fork_choice_block(block_root)?;
+                    continue;
+                }
+                let block = self
+                    .block_store
+                    .get_deserialized(block_root)?
+                    .ok_or(Error::MissingBeaconBlock(*block_root))?;
+                if descendants.contains(&block.parent_root) {
+                    descendants.insert(*block_root);
+                    retained.push(*block_root);
+                } else {
+                    self.chain_store.remove_fork_choice_block(block_root)?;
+                    self.block_store.delete(block_root)?;
+                    self.state_store.delete(&block.state_root)?;
+                    pruned_blocks += 1;
+                    pruned_states += 1;
+                }
+            }
+            *fork_choice_blocks = retained;
+        }
+        {
+            let mut targets = self.attestation_targets.write();
+            let pruned: Vec<u64> = targets
+                .values()
+                .filter(|target| !descendants.contains(&target.block_root))
+                .map(|target| target.validator_index)
+                .collect();
+            for validator_index in pruned {
+                self.chain_store
+                    .delete_attestation_target(validator_index)?;
+                targets.remove(&validator_index);
+            }
+        }
+
+        fork_choice.prune(&finalized_root, &self.spec)?;
+        let finalized_epoch = self
+            .finalized_head()
+            .beacon_block
+            .slot
+            .epoch(self.spec.epoch_length);
+        self.slasher.write().prune(finalized_epoch, &self.spec);
 
-            self.update_canonical_head(block, block_root, state, state_root);
+        debug!(
+            "Pruned {} blocks and {} states after finalizing block {}",
+            pruned_blocks, pruned_states, finalized_root
+        );
+        Ok(())
+    }
+
+    /// Replace `self.state` with the state of the canonical head, transitioned to the present
+    /// slot.
+    fn update_state(&self, mut state: BeaconState) -> Result<(), Error> {
+        let present_slot = self.present_slot();
+        let head_block_root = self.head().beacon_block_root;
+
+        for _ in state.slot.as_u64()..present_slot.as_u64() {
+            state.per_slot_processing(head_block_root, &self.spec)?;
         }
 
+        state.build_epoch_cache(RelativeEpoch::Previous, &self.spec)?;
+        state.build_epoch_cache(RelativeEpoch::Current, &self.spec)?;
+        state.build_epoch_cache(RelativeEpoch::Next, &self.spec)?;
+
+        *self.state.write() = state;
+        self.prune_attestations();
+        self.operation_pool
+            .write()
+            .prune(&self.state.read(), &self.spec);
         Ok(())
     }
+
+    /// Returns `true` if the block with `ancestor_root` at `ancestor_slot` is an ancestor of
+    /// `block`.
+    fn is_ancestor(
+        &self,
+        ancestor_root: Hash256,
+        ancestor_slot: Slot,
+        block: &BeaconBlock,
+    ) -> Result<bool, Error> {
+        let mut block = block.clone();
+        while block.slot > ancestor_slot {
+            if block.parent_root == ancestor_root {
+                return Ok(true);
+            }
+            let parent_root = block.parent_root;
+            block = self
+                .block_store
+                .get_deserialized(&parent_root)?
+                .ok_or(Error::MissingBeaconBlock(parent_root))?;
+        }
+        Ok(false)
+    }
+}
+
+/// Returns the genesis time of the chain stored in `chain_store`, or `None` if no chain has been
+/// stored.
+///
+/// Allows a slot clock to be built before resuming the chain with `BeaconChain::from_store`.
+pub fn stored_genesis_time<T: ClientDB>(
+    block_store: &BeaconBlockStore<T>,
+    state_store: &BeaconStateStore<T>,
+    chain_store: &ChainStore<T>,
+) -> Result<Option<u64>, Error> {
+    match chain_store.get_finalized_head()? {
+        Some(block_root) => {
+            let checkpoint = load_checkpoint(block_store, state_store, block_root)?;
+            Ok(Some(checkpoint.beacon_state.genesis_time))
+        }
+        None => Ok(None),
+    }
+}
+
+fn invalid_attestation(message: AggregationMessage) -> AggregationOutcome {
+    AggregationOutcome {
+        valid: false,
+        message,
+    }
+}
+
+/// Returns the root of the block at the start of `epoch` in the chain of `head`.
+///
+/// If the start slot was skipped this is the latest block prior to it.
+fn epoch_boundary_root(
+    head: &CheckPoint,
+    epoch: Epoch,
+    spec: &ChainSpec,
+) -> Result<Hash256, Error> {
+    let slot = epoch.start_slot(spec.epoch_length);
+    if slot >= head.beacon_block.slot {
+        Ok(head.beacon_block_root)
+    } else {
+        head.beacon_state
+            .get_block_root(slot, spec)
+            .cloned()
+            .ok_or(Error::BadRecentBlockRoots)
+    }
+}
+
+/// Load the block with `block_root` and its state from the stores.
+fn load_checkpoint<T: ClientDB>(
+    block_store: &BeaconBlockStore<T>,
+    state_store: &BeaconStateStore<T>,
+    block_root: Hash256,
+) -> Result<CheckPoint, Error> {
+    let block = block_store
+        .get_deserialized(&block_root)?
+        .ok_or(Error::MissingBeaconBlock(block_root))?;
+    let state_root = block.state_root;
+    let state = state_store
+        .get_deserialized(&state_root)?
+        .ok_or(Error::MissingBeaconState(state_root))?;
+
+    Ok(CheckPoint::new(block, block_root, state, state_root))
 }
 
 impl From<DBError> for Error {
@@ -609,8 +1667,61 @@ impl From<ForkChoiceError> for Error {
     }
 }
 
+impl From<SlotProcessingError> for Error {
+    fn from(e: SlotProcessingError) -> Error {
+        Error::SlotProcessingError(e)
+    }
+}
+
 impl From<BeaconStateError> for Error {
     fn from(e: BeaconStateError) -> Error {
         Error::BeaconStateError(e)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::test_utils::{SeedableRng, TestRandom, XorShiftRng};
+
+    #[test]
+    fn test_epoch_boundary_root() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let spec = ChainSpec::few_validators();
+        let genesis_epoch = spec.genesis_epoch;
+
+        // A head block at the start of the third epoch after genesis, whose state records a
+        // distinct root for every prior slot.
+        let mut head_block = BeaconBlock::random_for_test(&mut rng);
+        head_block.slot = (genesis_epoch + 3).start_slot(spec.epoch_length);
+        let mut head_state = BeaconState::default();
+        head_state.slot = head_block.slot;
+        let root_at_index = |i: usize| Hash256::from(&[(i % 251) as u8 + 1; 32][..]);
+        head_state.latest_block_roots = (0..spec.latest_block_roots_length)
+            .map(root_at_index)
+            .collect();
+        let head_root = Hash256::from(&[0; 32][..]);
+        let head = CheckPoint::new(head_block, head_root, head_state, Hash256::zero());
+
+        for epoch in 0..3 {
+            let epoch = genesis_epoch + epoch;
+            let start_slot = epoch.start_slot(spec.epoch_length);
+            assert_eq!(
+                epoch_boundary_root(&head, epoch, &spec),
+                Ok(root_at_index(
+                    start_slot.as_usize() % spec.latest_block_roots_length
+                ))
+            );
+        }
+
+        // Epochs starting at or after the head block resolve to the head block.
+        assert_eq!(
+            epoch_boundary_root(&head, genesis_epoch + 3, &spec),
+            Ok(head_root)
+        );
+        assert_eq!(
+            epoch_boundary_root(&head, genesis_epoch + 4, &spec),
+            Ok(head_root)
+        );
+    }
+}
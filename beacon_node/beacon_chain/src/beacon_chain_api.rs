@@ -0,0 +1,196 @@
+use crate::{AggregationOutcome, BeaconChain, BlockProcessingOutcome, Error, Reorg};
+use db::ClientDB;
+use fork_choice::ForkChoice;
+use slot_clock::SlotClock;
+use state_processing::SlotProcessingError;
+use std::sync::mpsc::Receiver;
+use types::{
+    AttestationData, BeaconBlock, BeaconState, BeaconStateError, ChainSpec, Epoch, FreeAttestation,
+    Hash256, PublicKey, Signature, Slot,
+};
+
+/// The API of the beacon chain used by the RPC and network services.
+///
+/// Removes the generic parameters of `BeaconChain` so the chain may be shared between services
+/// and their threads as a trait object.
+pub trait BeaconChainApi: Send + Sync {
+    fn get_spec(&self) -> &ChainSpec;
+
+    fn genesis_time(&self) -> u64;
+
+    fn genesis_block_root(&self) -> Result<Hash256, Error>;
+
+    fn read_slot_clock(&self) -> Option<Slot>;
+
+    fn present_slot(&self) -> Slot;
+
+    fn advance_state(&self, slot: Slot) -> Result<Vec<BeaconBlock>, SlotProcessingError>;
+
+    /// Transition the state to the present slot of the slot clock, so blocks and attestations for
+    /// the present slot may be processed. Returns the queued blocks which were imported.
+    fn catch_up_with_slot_clock(&self) -> Result<Vec<BeaconBlock>, SlotProcessingError>;
+
+    fn head_block_root(&self) -> Hash256;
+
+    fn head_slot(&self) -> Slot;
+
+    fn finalized_block_root(&self) -> Hash256;
+
+    fn finalized_epoch(&self) -> Epoch;
+
+    /// Returns a receiver of each subsequent re-org of the canonical head.
+    fn subscribe_reorgs(&self) -> Receiver<Reorg>;
+
+    fn is_known_block(&self, block_root: &Hash256) -> Result<bool, Error>;
+
+    fn get_block(&self, block_root: &Hash256) -> Result<Option<BeaconBlock>, Error>;
+
+    fn blocks_by_range(&self, start_slot: Slot, count: u64) -> Result<Vec<BeaconBlock>, Error>;
+
+    fn produce_block(&self, randao_reveal: Signature) -> Option<(BeaconBlock, BeaconState)>;
+
+    fn process_block(&self, block: BeaconBlock) -> Result<BlockProcessingOutcome, Error>;
+
+    fn max_orphans(&self) -> usize;
+
+    fn is_orphan(&self, block_root: &Hash256) -> bool;
+
+    fn missing_ancestor_root(&self, block_root: &Hash256) -> Option<Hash256>;
+
+    fn validator_index(&self, pubkey: &PublicKey) -> Option<usize>;
+
+    fn block_proposer(&self, slot: Slot) -> Result<usize, BeaconStateError>;
+
+    fn validator_attestation_duty(
+        &self,
+        validator_index: usize,
+    ) -> Result<Option<(Slot, u64, u64)>, BeaconStateError>;
+
+    fn produce_attestation_data(&self, shard: u64) -> Result<AttestationData, Error>;
+
+    fn process_free_attestation(
+        &self,
+        free_attestation: FreeAttestation,
+    ) -> Result<AggregationOutcome, Error>;
+
+    fn attestation_pool_size(&self) -> usize;
+}
+
+impl<T, U, F> BeaconChainApi for BeaconChain<T, U, F>
+where
+    T: ClientDB + Sized,
+    U: SlotClock,
+    F: ForkChoice,
+{
+    fn get_spec(&self) -> &ChainSpec {
+        &self.spec
+    }
+
+    fn genesis_time(&self) -> u64 {
+        self.state.read().genesis_time
+    }
+
+    fn genesis_block_root(&self) -> Result<Hash256, Error> {
+        self.genesis_block_root()
+    }
+
+    fn read_slot_clock(&self) -> Option<Slot> {
+        self.read_slot_clock()
+    }
+
+    fn present_slot(&self) -> Slot {
+        self.present_slot()
+    }
+
+    fn advance_state(&self, slot: Slot) -> Result<Vec<BeaconBlock>, SlotProcessingError> {
+        self.advance_state(slot)
+    }
+
+    fn catch_up_with_slot_clock(&self) -> Result<Vec<BeaconBlock>, SlotProcessingError> {
+        self.catch_up_with_slot_clock()
+    }
+
+    fn head_block_root(&self) -> Hash256 {
+        self.head().beacon_block_root
+    }
+
+    fn head_slot(&self) -> Slot {
+        self.head().beacon_block.slot
+    }
+
+    fn finalized_block_root(&self) -> Hash256 {
+        self.finalized_head().beacon_block_root
+    }
+
+    fn finalized_epoch(&self) -> Epoch {
+        self.head().beacon_state.finalized_epoch
+    }
+
+    fn subscribe_reorgs(&self) -> Receiver<Reorg> {
+        self.subscribe_reorgs()
+    }
+
+    fn is_known_block(&self, block_root: &Hash256) -> Result<bool, Error> {
+        Ok(self.block_store.exists(block_root)?)
+    }
+
+    fn get_block(&self, block_root: &Hash256) -> Result<Option<BeaconBlock>, Error> {
+        Ok(self.block_store.get_deserialized(block_root)?)
+    }
+
+    fn blocks_by_range(&self, start_slot: Slot, count: u64) -> Result<Vec<BeaconBlock>, Error> {
+        self.blocks_by_range(start_slot, count)
+    }
+
+    fn produce_block(&self, randao_reveal: Signature) -> Option<(BeaconBlock, BeaconState)> {
+        self.produce_block(randao_reveal)
+    }
+
+    fn process_block(&self, block: BeaconBlock) -> Result<BlockProcessingOutcome, Error> {
+        self.process_block(block)
+    }
+
+    fn max_orphans(&self) -> usize {
+        self.max_orphans()
+    }
+
+    fn is_orphan(&self, block_root: &Hash256) -> bool {
+        self.is_orphan(block_root)
+    }
+
+    fn missing_ancestor_root(&self, block_root: &Hash256) -> Option<Hash256> {
+        self.missing_ancestor_root(block_root)
+    }
+
+    fn validator_index(&self, pubkey: &PublicKey) -> Option<usize> {
+        self.validator_index(pubkey)
+    }
+
+    fn block_proposer(&self, slot: Slot) -> Result<usize, BeaconStateError> {
+        self.block_proposer(slot)
+    }
+
+    fn validator_attestation_duty(
+        &self,
+        validator_index: usize,
+    ) -> Result<Option<(Slot, u64, u64)>, BeaconStateError> {
+        self.state
+            .read()
+            .attestation_slot_and_shard_for_validator(validator_index, &self.spec)
+    }
+
+    fn produce_attestation_data(&self, shard: u64) -> Result<AttestationData, Error> {
+        self.produce_attestation_data(shard)
+    }
+
+    fn process_free_attestation(
+        &self,
+        free_attestation: FreeAttestation,
+    ) -> Result<AggregationOutcome, Error> {
+        self.process_free_attestation(free_attestation)
+    }
+
+    fn attestation_pool_size(&self) -> usize {
+        self.attestation_pool_size()
+    }
+}
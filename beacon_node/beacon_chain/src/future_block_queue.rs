@@ -0,0 +1,208 @@
+use log::trace;
+use std::collections::BTreeMap;
+use types::{BeaconBlock, Hash256, Slot};
+
+/// The number of slots ahead of the slot clock a block may be queued by a `FutureBlockQueue`
+/// created with `new()`.
+pub const DEFAULT_FUTURE_SLOT_TOLERANCE: u64 = 1;
+/// The maximum number of blocks stored by a `FutureBlockQueue`.
+const MAX_FUTURE_BLOCKS: usize = 64;
+/// The maximum number of blocks stored by a `FutureBlockQueue` for any one slot.
+pub const MAX_FUTURE_BLOCKS_PER_SLOT: usize = 4;
+
+/// A queued block, with whether its proposer signature was verified before it was queued.
+struct QueuedBlock {
+    block: BeaconBlock,
+    signature_verified: bool,
+}
+
+/// Stores blocks from slots which have not yet been reached, so they may be processed once they
+/// have.
+///
+/// Only blocks at most `tolerance` slots ahead of the present slot are queued, allowing for a
+/// small skew between the clocks of nodes. At most `MAX_FUTURE_BLOCKS` are stored, evicting the
+/// blocks with the latest slot first, and at most `MAX_FUTURE_BLOCKS_PER_SLOT` for each slot, where
+/// a block with a verified signature evicts one without.
+pub struct FutureBlockQueue {
+    blocks: BTreeMap<(Slot, Hash256), QueuedBlock>,
+    tolerance: u64,
+}
+
+impl FutureBlockQueue {
+    /// Instantiates an empty queue, with a tolerance of `DEFAULT_FUTURE_SLOT_TOLERANCE`.
+    pub fn new() -> Self {
+        Self::with_tolerance(DEFAULT_FUTURE_SLOT_TOLERANCE)
+    }
+
+    /// Instantiates an empty queue, storing blocks at most `tolerance` slots ahead.
+    pub fn with_tolerance(tolerance: u64) -> Self {
+        Self {
+            blocks: BTreeMap::new(),
+            tolerance,
+        }
+    }
+
+    /// Set the number of slots ahead a block may be queued. Blocks already queued are kept.
+    pub fn set_tolerance(&mut self, tolerance: u64) {
+        self.tolerance = tolerance;
+    }
+
+    /// Returns the number of queued blocks.
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// Queue a block if it is at most `tolerance` slots after `present_slot`, noting whether its
+    /// proposer signature has been verified.
+    ///
+    /// Returns `false` if the block is too far ahead, was already queued, is unverified whilst its
+    /// slot is full, or is later than every queued block whilst the queue is full.
+    pub fn insert(
+        &mut self,
+        block: BeaconBlock,
+        present_slot: Slot,
+        signature_verified: bool,
+    ) -> bool {
+        if block.slot > present_slot + self.tolerance {
+            return false;
+        }
+        let key = (block.slot, block.canonical_root());
+        if self.blocks.contains_key(&key) {
+            return false;
+        }
+        let slot_keys: Vec<(Slot, Hash256)> = self
+            .blocks
+            .range((block.slot, Hash256::zero())..(block.slot + 1, Hash256::zero()))
+            .map(|(key, _)| *key)
+            .collect();
+        if slot_keys.len() >= MAX_FUTURE_BLOCKS_PER_SLOT {
+            let unverified = match slot_keys
+                .into_iter()
+                .find(|key| !self.blocks[key].signature_verified)
+            {
+                Some(unverified) if signature_verified => unverified,
+                _ => return false,
+            };
+            trace!("Evicting unverified future block for slot {}", block.slot);
+            self.blocks.remove(&unverified);
+        } else if self.blocks.len() >= MAX_FUTURE_BLOCKS {
+            let latest = match self.blocks.keys().next_back() {
+                Some(latest) if latest.0 > block.slot => *latest,
+                _ => return false,
+            };
+            trace!("Evicting future block for slot {}", latest.0);
+            self.blocks.remove(&latest);
+        }
+        self.blocks.insert(
+            key,
+            QueuedBlock {
+                block,
+                signature_verified,
+            },
+        );
+        true
+    }
+
+    /// Remove and return the blocks at or prior to `present_slot`, ordered by slot.
+    pub fn remove_ready(&mut self, present_slot: Slot) -> Vec<BeaconBlock> {
+        let later = self.blocks.split_off(&(present_slot + 1, Hash256::zero()));
+        let ready = std::mem::replace(&mut self.blocks, later);
+        let mut blocks = Vec::with_capacity(ready.len());
+        for (_, queued) in ready {
+            blocks.push(queued.block);
+        }
+        blocks
+    }
+}
+
+impl Default for FutureBlockQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::test_utils::{SeedableRng, TestRandom, XorShiftRng};
+
+    fn block_at_slot(rng: &mut XorShiftRng, slot: u64) -> BeaconBlock {
+        let mut block = BeaconBlock::random_for_test(rng);
+        block.slot = Slot::new(slot);
+        block
+    }
+
+    #[test]
+    fn test_insert_within_tolerance() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let mut queue = FutureBlockQueue::with_tolerance(2);
+        let present_slot = Slot::new(10);
+
+        let block = block_at_slot(&mut rng, 12);
+        assert!(queue.insert(block.clone(), present_slot, true));
+        assert!(!queue.insert(block, present_slot, true));
+        assert!(!queue.insert(block_at_slot(&mut rng, 13), present_slot, true));
+        assert!(queue.insert(block_at_slot(&mut rng, 11), present_slot, true));
+        assert_eq!(queue.len(), 2);
+
+        queue.set_tolerance(3);
+        assert!(queue.insert(block_at_slot(&mut rng, 13), present_slot, true));
+    }
+
+    #[test]
+    fn test_remove_ready() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let mut queue = FutureBlockQueue::with_tolerance(3);
+        for slot in &[13, 11, 12, 11] {
+            queue.insert(block_at_slot(&mut rng, *slot), Slot::new(10), true);
+        }
+
+        assert!(queue.remove_ready(Slot::new(10)).is_empty());
+        let ready: Vec<u64> = queue
+            .remove_ready(Slot::new(12))
+            .iter()
+            .map(|block| block.slot.as_u64())
+            .collect();
+        assert_eq!(ready, vec![11, 11, 12]);
+        assert_eq!(queue.len(), 1);
+    }
+
+    #[test]
+    fn test_evicts_latest_when_full() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let mut queue = FutureBlockQueue::with_tolerance(MAX_FUTURE_BLOCKS as u64);
+        for i in 0..MAX_FUTURE_BLOCKS {
+            let slot = 2 + (i / MAX_FUTURE_BLOCKS_PER_SLOT) as u64;
+            assert!(queue.insert(block_at_slot(&mut rng, slot), Slot::new(0), true));
+        }
+        let latest_slot = 2 + ((MAX_FUTURE_BLOCKS - 1) / MAX_FUTURE_BLOCKS_PER_SLOT) as u64;
+
+        assert!(!queue.insert(block_at_slot(&mut rng, latest_slot + 1), Slot::new(0), true));
+        assert!(queue.insert(block_at_slot(&mut rng, 1), Slot::new(0), true));
+        assert_eq!(queue.len(), MAX_FUTURE_BLOCKS);
+        assert_eq!(queue.remove_ready(Slot::new(1)).len(), 1);
+    }
+
+    #[test]
+    fn test_verified_block_evicts_unverified_block_from_full_slot() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let mut queue = FutureBlockQueue::with_tolerance(2);
+        for _ in 0..MAX_FUTURE_BLOCKS_PER_SLOT {
+            assert!(queue.insert(block_at_slot(&mut rng, 2), Slot::new(0), false));
+        }
+
+        // Other slots are unaffected by a full slot.
+        assert!(queue.insert(block_at_slot(&mut rng, 1), Slot::new(0), false));
+        assert!(!queue.insert(block_at_slot(&mut rng, 2), Slot::new(0), false));
+        let verified = block_at_slot(&mut rng, 2);
+        assert!(queue.insert(verified.clone(), Slot::new(0), true));
+        assert_eq!(queue.len(), MAX_FUTURE_BLOCKS_PER_SLOT + 1);
+
+        let ready = queue.remove_ready(Slot::new(2));
+        assert!(ready.contains(&verified));
+    }
+}
@@ -1,9 +1,26 @@
 mod attestation_aggregator;
 mod beacon_chain;
+mod beacon_chain_api;
 mod checkpoint;
+mod future_block_queue;
+mod operation_pool;
+mod orphan_pool;
+mod slasher;
 
+pub use self::attestation_aggregator::{
+    AttestationAggregator, Message as AggregationMessage, Outcome as AggregationOutcome,
+    DEFAULT_MAX_ATTESTATIONS,
+};
 pub use self::beacon_chain::{
-    BeaconChain, BlockProcessingOutcome, Error, InvalidBlock, ValidBlock,
+    stored_genesis_time, BeaconChain, BlockProcessingOutcome, Error, InvalidBlock, Reorg,
+    ValidBlock, MAX_QUEUED_REORGS,
 };
+pub use self::beacon_chain_api::BeaconChainApi;
 pub use self::checkpoint::CheckPoint;
+pub use self::future_block_queue::{
+    FutureBlockQueue, DEFAULT_FUTURE_SLOT_TOLERANCE, MAX_FUTURE_BLOCKS_PER_SLOT,
+};
+pub use self::operation_pool::{BlockOperations, OperationPool};
+pub use self::orphan_pool::{OrphanPool, DEFAULT_MAX_ORPHANS};
+pub use self::slasher::Slasher;
 pub use fork_choice::{ForkChoice, ForkChoiceAlgorithm, ForkChoiceError};
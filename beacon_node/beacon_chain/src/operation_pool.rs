@@ -0,0 +1,199 @@
+use state_processing::{
+    process_attester_slashing, process_deposit, process_exit, process_proposer_slashing,
+    validate_attester_slashing, validate_deposit, validate_exit, validate_proposer_slashing,
+    BlockProcessingError,
+};
+use std::collections::BTreeMap;
+use types::{
+    AttesterSlashing, BeaconState, ChainSpec, Deposit, Exit, ProposerSlashing, ValidatorStatusFlags,
+};
+
+/// The operations, other than attestations, selected for inclusion in a block.
+#[derive(Debug, PartialEq, Default)]
+pub struct BlockOperations {
+    pub proposer_slashings: Vec<ProposerSlashing>,
+    pub attester_slashings: Vec<AttesterSlashing>,
+    pub deposits: Vec<Deposit>,
+    pub exits: Vec<Exit>,
+}
+
+/// Stores the operations, other than attestations, which may be included in a `BeaconBlock`:
+/// exits, deposits and proposer and attester slashings.
+///
+/// Operations are validated against the head state when they are received, and again when they
+/// are selected for a block as they may have been invalidated since.
+#[derive(Default)]
+pub struct OperationPool {
+    /// Exits, keyed by validator index.
+    exits: BTreeMap<u64, Exit>,
+    /// Proposer slashings, keyed by proposer index.
+    proposer_slashings: BTreeMap<u64, ProposerSlashing>,
+    attester_slashings: Vec<AttesterSlashing>,
+    /// Deposits, ordered by deposit index.
+    deposits: Vec<Deposit>,
+}
+
+impl OperationPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Validate an `Exit` against `state` and store it.
+    pub fn insert_exit(
+        &mut self,
+        exit: Exit,
+        state: &BeaconState,
+        spec: &ChainSpec,
+    ) -> Result<(), BlockProcessingError> {
+        validate_exit(state, &exit, spec)?;
+        self.exits.insert(exit.validator_index, exit);
+        Ok(())
+    }
+
+    /// Validate a `ProposerSlashing` against `state` and store it.
+    pub fn insert_proposer_slashing(
+        &mut self,
+        proposer_slashing: ProposerSlashing,
+        state: &BeaconState,
+        spec: &ChainSpec,
+    ) -> Result<(), BlockProcessingError> {
+        validate_proposer_slashing(state, &proposer_slashing, spec)?;
+        self.proposer_slashings
+            .insert(proposer_slashing.proposer_index, proposer_slashing);
+        Ok(())
+    }
+
+    /// Validate an `AttesterSlashing` against `state` and store it.
+    pub fn insert_attester_slashing(
+        &mut self,
+        attester_slashing: AttesterSlashing,
+        state: &BeaconState,
+        spec: &ChainSpec,
+    ) -> Result<(), BlockProcessingError> {
+        validate_attester_slashing(state, &attester_slashing, spec)?;
+        if !self.attester_slashings.contains(&attester_slashing) {
+            self.attester_slashings.push(attester_slashing);
+        }
+        Ok(())
+    }
+
+    /// Validate a `Deposit` against `state` and store it.
+    pub fn insert_deposit(
+        &mut self,
+        deposit: Deposit,
+        state: &BeaconState,
+        spec: &ChainSpec,
+    ) -> Result<(), BlockProcessingError> {
+        if deposit.index < state.deposit_index {
+            return Err(BlockProcessingError::BadDepositIndex);
+        }
+        validate_deposit(state, &deposit, spec)?;
+        if !self.deposits.contains(&deposit) {
+            let position = self
+                .deposits
+                .iter()
+                .position(|other| other.index > deposit.index)
+                .unwrap_or(self.deposits.len());
+            self.deposits.insert(position, deposit);
+        }
+        Ok(())
+    }
+
+    /// Returns the operations to include in a block upon `state`, at most `spec.max_*` of each.
+    ///
+    /// The operations are applied to a copy of `state` in the order of block processing, so an
+    /// operation invalidated by an earlier one (e.g., an attester slashing of a proposer which is
+    /// already slashed) is excluded. The `eth1_deposits` are included before pooled deposits.
+    pub fn get_block_operations(
+        &self,
+        state: &BeaconState,
+        eth1_deposits: &[Deposit],
+        spec: &ChainSpec,
+    ) -> BlockOperations {
+        let mut state = state.clone();
+
+        BlockOperations {
+            proposer_slashings: select(
+                self.proposer_slashings.values(),
+                spec.max_proposer_slashings,
+                |slashing| process_proposer_slashing(&mut state, slashing, spec),
+            ),
+            attester_slashings: select(
+                self.attester_slashings.iter(),
+                spec.max_attester_slashings,
+                |slashing| process_attester_slashing(&mut state, slashing, spec),
+            ),
+            deposits: select(
+                eth1_deposits.iter().chain(self.deposits.iter()),
+                spec.max_deposits,
+                |deposit| process_deposit(&mut state, deposit, spec),
+            ),
+            exits: select(self.exits.values(), spec.max_exits, |exit| {
+                process_exit(&mut state, exit, spec)
+            }),
+        }
+    }
+
+    /// Remove the operations which may no longer be included in a block upon `state`, e.g.,
+    /// exits of validators which have already initiated an exit.
+    pub fn prune(&mut self, state: &BeaconState, spec: &ChainSpec) {
+        let current_epoch = state.current_epoch(spec);
+        let exit_effect_epoch = state.get_entry_exit_effect_epoch(current_epoch, spec);
+        let is_penalized = |index: u64| match state.validator_registry.get(index as usize) {
+            Some(validator) => validator.penalized_epoch <= current_epoch,
+            None => true,
+        };
+
+        self.exits.retain(
+            |index, _| match state.validator_registry.get(*index as usize) {
+                Some(validator) => {
+                    validator.status_flags != Some(ValidatorStatusFlags::InitiatedExit)
+                        && validator.exit_epoch > exit_effect_epoch
+                }
+                None => false,
+            },
+        );
+        self.proposer_slashings
+            .retain(|index, _| !is_penalized(*index));
+        self.deposits
+            .retain(|deposit| deposit.index >= state.deposit_index);
+        self.attester_slashings.retain(|slashing| {
+            slashing
+                .slashable_attestation_1
+                .validator_indices
+                .iter()
+                .any(|index| {
+                    slashing
+                        .slashable_attestation_2
+                        .validator_indices
+                        .contains(index)
+                        && !is_penalized(*index)
+                })
+        });
+    }
+
+    /// Returns the total number of stored operations.
+    pub fn len(&self) -> usize {
+        self.exits.len()
+            + self.proposer_slashings.len()
+            + self.attester_slashings.len()
+            + self.deposits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Returns the first `max` of `operations` which are successfully processed by `process`.
+fn select<'a, T: Clone + 'a>(
+    operations: impl Iterator<Item = &'a T>,
+    max: u64,
+    mut process: impl FnMut(&T) -> Result<(), BlockProcessingError>,
+) -> Vec<T> {
+    operations
+        .filter(|operation| process(operation).is_ok())
+        .take(max as usize)
+        .cloned()
+        .collect()
+}
@@ -0,0 +1,263 @@
+use log::trace;
+use std::collections::HashMap;
+use types::{BeaconBlock, Hash256, Slot};
+
+/// The number of blocks stored by an `OrphanPool` created with `new()`.
+pub const DEFAULT_MAX_ORPHANS: usize = 256;
+
+/// Stores blocks whose parent is unknown, so they may be processed once their parent has been.
+///
+/// Orphans are indexed by `parent_root`, so the descendants of a newly imported block may be
+/// found without a scan. The parents which are not themselves orphans are the `missing_roots`,
+/// which must be fetched before any orphan may be imported.
+///
+/// At most `max_orphans` are stored, evicting the orphans with the earliest slot first.
+pub struct OrphanPool {
+    /// Orphans by block root.
+    blocks: HashMap<Hash256, BeaconBlock>,
+    /// The roots of the orphans with each parent root.
+    children: HashMap<Hash256, Vec<Hash256>>,
+    max_orphans: usize,
+}
+
+impl OrphanPool {
+    /// Instantiates an empty pool, storing at most `DEFAULT_MAX_ORPHANS`.
+    pub fn new() -> Self {
+        Self::with_max_orphans(DEFAULT_MAX_ORPHANS)
+    }
+
+    /// Instantiates an empty pool, storing at most `max_orphans`.
+    pub fn with_max_orphans(max_orphans: usize) -> Self {
+        Self {
+            blocks: HashMap::new(),
+            children: HashMap::new(),
+            max_orphans,
+        }
+    }
+
+    /// Set the maximum number of stored orphans, evicting orphans if there are now too many.
+    pub fn set_max_orphans(&mut self, max_orphans: usize) {
+        self.max_orphans = max_orphans;
+        while self.blocks.len() > self.max_orphans {
+            self.evict_earliest();
+        }
+    }
+
+    /// Returns the maximum number of stored orphans.
+    pub fn max_orphans(&self) -> usize {
+        self.max_orphans
+    }
+
+    /// Returns the number of stored orphans.
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// Returns `true` if the block with `block_root` is stored.
+    pub fn contains(&self, block_root: &Hash256) -> bool {
+        self.blocks.contains_key(block_root)
+    }
+
+    /// Store a block whose parent is unknown, evicting the earliest orphan if the pool is full.
+    ///
+    /// Returns `false` if the block was already stored.
+    pub fn insert(&mut self, block: BeaconBlock) -> bool {
+        let block_root = block.canonical_root();
+        if self.blocks.contains_key(&block_root) || self.max_orphans == 0 {
+            return false;
+        }
+        if self.blocks.len() >= self.max_orphans {
+            self.evict_earliest();
+        }
+
+        self.children
+            .entry(block.parent_root)
+            .or_default()
+            .push(block_root);
+        self.blocks.insert(block_root, block);
+        true
+    }
+
+    /// Remove and return the orphans whose parent is the block with `parent_root`, ordered by
+    /// slot.
+    pub fn remove_children(&mut self, parent_root: &Hash256) -> Vec<BeaconBlock> {
+        let mut children: Vec<BeaconBlock> = self
+            .children
+            .remove(parent_root)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|block_root| self.blocks.remove(block_root))
+            .collect();
+        children.sort_by_key(|block| block.slot);
+        children
+    }
+
+    /// Returns the roots of the blocks which must be imported before any orphan may be, i.e.,
+    /// the parents of orphans which are not orphans themselves.
+    pub fn missing_roots(&self) -> Vec<Hash256> {
+        let mut roots: Vec<Hash256> = self
+            .children
+            .keys()
+            .filter(|parent_root| !self.blocks.contains_key(parent_root))
+            .cloned()
+            .collect();
+        roots.sort();
+        roots
+    }
+
+    /// Returns the root of the block which must be imported before the orphan with `block_root`
+    /// may be, i.e., the parent of its earliest orphaned ancestor.
+    ///
+    /// Returns `None` if the block is not stored.
+    pub fn missing_root(&self, block_root: &Hash256) -> Option<Hash256> {
+        let mut block = self.blocks.get(block_root)?;
+        while let Some(parent) = self.blocks.get(&block.parent_root) {
+            block = parent;
+        }
+        Some(block.parent_root)
+    }
+
+    /// Removes all orphans at or prior to `finalized_slot`, as they may never become canonical.
+    ///
+    /// Returns the number of orphans removed.
+    pub fn prune(&mut self, finalized_slot: Slot) -> usize {
+        let pruned: Vec<Hash256> = self
+            .blocks
+            .iter()
+            .filter(|(_, block)| block.slot <= finalized_slot)
+            .map(|(block_root, _)| *block_root)
+            .collect();
+        for block_root in &pruned {
+            self.remove(block_root);
+        }
+        pruned.len()
+    }
+
+    fn evict_earliest(&mut self) {
+        let earliest = self
+            .blocks
+            .iter()
+            .min_by_key(|(_, block)| block.slot)
+            .map(|(block_root, _)| *block_root);
+        if let Some(block_root) = earliest {
+            trace!(
+                "Evicting orphan block for slot {}",
+                self.blocks[&block_root].slot
+            );
+            self.remove(&block_root);
+        }
+    }
+
+    fn remove(&mut self, block_root: &Hash256) {
+        if let Some(block) = self.blocks.remove(block_root) {
+            if let Some(siblings) = self.children.get_mut(&block.parent_root) {
+                siblings.retain(|root| root != block_root);
+                if siblings.is_empty() {
+                    self.children.remove(&block.parent_root);
+                }
+            }
+        }
+    }
+}
+
+impl Default for OrphanPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::test_utils::{SeedableRng, TestRandom, XorShiftRng};
+
+    /// Returns a chain of `length` blocks, from the slot after `parent_root`.
+    fn block_chain(rng: &mut XorShiftRng, parent_root: Hash256, length: u64) -> Vec<BeaconBlock> {
+        let mut parent_root = parent_root;
+        (1..=length)
+            .map(|slot| {
+                let mut block = BeaconBlock::random_for_test(rng);
+                block.slot = Slot::new(slot);
+                block.parent_root = parent_root;
+                parent_root = block.canonical_root();
+                block
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_insert_and_remove_children() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let mut pool = OrphanPool::new();
+        let genesis_root = Hash256::from(&[1; 32][..]);
+        let blocks = block_chain(&mut rng, genesis_root, 3);
+        let fork = block_chain(&mut rng, blocks[0].canonical_root(), 2);
+
+        for block in blocks.iter().skip(1).chain(fork.iter().skip(1)) {
+            assert!(pool.insert(block.clone()));
+        }
+        assert!(!pool.insert(blocks[2].clone()));
+        assert_eq!(pool.len(), 3);
+        assert!(pool.contains(&blocks[2].canonical_root()));
+
+        // The parent of the earliest orphan on each fork is missing.
+        let mut missing_roots = vec![blocks[0].canonical_root(), fork[0].canonical_root()];
+        missing_roots.sort();
+        assert_eq!(pool.missing_roots(), missing_roots);
+        assert_eq!(
+            pool.missing_root(&blocks[2].canonical_root()),
+            Some(blocks[0].canonical_root())
+        );
+        assert_eq!(
+            pool.missing_root(&fork[1].canonical_root()),
+            Some(fork[0].canonical_root())
+        );
+        assert_eq!(pool.missing_root(&blocks[0].canonical_root()), None);
+
+        let children = pool.remove_children(&blocks[0].canonical_root());
+        assert_eq!(children, vec![blocks[1].clone()]);
+        assert_eq!(pool.remove_children(&blocks[0].canonical_root()), vec![]);
+        assert_eq!(
+            pool.remove_children(&blocks[1].canonical_root()),
+            vec![blocks[2].clone()]
+        );
+        assert_eq!(pool.missing_roots(), vec![fork[0].canonical_root()]);
+        assert_eq!(pool.len(), 1);
+    }
+
+    #[test]
+    fn test_evicts_earliest_when_full() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let mut pool = OrphanPool::with_max_orphans(2);
+        let blocks = block_chain(&mut rng, Hash256::zero(), 4);
+
+        for block in &blocks[1..] {
+            pool.insert(block.clone());
+        }
+        assert_eq!(pool.len(), 2);
+        assert!(!pool.contains(&blocks[1].canonical_root()));
+        assert_eq!(pool.missing_roots(), vec![blocks[1].canonical_root()]);
+
+        pool.set_max_orphans(1);
+        assert!(pool.contains(&blocks[3].canonical_root()));
+        assert_eq!(pool.missing_roots(), vec![blocks[2].canonical_root()]);
+    }
+
+    #[test]
+    fn test_prune() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let mut pool = OrphanPool::new();
+        let blocks = block_chain(&mut rng, Hash256::zero(), 4);
+        for block in &blocks {
+            pool.insert(block.clone());
+        }
+
+        assert_eq!(pool.prune(Slot::new(2)), 2);
+        assert_eq!(pool.len(), 2);
+        assert_eq!(pool.missing_roots(), vec![blocks[1].canonical_root()]);
+    }
+}
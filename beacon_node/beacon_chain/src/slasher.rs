@@ -0,0 +1,352 @@
+use std::collections::{BTreeMap, HashMap};
+use types::{
+    AggregateSignature, Attestation, AttesterSlashing, BeaconBlock, BeaconState, BeaconStateError,
+    Bitfield, ChainSpec, Epoch, FreeAttestation, ProposalSignedData, ProposerSlashing, Signature,
+    SlashableAttestation, Slot,
+};
+
+/// Detects slashable behaviour in the blocks and attestations observed by the node.
+///
+/// Block proposals are indexed by `(proposer, slot)` and attestations by
+/// `(validator, source epoch, target epoch)`. Observing a block or attestation which conflicts
+/// with an earlier one produces a `ProposerSlashing` or `AttesterSlashing` for inclusion in a
+/// block.
+///
+/// Signatures are not verified, so only blocks and attestations which have been validated should
+/// be observed.
+#[derive(Default)]
+pub struct Slasher {
+    /// The first proposal observed from each proposer at each slot.
+    proposals: HashMap<(u64, Slot), (ProposalSignedData, Signature)>,
+    /// Attestations, keyed by an identifier referenced from `attestation_index`.
+    attestations: HashMap<u64, SlashableAttestation>,
+    /// The first attestation observed from each validator for each source and target epoch.
+    attestation_index: BTreeMap<(u64, Epoch, Epoch), u64>,
+    next_attestation_id: u64,
+}
+
+impl Slasher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the proposal of `block` by the validator with `proposer_index`.
+    ///
+    /// Returns a `ProposerSlashing` if the proposer has already proposed a different block at the
+    /// same slot.
+    pub fn observe_block(
+        &mut self,
+        block: &BeaconBlock,
+        proposer_index: u64,
+        spec: &ChainSpec,
+    ) -> Option<ProposerSlashing> {
+        let proposal_data = block.proposal_signed_data(spec);
+        match self.proposals.get(&(proposer_index, block.slot)) {
+            Some((other_data, other_signature)) => {
+                if *other_data == proposal_data {
+                    return None;
+                }
+                Some(ProposerSlashing {
+                    proposer_index,
+                    proposal_data_1: other_data.clone(),
+                    proposal_signature_1: other_signature.clone(),
+                    proposal_data_2: proposal_data,
+                    proposal_signature_2: block.signature.clone(),
+                })
+            }
+            None => {
+                self.proposals.insert(
+                    (proposer_index, block.slot),
+                    (proposal_data, block.signature.clone()),
+                );
+                None
+            }
+        }
+    }
+
+    /// Record an `Attestation`, as included in a block upon `state`.
+    ///
+    /// Returns an `AttesterSlashing` for each earlier attestation which conflicts with it.
+    pub fn observe_attestation(
+        &mut self,
+        attestation: &Attestation,
+        state: &BeaconState,
+        spec: &ChainSpec,
+    ) -> Result<Vec<AttesterSlashing>, BeaconStateError> {
+        // Only attestations without custody bits may be slashed.
+        if attestation.custody_bitfield.num_set_bits() > 0 {
+            return Ok(vec![]);
+        }
+
+        let mut validator_indices: Vec<u64> = state
+            .get_attestation_participants(
+                &attestation.data,
+                &attestation.aggregation_bitfield,
+                spec,
+            )?
+            .into_iter()
+            .map(|index| index as u64)
+            .collect();
+        validator_indices.sort();
+
+        Ok(self.observe_slashable_attestation(
+            SlashableAttestation {
+                validator_indices,
+                data: attestation.data.clone(),
+                custody_bitfield: Bitfield::new(),
+                aggregate_signature: attestation.aggregate_signature.clone(),
+            },
+            spec,
+        ))
+    }
+
+    /// Record a `FreeAttestation`.
+    ///
+    /// Returns an `AttesterSlashing` for each earlier attestation which conflicts with it.
+    pub fn observe_free_attestation(
+        &mut self,
+        free_attestation: &FreeAttestation,
+        spec: &ChainSpec,
+    ) -> Vec<AttesterSlashing> {
+        let mut aggregate_signature = AggregateSignature::new();
+        aggregate_signature.add(&free_attestation.signature);
+
+        self.observe_slashable_attestation(
+            SlashableAttestation {
+                validator_indices: vec![free_attestation.validator_index],
+                data: free_attestation.data.clone(),
+                custody_bitfield: Bitfield::new(),
+                aggregate_signature,
+            },
+            spec,
+        )
+    }
+
+    /// Record `attestation` for each of its validators which has not already attested with the
+    /// same source and target epochs.
+    ///
+    /// Returns an `AttesterSlashing` for each earlier attestation which is a double vote with, or
+    /// surrounds or is surrounded by, `attestation`.
+    fn observe_slashable_attestation(
+        &mut self,
+        attestation: SlashableAttestation,
+        spec: &ChainSpec,
+    ) -> Vec<AttesterSlashing> {
+        let source_epoch = attestation.data.justified_epoch;
+        let target_epoch = attestation.data.slot.epoch(spec.epoch_length);
+
+        let mut conflicting_ids = vec![];
+        let mut is_new = false;
+        for &validator_index in &attestation.validator_indices {
+            let validator_attestations = self.attestation_index.range(
+                (validator_index, Epoch::new(0), Epoch::new(0))
+                    ..=(validator_index, Epoch::max_value(), Epoch::max_value()),
+            );
+            for (_, id) in validator_attestations {
+                if !conflicting_ids.contains(id)
+                    && is_slashable(&self.attestations[id], &attestation, spec)
+                {
+                    conflicting_ids.push(*id);
+                }
+            }
+            is_new |= !self.attestation_index.contains_key(&(
+                validator_index,
+                source_epoch,
+                target_epoch,
+            ));
+        }
+
+        let slashings = conflicting_ids
+            .into_iter()
+            .map(|id| {
+                let other = self.attestations[&id].clone();
+                // The surrounding attestation must be the first in a slashing.
+                if attestation.is_surround_vote(&other, spec) {
+                    AttesterSlashing {
+                        slashable_attestation_1: attestation.clone(),
+                        slashable_attestation_2: other,
+                    }
+                } else {
+                    AttesterSlashing {
+                        slashable_attestation_1: other,
+                        slashable_attestation_2: attestation.clone(),
+                    }
+                }
+            })
+            .collect();
+
+        if is_new {
+            let id = self.next_attestation_id;
+            self.next_attestation_id += 1;
+            for &validator_index in &attestation.validator_indices {
+                self.attestation_index
+                    .entry((validator_index, source_epoch, target_epoch))
+                    .or_insert(id);
+            }
+            self.attestations.insert(id, attestation);
+        }
+
+        slashings
+    }
+
+    /// Remove the proposals and attestations prior to `finalized_epoch`.
+    ///
+    /// Conflicting blocks and attestations prior to the finalized epoch are no longer accepted,
+    /// however surround votes of pruned attestations are no longer detected.
+    pub fn prune(&mut self, finalized_epoch: Epoch, spec: &ChainSpec) {
+        let finalized_slot = finalized_epoch.start_slot(spec.epoch_length);
+        self.proposals
+            .retain(|(_, slot), _| *slot >= finalized_slot);
+
+        self.attestation_index
+            .retain(|(_, _, target_epoch), _| *target_epoch >= finalized_epoch);
+        let attestation_index = &self.attestation_index;
+        self.attestations
+            .retain(|id, _| attestation_index.values().any(|other| other == id));
+    }
+
+    /// Returns the number of stored proposals and attestations.
+    pub fn len(&self) -> usize {
+        self.proposals.len() + self.attestations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Returns `true` if `a` and `b` are distinct attestations which are a double vote, or where
+/// either surrounds the other.
+fn is_slashable(a: &SlashableAttestation, b: &SlashableAttestation, spec: &ChainSpec) -> bool {
+    a.data != b.data
+        && (a.is_double_vote(b, spec) || a.is_surround_vote(b, spec) || b.is_surround_vote(a, spec))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::test_utils::{SeedableRng, TestRandom, XorShiftRng};
+    use types::Hash256;
+
+    fn free_attestation(
+        rng: &mut XorShiftRng,
+        validator_index: u64,
+        source_epoch: u64,
+        target_epoch: u64,
+        spec: &ChainSpec,
+    ) -> FreeAttestation {
+        let mut free_attestation = FreeAttestation {
+            data: TestRandom::random_for_test(rng),
+            signature: TestRandom::random_for_test(rng),
+            validator_index,
+        };
+        free_attestation.data.justified_epoch = Epoch::new(source_epoch);
+        free_attestation.data.slot = Epoch::new(target_epoch).start_slot(spec.epoch_length);
+        free_attestation
+    }
+
+    #[test]
+    fn test_double_proposal() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let spec = ChainSpec::few_validators();
+        let mut slasher = Slasher::new();
+
+        let block = BeaconBlock::random_for_test(&mut rng);
+        let mut other_block = block.clone();
+        other_block.state_root = Hash256::from(&[42; 32][..]);
+        other_block.signature = Signature::random_for_test(&mut rng);
+
+        assert_eq!(slasher.observe_block(&block, 3, &spec), None);
+        assert_eq!(slasher.observe_block(&block, 3, &spec), None);
+        assert_eq!(slasher.observe_block(&other_block, 4, &spec), None);
+
+        let slashing = slasher.observe_block(&other_block, 3, &spec).unwrap();
+        assert_eq!(slashing.proposer_index, 3);
+        assert_eq!(slashing.proposal_data_1, block.proposal_signed_data(&spec));
+        assert_eq!(slashing.proposal_signature_1, block.signature);
+        assert_eq!(
+            slashing.proposal_data_2,
+            other_block.proposal_signed_data(&spec)
+        );
+        assert_eq!(slashing.proposal_signature_2, other_block.signature);
+    }
+
+    #[test]
+    fn test_double_vote() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let spec = ChainSpec::few_validators();
+        let mut slasher = Slasher::new();
+
+        let first = free_attestation(&mut rng, 1, 2, 3, &spec);
+        let second = free_attestation(&mut rng, 1, 1, 3, &spec);
+
+        assert!(slasher.observe_free_attestation(&first, &spec).is_empty());
+        assert!(slasher.observe_free_attestation(&first, &spec).is_empty());
+
+        let slashings = slasher.observe_free_attestation(&second, &spec);
+        assert_eq!(slashings.len(), 1);
+        assert_eq!(slashings[0].slashable_attestation_1.data, first.data);
+        assert_eq!(slashings[0].slashable_attestation_2.data, second.data);
+        assert_eq!(
+            slashings[0].slashable_attestation_2.validator_indices,
+            vec![1]
+        );
+    }
+
+    #[test]
+    fn test_surround_vote() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let spec = ChainSpec::few_validators();
+        let mut slasher = Slasher::new();
+
+        let surrounded = free_attestation(&mut rng, 1, 2, 3, &spec);
+        let surrounding = free_attestation(&mut rng, 1, 1, 4, &spec);
+        let other_validator = free_attestation(&mut rng, 2, 0, 5, &spec);
+        let later = free_attestation(&mut rng, 1, 3, 5, &spec);
+
+        assert!(slasher
+            .observe_free_attestation(&surrounded, &spec)
+            .is_empty());
+        assert!(slasher
+            .observe_free_attestation(&other_validator, &spec)
+            .is_empty());
+        assert!(slasher.observe_free_attestation(&later, &spec).is_empty());
+
+        // The surrounding attestation is the first in the slashing, regardless of the order the
+        // attestations were observed in.
+        let slashings = slasher.observe_free_attestation(&surrounding, &spec);
+        assert_eq!(slashings.len(), 1);
+        assert_eq!(slashings[0].slashable_attestation_1.data, surrounding.data);
+        assert_eq!(slashings[0].slashable_attestation_2.data, surrounded.data);
+
+        let surrounded_again = free_attestation(&mut rng, 1, 2, 3, &spec);
+        let slashings = slasher.observe_free_attestation(&surrounded_again, &spec);
+        assert_eq!(slashings.len(), 2);
+        assert!(slashings
+            .iter()
+            .any(|slashing| slashing.slashable_attestation_1.data == surrounding.data));
+    }
+
+    #[test]
+    fn test_prune() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let spec = ChainSpec::few_validators();
+        let mut slasher = Slasher::new();
+
+        let mut block = BeaconBlock::random_for_test(&mut rng);
+        block.slot = Epoch::new(1).start_slot(spec.epoch_length);
+        slasher.observe_block(&block, 1, &spec);
+        for target_epoch in 1..4 {
+            let attestation = free_attestation(&mut rng, 1, 0, target_epoch, &spec);
+            slasher.observe_free_attestation(&attestation, &spec);
+        }
+        assert_eq!(slasher.len(), 4);
+
+        slasher.prune(Epoch::new(2), &spec);
+        assert_eq!(slasher.len(), 2);
+
+        // Conflicts with pruned proposals are no longer detected.
+        block.state_root = Hash256::from(&[42; 32][..]);
+        assert_eq!(slasher.observe_block(&block, 1, &spec), None);
+    }
+}
@@ -3,7 +3,7 @@ use beacon_chain::{BeaconChain, BlockProcessingOutcome};
 pub use beacon_chain::{CheckPoint, Error as BeaconChainError};
 use bls::create_proof_of_possession;
 use db::{
-    stores::{BeaconBlockStore, BeaconStateStore},
+    stores::{BeaconBlockStore, BeaconStateStore, ChainStore},
     MemoryDB,
 };
 use fork_choice::BitwiseLMDGhost;
@@ -20,6 +20,8 @@ use types::{
     Keypair, Slot,
 };
 
+pub type TestingBeaconChain = BeaconChain<MemoryDB, TestingSlotClock, BitwiseLMDGhost<MemoryDB>>;
+
 /// The beacon chain harness simulates a single beacon node with `validator_count` validators connected
 /// to it. Each validator is provided a borrow to the beacon chain, where it may read
 /// information and submit blocks/attestations for processing.
@@ -28,9 +30,10 @@ use types::{
 /// is not useful for testing that multiple beacon nodes can reach consensus.
 pub struct BeaconChainHarness {
     pub db: Arc<MemoryDB>,
-    pub beacon_chain: Arc<BeaconChain<MemoryDB, TestingSlotClock, BitwiseLMDGhost<MemoryDB>>>,
+    pub beacon_chain: Arc<TestingBeaconChain>,
     pub block_store: Arc<BeaconBlockStore<MemoryDB>>,
     pub state_store: Arc<BeaconStateStore<MemoryDB>>,
+    pub chain_store: Arc<ChainStore<MemoryDB>>,
     pub validators: Vec<ValidatorHarness>,
     pub spec: Arc<ChainSpec>,
 }
@@ -41,16 +44,27 @@ impl BeaconChainHarness {
     /// - A keypair, `BlockProducer` and `Attester` for each validator.
     /// - A new BeaconChain struct where the given validators are in the genesis.
     pub fn new(spec: ChainSpec, validator_count: usize) -> Self {
+        let latest_eth1_data = Eth1Data {
+            deposit_root: Hash256::zero(),
+            block_hash: Hash256::zero(),
+        };
+        Self::with_latest_eth1_data(spec, validator_count, latest_eth1_data)
+    }
+
+    /// As `new`, but with the given `latest_eth1_data` in the genesis state, e.g., so deposits
+    /// may be proven against its `deposit_root`.
+    pub fn with_latest_eth1_data(
+        spec: ChainSpec,
+        validator_count: usize,
+        latest_eth1_data: Eth1Data,
+    ) -> Self {
         let db = Arc::new(MemoryDB::open());
         let block_store = Arc::new(BeaconBlockStore::new(db.clone()));
         let state_store = Arc::new(BeaconStateStore::new(db.clone()));
+        let chain_store = Arc::new(ChainStore::new(db.clone()));
         let genesis_time = 1_549_935_547; // 12th Feb 2018 (arbitrary value in the past).
         let slot_clock = TestingSlotClock::new(spec.genesis_slot.as_u64());
         let fork_choice = BitwiseLMDGhost::new(block_store.clone(), state_store.clone());
-        let latest_eth1_data = Eth1Data {
-            deposit_root: Hash256::zero(),
-            block_hash: Hash256::zero(),
-        };
 
         debug!("Generating validator keypairs...");
 
@@ -65,7 +79,7 @@ impl BeaconChainHarness {
         let initial_validator_deposits = keypairs
             .par_iter()
             .map(|keypair| Deposit {
-                branch: vec![], // branch verification is not specified.
+                branch: vec![], // genesis deposits are not proven.
                 index: 0,       // index verification is not specified.
                 deposit_data: DepositData {
                     amount: 32_000_000_000, // 32 ETH (in Gwei)
@@ -86,6 +100,7 @@ impl BeaconChainHarness {
             BeaconChain::genesis(
                 state_store.clone(),
                 block_store.clone(),
+                chain_store.clone(),
                 slot_clock,
                 genesis_time,
                 latest_eth1_data,
@@ -115,6 +130,7 @@ impl BeaconChainHarness {
             beacon_chain,
             block_store,
             state_store,
+            chain_store,
             validators,
             spec,
         }
@@ -249,6 +265,39 @@ impl BeaconChainHarness {
         self.beacon_chain.fork_choice().unwrap()
     }
 
+    /// Create a chain from the genesis state of the harness with its own database, as though it
+    /// were another node. Its slot clock is at the genesis slot.
+    pub fn chain_from_genesis(&self) -> Arc<TestingBeaconChain> {
+        let genesis_block_root = self.beacon_chain.genesis_block_root().unwrap();
+        let genesis_block = self
+            .block_store
+            .get_deserialized(&genesis_block_root)
+            .unwrap()
+            .unwrap();
+        let genesis_state = self
+            .state_store
+            .get_deserialized(&genesis_block.state_root)
+            .unwrap()
+            .unwrap();
+
+        let db = Arc::new(MemoryDB::open());
+        let block_store = Arc::new(BeaconBlockStore::new(db.clone()));
+        let state_store = Arc::new(BeaconStateStore::new(db.clone()));
+        let chain_store = Arc::new(ChainStore::new(db.clone()));
+        let fork_choice = BitwiseLMDGhost::new(block_store.clone(), state_store.clone());
+        let chain = BeaconChain::from_genesis_state(
+            state_store,
+            block_store,
+            chain_store,
+            TestingSlotClock::new(self.spec.genesis_slot.as_u64()),
+            genesis_state,
+            (*self.spec).clone(),
+            fork_choice,
+        )
+        .unwrap();
+        Arc::new(chain)
+    }
+
     /// Dump all blocks and states from the canonical beacon chain.
     pub fn chain_dump(&self) -> Result<Vec<CheckPoint>, BeaconChainError> {
         self.beacon_chain.chain_dump()
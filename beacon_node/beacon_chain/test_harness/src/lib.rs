@@ -1,5 +1,5 @@
 mod beacon_chain_harness;
 mod validator_harness;
 
-pub use self::beacon_chain_harness::BeaconChainHarness;
+pub use self::beacon_chain_harness::{BeaconChainHarness, TestingBeaconChain};
 pub use self::validator_harness::ValidatorHarness;
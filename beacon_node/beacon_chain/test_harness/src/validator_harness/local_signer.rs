@@ -1,19 +1,24 @@
 use attester::Signer as AttesterSigner;
 use block_proposer::Signer as BlockProposerSigner;
-use std::sync::RwLock;
-use types::{Keypair, Signature};
+use int_to_bytes::int_to_bytes32;
+use std::sync::{Arc, RwLock};
+use types::{AttestationData, BeaconBlock, ChainSpec, Epoch, Keypair, Signature};
+
+const PHASE_0_CUSTODY_BIT: bool = false;
 
 /// A test-only struct used to perform signing for a proposer or attester.
 pub struct LocalSigner {
     keypair: Keypair,
+    spec: Arc<ChainSpec>,
     should_sign: RwLock<bool>,
 }
 
 impl LocalSigner {
     /// Produce a new TestSigner with signing enabled by default.
-    pub fn new(keypair: Keypair) -> Self {
+    pub fn new(keypair: Keypair, spec: Arc<ChainSpec>) -> Self {
         Self {
             keypair,
+            spec,
             should_sign: RwLock::new(true),
         }
     }
@@ -31,17 +36,23 @@ impl LocalSigner {
 }
 
 impl BlockProposerSigner for LocalSigner {
-    fn sign_block_proposal(&self, message: &[u8], domain: u64) -> Option<Signature> {
-        self.bls_sign(message, domain)
+    fn sign_block_proposal(&self, block: &BeaconBlock) -> Option<Signature> {
+        self.bls_sign(
+            &block.proposal_root(&self.spec)[..],
+            self.spec.domain_proposal,
+        )
     }
 
-    fn sign_randao_reveal(&self, message: &[u8], domain: u64) -> Option<Signature> {
-        self.bls_sign(message, domain)
+    fn sign_randao_reveal(&self, epoch: Epoch) -> Option<Signature> {
+        self.bls_sign(&int_to_bytes32(epoch.as_u64()), self.spec.domain_randao)
     }
 }
 
 impl AttesterSigner for LocalSigner {
-    fn sign_attestation_message(&self, message: &[u8], domain: u64) -> Option<Signature> {
-        self.bls_sign(message, domain)
+    fn sign_attestation(&self, attestation_data: &AttestationData) -> Option<Signature> {
+        self.bls_sign(
+            &attestation_data.signable_message(PHASE_0_CUSTODY_BIT),
+            self.spec.domain_attestation,
+        )
     }
 }
@@ -7,7 +7,7 @@ use attester::{Attester, Error as AttestationPollError};
 use beacon_chain::BeaconChain;
 use block_proposer::PollOutcome as BlockPollOutcome;
 use block_proposer::{BlockProducer, Error as BlockPollError};
-use db::MemoryDB;
+use db::{stores::SlashingProtectionStore, MemoryDB};
 use direct_beacon_node::DirectBeaconNode;
 use direct_duties::DirectDuties;
 use fork_choice::BitwiseLMDGhost;
@@ -39,12 +39,14 @@ pub struct ValidatorHarness {
         DirectBeaconNode<MemoryDB, TestingSlotClock, BitwiseLMDGhost<MemoryDB>>,
         DirectDuties<MemoryDB, TestingSlotClock, BitwiseLMDGhost<MemoryDB>>,
         LocalSigner,
+        SlashingProtectionStore<MemoryDB>,
     >,
     pub attester: Attester<
         TestingSlotClock,
         DirectBeaconNode<MemoryDB, TestingSlotClock, BitwiseLMDGhost<MemoryDB>>,
         DirectDuties<MemoryDB, TestingSlotClock, BitwiseLMDGhost<MemoryDB>>,
         LocalSigner,
+        SlashingProtectionStore<MemoryDB>,
     >,
     pub spec: Arc<ChainSpec>,
     pub epoch_map: Arc<DirectDuties<MemoryDB, TestingSlotClock, BitwiseLMDGhost<MemoryDB>>>,
@@ -65,9 +67,13 @@ impl ValidatorHarness {
         spec: Arc<ChainSpec>,
     ) -> Self {
         let slot_clock = Arc::new(TestingSlotClock::new(spec.genesis_slot.as_u64()));
-        let signer = Arc::new(LocalSigner::new(keypair.clone()));
+        let signer = Arc::new(LocalSigner::new(keypair.clone(), spec.clone()));
         let beacon_node = Arc::new(DirectBeaconNode::new(beacon_chain.clone()));
         let epoch_map = Arc::new(DirectDuties::new(keypair.pk.clone(), beacon_chain.clone()));
+        let slashing_protection = Arc::new(SlashingProtectionStore::new(
+            Arc::new(MemoryDB::open()),
+            &keypair.pk,
+        ));
 
         let block_producer = BlockProducer::new(
             spec.clone(),
@@ -75,13 +81,16 @@ impl ValidatorHarness {
             slot_clock.clone(),
             beacon_node.clone(),
             signer.clone(),
+            slashing_protection.clone(),
         );
 
         let attester = Attester::new(
+            spec.clone(),
             epoch_map.clone(),
             slot_clock.clone(),
             beacon_node.clone(),
             signer.clone(),
+            slashing_protection.clone(),
         );
 
         Self {
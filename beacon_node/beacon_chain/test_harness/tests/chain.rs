@@ -1,7 +1,14 @@
+use beacon_chain::{
+    AggregationMessage, BeaconChain, BlockProcessingOutcome, InvalidBlock, Reorg, ValidBlock,
+    MAX_FUTURE_BLOCKS_PER_SLOT,
+};
 use env_logger::{Builder, Env};
+use fork_choice::BitwiseLMDGhost;
 use log::debug;
+use slot_clock::TestingSlotClock;
+use state_processing::BlockProcessingError;
 use test_harness::BeaconChainHarness;
-use types::ChainSpec;
+use types::{BeaconBlock, ChainSpec, FreeAttestation, Hash256, Signature, Slot};
 
 #[test]
 fn it_can_build_on_genesis_block() {
@@ -44,3 +51,804 @@ fn it_can_produce_past_first_epoch_boundary() {
 
     harness.dump_to_file("/tmp/chaindump.json".to_string(), &dump);
 }
+
+#[test]
+fn it_can_resume_from_store() {
+    let spec = ChainSpec::few_validators();
+    let validator_count = 8;
+
+    let mut harness = BeaconChainHarness::new(spec.clone(), validator_count);
+
+    harness.advance_chain_with_block();
+    harness.advance_chain_with_block();
+
+    let resumed = BeaconChain::from_store(
+        harness.state_store.clone(),
+        harness.block_store.clone(),
+        harness.chain_store.clone(),
+        TestingSlotClock::new(harness.beacon_chain.present_slot().as_u64()),
+        spec,
+        BitwiseLMDGhost::new(harness.block_store.clone(), harness.state_store.clone()),
+    )
+    .unwrap()
+    .expect("A chain should have been stored");
+
+    let head = harness.beacon_chain.head();
+    assert_eq!(resumed.head().beacon_block_root, head.beacon_block_root);
+    assert_eq!(resumed.head().beacon_state_root, head.beacon_state_root);
+    assert_eq!(
+        resumed.finalized_head().beacon_block_root,
+        harness.beacon_chain.finalized_head().beacon_block_root
+    );
+    assert_eq!(resumed.present_slot(), head.beacon_state.slot);
+
+    resumed.fork_choice().unwrap();
+    assert_eq!(resumed.head().beacon_block_root, head.beacon_block_root);
+}
+
+#[test]
+fn it_reorgs_to_the_block_with_the_most_votes() {
+    let spec = ChainSpec::few_validators();
+    let validator_count = 8;
+
+    let mut harness = BeaconChainHarness::new(spec, validator_count);
+
+    harness.advance_chain_with_block();
+    let parent_root = harness.beacon_chain.head().beacon_block_root;
+
+    // Produce two competing children of the head, importing the one from the earlier slot last.
+    harness.increment_beacon_chain_slot();
+    let early_block = harness.produce_block();
+    harness.increment_beacon_chain_slot();
+    let late_block = harness.produce_block();
+    assert_eq!(early_block.parent_root, parent_root);
+    assert_eq!(late_block.parent_root, parent_root);
+
+    let processed = Ok(BlockProcessingOutcome::ValidBlock(ValidBlock::Processed));
+    assert_eq!(
+        harness.beacon_chain.process_block(late_block.clone()),
+        processed
+    );
+    assert_eq!(
+        harness.beacon_chain.process_block(early_block.clone()),
+        processed
+    );
+
+    // Neither block has any votes, so the tie is broken on the block root.
+    let (head, other) = if early_block.canonical_root() > late_block.canonical_root() {
+        (early_block, late_block)
+    } else {
+        (late_block, early_block)
+    };
+    assert_eq!(
+        harness.beacon_chain.head().beacon_block_root,
+        head.canonical_root()
+    );
+
+    // Vote for the other block, which should then become the head.
+    let reorgs = harness.beacon_chain.subscribe_reorgs();
+    let present_slot = harness.beacon_chain.present_slot();
+    let free_attestations = harness.gather_free_attesations();
+    assert!(!free_attestations.is_empty());
+    for mut free_attestation in free_attestations {
+        free_attestation.data.beacon_block_root = other.canonical_root();
+        sign_free_attestation(&harness, &mut free_attestation);
+        let outcome = harness
+            .beacon_chain
+            .process_free_attestation(free_attestation)
+            .unwrap();
+        assert!(outcome.valid);
+    }
+
+    assert_eq!(
+        harness.beacon_chain.head().beacon_block_root,
+        other.canonical_root()
+    );
+    assert_eq!(harness.beacon_chain.present_slot(), present_slot);
+    assert_eq!(
+        reorgs.try_recv(),
+        Ok(Reorg {
+            old_head_root: head.canonical_root(),
+            old_head_slot: head.slot,
+            new_head_root: other.canonical_root(),
+            new_head_slot: other.slot,
+        })
+    );
+    assert!(reorgs.try_recv().is_err());
+
+    // The state was rebuilt on the new head, so the next block builds upon it, which is not a
+    // re-org.
+    harness.advance_chain_with_block();
+    assert_eq!(
+        harness.beacon_chain.head().beacon_block.parent_root,
+        other.canonical_root()
+    );
+    assert!(reorgs.try_recv().is_err());
+}
+
+#[test]
+fn it_tracks_justification() {
+    let spec = ChainSpec::few_validators();
+    let validator_count = 8;
+
+    let mut harness = BeaconChainHarness::new(spec, validator_count);
+
+    // With every validator attesting, the epoch after genesis is justified by the end of the
+    // third epoch.
+    let blocks = harness.spec.epoch_length * 3 + 1;
+    for _ in 0..blocks {
+        harness.advance_chain_with_block();
+    }
+
+    let head_state = harness.beacon_chain.head().beacon_state.clone();
+    assert!(head_state.justified_epoch > harness.spec.genesis_epoch);
+
+    let justified_head = harness.beacon_chain.justified_head().clone();
+    assert_eq!(
+        justified_head.beacon_block.slot,
+        head_state
+            .justified_epoch
+            .start_slot(harness.spec.epoch_length)
+    );
+    assert_eq!(
+        harness.chain_store.get_justified_head().unwrap(),
+        Some(justified_head.beacon_block_root)
+    );
+    assert_eq!(
+        harness.beacon_chain.finalized_head().beacon_block.slot,
+        harness.spec.genesis_slot
+    );
+}
+
+/// A spec with four slot epochs and attestations includable in the next slot, so blocks are
+/// finalized within a few epochs of short tests.
+fn short_epoch_spec() -> ChainSpec {
+    let spec = ChainSpec::few_validators();
+    let epoch_length = 4;
+    ChainSpec {
+        epoch_length,
+        genesis_epoch: spec.genesis_slot.epoch(epoch_length),
+        min_attestation_inclusion_delay: 1,
+        ..spec
+    }
+}
+
+#[test]
+fn it_tracks_justification_and_finality_with_short_epochs() {
+    let spec = short_epoch_spec();
+    let validator_count = 8;
+
+    let mut harness = BeaconChainHarness::new(spec, validator_count);
+    let genesis_root = harness.beacon_chain.finalized_head().beacon_block_root;
+
+    let blocks = harness.spec.epoch_length * 6 + 1;
+    for _ in 0..blocks {
+        harness.advance_chain_with_block();
+    }
+
+    let head_state = harness.beacon_chain.head().beacon_state.clone();
+    assert!(head_state.finalized_epoch > harness.spec.genesis_epoch);
+
+    let epoch_start_slot = |epoch: types::Epoch| epoch.start_slot(harness.spec.epoch_length);
+    let justified_head = harness.beacon_chain.justified_head().clone();
+    let finalized_head = harness.beacon_chain.finalized_head().clone();
+    assert_eq!(
+        justified_head.beacon_block.slot,
+        epoch_start_slot(head_state.justified_epoch)
+    );
+    assert_eq!(
+        finalized_head.beacon_block.slot,
+        epoch_start_slot(head_state.finalized_epoch)
+    );
+    assert_ne!(finalized_head.beacon_block_root, genesis_root);
+
+    // The checkpoints are persisted.
+    assert_eq!(
+        harness.chain_store.get_justified_head().unwrap(),
+        Some(justified_head.beacon_block_root)
+    );
+    assert_eq!(
+        harness.chain_store.get_finalized_head().unwrap(),
+        Some(finalized_head.beacon_block_root)
+    );
+}
+
+#[test]
+#[ignore]
+fn it_tracks_justification_and_finality() {
+    let spec = ChainSpec::few_validators();
+    let validator_count = 8;
+
+    let mut harness = BeaconChainHarness::new(spec, validator_count);
+    let genesis_root = harness.beacon_chain.finalized_head().beacon_block_root;
+
+    let blocks = harness.spec.epoch_length * 6 + 1;
+    for _ in 0..blocks {
+        harness.advance_chain_with_block();
+    }
+
+    let head_state = harness.beacon_chain.head().beacon_state.clone();
+    assert!(head_state.finalized_epoch > harness.spec.genesis_epoch);
+
+    let epoch_start_slot = |epoch: types::Epoch| epoch.start_slot(harness.spec.epoch_length);
+    let justified_head = harness.beacon_chain.justified_head().clone();
+    let finalized_head = harness.beacon_chain.finalized_head().clone();
+    assert_eq!(
+        justified_head.beacon_block.slot,
+        epoch_start_slot(head_state.justified_epoch)
+    );
+    assert_eq!(
+        finalized_head.beacon_block.slot,
+        epoch_start_slot(head_state.finalized_epoch)
+    );
+    assert_ne!(finalized_head.beacon_block_root, genesis_root);
+
+    // The checkpoints are persisted.
+    assert_eq!(
+        harness.chain_store.get_justified_head().unwrap(),
+        Some(justified_head.beacon_block_root)
+    );
+    assert_eq!(
+        harness.chain_store.get_finalized_head().unwrap(),
+        Some(finalized_head.beacon_block_root)
+    );
+}
+
+#[test]
+fn it_prunes_after_finalization_with_short_epochs() {
+    let spec = short_epoch_spec();
+    let validator_count = 8;
+    // The interval of slots in which one state is kept after finalization.
+    let snapshot_interval = 256;
+
+    let mut harness = BeaconChainHarness::new(spec, validator_count);
+    let genesis = harness.beacon_chain.finalized_head().clone();
+
+    // Produce two competing children of the genesis block, one of which is abandoned.
+    harness.increment_beacon_chain_slot();
+    let first_block = harness.produce_block();
+    harness.increment_beacon_chain_slot();
+    let second_block = harness.produce_block();
+    for block in &[first_block.clone(), second_block.clone()] {
+        assert_eq!(
+            harness.beacon_chain.process_block(block.clone()),
+            Ok(BlockProcessingOutcome::ValidBlock(ValidBlock::Processed))
+        );
+    }
+    let (canonical, abandoned) =
+        if harness.beacon_chain.head().beacon_block_root == first_block.canonical_root() {
+            (first_block, second_block)
+        } else {
+            (second_block, first_block)
+        };
+
+    // The last block before the end of the snapshot interval is followed by skipped slots.
+    harness.advance_chain_with_block();
+    let snapshot = harness.beacon_chain.head().clone();
+    let interval = |slot: Slot| slot.as_u64() / snapshot_interval;
+    while interval(harness.beacon_chain.present_slot() + 1) == interval(snapshot.beacon_block.slot)
+    {
+        harness.increment_beacon_chain_slot();
+    }
+
+    let blocks = harness.spec.epoch_length * 6 + 1;
+    for _ in 0..blocks {
+        harness.advance_chain_with_block();
+    }
+
+    let finalized_head = harness.beacon_chain.finalized_head().clone();
+    assert!(finalized_head.beacon_block.slot > snapshot.beacon_block.slot);
+
+    // The abandoned fork is deleted.
+    assert!(!harness
+        .block_store
+        .exists(&abandoned.canonical_root())
+        .unwrap());
+    assert!(!harness.state_store.exists(&abandoned.state_root).unwrap());
+    assert_eq!(
+        harness.beacon_chain.process_block(abandoned),
+        Ok(BlockProcessingOutcome::InvalidBlock(
+            InvalidBlock::ConflictsWithFinalized
+        ))
+    );
+
+    // Canonical blocks are kept, but only the states of genesis, the last block of each snapshot
+    // interval and the finalized block onwards.
+    assert!(harness
+        .block_store
+        .exists(&canonical.canonical_root())
+        .unwrap());
+    assert!(!harness.state_store.exists(&canonical.state_root).unwrap());
+    assert!(harness
+        .state_store
+        .exists(&snapshot.beacon_state_root)
+        .unwrap());
+    assert!(harness
+        .state_store
+        .exists(&genesis.beacon_state_root)
+        .unwrap());
+    assert!(harness
+        .state_store
+        .exists(&finalized_head.beacon_state_root)
+        .unwrap());
+}
+
+#[test]
+#[ignore]
+fn it_prunes_after_finalization() {
+    let spec = ChainSpec::few_validators();
+    let validator_count = 8;
+
+    let mut harness = BeaconChainHarness::new(spec.clone(), validator_count);
+    let genesis = harness.beacon_chain.finalized_head().clone();
+
+    // Produce two competing children of the genesis block, one of which is abandoned.
+    harness.increment_beacon_chain_slot();
+    let first_block = harness.produce_block();
+    harness.increment_beacon_chain_slot();
+    let second_block = harness.produce_block();
+    for block in &[first_block.clone(), second_block.clone()] {
+        assert_eq!(
+            harness.beacon_chain.process_block(block.clone()),
+            Ok(BlockProcessingOutcome::ValidBlock(ValidBlock::Processed))
+        );
+    }
+    let abandoned = if harness.beacon_chain.head().beacon_block_root == first_block.canonical_root()
+    {
+        second_block
+    } else {
+        first_block
+    };
+
+    let blocks = harness.spec.epoch_length * 6 + 1;
+    for _ in 0..blocks {
+        harness.advance_chain_with_block();
+    }
+
+    let finalized_head = harness.beacon_chain.finalized_head().clone();
+    assert_ne!(finalized_head.beacon_block_root, genesis.beacon_block_root);
+
+    // The abandoned fork is deleted.
+    assert!(!harness
+        .block_store
+        .exists(&abandoned.canonical_root())
+        .unwrap());
+    assert!(!harness.state_store.exists(&abandoned.state_root).unwrap());
+    assert_eq!(
+        harness.beacon_chain.process_block(abandoned),
+        Ok(BlockProcessingOutcome::InvalidBlock(
+            InvalidBlock::ConflictsWithFinalized
+        ))
+    );
+
+    // Canonical blocks are kept, but only the states of genesis and the finalized block onwards.
+    let parent_root = finalized_head.beacon_block.parent_root;
+    let parent = harness
+        .block_store
+        .get_deserialized(&parent_root)
+        .unwrap()
+        .expect("Canonical blocks should be kept");
+    assert!(!harness.state_store.exists(&parent.state_root).unwrap());
+    assert!(harness
+        .state_store
+        .exists(&genesis.beacon_state_root)
+        .unwrap());
+    assert!(harness
+        .state_store
+        .exists(&finalized_head.beacon_state_root)
+        .unwrap());
+
+    // The pruned chain can still be extended and resumed.
+    harness.advance_chain_with_block();
+    let resumed = BeaconChain::from_store(
+        harness.state_store.clone(),
+        harness.block_store.clone(),
+        harness.chain_store.clone(),
+        TestingSlotClock::new(harness.beacon_chain.present_slot().as_u64()),
+        spec,
+        BitwiseLMDGhost::new(harness.block_store.clone(), harness.state_store.clone()),
+    )
+    .unwrap()
+    .expect("A chain should have been stored");
+    resumed.fork_choice().unwrap();
+    assert_eq!(
+        resumed.head().beacon_block_root,
+        harness.beacon_chain.head().beacon_block_root
+    );
+}
+
+#[test]
+fn it_validates_attestations_from_the_previous_epoch() {
+    let spec = ChainSpec::few_validators();
+    let validator_count = 8;
+
+    let mut harness = BeaconChainHarness::new(spec, validator_count);
+
+    // Produce a block in the last slot of an epoch.
+    let epoch_length = harness.spec.epoch_length;
+    while (harness.beacon_chain.present_slot() + 2) % epoch_length != 0 {
+        harness.advance_chain_with_block();
+    }
+    harness.increment_beacon_chain_slot();
+    let block = harness.produce_block();
+    assert_eq!(
+        harness.beacon_chain.process_block(block),
+        Ok(BlockProcessingOutcome::ValidBlock(ValidBlock::Processed))
+    );
+    let free_attestations = harness.gather_free_attesations();
+    assert!(!free_attestations.is_empty());
+
+    // The attestations are only processed once the next epoch has begun.
+    let attestation_epoch = harness.beacon_chain.present_slot().epoch(epoch_length);
+    harness.increment_beacon_chain_slot();
+    assert_eq!(
+        harness
+            .beacon_chain
+            .state
+            .read()
+            .current_epoch(&harness.spec),
+        attestation_epoch + 1
+    );
+    for free_attestation in free_attestations {
+        let outcome = harness
+            .beacon_chain
+            .process_free_attestation(free_attestation)
+            .unwrap();
+        assert!(outcome.valid, "{:?}", outcome);
+    }
+
+    // The attestations are included in the next block.
+    harness.advance_chain_with_block();
+    assert!(!harness
+        .beacon_chain
+        .head()
+        .beacon_block
+        .body
+        .attestations
+        .is_empty());
+}
+
+#[test]
+fn it_validates_attestations_against_the_attested_block() {
+    let spec = ChainSpec::few_validators();
+    let validator_count = 8;
+
+    let mut harness = BeaconChainHarness::new(spec, validator_count);
+
+    harness.advance_chain_with_block();
+
+    // Produce two competing children of the head.
+    harness.increment_beacon_chain_slot();
+    let first_block = harness.produce_block();
+    harness.increment_beacon_chain_slot();
+    let second_block = harness.produce_block();
+    for block in &[first_block.clone(), second_block.clone()] {
+        assert_eq!(
+            harness.beacon_chain.process_block(block.clone()),
+            Ok(BlockProcessingOutcome::ValidBlock(ValidBlock::Processed))
+        );
+    }
+    let fork = if harness.beacon_chain.head().beacon_block_root == first_block.canonical_root() {
+        second_block
+    } else {
+        first_block
+    };
+
+    let free_attestation = harness.gather_free_attesations().pop().unwrap();
+    let outcome = |mut free_attestation: FreeAttestation, block_root: Hash256| {
+        free_attestation.data.beacon_block_root = block_root;
+        sign_free_attestation(&harness, &mut free_attestation);
+        harness
+            .beacon_chain
+            .process_free_attestation(free_attestation)
+            .unwrap()
+    };
+
+    // An attestation to the competing fork is valid.
+    let fork_outcome = outcome(free_attestation.clone(), fork.canonical_root());
+    assert!(fork_outcome.valid, "{:?}", fork_outcome);
+
+    // Attestations to unknown blocks, or blocks after the attestation slot, are not.
+    let unknown_outcome = outcome(free_attestation.clone(), Hash256::from(&[42; 32][..]));
+    assert!(!unknown_outcome.valid);
+    assert_eq!(
+        unknown_outcome.message,
+        AggregationMessage::UnknownBeaconBlock
+    );
+
+    let mut early_attestation = free_attestation.clone();
+    early_attestation.data.slot = fork.slot - 1;
+    let early_outcome = outcome(early_attestation, fork.canonical_root());
+    assert!(!early_outcome.valid);
+    assert_eq!(early_outcome.message, AggregationMessage::BadSlot);
+
+    // An attestation to the competing fork with a bad signature is rejected.
+    let mut bad_attestation = free_attestation.clone();
+    bad_attestation.data.beacon_block_root = fork.canonical_root();
+    let bad_outcome = harness
+        .beacon_chain
+        .process_free_attestation(bad_attestation)
+        .unwrap();
+    assert!(!bad_outcome.valid);
+    assert_eq!(bad_outcome.message, AggregationMessage::BadSignature);
+}
+
+#[test]
+fn it_rejects_attestations_to_blocks_before_the_previous_epoch() {
+    let spec = ChainSpec::few_validators();
+    let validator_count = 8;
+
+    let mut harness = BeaconChainHarness::new(spec, validator_count);
+
+    harness.advance_chain_with_block();
+    let old_block = harness.beacon_chain.head().clone();
+    let epoch_length = harness.spec.epoch_length;
+    for _ in 0..epoch_length * 2 {
+        harness.advance_chain_with_block();
+    }
+    let previous_epoch = harness
+        .beacon_chain
+        .state
+        .read()
+        .previous_epoch(&harness.spec);
+    assert!(old_block.beacon_block.slot < previous_epoch.start_slot(epoch_length));
+
+    // The attestation is otherwise valid, but is not processed to avoid replaying many slots.
+    harness.increment_beacon_chain_slot();
+    let mut free_attestation = harness.gather_free_attesations().pop().unwrap();
+    free_attestation.data.beacon_block_root = old_block.beacon_block_root;
+    sign_free_attestation(&harness, &mut free_attestation);
+    let outcome = harness
+        .beacon_chain
+        .process_free_attestation(free_attestation)
+        .unwrap();
+    assert!(!outcome.valid);
+    assert_eq!(outcome.message, AggregationMessage::TooOld);
+}
+
+#[test]
+fn it_rejects_blocks_with_a_bad_proposer_signature() {
+    let spec = ChainSpec::few_validators();
+    let validator_count = 8;
+
+    let mut harness = BeaconChainHarness::new(spec, validator_count);
+    let bad_signature = Signature::new(&[42], 0, &harness.validators[0].keypair.sk);
+
+    harness.advance_chain_with_block();
+
+    // A block in the epoch of its parent, and a block after an epoch of skipped slots.
+    let skipped_slots = vec![0, harness.spec.epoch_length];
+    for skipped_slots in skipped_slots {
+        for _ in 0..=skipped_slots {
+            harness.increment_beacon_chain_slot();
+        }
+        let block = harness.produce_block();
+
+        let mut bad_block = block.clone();
+        bad_block.signature = bad_signature.clone();
+        assert_eq!(
+            harness.beacon_chain.process_block(bad_block),
+            Ok(BlockProcessingOutcome::InvalidBlock(
+                InvalidBlock::PerBlockProcessingError(BlockProcessingError::BadBlockSignature)
+            ))
+        );
+
+        assert_eq!(
+            harness.beacon_chain.process_block(block),
+            Ok(BlockProcessingOutcome::ValidBlock(ValidBlock::Processed))
+        );
+    }
+}
+
+#[test]
+fn it_reads_canonical_blocks_by_range() {
+    let spec = ChainSpec::few_validators();
+    let validator_count = 8;
+
+    let mut harness = BeaconChainHarness::new(spec, validator_count);
+    let genesis_block_root = harness.beacon_chain.head().beacon_block_root;
+
+    harness.advance_chain_with_block();
+    // Skip a slot.
+    harness.increment_beacon_chain_slot();
+    harness.advance_chain_with_block();
+    harness.advance_chain_with_block();
+
+    let chain = &harness.beacon_chain;
+    assert_eq!(chain.genesis_block_root(), Ok(genesis_block_root));
+
+    let genesis_slot = harness.spec.genesis_slot;
+    let slots = |blocks: Vec<BeaconBlock>| -> Vec<u64> {
+        blocks
+            .iter()
+            .map(|block| (block.slot - genesis_slot).as_u64())
+            .collect()
+    };
+    assert_eq!(
+        slots(chain.blocks_by_range(genesis_slot, 10).unwrap()),
+        vec![0, 1, 3, 4]
+    );
+    assert_eq!(
+        slots(chain.blocks_by_range(genesis_slot + 1, 3).unwrap()),
+        vec![1, 3]
+    );
+    assert!(chain
+        .blocks_by_range(genesis_slot + 5, 10)
+        .unwrap()
+        .is_empty());
+
+    let head = chain.head();
+    let blocks = chain.blocks_by_range(head.beacon_block.slot, 1).unwrap();
+    assert_eq!(blocks, vec![head.beacon_block.clone()]);
+}
+
+#[test]
+fn it_reads_blocks_by_range_older_than_the_latest_block_roots() {
+    let mut spec = ChainSpec::few_validators();
+    spec.latest_block_roots_length = 4;
+    let validator_count = 8;
+
+    let mut harness = BeaconChainHarness::new(spec, validator_count);
+    harness.advance_chain_with_block();
+    // Skip a slot.
+    harness.increment_beacon_chain_slot();
+    for _ in 0..6 {
+        harness.advance_chain_with_block();
+    }
+
+    let chain = &harness.beacon_chain;
+    let genesis_slot = harness.spec.genesis_slot;
+    let slots = |blocks: Vec<BeaconBlock>| -> Vec<u64> {
+        blocks
+            .iter()
+            .map(|block| (block.slot - genesis_slot).as_u64())
+            .collect()
+    };
+    assert_eq!(
+        slots(chain.blocks_by_range(genesis_slot, 10).unwrap()),
+        vec![0, 1, 3, 4, 5, 6, 7, 8]
+    );
+    assert_eq!(
+        slots(chain.blocks_by_range(genesis_slot + 1, 2).unwrap()),
+        vec![1]
+    );
+    assert_eq!(
+        slots(chain.blocks_by_range(genesis_slot + 2, 4).unwrap()),
+        vec![3, 4, 5]
+    );
+}
+
+#[test]
+fn it_processes_orphans_once_their_parent_is_imported() {
+    let spec = ChainSpec::few_validators();
+    let validator_count = 8;
+
+    let mut harness = BeaconChainHarness::new(spec.clone(), validator_count);
+    let mut blocks = vec![];
+    for _ in 0..3 {
+        harness.advance_chain_with_block();
+        blocks.push(harness.beacon_chain.head().beacon_block.clone());
+    }
+
+    // Another chain from the same genesis receives the blocks in reverse order.
+    let chain = harness.chain_from_genesis();
+    chain
+        .slot_clock
+        .set_slot(harness.beacon_chain.present_slot().as_u64());
+    chain.catch_up_with_slot_clock().unwrap();
+
+    let parent_unknown = Ok(BlockProcessingOutcome::InvalidBlock(
+        InvalidBlock::ParentUnknown,
+    ));
+    assert_eq!(chain.process_block(blocks[2].clone()), parent_unknown);
+    assert_eq!(chain.process_block(blocks[1].clone()), parent_unknown);
+    assert_eq!(chain.orphan_pool.read().len(), 2);
+    assert_eq!(
+        chain.missing_block_roots(),
+        vec![blocks[0].canonical_root()]
+    );
+
+    assert_eq!(
+        chain.process_block(blocks[0].clone()),
+        Ok(BlockProcessingOutcome::ValidBlock(ValidBlock::Processed))
+    );
+    assert_eq!(
+        chain.head().beacon_block_root,
+        harness.beacon_chain.head().beacon_block_root
+    );
+    assert!(chain.orphan_pool.read().is_empty());
+    assert!(chain.missing_block_roots().is_empty());
+}
+
+#[test]
+fn it_processes_future_blocks_once_their_slot_is_reached() {
+    let spec = ChainSpec::few_validators();
+    let validator_count = 8;
+
+    let mut harness = BeaconChainHarness::new(spec.clone(), validator_count);
+    let mut blocks = vec![];
+    for _ in 0..2 {
+        harness.advance_chain_with_block();
+        blocks.push(harness.beacon_chain.head().beacon_block.clone());
+    }
+
+    // Another chain from the same genesis, whose slot clock is behind.
+    let genesis_slot = spec.genesis_slot;
+    let chain = harness.chain_from_genesis();
+
+    // Only the block within the tolerance of a slot is queued.
+    let future_slot = Ok(BlockProcessingOutcome::InvalidBlock(
+        InvalidBlock::FutureSlot,
+    ));
+    assert_eq!(chain.process_block(blocks[1].clone()), future_slot);
+    assert_eq!(chain.process_block(blocks[0].clone()), future_slot);
+    assert_eq!(chain.future_blocks.read().len(), 1);
+
+    // The imported blocks are returned, so they may be gossiped.
+    chain.slot_clock.set_slot((genesis_slot + 1).as_u64());
+    assert_eq!(
+        chain.catch_up_with_slot_clock(),
+        Ok(vec![blocks[0].clone()])
+    );
+    assert_eq!(chain.head().beacon_block_root, blocks[0].canonical_root());
+    assert!(chain.future_blocks.read().is_empty());
+    assert_eq!(chain.catch_up_with_slot_clock(), Ok(vec![]));
+
+    assert_eq!(chain.process_block(blocks[1].clone()), future_slot);
+    chain.slot_clock.set_slot((genesis_slot + 2).as_u64());
+    assert_eq!(
+        chain.catch_up_with_slot_clock(),
+        Ok(vec![blocks[1].clone()])
+    );
+    assert_eq!(chain.head().beacon_block_root, blocks[1].canonical_root());
+}
+
+#[test]
+fn it_does_not_queue_unsigned_future_blocks() {
+    let spec = ChainSpec::few_validators();
+    let validator_count = 8;
+
+    let mut harness = BeaconChainHarness::new(spec.clone(), validator_count);
+    harness.advance_chain_with_block();
+    let block = harness.beacon_chain.head().beacon_block.clone();
+
+    // Another chain from the same genesis, whose slot clock is behind.
+    let chain = harness.chain_from_genesis();
+
+    // Blocks at the same slot which were not signed by its proposer are rejected, so they may
+    // not fill the queue before the proposer's block arrives.
+    for i in 0..=MAX_FUTURE_BLOCKS_PER_SLOT {
+        let mut junk = block.clone();
+        junk.state_root = Hash256::from(&[i as u8 + 1; 32][..]);
+        assert_eq!(
+            chain.process_block(junk),
+            Ok(BlockProcessingOutcome::InvalidBlock(
+                InvalidBlock::PerBlockProcessingError(BlockProcessingError::BadBlockSignature)
+            ))
+        );
+    }
+    assert!(chain.future_blocks.read().is_empty());
+
+    assert_eq!(
+        chain.process_block(block.clone()),
+        Ok(BlockProcessingOutcome::InvalidBlock(
+            InvalidBlock::FutureSlot
+        ))
+    );
+    chain.slot_clock.set_slot((spec.genesis_slot + 1).as_u64());
+    assert_eq!(chain.catch_up_with_slot_clock(), Ok(vec![block]));
+}
+
+/// Re-sign `free_attestation` after its data has been modified.
+fn sign_free_attestation(harness: &BeaconChainHarness, free_attestation: &mut FreeAttestation) {
+    let domain = harness.beacon_chain.state.read().fork.get_domain(
+        free_attestation.data.slot.epoch(harness.spec.epoch_length),
+        harness.spec.domain_attestation,
+    );
+    let keypair = &harness.validators[free_attestation.validator_index as usize].keypair;
+    free_attestation.signature = Signature::new(
+        &free_attestation.data.signable_message(false),
+        domain,
+        &keypair.sk,
+    );
+}
@@ -0,0 +1,100 @@
+use bls::create_proof_of_possession;
+use eth1::{Eth1Follower, Eth1Rpc, MockEth1Chain};
+use std::sync::Arc;
+use test_harness::BeaconChainHarness;
+use types::{ChainSpec, DepositData, DepositInput, Hash256, Keypair};
+
+#[test]
+fn it_votes_for_eth1_data_and_includes_deposits() {
+    let mut spec = ChainSpec::few_validators();
+    spec.eth1_data_voting_period = 1;
+    let validator_count = 8;
+    let follow_distance = 2;
+
+    let mut harness = BeaconChainHarness::new(spec, validator_count);
+
+    // The genesis validators are the first deposits to the deposit contract.
+    let eth1_chain = Arc::new(MockEth1Chain::new());
+    for validator in &harness.validators {
+        eth1_chain.deposit(deposit_data(&validator.keypair));
+    }
+    let follower = Arc::new(Eth1Follower::new(
+        eth1_chain.clone(),
+        follow_distance,
+        0,
+        &harness.spec,
+    ));
+    *harness.beacon_chain.eth1_follower.write() = Some(follower.clone());
+
+    let keypair = Keypair::random();
+    eth1_chain.deposit(deposit_data(&keypair));
+    eth1_chain.mine_blocks(follow_distance + 1);
+    follower.update().unwrap();
+    let voted_block = eth1_chain
+        .block_by_number(eth1_chain.head().number - follow_distance)
+        .unwrap()
+        .unwrap();
+
+    // The eth1 data is adopted once it has been voted for in a majority of the slots of a voting
+    // period.
+    let max_slots = harness.spec.eth1_data_voting_period * harness.spec.epoch_length * 2;
+    for _ in 0..max_slots {
+        harness.advance_chain_with_block();
+        if harness
+            .beacon_chain
+            .head()
+            .beacon_state
+            .latest_eth1_data
+            .block_hash
+            == voted_block.hash
+        {
+            break;
+        }
+    }
+
+    // The deposit is included as soon as the eth1 data is adopted, with its branch against the
+    // voted deposit root.
+    let head = harness.beacon_chain.head();
+    let latest_eth1_data = &head.beacon_state.latest_eth1_data;
+    assert_eq!(latest_eth1_data.block_hash, voted_block.hash);
+    assert_ne!(latest_eth1_data.deposit_root, Hash256::zero());
+
+    let deposits = &head.beacon_block.body.deposits;
+    assert_eq!(deposits.len(), 1);
+    assert_eq!(deposits[0].index, validator_count as u64);
+    assert_eq!(
+        deposits[0].branch.len() as u64,
+        harness.spec.deposit_contract_tree_depth
+    );
+    assert_eq!(
+        head.beacon_state.validator_registry.len(),
+        validator_count + 1
+    );
+    assert_eq!(
+        head.beacon_state.validator_registry[validator_count].pubkey,
+        keypair.pk
+    );
+
+    // It is not included again.
+    drop(head);
+    harness.advance_chain_with_block();
+    assert!(harness
+        .beacon_chain
+        .head()
+        .beacon_block
+        .body
+        .deposits
+        .is_empty());
+}
+
+fn deposit_data(keypair: &Keypair) -> DepositData {
+    DepositData {
+        amount: 32_000_000_000,
+        timestamp: 0,
+        deposit_input: DepositInput {
+            pubkey: keypair.pk.clone(),
+            withdrawal_credentials: Hash256::zero(),
+            proof_of_possession: create_proof_of_possession(keypair),
+        },
+    }
+}
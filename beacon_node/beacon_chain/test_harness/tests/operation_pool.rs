@@ -0,0 +1,230 @@
+use bls::create_proof_of_possession;
+use ssz::TreeHash;
+use state_processing::{BlockProcessingError, DepositTree};
+use test_harness::BeaconChainHarness;
+use types::{
+    AggregateSignature, AttestationData, AttesterSlashing, Bitfield, ChainSpec, Deposit,
+    DepositData, DepositInput, Eth1Data, Exit, Hash256, Keypair, ProposalSignedData,
+    ProposerSlashing, Signature, SlashableAttestation, ValidatorStatusFlags,
+};
+
+#[test]
+fn it_includes_pooled_operations_in_produced_blocks() {
+    let spec = ChainSpec::few_validators();
+    let validator_count = 8;
+
+    // The genesis deposits are not made via the deposit tree, so any leaves may precede the
+    // deposit of the new validator.
+    let keypair = Keypair::random();
+    let mut deposit_tree = DepositTree::new(spec.deposit_contract_tree_depth);
+    for _ in 0..validator_count {
+        deposit_tree.push(&deposit_data(&Keypair::random()));
+    }
+    deposit_tree.push(&deposit_data(&keypair));
+    let latest_eth1_data = Eth1Data {
+        deposit_root: deposit_tree.root(validator_count + 1).unwrap(),
+        block_hash: Hash256::zero(),
+    };
+
+    let mut harness =
+        BeaconChainHarness::with_latest_eth1_data(spec, validator_count, latest_eth1_data);
+    harness.advance_chain_with_block();
+
+    let chain = harness.beacon_chain.clone();
+
+    let exit = signed_exit(&harness, 0);
+    let mut bad_exit = exit.clone();
+    bad_exit.signature = signed_exit(&harness, 1).signature;
+    assert_eq!(
+        chain.process_exit(bad_exit),
+        Err(BlockProcessingError::BadExit)
+    );
+    assert_eq!(chain.process_exit(exit.clone()), Ok(()));
+
+    let proposer_slashing = proposer_slashing(&harness, 1);
+    assert_eq!(
+        chain.process_proposer_slashing(proposer_slashing.clone()),
+        Ok(())
+    );
+
+    let attester_slashing = attester_slashing(&harness, 2);
+    assert_eq!(
+        chain.process_attester_slashing(attester_slashing.clone()),
+        Ok(())
+    );
+
+    // Deposits must be proven against the deposit root of the state, and must not have been
+    // processed already.
+    let deposit = Deposit {
+        branch: deposit_tree
+            .branch(validator_count, validator_count + 1)
+            .unwrap(),
+        index: validator_count as u64,
+        deposit_data: deposit_data(&keypair),
+    };
+    let mut processed_deposit = deposit.clone();
+    processed_deposit.index = 0;
+    assert_eq!(
+        chain.process_deposit(processed_deposit),
+        Err(BlockProcessingError::BadDepositIndex)
+    );
+    let mut unproven_deposit = deposit.clone();
+    unproven_deposit.deposit_data = deposit_data(&Keypair::random());
+    assert_eq!(
+        chain.process_deposit(unproven_deposit),
+        Err(BlockProcessingError::BadDepositBranch)
+    );
+    assert_eq!(chain.process_deposit(deposit.clone()), Ok(()));
+    assert_eq!(chain.operation_pool.read().len(), 4);
+
+    harness.advance_chain_with_block();
+
+    let head = chain.head();
+    let body = &head.beacon_block.body;
+    assert_eq!(body.exits, vec![exit]);
+    assert_eq!(body.proposer_slashings, vec![proposer_slashing]);
+    assert_eq!(body.attester_slashings, vec![attester_slashing]);
+    assert_eq!(body.deposits, vec![deposit]);
+
+    let state = &head.beacon_state;
+    let current_epoch = state.current_epoch(&harness.spec);
+    assert_eq!(
+        state.validator_registry[0].status_flags,
+        Some(ValidatorStatusFlags::InitiatedExit)
+    );
+    assert_eq!(state.validator_registry[1].penalized_epoch, current_epoch);
+    assert_eq!(state.validator_registry[2].penalized_epoch, current_epoch);
+    assert_eq!(state.validator_registry.len(), validator_count + 1);
+    assert_eq!(state.validator_registry[validator_count].pubkey, keypair.pk);
+    assert_eq!(state.deposit_index, validator_count as u64 + 1);
+
+    // The included operations are no longer pooled.
+    assert!(chain.operation_pool.read().is_empty());
+}
+
+#[test]
+fn it_excludes_operations_invalidated_by_earlier_operations() {
+    let spec = ChainSpec::few_validators();
+    let validator_count = 8;
+
+    let mut harness = BeaconChainHarness::new(spec, validator_count);
+    harness.advance_chain_with_block();
+
+    let chain = harness.beacon_chain.clone();
+
+    // Each slashing is valid against the present state, but after the proposer slashing the
+    // attester slashing of the same validator penalizes no one.
+    let proposer_slashing = proposer_slashing(&harness, 1);
+    let attester_slashing = attester_slashing(&harness, 1);
+    assert_eq!(
+        chain.process_proposer_slashing(proposer_slashing.clone()),
+        Ok(())
+    );
+    assert_eq!(
+        chain.process_attester_slashing(attester_slashing.clone()),
+        Ok(())
+    );
+
+    harness.advance_chain_with_block();
+
+    let head = chain.head();
+    let body = &head.beacon_block.body;
+    assert_eq!(body.proposer_slashings, vec![proposer_slashing]);
+    assert!(body.attester_slashings.is_empty());
+    assert_eq!(
+        head.beacon_state.validator_registry[1].penalized_epoch,
+        head.beacon_state.current_epoch(&harness.spec)
+    );
+    drop(head);
+
+    // The invalidated slashing is pruned.
+    assert!(chain.operation_pool.read().is_empty());
+}
+
+fn signed_exit(harness: &BeaconChainHarness, validator_index: u64) -> Exit {
+    let state = harness.beacon_chain.state.read();
+    let epoch = state.current_epoch(&harness.spec);
+    let mut exit = Exit {
+        epoch,
+        validator_index,
+        signature: harness.spec.empty_signature.clone(),
+    };
+    let domain = state.fork.get_domain(epoch, harness.spec.domain_exit);
+    let keypair = &harness.validators[validator_index as usize].keypair;
+    exit.signature = Signature::new(&exit.hash_tree_root(), domain, &keypair.sk);
+    exit
+}
+
+/// Two signed proposals from `proposer_index` for the same slot with different block roots.
+fn proposer_slashing(harness: &BeaconChainHarness, proposer_index: u64) -> ProposerSlashing {
+    let state = harness.beacon_chain.state.read();
+    let keypair = &harness.validators[proposer_index as usize].keypair;
+    let domain = state.fork.get_domain(
+        state.current_epoch(&harness.spec),
+        harness.spec.domain_proposal,
+    );
+    let proposal = |block_root: Hash256| {
+        let data = ProposalSignedData {
+            slot: state.slot,
+            shard: harness.spec.beacon_chain_shard_number,
+            block_root,
+        };
+        let signature = Signature::new(&data.hash_tree_root(), domain, &keypair.sk);
+        (data, signature)
+    };
+    let (proposal_data_1, proposal_signature_1) = proposal(Hash256::from(&[1; 32][..]));
+    let (proposal_data_2, proposal_signature_2) = proposal(Hash256::from(&[2; 32][..]));
+
+    ProposerSlashing {
+        proposer_index,
+        proposal_data_1,
+        proposal_signature_1,
+        proposal_data_2,
+        proposal_signature_2,
+    }
+}
+
+/// Two signed attestations from `validator_index` for the same target epoch with different
+/// block roots.
+fn attester_slashing(harness: &BeaconChainHarness, validator_index: u64) -> AttesterSlashing {
+    let data = harness.beacon_chain.produce_attestation_data(0).unwrap();
+    let domain = harness.beacon_chain.state.read().fork.get_domain(
+        data.slot.epoch(harness.spec.epoch_length),
+        harness.spec.domain_attestation,
+    );
+    let keypair = &harness.validators[validator_index as usize].keypair;
+    let slashable_attestation = |data: AttestationData| {
+        let mut aggregate_signature = AggregateSignature::new();
+        aggregate_signature.add(&Signature::new(
+            &data.signable_message(false),
+            domain,
+            &keypair.sk,
+        ));
+        SlashableAttestation {
+            validator_indices: vec![validator_index],
+            data,
+            custody_bitfield: Bitfield::new(),
+            aggregate_signature,
+        }
+    };
+
+    let mut other_data = data.clone();
+    other_data.beacon_block_root = Hash256::from(&[3; 32][..]);
+
+    AttesterSlashing {
+        slashable_attestation_1: slashable_attestation(data),
+        slashable_attestation_2: slashable_attestation(other_data),
+    }
+}
+
+fn deposit_data(keypair: &Keypair) -> DepositData {
+    DepositData {
+        amount: 32_000_000_000,
+        timestamp: 0,
+        deposit_input: DepositInput {
+            pubkey: keypair.pk.clone(),
+            withdrawal_credentials: Hash256::zero(),
+            proof_of_possession: create_proof_of_possession(keypair),
+        },
+    }
+}
@@ -0,0 +1,145 @@
+use beacon_chain::{BlockProcessingOutcome, ValidBlock};
+use ssz::TreeHash;
+use test_harness::BeaconChainHarness;
+use types::{BeaconBlock, ChainSpec, Exit, Signature};
+
+#[test]
+fn it_slashes_double_proposals() {
+    let spec = ChainSpec::few_validators();
+    let validator_count = 8;
+
+    let mut harness = BeaconChainHarness::new(spec, validator_count);
+    harness.advance_chain_with_block();
+    harness.increment_beacon_chain_slot();
+
+    // Produce a second block at the same slot, which differs by including an exit.
+    let block = harness.produce_block();
+    let proposer = harness.beacon_chain.block_proposer(block.slot).unwrap();
+    harness
+        .beacon_chain
+        .process_exit(signed_exit(&harness, (proposer as u64 + 1) % 8))
+        .unwrap();
+    let (mut other_block, _) = harness
+        .beacon_chain
+        .produce_block(block.randao_reveal.clone())
+        .unwrap();
+    sign_block(&harness, &mut other_block, proposer);
+    assert_ne!(block.canonical_root(), other_block.canonical_root());
+
+    for block in &[block, other_block] {
+        assert_eq!(
+            harness.beacon_chain.process_block(block.clone()),
+            Ok(BlockProcessingOutcome::ValidBlock(ValidBlock::Processed))
+        );
+    }
+
+    let proposer_slashings = harness
+        .beacon_chain
+        .operation_pool
+        .read()
+        .get_block_operations(&harness.beacon_chain.state.read(), &[], &harness.spec)
+        .proposer_slashings;
+    assert_eq!(proposer_slashings.len(), 1);
+    assert_eq!(proposer_slashings[0].proposer_index, proposer as u64);
+
+    // The slashing is included in the next block.
+    harness.advance_chain_with_block();
+    let head = harness.beacon_chain.head();
+    assert_eq!(
+        head.beacon_block.body.proposer_slashings,
+        proposer_slashings
+    );
+    assert_eq!(
+        head.beacon_state.validator_registry[proposer].penalized_epoch,
+        head.beacon_state.current_epoch(&harness.spec)
+    );
+}
+
+#[test]
+fn it_slashes_double_votes() {
+    let spec = ChainSpec::few_validators();
+    let validator_count = 8;
+
+    let mut harness = BeaconChainHarness::new(spec, validator_count);
+    harness.advance_chain_with_block();
+    harness.advance_chain_with_block();
+
+    // Attest to both the head and its parent at the same slot.
+    let parent_root = harness.beacon_chain.head().beacon_block.parent_root;
+    harness.increment_beacon_chain_slot();
+    let free_attestation = harness.gather_free_attesations().pop().unwrap();
+    let validator_index = free_attestation.validator_index;
+
+    let mut other_attestation = free_attestation.clone();
+    other_attestation.data.beacon_block_root = parent_root;
+    let domain = harness.beacon_chain.state.read().fork.get_domain(
+        other_attestation.data.slot.epoch(harness.spec.epoch_length),
+        harness.spec.domain_attestation,
+    );
+    other_attestation.signature = Signature::new(
+        &other_attestation.data.signable_message(false),
+        domain,
+        &harness.validators[validator_index as usize].keypair.sk,
+    );
+
+    for free_attestation in &[free_attestation, other_attestation] {
+        let outcome = harness
+            .beacon_chain
+            .process_free_attestation(free_attestation.clone())
+            .unwrap();
+        assert!(outcome.valid, "{:?}", outcome);
+    }
+
+    let attester_slashings = harness
+        .beacon_chain
+        .operation_pool
+        .read()
+        .get_block_operations(&harness.beacon_chain.state.read(), &[], &harness.spec)
+        .attester_slashings;
+    assert_eq!(attester_slashings.len(), 1);
+    assert_eq!(
+        attester_slashings[0]
+            .slashable_attestation_1
+            .validator_indices,
+        vec![validator_index]
+    );
+
+    // The slashing is included in the next block.
+    let block = harness.produce_block();
+    assert_eq!(block.body.attester_slashings, attester_slashings);
+    assert_eq!(
+        harness.beacon_chain.process_block(block),
+        Ok(BlockProcessingOutcome::ValidBlock(ValidBlock::Processed))
+    );
+    let head = harness.beacon_chain.head();
+    assert_eq!(
+        head.beacon_state.validator_registry[validator_index as usize].penalized_epoch,
+        head.beacon_state.current_epoch(&harness.spec)
+    );
+}
+
+fn signed_exit(harness: &BeaconChainHarness, validator_index: u64) -> Exit {
+    let state = harness.beacon_chain.state.read();
+    let epoch = state.current_epoch(&harness.spec);
+    let mut exit = Exit {
+        epoch,
+        validator_index,
+        signature: harness.spec.empty_signature.clone(),
+    };
+    let domain = state.fork.get_domain(epoch, harness.spec.domain_exit);
+    let keypair = &harness.validators[validator_index as usize].keypair;
+    exit.signature = Signature::new(&exit.hash_tree_root(), domain, &keypair.sk);
+    exit
+}
+
+fn sign_block(harness: &BeaconChainHarness, block: &mut BeaconBlock, proposer: usize) {
+    let domain = harness.beacon_chain.state.read().fork.get_domain(
+        block.slot.epoch(harness.spec.epoch_length),
+        harness.spec.domain_proposal,
+    );
+    block.signature = Signature::new(
+        &block.proposal_root(&harness.spec)[..],
+        domain,
+        &harness.validators[proposer].keypair.sk,
+    );
+}
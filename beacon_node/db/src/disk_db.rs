@@ -28,9 +28,7 @@ impl DiskDB {
          */
         let mut options = Options::default();
         options.create_if_missing(true);
-
-        // TODO: ensure that columns are created (and remove
-        // the dead_code allow)
+        options.create_missing_column_families(true);
 
         /*
          * Initialise the path
@@ -45,7 +43,7 @@ impl DiskDB {
             None => DB::open(&options, db_path),
             Some(columns) => DB::open_cf(&options, db_path, columns),
         }
-        .expect("Unable to open local database");;
+        .expect("Unable to open local database");
 
         Self { db }
     }
@@ -0,0 +1,377 @@
+use super::CHAIN_DB_COLUMN as DB_COLUMN;
+use super::{ClientDB, DBError};
+use ssz::{ssz_encode, Decodable, DecodeError, Encodable, SszStream};
+use std::sync::Arc;
+use types::{Hash256, Slot};
+
+const CANONICAL_HEAD_KEY: &[u8] = b"canonical_head";
+const JUSTIFIED_HEAD_KEY: &[u8] = b"justified_head";
+const FINALIZED_HEAD_KEY: &[u8] = b"finalized_head";
+const FIRST_FORK_CHOICE_BLOCK_KEY: &[u8] = b"first_fork_choice_block";
+const LAST_FORK_CHOICE_BLOCK_KEY: &[u8] = b"last_fork_choice_block";
+const FORK_CHOICE_BLOCK_PREFIX: &[u8] = b"fork_choice_block";
+const ATTESTATION_TARGET_COUNT_KEY: &[u8] = b"attestation_target_count";
+const ATTESTATION_TARGET_PREFIX: &[u8] = b"attestation_target";
+
+/// The latest block a validator has attested to, as supplied to the fork choice.
+#[derive(Debug, PartialEq, Clone)]
+pub struct AttestationTarget {
+    pub validator_index: u64,
+    pub block_root: Hash256,
+    pub slot: Slot,
+}
+
+impl Encodable for AttestationTarget {
+    fn ssz_append(&self, s: &mut SszStream) {
+        s.append(&self.validator_index);
+        s.append(&self.block_root);
+        s.append(&self.slot);
+    }
+}
+
+impl Decodable for AttestationTarget {
+    fn ssz_decode(bytes: &[u8], i: usize) -> Result<(Self, usize), DecodeError> {
+        let (validator_index, i) = <_>::ssz_decode(bytes, i)?;
+        let (block_root, i) = <_>::ssz_decode(bytes, i)?;
+        let (slot, i) = <_>::ssz_decode(bytes, i)?;
+        Ok((
+            AttestationTarget {
+                validator_index,
+                block_root,
+                slot,
+            },
+            i,
+        ))
+    }
+}
+
+/// The neighbours of a block in the list of blocks supplied to the fork choice, which is stored
+/// as a doubly linked list so blocks may be added and removed without rewriting the list.
+///
+/// A zero hash indicates there is no neighbour.
+#[derive(Debug, PartialEq, Clone)]
+struct ForkChoiceLink {
+    previous: Hash256,
+    next: Hash256,
+}
+
+impl Encodable for ForkChoiceLink {
+    fn ssz_append(&self, s: &mut SszStream) {
+        s.append(&self.previous);
+        s.append(&self.next);
+    }
+}
+
+impl Decodable for ForkChoiceLink {
+    fn ssz_decode(bytes: &[u8], i: usize) -> Result<(Self, usize), DecodeError> {
+        let (previous, i) = <_>::ssz_decode(bytes, i)?;
+        let (next, i) = <_>::ssz_decode(bytes, i)?;
+        Ok((ForkChoiceLink { previous, next }, i))
+    }
+}
+
+/// Stores the information required to resume a `BeaconChain` after a restart: the canonical,
+/// justified and finalized heads, and the blocks and attestations which have been supplied to the fork choice.
+pub struct ChainStore<T>
+where
+    T: ClientDB,
+{
+    db: Arc<T>,
+}
+
+impl<T: ClientDB> ChainStore<T> {
+    pub fn new(db: Arc<T>) -> Self {
+        Self { db }
+    }
+
+    pub fn put_canonical_head(&self, block_root: &Hash256) -> Result<(), DBError> {
+        self.db
+            .put(DB_COLUMN, CANONICAL_HEAD_KEY, &ssz_encode(block_root))
+    }
+
+    /// Returns the root of the canonical head block, or `None` if no chain has been stored.
+    pub fn get_canonical_head(&self) -> Result<Option<Hash256>, DBError> {
+        self.get_hash(CANONICAL_HEAD_KEY)
+    }
+
+    pub fn put_justified_head(&self, block_root: &Hash256) -> Result<(), DBError> {
+        self.db
+            .put(DB_COLUMN, JUSTIFIED_HEAD_KEY, &ssz_encode(block_root))
+    }
+
+    /// Returns the root of the justified head block, or `None` if no chain has been stored.
+    pub fn get_justified_head(&self) -> Result<Option<Hash256>, DBError> {
+        self.get_hash(JUSTIFIED_HEAD_KEY)
+    }
+
+    pub fn put_finalized_head(&self, block_root: &Hash256) -> Result<(), DBError> {
+        self.db
+            .put(DB_COLUMN, FINALIZED_HEAD_KEY, &ssz_encode(block_root))
+    }
+
+    /// Returns the root of the finalized head block, or `None` if no chain has been stored.
+    pub fn get_finalized_head(&self) -> Result<Option<Hash256>, DBError> {
+        self.get_hash(FINALIZED_HEAD_KEY)
+    }
+
+    /// Forget all blocks and attestation targets supplied to the fork choice.
+    pub fn clear_fork_choice(&self) -> Result<(), DBError> {
+        self.db.delete(DB_COLUMN, FIRST_FORK_CHOICE_BLOCK_KEY)?;
+        self.db.delete(DB_COLUMN, LAST_FORK_CHOICE_BLOCK_KEY)?;
+        self.db.delete(DB_COLUMN, ATTESTATION_TARGET_COUNT_KEY)
+    }
+
+    /// Append `block_root` to the blocks supplied to the fork choice.
+    pub fn add_fork_choice_block(&self, block_root: &Hash256) -> Result<(), DBError> {
+        let zero = Hash256::zero();
+        match self.get_hash(LAST_FORK_CHOICE_BLOCK_KEY)? {
+            Some(last) => {
+                let mut link = self.get_link(&last)?;
+                link.next = *block_root;
+                self.put_link(&last, &link)?;
+                self.put_link(
+                    block_root,
+                    &ForkChoiceLink {
+                        previous: last,
+                        next: zero,
+                    },
+                )?;
+            }
+            None => {
+                self.put_link(
+                    block_root,
+                    &ForkChoiceLink {
+                        previous: zero,
+                        next: zero,
+                    },
+                )?;
+                self.put_hash(FIRST_FORK_CHOICE_BLOCK_KEY, block_root)?;
+            }
+        }
+        self.put_hash(LAST_FORK_CHOICE_BLOCK_KEY, block_root)
+    }
+
+    /// Remove `block_root` from the blocks supplied to the fork choice.
+    pub fn remove_fork_choice_block(&self, block_root: &Hash256) -> Result<(), DBError> {
+        let link = self.get_link(block_root)?;
+
+        if link.previous.is_zero() {
+            self.put_or_delete_hash(FIRST_FORK_CHOICE_BLOCK_KEY, &link.next)?;
+        } else {
+            let mut previous = self.get_link(&link.previous)?;
+            previous.next = link.next;
+            self.put_link(&link.previous, &previous)?;
+        }
+        if link.next.is_zero() {
+            self.put_or_delete_hash(LAST_FORK_CHOICE_BLOCK_KEY, &link.previous)?;
+        } else {
+            let mut next = self.get_link(&link.next)?;
+            next.previous = link.previous;
+            self.put_link(&link.next, &next)?;
+        }
+
+        self.db.delete(
+            DB_COLUMN,
+            &prefixed_key(FORK_CHOICE_BLOCK_PREFIX, block_root),
+        )
+    }
+
+    /// Returns the roots of all blocks supplied to the fork choice, in the order they were
+    /// supplied.
+    pub fn get_fork_choice_blocks(&self) -> Result<Vec<Hash256>, DBError> {
+        let mut block_roots = vec![];
+        let mut next = self.get_hash(FIRST_FORK_CHOICE_BLOCK_KEY)?;
+        while let Some(block_root) = next {
+            next = Some(self.get_link(&block_root)?.next).filter(|root| !root.is_zero());
+            block_roots.push(block_root);
+        }
+        Ok(block_roots)
+    }
+
+    /// Store the latest attestation target of a validator, replacing any previous target.
+    pub fn put_attestation_target(&self, target: &AttestationTarget) -> Result<(), DBError> {
+        let count = self.get_attestation_target_count()?;
+        if target.validator_index >= count {
+            self.db.put(
+                DB_COLUMN,
+                ATTESTATION_TARGET_COUNT_KEY,
+                &ssz_encode(&(target.validator_index + 1)),
+            )?;
+        }
+        self.db.put(
+            DB_COLUMN,
+            &prefixed_key(ATTESTATION_TARGET_PREFIX, &target.validator_index),
+            &ssz_encode(target),
+        )
+    }
+
+    pub fn delete_attestation_target(&self, validator_index: u64) -> Result<(), DBError> {
+        self.db.delete(
+            DB_COLUMN,
+            &prefixed_key(ATTESTATION_TARGET_PREFIX, &validator_index),
+        )
+    }
+
+    /// Returns the latest attestation target of each validator which has one, ordered by
+    /// validator index.
+    pub fn get_attestation_targets(&self) -> Result<Vec<AttestationTarget>, DBError> {
+        let mut targets = vec![];
+        for validator_index in 0..self.get_attestation_target_count()? {
+            let key = prefixed_key(ATTESTATION_TARGET_PREFIX, &validator_index);
+            if let Some(bytes) = self.db.get(DB_COLUMN, &key)? {
+                let (target, _) =
+                    AttestationTarget::ssz_decode(&bytes, 0).map_err(|_| DBError {
+                        message: "Bad AttestationTarget SSZ.".to_string(),
+                    })?;
+                targets.push(target);
+            }
+        }
+        Ok(targets)
+    }
+
+    /// Returns one more than the highest validator index with a stored attestation target.
+    fn get_attestation_target_count(&self) -> Result<u64, DBError> {
+        match self.db.get(DB_COLUMN, ATTESTATION_TARGET_COUNT_KEY)? {
+            None => Ok(0),
+            Some(bytes) => {
+                let (count, _) = u64::ssz_decode(&bytes, 0).map_err(|_| DBError {
+                    message: "Bad u64 SSZ.".to_string(),
+                })?;
+                Ok(count)
+            }
+        }
+    }
+
+    fn get_link(&self, block_root: &Hash256) -> Result<ForkChoiceLink, DBError> {
+        let key = prefixed_key(FORK_CHOICE_BLOCK_PREFIX, block_root);
+        let bytes = self.db.get(DB_COLUMN, &key)?.ok_or_else(|| DBError {
+            message: "Missing fork choice block.".to_string(),
+        })?;
+        let (link, _) = ForkChoiceLink::ssz_decode(&bytes, 0).map_err(|_| DBError {
+            message: "Bad ForkChoiceLink SSZ.".to_string(),
+        })?;
+        Ok(link)
+    }
+
+    fn put_link(&self, block_root: &Hash256, link: &ForkChoiceLink) -> Result<(), DBError> {
+        let key = prefixed_key(FORK_CHOICE_BLOCK_PREFIX, block_root);
+        self.db.put(DB_COLUMN, &key, &ssz_encode(link))
+    }
+
+    fn put_hash(&self, key: &[u8], hash: &Hash256) -> Result<(), DBError> {
+        self.db.put(DB_COLUMN, key, &ssz_encode(hash))
+    }
+
+    /// Store `hash` at `key`, or delete `key` if `hash` is zero.
+    fn put_or_delete_hash(&self, key: &[u8], hash: &Hash256) -> Result<(), DBError> {
+        if hash.is_zero() {
+            self.db.delete(DB_COLUMN, key)
+        } else {
+            self.put_hash(key, hash)
+        }
+    }
+
+    fn get_hash(&self, key: &[u8]) -> Result<Option<Hash256>, DBError> {
+        match self.db.get(DB_COLUMN, key)? {
+            None => Ok(None),
+            Some(bytes) => {
+                let (hash, _) = Hash256::ssz_decode(&bytes, 0).map_err(|_| DBError {
+                    message: "Bad Hash256 SSZ.".to_string(),
+                })?;
+                Ok(Some(hash))
+            }
+        }
+    }
+}
+
+/// Returns `prefix` followed by the SSZ encoding of `item`.
+fn prefixed_key<E: Encodable>(prefix: &[u8], item: &E) -> Vec<u8> {
+    let mut key = prefix.to_vec();
+    key.extend_from_slice(&ssz_encode(item));
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::MemoryDB;
+    use super::*;
+
+    #[test]
+    fn test_heads() {
+        let store = ChainStore::new(Arc::new(MemoryDB::open()));
+
+        assert_eq!(store.get_canonical_head().unwrap(), None);
+        assert_eq!(store.get_justified_head().unwrap(), None);
+        assert_eq!(store.get_finalized_head().unwrap(), None);
+
+        let canonical = Hash256::from(&[1; 32][..]);
+        let justified = Hash256::from(&[3; 32][..]);
+        let finalized = Hash256::from(&[2; 32][..]);
+        store.put_canonical_head(&canonical).unwrap();
+        store.put_justified_head(&justified).unwrap();
+        store.put_finalized_head(&finalized).unwrap();
+
+        assert_eq!(store.get_canonical_head().unwrap(), Some(canonical));
+        assert_eq!(store.get_justified_head().unwrap(), Some(justified));
+        assert_eq!(store.get_finalized_head().unwrap(), Some(finalized));
+    }
+
+    #[test]
+    fn test_fork_choice_blocks() {
+        let store = ChainStore::new(Arc::new(MemoryDB::open()));
+        let roots: Vec<Hash256> = (1..5).map(|i| Hash256::from(&[i; 32][..])).collect();
+
+        assert!(store.get_fork_choice_blocks().unwrap().is_empty());
+
+        for root in &roots {
+            store.add_fork_choice_block(root).unwrap();
+        }
+        assert_eq!(store.get_fork_choice_blocks().unwrap(), roots);
+
+        // Remove from the middle, the start and the end of the list.
+        store.remove_fork_choice_block(&roots[2]).unwrap();
+        store.remove_fork_choice_block(&roots[0]).unwrap();
+        assert_eq!(
+            store.get_fork_choice_blocks().unwrap(),
+            vec![roots[1], roots[3]]
+        );
+        store.remove_fork_choice_block(&roots[3]).unwrap();
+        assert_eq!(store.get_fork_choice_blocks().unwrap(), vec![roots[1]]);
+
+        store.add_fork_choice_block(&roots[0]).unwrap();
+        assert_eq!(
+            store.get_fork_choice_blocks().unwrap(),
+            vec![roots[1], roots[0]]
+        );
+
+        store.remove_fork_choice_block(&roots[1]).unwrap();
+        store.remove_fork_choice_block(&roots[0]).unwrap();
+        assert!(store.get_fork_choice_blocks().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_attestation_targets() {
+        let store = ChainStore::new(Arc::new(MemoryDB::open()));
+        let target = |validator_index, slot| AttestationTarget {
+            validator_index,
+            block_root: Hash256::from(&[1; 32][..]),
+            slot: Slot::new(slot),
+        };
+
+        assert!(store.get_attestation_targets().unwrap().is_empty());
+
+        store.put_attestation_target(&target(3, 7)).unwrap();
+        store.put_attestation_target(&target(1, 7)).unwrap();
+        store.put_attestation_target(&target(3, 8)).unwrap();
+        assert_eq!(
+            store.get_attestation_targets().unwrap(),
+            vec![target(1, 7), target(3, 8)]
+        );
+
+        store.delete_attestation_target(3).unwrap();
+        assert_eq!(store.get_attestation_targets().unwrap(), vec![target(1, 7)]);
+
+        store.clear_fork_choice().unwrap();
+        assert!(store.get_attestation_targets().unwrap().is_empty());
+    }
+}
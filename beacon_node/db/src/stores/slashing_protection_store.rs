@@ -0,0 +1,269 @@
+extern crate bytes;
+
+use self::bytes::{BufMut, BytesMut};
+use super::SLASHING_PROTECTION_DB_COLUMN as DB_COLUMN;
+use super::{ClientDB, DBError};
+use bls::PublicKey;
+use slashing_protection::{AttestationWatermark, SlashingProtection, SlashingProtectionError};
+use ssz::{ssz_encode, Decodable};
+use std::sync::Arc;
+use types::{Epoch, Hash256, Slot};
+
+impl From<DBError> for SlashingProtectionError {
+    fn from(error: DBError) -> Self {
+        SlashingProtectionError::DBError(error.message)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum KeyPrefixes {
+    Proposal,
+    Attestations,
+}
+
+/// Stores the block proposals and attestation watermarks signed by a single validator.
+///
+/// Each proposal is stored under its slot, whilst attestations are summarised by a single
+/// `AttestationWatermark`, so checking and recording either is a single read or write.
+///
+/// Many validators may share the same underlying `ClientDB`, as all keys are prefixed with the
+/// public key of the validator.
+pub struct SlashingProtectionStore<T>
+where
+    T: ClientDB,
+{
+    db: Arc<T>,
+    pubkey_bytes: Vec<u8>,
+}
+
+impl<T: ClientDB> SlashingProtectionStore<T> {
+    /// Create a new store for the validator with the given `pubkey`.
+    pub fn new(db: Arc<T>, pubkey: &PublicKey) -> Self {
+        Self {
+            db,
+            pubkey_bytes: ssz_encode(pubkey),
+        }
+    }
+
+    fn prefix_bytes(&self, key_prefix: &KeyPrefixes) -> Vec<u8> {
+        match key_prefix {
+            KeyPrefixes::Proposal => b"proposal".to_vec(),
+            KeyPrefixes::Attestations => b"attestations".to_vec(),
+        }
+    }
+
+    fn get_db_key_for_proposal(&self, slot: Slot) -> Vec<u8> {
+        let prefix = self.prefix_bytes(&KeyPrefixes::Proposal);
+        let mut buf = BytesMut::with_capacity(prefix.len() + self.pubkey_bytes.len() + 8);
+        buf.put(prefix);
+        buf.put(&self.pubkey_bytes[..]);
+        buf.put_u64_be(slot.as_u64());
+        buf.take().to_vec()
+    }
+
+    fn get_db_key_for_attestations(&self) -> Vec<u8> {
+        let prefix = self.prefix_bytes(&KeyPrefixes::Attestations);
+        let mut buf = BytesMut::with_capacity(prefix.len() + self.pubkey_bytes.len());
+        buf.put(prefix);
+        buf.put(&self.pubkey_bytes[..]);
+        buf.take().to_vec()
+    }
+
+    /// Returns the watermark of the attestations previously signed by the validator, if any.
+    fn attestation_watermark(
+        &self,
+    ) -> Result<Option<AttestationWatermark>, SlashingProtectionError> {
+        let key = self.get_db_key_for_attestations();
+        match self.db.get(DB_COLUMN, &key[..])? {
+            None => Ok(None),
+            Some(val) => {
+                let (watermark, _) = AttestationWatermark::ssz_decode(&val, 0)
+                    .map_err(|_| SlashingProtectionError::DecodeError)?;
+                Ok(Some(watermark))
+            }
+        }
+    }
+}
+
+impl<T: ClientDB> SlashingProtection for SlashingProtectionStore<T> {
+    fn is_safe_proposal(
+        &self,
+        slot: Slot,
+        proposal_root: &Hash256,
+    ) -> Result<bool, SlashingProtectionError> {
+        let key = self.get_db_key_for_proposal(slot);
+        match self.db.get(DB_COLUMN, &key[..])? {
+            None => Ok(true),
+            Some(val) => {
+                let (signed_root, _) = Hash256::ssz_decode(&val, 0)
+                    .map_err(|_| SlashingProtectionError::DecodeError)?;
+                Ok(signed_root == *proposal_root)
+            }
+        }
+    }
+
+    fn record_proposal(
+        &self,
+        slot: Slot,
+        proposal_root: &Hash256,
+    ) -> Result<(), SlashingProtectionError> {
+        let key = self.get_db_key_for_proposal(slot);
+        self.db
+            .put(DB_COLUMN, &key[..], &ssz_encode(proposal_root)[..])
+            .map_err(SlashingProtectionError::from)
+    }
+
+    fn is_safe_attestation(
+        &self,
+        source_epoch: Epoch,
+        target_epoch: Epoch,
+        signing_root: &Hash256,
+    ) -> Result<bool, SlashingProtectionError> {
+        Ok(match self.attestation_watermark()? {
+            Some(watermark) => watermark.is_safe(source_epoch, target_epoch, signing_root),
+            None => true,
+        })
+    }
+
+    fn record_attestation(
+        &self,
+        source_epoch: Epoch,
+        target_epoch: Epoch,
+        signing_root: &Hash256,
+    ) -> Result<(), SlashingProtectionError> {
+        let watermark = match self.attestation_watermark()? {
+            Some(mut watermark) => {
+                watermark.update(source_epoch, target_epoch, signing_root);
+                watermark
+            }
+            None => AttestationWatermark::new(source_epoch, target_epoch, *signing_root),
+        };
+
+        let key = self.get_db_key_for_attestations();
+        self.db
+            .put(DB_COLUMN, &key[..], &ssz_encode(&watermark)[..])
+            .map_err(SlashingProtectionError::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::MemoryDB;
+    use super::*;
+    use bls::Keypair;
+
+    fn root(byte: u8) -> Hash256 {
+        Hash256::from(&[byte; 32][..])
+    }
+
+    #[test]
+    fn test_prefix_bytes() {
+        let db = Arc::new(MemoryDB::open());
+        let store = SlashingProtectionStore::new(db.clone(), &Keypair::random().pk);
+
+        assert_eq!(
+            store.prefix_bytes(&KeyPrefixes::Proposal),
+            b"proposal".to_vec()
+        );
+        assert_eq!(
+            store.prefix_bytes(&KeyPrefixes::Attestations),
+            b"attestations".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_double_proposal() {
+        let db = Arc::new(MemoryDB::open());
+        let store = SlashingProtectionStore::new(db.clone(), &Keypair::random().pk);
+
+        let slot = Slot::new(10);
+        assert!(store.is_safe_proposal(slot, &root(1)).unwrap());
+
+        store.record_proposal(slot, &root(1)).unwrap();
+
+        // Re-signing the same proposal is safe.
+        assert!(store.is_safe_proposal(slot, &root(1)).unwrap());
+        // Signing a different proposal in the same slot is slashable.
+        assert!(!store.is_safe_proposal(slot, &root(2)).unwrap());
+        // Signing a proposal in another slot is safe.
+        assert!(store.is_safe_proposal(slot + 1, &root(2)).unwrap());
+    }
+
+    #[test]
+    fn test_double_vote() {
+        let db = Arc::new(MemoryDB::open());
+        let store = SlashingProtectionStore::new(db.clone(), &Keypair::random().pk);
+
+        store
+            .record_attestation(Epoch::new(1), Epoch::new(2), &root(1))
+            .unwrap();
+
+        // Re-signing the same attestation is safe.
+        assert!(store
+            .is_safe_attestation(Epoch::new(1), Epoch::new(2), &root(1))
+            .unwrap());
+        // Signing a different attestation with the same target is slashable.
+        assert!(!store
+            .is_safe_attestation(Epoch::new(1), Epoch::new(2), &root(2))
+            .unwrap());
+        // Signing an attestation with a later target is safe.
+        assert!(store
+            .is_safe_attestation(Epoch::new(2), Epoch::new(3), &root(2))
+            .unwrap());
+    }
+
+    #[test]
+    fn test_surround_vote() {
+        let db = Arc::new(MemoryDB::open());
+        let store = SlashingProtectionStore::new(db.clone(), &Keypair::random().pk);
+
+        store
+            .record_attestation(Epoch::new(2), Epoch::new(3), &root(1))
+            .unwrap();
+
+        // Surrounding the previous attestation is slashable.
+        assert!(!store
+            .is_safe_attestation(Epoch::new(1), Epoch::new(4), &root(2))
+            .unwrap());
+
+        store
+            .record_attestation(Epoch::new(0), Epoch::new(10), &root(3))
+            .unwrap();
+
+        // Being surrounded by a previous attestation is slashable.
+        assert!(!store
+            .is_safe_attestation(Epoch::new(4), Epoch::new(5), &root(4))
+            .unwrap());
+    }
+
+    #[test]
+    fn test_validators_are_independent() {
+        let db = Arc::new(MemoryDB::open());
+        let store_a = SlashingProtectionStore::new(db.clone(), &Keypair::random().pk);
+        let store_b = SlashingProtectionStore::new(db.clone(), &Keypair::random().pk);
+
+        store_a.record_proposal(Slot::new(1), &root(1)).unwrap();
+        store_a
+            .record_attestation(Epoch::new(1), Epoch::new(2), &root(1))
+            .unwrap();
+
+        assert!(store_b.is_safe_proposal(Slot::new(1), &root(2)).unwrap());
+        assert!(store_b
+            .is_safe_attestation(Epoch::new(1), Epoch::new(2), &root(2))
+            .unwrap());
+    }
+
+    #[test]
+    fn test_get_invalid_attestations() {
+        let db = Arc::new(MemoryDB::open());
+        let store = SlashingProtectionStore::new(db.clone(), &Keypair::random().pk);
+
+        let key = store.get_db_key_for_attestations();
+        db.put(DB_COLUMN, &key[..], "cats".as_bytes()).unwrap();
+
+        assert_eq!(
+            store.is_safe_attestation(Epoch::new(1), Epoch::new(2), &root(1)),
+            Err(SlashingProtectionError::DecodeError)
+        );
+    }
+}
@@ -0,0 +1,393 @@
+use crate::{DepositLog, Error, Eth1Block, Eth1Rpc};
+use parking_lot::RwLock;
+use state_processing::DepositTree;
+use std::sync::Arc;
+use types::{BeaconState, ChainSpec, Deposit, DepositData, Eth1Data, Hash256};
+
+/// The number of blocks an Ethereum 1.0 block must be behind the head before it may be voted
+/// for, so it is unlikely to be re-organised.
+pub const ETH1_FOLLOW_DISTANCE: u64 = 1024;
+
+/// The maximum number of blocks requested from the RPC endpoint before they are imported.
+const MAX_BLOCKS_PER_BATCH: u64 = 256;
+
+/// An Ethereum 1.0 block which has been imported by the `Eth1Follower`.
+struct ImportedBlock {
+    number: u64,
+    hash: Hash256,
+    /// The number of deposits made up to and including this block.
+    deposit_count: u64,
+    /// The root of the deposit tree after `deposit_count` deposits.
+    deposit_root: Hash256,
+}
+
+impl ImportedBlock {
+    fn eth1_data(&self) -> Eth1Data {
+        Eth1Data {
+            deposit_root: self.deposit_root,
+            block_hash: self.hash,
+        }
+    }
+}
+
+/// The blocks and deposits imported by an `Eth1Follower`.
+struct ImportedChain {
+    blocks: Vec<ImportedBlock>,
+    /// The data of each deposit, by index.
+    deposits: Vec<DepositData>,
+    deposit_tree: DepositTree,
+}
+
+impl ImportedChain {
+    /// Import `block` and the deposits logged in it.
+    fn import_block(
+        &mut self,
+        block: &Eth1Block,
+        deposit_logs: &[&DepositLog],
+    ) -> Result<(), Error> {
+        let deposit_count = self.deposits.len();
+        for log in deposit_logs {
+            self.import_deposit(log)?;
+        }
+
+        let deposit_root = match self.blocks.last() {
+            Some(previous) if deposit_count == self.deposits.len() => previous.deposit_root,
+            _ => self
+                .deposit_tree
+                .root(self.deposits.len())
+                .unwrap_or_else(Hash256::zero),
+        };
+        self.blocks.push(ImportedBlock {
+            number: block.number,
+            hash: block.hash,
+            deposit_count: self.deposits.len() as u64,
+            deposit_root,
+        });
+        Ok(())
+    }
+
+    /// Add a logged deposit to the deposit tree. Deposits which have already been imported are
+    /// ignored.
+    fn import_deposit(&mut self, log: &DepositLog) -> Result<(), Error> {
+        let next_index = self.deposits.len() as u64;
+        if log.index < next_index {
+            return Ok(());
+        }
+        if log.index > next_index {
+            return Err(Error::UnexpectedDepositIndex(next_index));
+        }
+        self.deposit_tree.push(&log.deposit_data);
+        self.deposits.push(log.deposit_data.clone());
+        Ok(())
+    }
+}
+
+/// Follows the Ethereum 1.0 chain and its deposit contract, providing the `Eth1Data` vote and
+/// `Deposit`s for produced blocks.
+///
+/// Only blocks at least `follow_distance` behind the Ethereum 1.0 head are imported, starting from
+/// the block in which the deposit contract was deployed. The follower is not updated by the
+/// beacon chain, `update` should be called periodically from a separate thread.
+pub struct Eth1Follower {
+    rpc: Arc<dyn Eth1Rpc>,
+    follow_distance: u64,
+    deposit_contract_block: u64,
+    chain: RwLock<ImportedChain>,
+}
+
+impl Eth1Follower {
+    /// Create a follower reading from `rpc`, for a deposit contract deployed in block number
+    /// `deposit_contract_block`.
+    pub fn new(
+        rpc: Arc<dyn Eth1Rpc>,
+        follow_distance: u64,
+        deposit_contract_block: u64,
+        spec: &ChainSpec,
+    ) -> Self {
+        Self {
+            rpc,
+            follow_distance,
+            deposit_contract_block,
+            chain: RwLock::new(ImportedChain {
+                blocks: vec![],
+                deposits: vec![],
+                deposit_tree: DepositTree::new(spec.deposit_contract_tree_depth),
+            }),
+        }
+    }
+
+    /// Import the blocks, and their deposits, which have reached `follow_distance` behind the
+    /// Ethereum 1.0 head since the last update.
+    ///
+    /// Blocks are requested in batches of at most `MAX_BLOCKS_PER_BATCH`, without holding the lock
+    /// on the imported chain, so votes and deposits may be read whilst updating.
+    pub fn update(&self) -> Result<(), Error> {
+        let head = self.rpc.block_number()?;
+        let target = match head.checked_sub(self.follow_distance) {
+            Some(target) => target,
+            None => return Ok(()),
+        };
+
+        loop {
+            let next = self.next_block_number(&self.chain.read());
+            if next > target {
+                return Ok(());
+            }
+            let last = std::cmp::min(target, next + MAX_BLOCKS_PER_BATCH - 1);
+
+            let deposit_logs = self.rpc.deposit_logs(next, last)?;
+            let blocks = (next..=last)
+                .map(|number| {
+                    self.rpc
+                        .block_by_number(number)?
+                        .ok_or(Error::MissingBlock(number))
+                })
+                .collect::<Result<Vec<Eth1Block>, Error>>()?;
+
+            let mut chain = self.chain.write();
+            // Another update may have imported blocks whilst the batch was requested.
+            if self.next_block_number(&chain) != next {
+                continue;
+            }
+            for block in &blocks {
+                let block_logs: Vec<&DepositLog> = deposit_logs
+                    .iter()
+                    .filter(|log| log.block_number == block.number)
+                    .collect();
+                chain.import_block(block, &block_logs)?;
+            }
+        }
+    }
+
+    /// Returns the number of the next block to be imported.
+    fn next_block_number(&self, chain: &ImportedChain) -> u64 {
+        match chain.blocks.last() {
+            Some(block) => block.number + 1,
+            None => self.deposit_contract_block,
+        }
+    }
+
+    /// Returns the `Eth1Data` to be voted for in a block upon `state`, or `None` if no block has
+    /// been imported since `state.latest_eth1_data`.
+    ///
+    /// As per the honest validator guide, this is the vote in `state` with the highest count for
+    /// an imported block newer than `state.latest_eth1_data`, or the latest imported block if
+    /// there is no such vote. Ties are broken in favour of the earliest vote.
+    pub fn eth1_data_vote(&self, state: &BeaconState) -> Option<Eth1Data> {
+        let chain = self.chain.read();
+        let newer = match chain
+            .blocks
+            .iter()
+            .position(|block| block.hash == state.latest_eth1_data.block_hash)
+        {
+            Some(position) => &chain.blocks[position + 1..],
+            None => &chain.blocks[..],
+        };
+
+        // `max_by_key` returns the last of equal elements, so iterate in reverse.
+        let best_vote = state
+            .eth1_data_votes
+            .iter()
+            .rev()
+            .filter(|vote| {
+                newer
+                    .iter()
+                    .any(|block| block.eth1_data() == vote.eth1_data)
+            })
+            .max_by_key(|vote| vote.vote_count);
+
+        match best_vote {
+            Some(vote) => Some(vote.eth1_data.clone()),
+            None => newer.last().map(ImportedBlock::eth1_data),
+        }
+    }
+
+    /// Returns the deposits to be processed next by `state`, from `state.deposit_index`, at most
+    /// `spec.max_deposits`.
+    ///
+    /// Deposits are proven against `state.latest_eth1_data`, so only deposits made before that
+    /// block are returned. They are validated when they are selected for a block.
+    pub fn get_deposits(&self, state: &BeaconState, spec: &ChainSpec) -> Vec<Deposit> {
+        let chain = self.chain.read();
+        let deposit_count = match chain
+            .blocks
+            .iter()
+            .find(|block| block.eth1_data() == state.latest_eth1_data)
+        {
+            Some(block) => block.deposit_count,
+            None => return vec![],
+        };
+
+        (state.deposit_index..deposit_count)
+            .take(spec.max_deposits as usize)
+            .filter_map(|index| {
+                Some(Deposit {
+                    branch: chain
+                        .deposit_tree
+                        .branch(index as usize, deposit_count as usize)?,
+                    index,
+                    deposit_data: chain.deposits[index as usize].clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the number of the latest imported block, if any.
+    pub fn latest_block_number(&self) -> Option<u64> {
+        self.chain.read().blocks.last().map(|block| block.number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockEth1Chain;
+    use bls::{create_proof_of_possession, Keypair};
+    use types::test_utils::{SeedableRng, TestRandom, XorShiftRng};
+    use types::{DepositInput, Eth1DataVote};
+
+    fn deposit_data() -> DepositData {
+        let keypair = Keypair::random();
+        DepositData {
+            amount: 32_000_000_000,
+            timestamp: 0,
+            deposit_input: DepositInput {
+                pubkey: keypair.pk.clone(),
+                withdrawal_credentials: Hash256::zero(),
+                proof_of_possession: create_proof_of_possession(&keypair),
+            },
+        }
+    }
+
+    fn beacon_state(rng: &mut XorShiftRng, latest_eth1_data: Eth1Data) -> BeaconState {
+        let mut state = BeaconState::random_for_test(rng);
+        state.latest_eth1_data = latest_eth1_data;
+        state.eth1_data_votes = vec![];
+        state.validator_registry = vec![];
+        state.deposit_index = 0;
+        state
+    }
+
+    #[test]
+    fn test_follows_at_distance() {
+        let spec = ChainSpec::few_validators();
+        let chain = Arc::new(MockEth1Chain::new());
+        let follower = Eth1Follower::new(chain.clone(), 4, 0, &spec);
+
+        chain.mine_blocks(3);
+        follower.update().unwrap();
+        assert_eq!(follower.latest_block_number(), None);
+
+        chain.deposit(deposit_data());
+        chain.mine_blocks(2);
+        follower.update().unwrap();
+        assert_eq!(follower.latest_block_number(), Some(1));
+        assert!(follower.chain.read().deposits.is_empty());
+
+        chain.mine_blocks(3);
+        follower.update().unwrap();
+        assert_eq!(follower.latest_block_number(), Some(4));
+        assert_eq!(follower.chain.read().deposits.len(), 1);
+        assert_eq!(follower.chain.read().blocks[3].deposit_count, 0);
+        assert_eq!(follower.chain.read().blocks[4].deposit_count, 1);
+        assert_eq!(
+            follower.chain.read().blocks[4].deposit_root,
+            follower.chain.read().deposit_tree.root(1).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_imports_from_the_deposit_contract_block_in_batches() {
+        let spec = ChainSpec::few_validators();
+        let chain = Arc::new(MockEth1Chain::new());
+        let deposit_contract_block = 3;
+        let follower = Eth1Follower::new(chain.clone(), 0, deposit_contract_block, &spec);
+
+        chain.mine_blocks(deposit_contract_block);
+        chain.deposit(deposit_data());
+        chain.mine_blocks(MAX_BLOCKS_PER_BATCH * 2);
+        follower.update().unwrap();
+
+        let imported = follower.chain.read();
+        assert_eq!(imported.blocks[0].number, deposit_contract_block);
+        assert_eq!(imported.blocks.len() as u64, MAX_BLOCKS_PER_BATCH * 2 + 1);
+        assert_eq!(imported.blocks[0].deposit_count, 0);
+        assert_eq!(imported.blocks[1].deposit_count, 1);
+    }
+
+    #[test]
+    fn test_eth1_data_vote() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let spec = ChainSpec::few_validators();
+        let chain = Arc::new(MockEth1Chain::new());
+        let follower = Eth1Follower::new(chain.clone(), 0, 0, &spec);
+
+        chain.mine_blocks(3);
+        follower.update().unwrap();
+        let eth1_data: Vec<Eth1Data> = follower
+            .chain
+            .read()
+            .blocks
+            .iter()
+            .map(|b| b.eth1_data())
+            .collect();
+
+        // With no votes, the latest block newer than the latest eth1 data is voted for.
+        let mut state = beacon_state(&mut rng, eth1_data[0].clone());
+        assert_eq!(follower.eth1_data_vote(&state), Some(eth1_data[3].clone()));
+
+        // Otherwise, the most popular vote for a newer imported block.
+        let vote = |eth1_data: &Eth1Data, vote_count| Eth1DataVote {
+            eth1_data: eth1_data.clone(),
+            vote_count,
+        };
+        state.eth1_data_votes = vec![
+            vote(&eth1_data[0], 5),
+            vote(&eth1_data[1], 2),
+            vote(&eth1_data[2], 2),
+            vote(&Eth1Data::random_for_test(&mut rng), 3),
+        ];
+        assert_eq!(follower.eth1_data_vote(&state), Some(eth1_data[1].clone()));
+
+        let state = beacon_state(&mut rng, eth1_data[3].clone());
+        assert_eq!(follower.eth1_data_vote(&state), None);
+    }
+
+    #[test]
+    fn test_get_deposits() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let spec = ChainSpec::few_validators();
+        let chain = Arc::new(MockEth1Chain::new());
+        let follower = Eth1Follower::new(chain.clone(), 0, 0, &spec);
+
+        for _ in 0..3 {
+            chain.deposit(deposit_data());
+        }
+        chain.mine_blocks(1);
+        chain.deposit(deposit_data());
+        chain.mine_blocks(1);
+        follower.update().unwrap();
+
+        // Deposits are proven against the latest eth1 data, excluding those made since and those
+        // already processed.
+        let mut state = beacon_state(&mut rng, follower.chain.read().blocks[1].eth1_data());
+        state.deposit_index = 1;
+        let deposits = follower.get_deposits(&state, &spec);
+        let indices: Vec<u64> = deposits.iter().map(|deposit| deposit.index).collect();
+        assert_eq!(indices, vec![1, 2]);
+        assert_eq!(
+            deposits[0].branch,
+            follower.chain.read().deposit_tree.branch(1, 3).unwrap()
+        );
+        assert_eq!(deposits[0].deposit_data, follower.chain.read().deposits[1]);
+
+        state.deposit_index = 2;
+        let deposits = follower.get_deposits(&state, &spec);
+        assert_eq!(deposits.len(), 1);
+        assert_eq!(deposits[0].index, 2);
+
+        let unknown_eth1_data = Eth1Data::random_for_test(&mut rng);
+        let state = beacon_state(&mut rng, unknown_eth1_data);
+        assert!(follower.get_deposits(&state, &spec).is_empty());
+    }
+}
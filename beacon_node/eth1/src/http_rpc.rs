@@ -0,0 +1,326 @@
+use crate::{DepositLog, Error, Eth1Block, Eth1Rpc};
+use hashing::hash;
+use serde_json::{json, Value};
+use ssz::Decodable;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+use types::{Address, ChainSpec, DepositData, Hash256};
+
+/// The signature of the event logged by the deposit contract for each deposit, with the previous
+/// deposit root, the deposit data and the index in the deposit tree.
+const DEPOSIT_EVENT_SIGNATURE: &str = "Deposit(bytes32,bytes,bytes)";
+
+/// The time allowed for connecting to the endpoint and for each read or write.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Reads the Ethereum 1.0 chain from the HTTP JSON-RPC endpoint of a node (e.g., geth or parity).
+pub struct HttpEth1Rpc {
+    address: SocketAddr,
+    deposit_contract_address: Address,
+    deposit_contract_tree_depth: u64,
+}
+
+impl HttpEth1Rpc {
+    /// Create a client of the endpoint at `address`, following the deposit contract of `spec`.
+    pub fn new(address: SocketAddr, spec: &ChainSpec) -> Self {
+        Self {
+            address,
+            deposit_contract_address: spec.deposit_contract_address,
+            deposit_contract_tree_depth: spec.deposit_contract_tree_depth,
+        }
+    }
+
+    /// Call `method` with `params`, returning the `result` of the response.
+    fn call(&self, method: &str, params: Value) -> Result<Value, Error> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+            "id": 1,
+        })
+        .to_string();
+
+        // HTTP/1.0 is used so the response is neither chunked nor kept alive.
+        let request = format!(
+            "POST / HTTP/1.0\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            self.address,
+            body.len(),
+            body
+        );
+        let mut response = vec![];
+        TcpStream::connect_timeout(&self.address, REQUEST_TIMEOUT)
+            .and_then(|mut stream| {
+                stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+                stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+                stream.write_all(request.as_bytes())?;
+                stream.read_to_end(&mut response)
+            })
+            .map_err(|e| Error::Rpc(format!("{}", e)))?;
+
+        let response = String::from_utf8_lossy(&response);
+        let (status, body) = match response.find("\r\n\r\n") {
+            Some(index) => (&response[..index], &response[index + 4..]),
+            None => return Err(invalid_response("incomplete HTTP response")),
+        };
+        if status.split_whitespace().nth(1) != Some("200") {
+            return Err(Error::Rpc(status.lines().next().unwrap_or("").to_string()));
+        }
+
+        let mut body: Value =
+            serde_json::from_str(body).map_err(|_| invalid_response("invalid JSON"))?;
+        if let Some(error) = body.get("error") {
+            return Err(Error::Rpc(error.to_string()));
+        }
+        Ok(body["result"].take())
+    }
+
+    /// Decode the data of a deposit event, returning the deposit index and data.
+    fn decode_deposit_event(&self, data: &[u8]) -> Result<(u64, DepositData), Error> {
+        let bytes = abi_bytes(data, 1).ok_or_else(|| invalid_response("deposit data"))?;
+        let deposit_data = match DepositData::ssz_decode(bytes, 0) {
+            Ok((deposit_data, index)) if index == bytes.len() => deposit_data,
+            _ => return Err(invalid_response("deposit data")),
+        };
+
+        // The contract logs the index of the leaf in the deposit tree, i.e., offset by the number
+        // of leaves.
+        let tree_index = abi_bytes(data, 2)
+            .filter(|bytes| bytes.len() == 8)
+            .ok_or_else(|| invalid_response("deposit index"))?;
+        let tree_index = tree_index
+            .iter()
+            .fold(0, |value, byte| (value << 8) | u64::from(*byte));
+        let index = tree_index
+            .checked_sub(1 << self.deposit_contract_tree_depth)
+            .ok_or_else(|| invalid_response("deposit index"))?;
+
+        Ok((index, deposit_data))
+    }
+}
+
+impl Eth1Rpc for HttpEth1Rpc {
+    fn block_number(&self) -> Result<u64, Error> {
+        quantity(&self.call("eth_blockNumber", json!([]))?)
+    }
+
+    fn block_by_number(&self, number: u64) -> Result<Option<Eth1Block>, Error> {
+        let block = self.call(
+            "eth_getBlockByNumber",
+            json!([format!("{:#x}", number), false]),
+        )?;
+        if block.is_null() {
+            return Ok(None);
+        }
+        let hash = data(&block["hash"])?;
+        if hash.len() != 32 {
+            return Err(invalid_response("block hash"));
+        }
+        Ok(Some(Eth1Block {
+            number,
+            hash: Hash256::from(&hash[..]),
+        }))
+    }
+
+    fn deposit_logs(&self, from: u64, to: u64) -> Result<Vec<DepositLog>, Error> {
+        let filter = json!({
+            "fromBlock": format!("{:#x}", from),
+            "toBlock": format!("{:#x}", to),
+            "address": format!("0x{}", hex::encode(&self.deposit_contract_address[..])),
+            "topics": [format!("0x{}", hex::encode(hash(DEPOSIT_EVENT_SIGNATURE.as_bytes())))],
+        });
+        let logs = self.call("eth_getLogs", json!([filter]))?;
+        let logs = logs
+            .as_array()
+            .ok_or_else(|| invalid_response("logs are not an array"))?;
+
+        let mut deposit_logs = vec![];
+        for log in logs {
+            // Logs of blocks which have been re-organised out of the chain are ignored.
+            if log["removed"].as_bool() == Some(true) {
+                continue;
+            }
+            let (index, deposit_data) = self.decode_deposit_event(&data(&log["data"])?)?;
+            deposit_logs.push(DepositLog {
+                block_number: quantity(&log["blockNumber"])?,
+                index,
+                deposit_data,
+            });
+        }
+        deposit_logs.sort_by_key(|log| log.index);
+        Ok(deposit_logs)
+    }
+}
+
+fn invalid_response(reason: &str) -> Error {
+    Error::InvalidResponse(reason.to_string())
+}
+
+/// Decode a hex encoded quantity, e.g., `"0x1a"`.
+fn quantity(value: &Value) -> Result<u64, Error> {
+    value
+        .as_str()
+        .filter(|string| string.starts_with("0x"))
+        .and_then(|string| u64::from_str_radix(&string[2..], 16).ok())
+        .ok_or_else(|| invalid_response("quantity"))
+}
+
+/// Decode hex encoded data, e.g., `"0x00ff"`.
+fn data(value: &Value) -> Result<Vec<u8>, Error> {
+    value
+        .as_str()
+        .filter(|string| string.starts_with("0x"))
+        .and_then(|string| hex::decode(&string[2..]).ok())
+        .ok_or_else(|| invalid_response("data"))
+}
+
+/// Returns the dynamic `bytes` value which is the `position`th parameter of ABI encoded `data`.
+fn abi_bytes(data: &[u8], position: usize) -> Option<&[u8]> {
+    let word = |offset: usize| -> Option<usize> {
+        let word = data.get(offset..offset.checked_add(32)?)?;
+        // Offsets and lengths must fit in the last eight bytes.
+        if word[..24].iter().any(|byte| *byte != 0) {
+            return None;
+        }
+        Some(
+            word[24..]
+                .iter()
+                .fold(0, |value, byte| (value << 8) | *byte as usize),
+        )
+    };
+    let offset = word(position * 32)?;
+    let length = word(offset)?;
+    let start = offset + 32;
+    data.get(start..start.checked_add(length)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bls::{create_proof_of_possession, Keypair};
+    use ssz::ssz_encode;
+    use std::net::TcpListener;
+    use std::thread;
+    use types::DepositInput;
+
+    /// Serve a single request with `result` as the JSON-RPC result, returning the address of
+    /// the endpoint.
+    fn serve(result: Value) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = vec![0; 4096];
+            let _ = stream.read(&mut request).unwrap();
+            let body = json!({ "jsonrpc": "2.0", "id": 1, "result": result }).to_string();
+            write!(
+                stream,
+                "HTTP/1.0 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        });
+        address
+    }
+
+    /// ABI encode a deposit event, as logged by the deposit contract.
+    fn deposit_event(deposit_data: &DepositData, tree_index: u64) -> Vec<u8> {
+        let word = |value: usize| {
+            let mut word = vec![0; 24];
+            word.extend_from_slice(&(value as u64).to_be_bytes());
+            word
+        };
+        let padded = |bytes: &[u8]| {
+            let mut padded = word(bytes.len());
+            padded.extend_from_slice(bytes);
+            padded.resize(padded.len() + (32 - bytes.len() % 32) % 32, 0);
+            padded
+        };
+        let data = padded(&ssz_encode(deposit_data));
+
+        let mut event = vec![0; 32];
+        event.append(&mut word(96));
+        event.append(&mut word(96 + data.len()));
+        event.extend_from_slice(&data);
+        event.append(&mut padded(&tree_index.to_be_bytes()));
+        event
+    }
+
+    #[test]
+    fn test_block_number() {
+        let spec = ChainSpec::few_validators();
+        let rpc = HttpEth1Rpc::new(serve(json!("0x1a")), &spec);
+        assert_eq!(rpc.block_number(), Ok(26));
+    }
+
+    #[test]
+    fn test_block_by_number() {
+        let spec = ChainSpec::few_validators();
+        let hash = Hash256::from(&[42; 32][..]);
+        let block = json!({ "hash": format!("0x{}", hex::encode(&hash[..])) });
+        let rpc = HttpEth1Rpc::new(serve(block), &spec);
+        assert_eq!(
+            rpc.block_by_number(3),
+            Ok(Some(Eth1Block { number: 3, hash }))
+        );
+
+        let rpc = HttpEth1Rpc::new(serve(Value::Null), &spec);
+        assert_eq!(rpc.block_by_number(3), Ok(None));
+    }
+
+    #[test]
+    fn test_deposit_logs() {
+        let spec = ChainSpec::few_validators();
+        let keypair = Keypair::random();
+        let deposit_data = DepositData {
+            amount: 32_000_000_000,
+            timestamp: 0,
+            deposit_input: DepositInput {
+                pubkey: keypair.pk.clone(),
+                withdrawal_credentials: Hash256::zero(),
+                proof_of_possession: create_proof_of_possession(&keypair),
+            },
+        };
+        let event = deposit_event(&deposit_data, 5 + (1 << spec.deposit_contract_tree_depth));
+        let logs = json!([
+            {
+                "blockNumber": "0x7",
+                "data": format!("0x{}", hex::encode(&event)),
+                "removed": false,
+            },
+            {
+                "blockNumber": "0x6",
+                "data": format!("0x{}", hex::encode(&event)),
+                "removed": true,
+            },
+        ]);
+
+        let rpc = HttpEth1Rpc::new(serve(logs), &spec);
+        assert_eq!(
+            rpc.deposit_logs(0, 7),
+            Ok(vec![DepositLog {
+                block_number: 7,
+                index: 5,
+                deposit_data,
+            }])
+        );
+    }
+
+    #[test]
+    fn test_invalid_responses() {
+        let spec = ChainSpec::few_validators();
+        let rpc = HttpEth1Rpc::new(serve(json!(26)), &spec);
+        assert_eq!(
+            rpc.block_number(),
+            Err(Error::InvalidResponse("quantity".to_string()))
+        );
+
+        let logs = json!([{ "blockNumber": "0x7", "data": "0x00" }]);
+        let rpc = HttpEth1Rpc::new(serve(logs), &spec);
+        assert_eq!(
+            rpc.deposit_logs(0, 7),
+            Err(Error::InvalidResponse("deposit data".to_string()))
+        );
+    }
+}
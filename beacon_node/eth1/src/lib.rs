@@ -0,0 +1,54 @@
+mod eth1_follower;
+mod http_rpc;
+mod mock_eth1_chain;
+
+pub use crate::eth1_follower::{Eth1Follower, ETH1_FOLLOW_DISTANCE};
+pub use crate::http_rpc::HttpEth1Rpc;
+pub use crate::mock_eth1_chain::MockEth1Chain;
+
+use types::{DepositData, Hash256};
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// The RPC endpoint returned an error, or could not be reached.
+    Rpc(String),
+    /// The RPC endpoint returned a response which could not be decoded.
+    InvalidResponse(String),
+    /// The RPC endpoint did not return a block it had previously reported.
+    MissingBlock(u64),
+    /// A deposit log was received out of order, the next expected index is given.
+    UnexpectedDepositIndex(u64),
+}
+
+/// A block of the Ethereum 1.0 chain.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Eth1Block {
+    pub number: u64,
+    pub hash: Hash256,
+}
+
+/// A deposit, as logged by the deposit contract.
+#[derive(Debug, PartialEq, Clone)]
+pub struct DepositLog {
+    /// The number of the block in which the deposit was made.
+    pub block_number: u64,
+    /// The index of the deposit in the deposit contract.
+    pub index: u64,
+    pub deposit_data: DepositData,
+}
+
+/// The subset of the Ethereum 1.0 JSON-RPC API required to follow the deposit contract.
+///
+/// The purpose of having this trait is to allow an Ethereum 1.0 node to be used in production,
+/// but use an in-process `MockEth1Chain` during tests.
+pub trait Eth1Rpc: Send + Sync {
+    /// Returns the number of the latest block, as `eth_blockNumber`.
+    fn block_number(&self) -> Result<u64, Error>;
+
+    /// Returns the block with `number`, as `eth_getBlockByNumber`.
+    fn block_by_number(&self, number: u64) -> Result<Option<Eth1Block>, Error>;
+
+    /// Returns the deposits logged by the deposit contract in blocks `from..=to`, ordered by
+    /// index, as `eth_getLogs`.
+    fn deposit_logs(&self, from: u64, to: u64) -> Result<Vec<DepositLog>, Error>;
+}
@@ -0,0 +1,86 @@
+use crate::{DepositLog, Error, Eth1Block, Eth1Rpc};
+use hashing::hash;
+use int_to_bytes::int_to_bytes32;
+use parking_lot::RwLock;
+use types::{DepositData, Hash256};
+
+/// An in-process Ethereum 1.0 chain with a deposit contract, for testing.
+///
+/// Deposits are logged in the next block to be mined.
+pub struct MockEth1Chain {
+    blocks: RwLock<Vec<Eth1Block>>,
+    deposit_logs: RwLock<Vec<DepositLog>>,
+}
+
+impl MockEth1Chain {
+    /// Create a chain containing only a genesis block, with number zero.
+    pub fn new() -> Self {
+        let genesis = Eth1Block {
+            number: 0,
+            hash: Hash256::from(&hash(&int_to_bytes32(0))[..]),
+        };
+        Self {
+            blocks: RwLock::new(vec![genesis]),
+            deposit_logs: RwLock::new(vec![]),
+        }
+    }
+
+    /// Make a deposit, which is logged in the next block to be mined.
+    pub fn deposit(&self, deposit_data: DepositData) {
+        let mut deposit_logs = self.deposit_logs.write();
+        let log = DepositLog {
+            block_number: self.head().number + 1,
+            index: deposit_logs.len() as u64,
+            deposit_data,
+        };
+        deposit_logs.push(log);
+    }
+
+    /// Mine `count` blocks.
+    pub fn mine_blocks(&self, count: u64) {
+        let mut blocks = self.blocks.write();
+        for _ in 0..count {
+            let parent = blocks[blocks.len() - 1].clone();
+            let number = parent.number + 1;
+            let mut preimage = parent.hash.to_vec();
+            preimage.append(&mut int_to_bytes32(number));
+            blocks.push(Eth1Block {
+                number,
+                hash: Hash256::from(&hash(&preimage)[..]),
+            });
+        }
+    }
+
+    /// Returns the latest mined block.
+    pub fn head(&self) -> Eth1Block {
+        let blocks = self.blocks.read();
+        blocks[blocks.len() - 1].clone()
+    }
+}
+
+impl Default for MockEth1Chain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Eth1Rpc for MockEth1Chain {
+    fn block_number(&self) -> Result<u64, Error> {
+        Ok(self.head().number)
+    }
+
+    fn block_by_number(&self, number: u64) -> Result<Option<Eth1Block>, Error> {
+        Ok(self.blocks.read().get(number as usize).cloned())
+    }
+
+    fn deposit_logs(&self, from: u64, to: u64) -> Result<Vec<DepositLog>, Error> {
+        let head = self.head().number;
+        Ok(self
+            .deposit_logs
+            .read()
+            .iter()
+            .filter(|log| log.block_number >= from && log.block_number <= to.min(head))
+            .cloned()
+            .collect())
+    }
+}
@@ -0,0 +1,70 @@
+mod message;
+mod network_service;
+mod peer_manager;
+mod sync_manager;
+
+pub use crate::message::{
+    read_message, write_message, Blocks, BlocksByRange, BlocksByRoot, Hello, Message, Status,
+    MAX_MESSAGE_LENGTH,
+};
+pub use crate::network_service::{NetworkConfig, NetworkService, DEFAULT_MAX_PEERS};
+pub use crate::peer_manager::{Peer, PeerId, PeerManager};
+pub use crate::sync_manager::{SyncManager, Transport, MAX_BLOCKS_PER_REQUEST, REQUEST_TIMEOUT};
+
+use ::beacon_chain::Error as BeaconChainError;
+use ssz::DecodeError;
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    Io(String),
+    BeaconChainError(BeaconChainError),
+    /// The peer closed the connection.
+    ConnectionClosed,
+    /// A message could not be decoded.
+    InvalidMessage(DecodeError),
+    /// A message was longer than `MAX_MESSAGE_LENGTH`.
+    MessageTooLong(usize),
+    /// The first message on a connection was not a `Hello`.
+    MissingHello,
+    /// A `Hello` was received after the handshake.
+    UnexpectedHello,
+    /// A message was passed to a handler for another kind of message.
+    UnexpectedMessage,
+    /// A `Blocks` response did not match any outstanding request.
+    UnexpectedResponse,
+    /// A `Blocks` response contained blocks which were not requested.
+    InvalidResponse,
+    /// The peer is following a chain with a different genesis.
+    GenesisMismatch,
+    /// The connection is from this node to itself.
+    SelfConnection,
+    /// A connection to the node already exists.
+    AlreadyConnected,
+    /// The maximum number of peers are already connected.
+    TooManyPeers,
+    /// The peer sent a block which may never be valid.
+    InvalidBlock,
+    /// The peer sent an attestation which may never be valid.
+    InvalidAttestation,
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Error {
+        match e.kind() {
+            std::io::ErrorKind::UnexpectedEof => Error::ConnectionClosed,
+            _ => Error::Io(e.to_string()),
+        }
+    }
+}
+
+impl From<BeaconChainError> for Error {
+    fn from(e: BeaconChainError) -> Error {
+        Error::BeaconChainError(e)
+    }
+}
+
+impl From<DecodeError> for Error {
+    fn from(e: DecodeError) -> Error {
+        Error::InvalidMessage(e)
+    }
+}
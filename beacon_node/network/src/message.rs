@@ -0,0 +1,244 @@
+use crate::Error;
+use ssz::{ssz_encode, Decodable, DecodeError};
+use ssz_derive::{Decode, Encode};
+use std::io::{Read, Write};
+use types::{BeaconBlock, Epoch, FreeAttestation, Hash256, Slot};
+
+/// The maximum length of an encoded message, excluding its length prefix.
+pub const MAX_MESSAGE_LENGTH: usize = 1 << 22;
+
+const HELLO: u8 = 0;
+const BLOCK: u8 = 1;
+const ATTESTATION: u8 = 2;
+const STATUS: u8 = 3;
+const BLOCKS_BY_RANGE: u8 = 4;
+const BLOCKS_BY_ROOT: u8 = 5;
+const BLOCKS: u8 = 6;
+
+/// The first message sent by each node on a new connection.
+#[derive(Debug, PartialEq, Clone, Encode, Decode)]
+pub struct Hello {
+    /// Randomly chosen on start up, identifies the node so duplicate connections may be detected.
+    pub node_id: u64,
+    /// The root of the genesis block of the chain the node is following.
+    pub genesis_block_root: Hash256,
+}
+
+/// A summary of a node's chain, sent after the `Hello` so peers may determine whether they should
+/// sync from the node.
+#[derive(Debug, PartialEq, Clone, Encode, Decode)]
+pub struct Status {
+    pub finalized_root: Hash256,
+    pub finalized_epoch: Epoch,
+    pub head_root: Hash256,
+    pub head_slot: Slot,
+}
+
+/// Requests the canonical blocks with slots from `start_slot` up to, but excluding,
+/// `start_slot + count`.
+#[derive(Debug, PartialEq, Clone, Encode, Decode)]
+pub struct BlocksByRange {
+    pub request_id: u64,
+    pub start_slot: Slot,
+    pub count: u64,
+}
+
+/// Requests the blocks with each of `block_roots`.
+#[derive(Debug, PartialEq, Clone, Encode, Decode)]
+pub struct BlocksByRoot {
+    pub request_id: u64,
+    pub block_roots: Vec<Hash256>,
+}
+
+/// The response to a `BlocksByRange` or `BlocksByRoot` request with `request_id`.
+///
+/// Blocks which the responding node does not have are omitted.
+#[derive(Debug, PartialEq, Clone, Encode, Decode)]
+pub struct Blocks {
+    pub request_id: u64,
+    pub blocks: Vec<BeaconBlock>,
+}
+
+/// A message exchanged between peers.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Message {
+    Hello(Hello),
+    /// A block to be gossiped.
+    Block(BeaconBlock),
+    /// An attestation to be gossiped.
+    Attestation(FreeAttestation),
+    Status(Status),
+    BlocksByRange(BlocksByRange),
+    BlocksByRoot(BlocksByRoot),
+    Blocks(Blocks),
+}
+
+impl Message {
+    /// Encode the message as a type byte followed by the SSZ encoding of its contents.
+    pub fn encode(&self) -> Vec<u8> {
+        let (message_type, mut bytes) = match self {
+            Message::Hello(hello) => (HELLO, ssz_encode(hello)),
+            Message::Block(block) => (BLOCK, ssz_encode(block)),
+            Message::Attestation(free_attestation) => (ATTESTATION, ssz_encode(free_attestation)),
+            Message::Status(status) => (STATUS, ssz_encode(status)),
+            Message::BlocksByRange(request) => (BLOCKS_BY_RANGE, ssz_encode(request)),
+            Message::BlocksByRoot(request) => (BLOCKS_BY_ROOT, ssz_encode(request)),
+            Message::Blocks(response) => (BLOCKS, ssz_encode(response)),
+        };
+        bytes.insert(0, message_type);
+        bytes
+    }
+
+    /// Decode a message encoded with `Message::encode`, which must fill all of `bytes`.
+    pub fn decode(bytes: &[u8]) -> Result<Message, Error> {
+        let (message, index) = match bytes.first() {
+            Some(&HELLO) => {
+                let (hello, index) = Hello::ssz_decode(bytes, 1)?;
+                (Message::Hello(hello), index)
+            }
+            Some(&BLOCK) => {
+                let (block, index) = BeaconBlock::ssz_decode(bytes, 1)?;
+                (Message::Block(block), index)
+            }
+            Some(&ATTESTATION) => {
+                let (free_attestation, index) = FreeAttestation::ssz_decode(bytes, 1)?;
+                (Message::Attestation(free_attestation), index)
+            }
+            Some(&STATUS) => {
+                let (status, index) = Status::ssz_decode(bytes, 1)?;
+                (Message::Status(status), index)
+            }
+            Some(&BLOCKS_BY_RANGE) => {
+                let (request, index) = BlocksByRange::ssz_decode(bytes, 1)?;
+                (Message::BlocksByRange(request), index)
+            }
+            Some(&BLOCKS_BY_ROOT) => {
+                let (request, index) = BlocksByRoot::ssz_decode(bytes, 1)?;
+                (Message::BlocksByRoot(request), index)
+            }
+            Some(&BLOCKS) => {
+                let (response, index) = Blocks::ssz_decode(bytes, 1)?;
+                (Message::Blocks(response), index)
+            }
+            Some(_) => return Err(Error::InvalidMessage(DecodeError::Invalid)),
+            None => return Err(Error::InvalidMessage(DecodeError::TooShort)),
+        };
+        if index != bytes.len() {
+            return Err(Error::InvalidMessage(DecodeError::TooLong));
+        }
+        Ok(message)
+    }
+}
+
+/// Write an encoded message, prefixed with its length as a big-endian `u32`.
+pub fn write_message<W: Write>(writer: &mut W, bytes: &[u8]) -> Result<(), Error> {
+    if bytes.len() > MAX_MESSAGE_LENGTH {
+        return Err(Error::MessageTooLong(bytes.len()));
+    }
+    writer.write_all(&(bytes.len() as u32).to_be_bytes())?;
+    writer.write_all(bytes)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Read a message written with `write_message`, returning its encoding.
+///
+/// The message is not decoded, so its encoding may be forwarded to other peers.
+pub fn read_message<R: Read>(reader: &mut R) -> Result<Vec<u8>, Error> {
+    let mut length = [0; 4];
+    reader.read_exact(&mut length)?;
+    let length = u32::from_be_bytes(length) as usize;
+    if length > MAX_MESSAGE_LENGTH {
+        return Err(Error::MessageTooLong(length));
+    }
+    let mut bytes = vec![0; length];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::test_utils::{SeedableRng, TestRandom, XorShiftRng};
+
+    #[test]
+    fn test_round_trip() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let messages = vec![
+            Message::Hello(Hello {
+                node_id: 42,
+                genesis_block_root: Hash256::from(&[1; 32][..]),
+            }),
+            Message::Block(BeaconBlock::random_for_test(&mut rng)),
+            Message::Status(Status {
+                finalized_root: Hash256::from(&[2; 32][..]),
+                finalized_epoch: Epoch::new(3),
+                head_root: Hash256::from(&[4; 32][..]),
+                head_slot: Slot::new(5),
+            }),
+            Message::BlocksByRange(BlocksByRange {
+                request_id: 6,
+                start_slot: Slot::new(7),
+                count: 8,
+            }),
+            Message::BlocksByRoot(BlocksByRoot {
+                request_id: 9,
+                block_roots: vec![Hash256::from(&[10; 32][..]), Hash256::zero()],
+            }),
+            Message::Blocks(Blocks {
+                request_id: 11,
+                blocks: vec![
+                    BeaconBlock::random_for_test(&mut rng),
+                    BeaconBlock::random_for_test(&mut rng),
+                ],
+            }),
+        ];
+
+        for message in messages {
+            let mut stream = vec![];
+            write_message(&mut stream, &message.encode()).unwrap();
+            let bytes = read_message(&mut &stream[..]).unwrap();
+            assert_eq!(Message::decode(&bytes), Ok(message));
+        }
+    }
+
+    #[test]
+    fn test_invalid_messages() {
+        let mut bytes = Message::Hello(Hello {
+            node_id: 42,
+            genesis_block_root: Hash256::zero(),
+        })
+        .encode();
+
+        assert_eq!(
+            Message::decode(&[]),
+            Err(Error::InvalidMessage(DecodeError::TooShort))
+        );
+        assert_eq!(
+            Message::decode(&bytes[..bytes.len() - 1]),
+            Err(Error::InvalidMessage(DecodeError::TooShort))
+        );
+        bytes.push(0);
+        assert_eq!(
+            Message::decode(&bytes),
+            Err(Error::InvalidMessage(DecodeError::TooLong))
+        );
+        bytes[0] = 255;
+        assert_eq!(
+            Message::decode(&bytes),
+            Err(Error::InvalidMessage(DecodeError::Invalid))
+        );
+    }
+
+    #[test]
+    fn test_read_message() {
+        let stream = ((MAX_MESSAGE_LENGTH + 1) as u32).to_be_bytes();
+        assert_eq!(
+            read_message(&mut &stream[..]),
+            Err(Error::MessageTooLong(MAX_MESSAGE_LENGTH + 1))
+        );
+
+        let stream = [0, 0, 0, 2, 0];
+        assert_eq!(read_message(&mut &stream[..]), Err(Error::ConnectionClosed));
+    }
+}
@@ -0,0 +1,404 @@
+use crate::message::{read_message, write_message, Hello, Message};
+use crate::peer_manager::{Peer, PeerId, PeerManager, MAX_QUEUED_MESSAGES};
+use crate::sync_manager::{SyncManager, Transport, MAX_BLOCKS_PER_REQUEST, REQUEST_TIMEOUT};
+use crate::Error;
+use ::beacon_chain::{AggregationMessage, BeaconChainApi};
+use hashing::hash;
+use parking_lot::{Mutex, RwLock};
+use slog::{debug, error, info, warn, Logger};
+use std::collections::{HashSet, VecDeque};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::sync_channel;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use types::{BeaconBlock, FreeAttestation, Hash256, Slot};
+
+/// The default maximum number of connected peers.
+pub const DEFAULT_MAX_PEERS: usize = 16;
+/// The number of gossiped messages remembered, so they are not processed or forwarded again.
+const SEEN_MESSAGES: usize = 4_096;
+/// How long to wait whilst connecting to a peer and for its `Hello`.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone)]
+pub struct NetworkConfig {
+    /// The address on which connections from peers are accepted.
+    pub listen_address: SocketAddr,
+    /// The nodes connected to on start up.
+    pub boot_nodes: Vec<SocketAddr>,
+    /// The maximum number of connected peers, inbound and outbound.
+    pub max_peers: usize,
+}
+
+/// The identifiers of recently seen gossip messages, forgetting the oldest beyond `capacity`.
+struct SeenMessages {
+    ids: HashSet<Hash256>,
+    order: VecDeque<Hash256>,
+    capacity: usize,
+}
+
+impl SeenMessages {
+    fn new(capacity: usize) -> Self {
+        Self {
+            ids: HashSet::new(),
+            order: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    /// Record a message as seen. Returns `false` if it had already been seen.
+    fn insert(&mut self, id: Hash256) -> bool {
+        if !self.ids.insert(id) {
+            return false;
+        }
+        self.order.push_back(id);
+        if self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.ids.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+/// Gossips blocks and attestations between this node and its peers, and syncs blocks from them.
+///
+/// Peers exchange SSZ encoded `Message`s over TCP, each connection beginning with a `Hello` from
+/// both nodes, followed by their `Status`. Gossiped blocks and attestations are processed by the
+/// `BeaconChain` and forwarded to the other peers if they are valid and have not been seen before.
+/// Sync messages are handled by the `SyncManager`. Peers which send messages that may never be
+/// valid are disconnected.
+///
+/// Each connection is served by a thread reading from it and a thread writing to it.
+pub struct NetworkService {
+    chain: Arc<dyn BeaconChainApi>,
+    /// Sent in our `Hello`, so connections from this node to itself may be detected.
+    node_id: u64,
+    genesis_block_root: Hash256,
+    listen_address: SocketAddr,
+    peers: RwLock<PeerManager>,
+    /// The number of inbound connections whose handshake is in progress.
+    handshakes: AtomicUsize,
+    max_peers: usize,
+    next_peer_id: Mutex<PeerId>,
+    seen_messages: Mutex<SeenMessages>,
+    sync_manager: Mutex<SyncManager>,
+    log: Logger,
+}
+
+impl NetworkService {
+    /// Listen for peers on `config.listen_address`, then connect to each of `config.boot_nodes`.
+    pub fn start(
+        chain: Arc<dyn BeaconChainApi>,
+        config: &NetworkConfig,
+        log: Logger,
+    ) -> Result<Arc<Self>, Error> {
+        let listener = TcpListener::bind(config.listen_address)?;
+        let service = Arc::new(Self {
+            genesis_block_root: chain.genesis_block_root()?,
+            sync_manager: Mutex::new(SyncManager::new(
+                chain.clone(),
+                MAX_BLOCKS_PER_REQUEST,
+                config.max_peers,
+                log.clone(),
+            )),
+            chain,
+            node_id: rand::random(),
+            listen_address: listener.local_addr()?,
+            peers: RwLock::new(PeerManager::new(config.max_peers)),
+            handshakes: AtomicUsize::new(0),
+            max_peers: config.max_peers,
+            next_peer_id: Mutex::new(0),
+            seen_messages: Mutex::new(SeenMessages::new(SEEN_MESSAGES)),
+            log,
+        });
+        info!(service.log, "Network listening"; "address" => service.listen_address.to_string(), "node_id" => service.node_id);
+
+        let accepting = service.clone();
+        thread::spawn(move || accepting.accept(listener));
+
+        let expiring = service.clone();
+        thread::spawn(move || loop {
+            thread::sleep(REQUEST_TIMEOUT / 2);
+            expiring
+                .sync_manager
+                .lock()
+                .expire_requests(Instant::now(), &*expiring.peers.read());
+        });
+
+        for address in &config.boot_nodes {
+            if let Err(e) = service.connect(*address) {
+                warn!(service.log, "Unable to connect to boot node"; "address" => address.to_string(), "error" => format!("{:?}", e));
+            }
+        }
+
+        Ok(service)
+    }
+
+    /// Returns the address on which connections from peers are accepted.
+    pub fn listen_address(&self) -> SocketAddr {
+        self.listen_address
+    }
+
+    pub fn peer_count(&self) -> usize {
+        self.peers.read().len()
+    }
+
+    /// Connect to the node listening on `address` and add it as a peer.
+    pub fn connect(self: &Arc<Self>, address: SocketAddr) -> Result<PeerId, Error> {
+        let stream = TcpStream::connect_timeout(&address, HANDSHAKE_TIMEOUT)?;
+        self.add_peer(stream)
+    }
+
+    /// Gossip a block which has been imported into the chain, e.g., one published by a local
+    /// validator.
+    pub fn publish_block(&self, block: BeaconBlock) {
+        self.publish(&Message::Block(block));
+    }
+
+    /// Gossip an attestation which has been processed by the chain, e.g., one published by a
+    /// local validator.
+    pub fn publish_attestation(&self, free_attestation: FreeAttestation) {
+        self.publish(&Message::Attestation(free_attestation));
+    }
+
+    /// Transition the state of the chain to `slot`, gossiping the queued blocks which are imported
+    /// as their slot is reached.
+    pub fn advance_state(&self, slot: Slot) {
+        match self.chain.advance_state(slot) {
+            Ok(blocks) => {
+                for block in blocks {
+                    self.publish_block(block);
+                }
+            }
+            Err(e) => {
+                error!(self.log, "Unable to advance state"; "slot" => slot.as_u64(), "error" => format!("{:?}", e))
+            }
+        }
+    }
+
+    /// Transition the state of the chain to the present slot of the slot clock, as
+    /// `advance_state`.
+    pub fn catch_up_with_slot_clock(&self) {
+        if let Some(slot) = self.chain.read_slot_clock() {
+            self.advance_state(slot);
+        }
+    }
+
+    fn publish(&self, message: &Message) {
+        let bytes = message.encode();
+        self.seen_messages.lock().insert(message_id(&bytes));
+        self.peers.read().broadcast(&Arc::new(bytes), None);
+    }
+
+    /// Add each node which connects to `listener` as a peer, until the listener fails.
+    ///
+    /// Connections are closed without a handshake whilst the connected peers and those part way
+    /// through their handshake number `max_peers`.
+    fn accept(self: Arc<Self>, listener: TcpListener) {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let handshakes = self.handshakes.fetch_add(1, Ordering::SeqCst);
+                    if self.peers.read().len() + handshakes >= self.max_peers {
+                        self.handshakes.fetch_sub(1, Ordering::SeqCst);
+                        debug!(self.log, "Rejected peer"; "error" => format!("{:?}", Error::TooManyPeers));
+                        continue;
+                    }
+                    let service = self.clone();
+                    thread::spawn(move || {
+                        let added = service.add_peer(stream);
+                        service.handshakes.fetch_sub(1, Ordering::SeqCst);
+                        if let Err(e) = added {
+                            debug!(service.log, "Rejected peer"; "error" => format!("{:?}", e));
+                        }
+                    });
+                }
+                Err(e) => {
+                    error!(self.log, "Network listener failed"; "error" => format!("{:?}", e));
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Exchange `Hello`s with the node at the other end of `stream` and, if it is following the
+    /// same chain, add it as a peer, send it our `Status` and begin serving the connection.
+    fn add_peer(self: &Arc<Self>, stream: TcpStream) -> Result<PeerId, Error> {
+        let address = stream.peer_addr()?;
+        let mut writer = stream.try_clone()?;
+        let mut reader = stream;
+
+        let hello = Hello {
+            node_id: self.node_id,
+            genesis_block_root: self.genesis_block_root,
+        };
+        write_message(&mut writer, &Message::Hello(hello).encode())?;
+
+        reader.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+        let hello = match Message::decode(&read_message(&mut reader)?)? {
+            Message::Hello(hello) => hello,
+            _ => return Err(Error::MissingHello),
+        };
+        reader.set_read_timeout(None)?;
+
+        if hello.node_id == self.node_id {
+            return Err(Error::SelfConnection);
+        }
+        if hello.genesis_block_root != self.genesis_block_root {
+            return Err(Error::GenesisMismatch);
+        }
+
+        let (sender, receiver) = sync_channel::<Arc<Vec<u8>>>(MAX_QUEUED_MESSAGES);
+        let peer_id = {
+            let mut next_peer_id = self.next_peer_id.lock();
+            *next_peer_id += 1;
+            *next_peer_id
+        };
+        let peer_count = {
+            let mut peers = self.peers.write();
+            peers.add(peer_id, Peer::new(hello.node_id, address, sender))?;
+            peers.len()
+        };
+        info!(self.log, "Peer connected"; "peer_id" => peer_id, "address" => address.to_string(), "peers" => peer_count);
+
+        // Write queued messages until the peer is removed, then close the connection.
+        thread::spawn(move || {
+            for bytes in receiver {
+                if write_message(&mut writer, &bytes).is_err() {
+                    break;
+                }
+            }
+            let _ = writer.shutdown(Shutdown::Both);
+        });
+
+        let status = self.sync_manager.lock().status();
+        self.peers.read().send(peer_id, Message::Status(status));
+
+        let service = self.clone();
+        thread::spawn(move || service.handle_connection(peer_id, reader));
+
+        Ok(peer_id)
+    }
+
+    /// Process each message read from a peer until the connection is closed, or the peer
+    /// misbehaves, then remove the peer.
+    fn handle_connection(&self, peer_id: PeerId, mut stream: TcpStream) {
+        let result = loop {
+            let handled = read_message(&mut stream).and_then(|bytes| self.handle(peer_id, bytes));
+            if let Err(e) = handled {
+                break e;
+            }
+        };
+
+        self.sync_manager.lock().remove_peer(peer_id);
+
+        // The peer may have been removed already, closing the connection.
+        if self.peers.write().remove(peer_id) {
+            match result {
+                Error::ConnectionClosed => {
+                    info!(self.log, "Peer disconnected"; "peer_id" => peer_id)
+                }
+                e => {
+                    warn!(self.log, "Disconnected peer"; "peer_id" => peer_id, "error" => format!("{:?}", e))
+                }
+            }
+        }
+    }
+
+    /// Process a message from a peer, forwarding gossip to the other peers if it is new and valid.
+    ///
+    /// Returns an error if the peer should be disconnected.
+    fn handle(&self, peer_id: PeerId, bytes: Vec<u8>) -> Result<(), Error> {
+        // Blocks and attestations for the present slot may only be processed once the state has
+        // reached it.
+        self.catch_up_with_slot_clock();
+
+        let forward = match Message::decode(&bytes)? {
+            Message::Hello(_) => return Err(Error::UnexpectedHello),
+            Message::Block(block) => {
+                self.is_new(&bytes)
+                    && self.sync_manager.lock().process_block(
+                        peer_id,
+                        block,
+                        &*self.peers.read(),
+                    )?
+            }
+            Message::Attestation(free_attestation) => {
+                self.is_new(&bytes) && self.process_attestation(peer_id, free_attestation)?
+            }
+            message => {
+                return self
+                    .sync_manager
+                    .lock()
+                    .handle(peer_id, message, &*self.peers.read());
+            }
+        };
+        if forward {
+            self.peers.read().broadcast(&Arc::new(bytes), Some(peer_id));
+        }
+
+        Ok(())
+    }
+
+    /// Record a gossip message as seen, returning `false` if it had been seen already.
+    fn is_new(&self, bytes: &[u8]) -> bool {
+        self.seen_messages.lock().insert(message_id(bytes))
+    }
+
+    /// Process a gossiped attestation, returning `Ok(true)` if it was new to the attestation
+    /// aggregator.
+    ///
+    /// Whether an attestation is for the correct committee depends upon the state of this node's
+    /// chain, so only attestations with an invalid signature return an error.
+    fn process_attestation(
+        &self,
+        peer_id: PeerId,
+        free_attestation: FreeAttestation,
+    ) -> Result<bool, Error> {
+        let validator_index = free_attestation.validator_index;
+        match self.chain.process_free_attestation(free_attestation) {
+            Ok(outcome) => match outcome.message {
+                AggregationMessage::Aggregated | AggregationMessage::NewAttestationCreated => {
+                    Ok(true)
+                }
+                AggregationMessage::BadSignature => Err(Error::InvalidAttestation),
+                message => {
+                    debug!(self.log, "Ignored gossiped attestation"; "peer_id" => peer_id, "validator_index" => validator_index, "reason" => format!("{:?}", message));
+                    Ok(false)
+                }
+            },
+            Err(e) => {
+                error!(self.log, "Unable to process gossiped attestation"; "error" => format!("{:?}", e));
+                Ok(false)
+            }
+        }
+    }
+}
+
+/// Identifies a gossip message by the hash of its encoding.
+fn message_id(bytes: &[u8]) -> Hash256 {
+    Hash256::from(&hash(bytes)[..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seen_messages() {
+        let mut seen = SeenMessages::new(2);
+        let ids: Vec<Hash256> = (0..3).map(|i| Hash256::from(&[i; 32][..])).collect();
+
+        assert!(seen.insert(ids[0]));
+        assert!(!seen.insert(ids[0]));
+        assert!(seen.insert(ids[1]));
+
+        // The oldest message is forgotten.
+        assert!(seen.insert(ids[2]));
+        assert!(seen.insert(ids[0]));
+        assert!(!seen.insert(ids[2]));
+    }
+}
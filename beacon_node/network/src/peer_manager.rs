@@ -0,0 +1,183 @@
+use crate::message::Message;
+use crate::sync_manager::Transport;
+use crate::Error;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::mpsc::SyncSender;
+use std::sync::Arc;
+
+/// Identifies a connection to a peer, unique for the lifetime of the `NetworkService`.
+pub type PeerId = u64;
+
+/// The maximum number of encoded messages queued to be written to a peer. Further messages are
+/// dropped until the peer has read some of the queue.
+pub const MAX_QUEUED_MESSAGES: usize = 256;
+
+/// A connected peer.
+pub struct Peer {
+    /// The `node_id` from the peer's `Hello`.
+    pub node_id: u64,
+    /// The remote address of the connection.
+    pub address: SocketAddr,
+    /// Queues encoded messages to be written to the connection.
+    sender: SyncSender<Arc<Vec<u8>>>,
+}
+
+impl Peer {
+    pub fn new(node_id: u64, address: SocketAddr, sender: SyncSender<Arc<Vec<u8>>>) -> Self {
+        Self {
+            node_id,
+            address,
+            sender,
+        }
+    }
+
+    /// Queue an encoded message to be sent to the peer.
+    ///
+    /// Returns `false` if the message was dropped, because the connection has been closed or the
+    /// queue is full.
+    pub fn send(&self, bytes: Arc<Vec<u8>>) -> bool {
+        self.sender.try_send(bytes).is_ok()
+    }
+}
+
+/// The set of connected peers.
+///
+/// Removing a peer drops its `Sender`, which closes the connection.
+pub struct PeerManager {
+    peers: HashMap<PeerId, Peer>,
+    max_peers: usize,
+}
+
+impl PeerManager {
+    pub fn new(max_peers: usize) -> Self {
+        Self {
+            peers: HashMap::new(),
+            max_peers,
+        }
+    }
+
+    /// Add a newly connected peer.
+    ///
+    /// Returns an error if the node is already connected, or there are already `max_peers`.
+    pub fn add(&mut self, peer_id: PeerId, peer: Peer) -> Result<(), Error> {
+        if self.is_connected(peer.node_id) {
+            return Err(Error::AlreadyConnected);
+        }
+        if self.is_full() {
+            return Err(Error::TooManyPeers);
+        }
+        self.peers.insert(peer_id, peer);
+        Ok(())
+    }
+
+    /// Remove a peer, closing its connection. Returns `false` if the peer was not connected.
+    pub fn remove(&mut self, peer_id: PeerId) -> bool {
+        self.peers.remove(&peer_id).is_some()
+    }
+
+    /// Returns `true` if there is a peer with `node_id`.
+    pub fn is_connected(&self, node_id: u64) -> bool {
+        self.peers.values().any(|peer| peer.node_id == node_id)
+    }
+
+    pub fn get(&self, peer_id: PeerId) -> Option<&Peer> {
+        self.peers.get(&peer_id)
+    }
+
+    /// Returns `true` if there are already `max_peers`.
+    pub fn is_full(&self) -> bool {
+        self.peers.len() >= self.max_peers
+    }
+
+    pub fn len(&self) -> usize {
+        self.peers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.peers.is_empty()
+    }
+
+    /// Send an encoded message to every peer, except `source` (the peer from which it was
+    /// received, if any).
+    pub fn broadcast(&self, bytes: &Arc<Vec<u8>>, source: Option<PeerId>) {
+        for (peer_id, peer) in &self.peers {
+            if Some(*peer_id) != source {
+                peer.send(bytes.clone());
+            }
+        }
+    }
+}
+
+impl Transport for PeerManager {
+    /// Encode a message and queue it to be sent to the peer, if it is connected.
+    fn send(&self, peer_id: PeerId, message: Message) {
+        if let Some(peer) = self.get(peer_id) {
+            peer.send(Arc::new(message.encode()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::{sync_channel, Receiver};
+
+    fn peer(node_id: u64) -> (Peer, Receiver<Arc<Vec<u8>>>) {
+        let (sender, receiver) = sync_channel(MAX_QUEUED_MESSAGES);
+        let address = SocketAddr::from(([127, 0, 0, 1], 9000 + node_id as u16));
+        (Peer::new(node_id, address, sender), receiver)
+    }
+
+    #[test]
+    fn test_add_and_remove() {
+        let mut peers = PeerManager::new(2);
+
+        assert_eq!(peers.add(0, peer(10).0), Ok(()));
+        assert_eq!(peers.add(1, peer(10).0), Err(Error::AlreadyConnected));
+        assert_eq!(peers.add(1, peer(11).0), Ok(()));
+        assert!(peers.is_full());
+        assert_eq!(peers.add(2, peer(12).0), Err(Error::TooManyPeers));
+        assert_eq!(peers.len(), 2);
+        assert!(peers.is_connected(11));
+
+        assert!(peers.remove(1));
+        assert!(!peers.remove(1));
+        assert!(!peers.is_connected(11));
+        assert_eq!(peers.add(2, peer(12).0), Ok(()));
+    }
+
+    #[test]
+    fn test_broadcast() {
+        let mut peers = PeerManager::new(3);
+        let mut receivers = vec![];
+        for peer_id in 0..3 {
+            let (peer, receiver) = peer(peer_id);
+            peers.add(peer_id, peer).unwrap();
+            receivers.push(receiver);
+        }
+
+        let bytes = Arc::new(vec![1, 2, 3]);
+        peers.broadcast(&bytes, Some(1));
+        assert_eq!(receivers[0].try_recv(), Ok(bytes.clone()));
+        assert!(receivers[1].try_recv().is_err());
+        assert_eq!(receivers[2].try_recv(), Ok(bytes.clone()));
+
+        // Removing a peer closes its channel.
+        peers.remove(2);
+        assert!(receivers[2].recv().is_err());
+    }
+
+    #[test]
+    fn test_send_drops_messages_when_queue_is_full() {
+        let (peer, receiver) = peer(0);
+        for _ in 0..MAX_QUEUED_MESSAGES {
+            assert!(peer.send(Arc::new(vec![1])));
+        }
+        assert!(!peer.send(Arc::new(vec![2])));
+
+        // Reading from the queue makes room for further messages.
+        assert_eq!(receiver.recv(), Ok(Arc::new(vec![1])));
+        assert!(peer.send(Arc::new(vec![3])));
+    }
+}
@@ -0,0 +1,433 @@
+use crate::message::{Blocks, BlocksByRange, BlocksByRoot, Message, Status};
+use crate::peer_manager::PeerId;
+use crate::Error;
+use ::beacon_chain::{BeaconChainApi, BlockProcessingOutcome, InvalidBlock};
+use slog::{debug, error, info, Logger};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use types::{BeaconBlock, Hash256, Slot};
+
+/// The maximum number of blocks requested from, or served to, a peer in a single request.
+pub const MAX_BLOCKS_PER_REQUEST: u64 = 64;
+/// How long a peer has to respond to a request before it is abandoned.
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Sends messages to peers.
+///
+/// Implemented by the `PeerManager` of the `NetworkService`, so the `SyncManager` may also be
+/// driven over a local transport.
+pub trait Transport {
+    fn send(&self, peer_id: PeerId, message: Message);
+}
+
+/// A request sent to a peer, which awaits its `Blocks` response.
+#[derive(Debug, PartialEq)]
+enum Request {
+    Range { start_slot: Slot, count: u64 },
+    Roots(Vec<Hash256>),
+}
+
+/// A request and the time it was sent.
+struct PendingRequest {
+    request: Request,
+    sent: Instant,
+}
+
+/// Brings the chain up to date with the chains of its peers.
+///
+/// Peers exchange `Status`es upon connecting. If a peer's head is later than ours and unknown to
+/// us, the blocks following our head are requested from it by slot range, one batch at a time.
+///
+/// Blocks whose parent is unknown (e.g., those gossiped whilst syncing, or on another fork) are
+/// held in the orphan pool of the chain, whilst the missing ancestor of each is requested by root
+/// from the peer which sent it. The orphan pool is shared equally between `max_peers`, so the
+/// orphans from one peer may not evict those from another.
+///
+/// Requests which are not answered within `REQUEST_TIMEOUT` are abandoned by `expire_requests`,
+/// after which a response to them is unexpected.
+pub struct SyncManager {
+    chain: Arc<dyn BeaconChainApi>,
+    blocks_per_request: u64,
+    max_peers: usize,
+    /// The latest `Status` of each peer.
+    peers: HashMap<PeerId, Status>,
+    /// The roots of the orphans received from each peer.
+    orphans: HashMap<PeerId, Vec<Hash256>>,
+    /// Requests awaiting a response, by peer and `request_id`.
+    requests: HashMap<(PeerId, u64), PendingRequest>,
+    next_request_id: u64,
+    log: Logger,
+}
+
+impl SyncManager {
+    /// Create a new manager for at most `max_peers`, requesting at most `blocks_per_request`
+    /// blocks at a time during a range sync.
+    pub fn new(
+        chain: Arc<dyn BeaconChainApi>,
+        blocks_per_request: u64,
+        max_peers: usize,
+        log: Logger,
+    ) -> Self {
+        Self {
+            chain,
+            blocks_per_request: blocks_per_request.min(MAX_BLOCKS_PER_REQUEST),
+            max_peers,
+            peers: HashMap::new(),
+            orphans: HashMap::new(),
+            requests: HashMap::new(),
+            next_request_id: 0,
+            log,
+        }
+    }
+
+    /// Returns the maximum number of orphans from a peer held in the orphan pool of the chain, its
+    /// share of the pool. Further blocks from the peer with an unknown parent are ignored.
+    pub fn max_orphans_per_peer(&self) -> usize {
+        self.chain.max_orphans() / self.max_peers.max(1)
+    }
+
+    /// Returns the `Status` of our chain, to be sent to peers.
+    pub fn status(&self) -> Status {
+        Status {
+            finalized_root: self.chain.finalized_block_root(),
+            finalized_epoch: self.chain.finalized_epoch(),
+            head_root: self.chain.head_block_root(),
+            head_slot: self.chain.head_slot(),
+        }
+    }
+
+    /// Returns `true` if blocks are being requested from a peer by slot range.
+    pub fn is_syncing(&self) -> bool {
+        self.requests.values().any(|pending| match pending.request {
+            Request::Range { .. } => true,
+            Request::Roots(_) => false,
+        })
+    }
+
+    /// Abandon the requests sent before `now - REQUEST_TIMEOUT`.
+    ///
+    /// If a range sync is abandoned, it is resumed from a peer which is still ahead, preferring
+    /// peers other than the one which did not respond.
+    pub fn expire_requests(&mut self, now: Instant, transport: &dyn Transport) {
+        let was_syncing = self.is_syncing();
+        let mut unresponsive = vec![];
+        self.requests.retain(|(peer_id, _), pending| {
+            let expired = now.duration_since(pending.sent) >= REQUEST_TIMEOUT;
+            if expired {
+                unresponsive.push(*peer_id);
+            }
+            !expired
+        });
+        if unresponsive.is_empty() || !was_syncing || self.is_syncing() {
+            return;
+        }
+
+        let mut ahead: Vec<PeerId> = self
+            .peers
+            .iter()
+            .filter(|(_, status)| self.is_ahead(status))
+            .map(|(peer_id, _)| *peer_id)
+            .collect();
+        ahead.sort_by_key(|peer_id| (unresponsive.contains(peer_id), *peer_id));
+        if let Some(peer_id) = ahead.first() {
+            let start_slot = self.chain.head_slot() + 1;
+            info!(self.log, "Resuming sync from peer"; "peer_id" => peer_id, "start_slot" => start_slot.as_u64());
+            self.request_range(*peer_id, start_slot, transport);
+        }
+    }
+
+    /// Forget a disconnected peer and abandon its outstanding requests.
+    pub fn remove_peer(&mut self, peer_id: PeerId) {
+        self.peers.remove(&peer_id);
+        self.orphans.remove(&peer_id);
+        self.requests.retain(|(id, _), _| *id != peer_id);
+    }
+
+    /// Process a `Status`, `BlocksByRange`, `BlocksByRoot` or `Blocks` message from a peer.
+    ///
+    /// Returns an error if the peer should be disconnected.
+    pub fn handle(
+        &mut self,
+        peer_id: PeerId,
+        message: Message,
+        transport: &dyn Transport,
+    ) -> Result<(), Error> {
+        match message {
+            Message::Status(status) => self.on_status(peer_id, status, transport),
+            Message::BlocksByRange(request) => self.on_blocks_by_range(peer_id, request, transport),
+            Message::BlocksByRoot(request) => self.on_blocks_by_root(peer_id, request, transport),
+            Message::Blocks(response) => self.on_blocks(peer_id, response, transport),
+            _ => Err(Error::UnexpectedMessage),
+        }
+    }
+
+    /// Import a block received from a peer.
+    ///
+    /// If the parent of the block is unknown, the missing ancestor of the block is requested from
+    /// the peer, unless it has sent `max_orphans_per_peer` orphans already, in which case the block
+    /// is ignored. Returns `Ok(true)` if the block was imported, or an error if it may never be
+    /// valid.
+    pub fn process_block(
+        &mut self,
+        peer_id: PeerId,
+        block: BeaconBlock,
+        transport: &dyn Transport,
+    ) -> Result<bool, Error> {
+        let block_root = block.canonical_root();
+        if self.is_known_block(&block_root) || self.chain.is_orphan(&block_root) {
+            return Ok(false);
+        }
+
+        let slot = block.slot.as_u64();
+        if !self.is_known_block(&block.parent_root)
+            && self.orphan_count(peer_id) >= self.max_orphans_per_peer()
+        {
+            debug!(self.log, "Ignored orphan block"; "peer_id" => peer_id, "slot" => slot, "reason" => "too many orphans from peer");
+            return Ok(false);
+        }
+
+        match self.chain.process_block(block) {
+            Ok(BlockProcessingOutcome::ValidBlock(_)) => {
+                debug!(self.log, "Imported block"; "peer_id" => peer_id, "slot" => slot);
+                Ok(true)
+            }
+            Ok(BlockProcessingOutcome::InvalidBlock(reason)) => match reason {
+                InvalidBlock::ParentUnknown => {
+                    debug!(self.log, "Queued orphan block"; "peer_id" => peer_id, "slot" => slot);
+                    self.orphans.entry(peer_id).or_default().push(block_root);
+                    self.request_missing_ancestor(peer_id, &block_root, transport);
+                    Ok(false)
+                }
+                InvalidBlock::StateRootMismatch | InvalidBlock::PerBlockProcessingError(_) => {
+                    Err(Error::InvalidBlock)
+                }
+                reason => {
+                    debug!(self.log, "Ignored block"; "peer_id" => peer_id, "slot" => slot, "reason" => format!("{:?}", reason));
+                    Ok(false)
+                }
+            },
+            Err(e) => {
+                error!(self.log, "Unable to process block"; "error" => format!("{:?}", e));
+                Ok(false)
+            }
+        }
+    }
+
+    /// Record the peer's status, beginning a range sync from it if its head is ahead of ours.
+    fn on_status(
+        &mut self,
+        peer_id: PeerId,
+        status: Status,
+        transport: &dyn Transport,
+    ) -> Result<(), Error> {
+        let is_ahead = self.is_ahead(&status);
+        self.peers.insert(peer_id, status);
+        if is_ahead && !self.is_syncing() {
+            let start_slot = self.chain.head_slot() + 1;
+            info!(self.log, "Syncing from peer"; "peer_id" => peer_id, "start_slot" => start_slot.as_u64());
+            self.request_range(peer_id, start_slot, transport);
+        }
+        Ok(())
+    }
+
+    /// Serve the canonical blocks in the requested range, up to `MAX_BLOCKS_PER_REQUEST`.
+    fn on_blocks_by_range(
+        &mut self,
+        peer_id: PeerId,
+        request: BlocksByRange,
+        transport: &dyn Transport,
+    ) -> Result<(), Error> {
+        let count = request.count.min(MAX_BLOCKS_PER_REQUEST);
+        let blocks = match self.chain.blocks_by_range(request.start_slot, count) {
+            Ok(blocks) => blocks,
+            Err(e) => {
+                error!(self.log, "Unable to read blocks by range"; "error" => format!("{:?}", e));
+                vec![]
+            }
+        };
+        transport.send(
+            peer_id,
+            Message::Blocks(Blocks {
+                request_id: request.request_id,
+                blocks,
+            }),
+        );
+        Ok(())
+    }
+
+    /// Serve each of the requested blocks which is known, up to `MAX_BLOCKS_PER_REQUEST`.
+    fn on_blocks_by_root(
+        &mut self,
+        peer_id: PeerId,
+        request: BlocksByRoot,
+        transport: &dyn Transport,
+    ) -> Result<(), Error> {
+        let blocks = request
+            .block_roots
+            .iter()
+            .take(MAX_BLOCKS_PER_REQUEST as usize)
+            .filter_map(|block_root| match self.chain.get_block(block_root) {
+                Ok(block) => block,
+                Err(e) => {
+                    error!(self.log, "Unable to read block"; "error" => format!("{:?}", e));
+                    None
+                }
+            })
+            .collect();
+        transport.send(
+            peer_id,
+            Message::Blocks(Blocks {
+                request_id: request.request_id,
+                blocks,
+            }),
+        );
+        Ok(())
+    }
+
+    /// Import the blocks in response to one of our requests, continuing a range sync if the peer
+    /// is still ahead.
+    ///
+    /// Responses which were not requested, or contain blocks which were not requested, return an
+    /// error.
+    fn on_blocks(
+        &mut self,
+        peer_id: PeerId,
+        response: Blocks,
+        transport: &dyn Transport,
+    ) -> Result<(), Error> {
+        let request = self
+            .requests
+            .remove(&(peer_id, response.request_id))
+            .ok_or(Error::UnexpectedResponse)?
+            .request;
+        let blocks = response.blocks;
+
+        match request {
+            Request::Range { start_slot, count } => {
+                let end_slot = start_slot + count;
+                if blocks.len() as u64 > count
+                    || blocks
+                        .iter()
+                        .any(|block| block.slot < start_slot || block.slot >= end_slot)
+                    || blocks.windows(2).any(|pair| pair[0].slot >= pair[1].slot)
+                {
+                    return Err(Error::InvalidResponse);
+                }
+
+                for block in blocks {
+                    self.process_block(peer_id, block, transport)?;
+                }
+
+                let is_ahead = self.peers.get(&peer_id).map(|status| {
+                    status.head_slot >= end_slot && !self.is_known_block(&status.head_root)
+                });
+                if is_ahead == Some(true) {
+                    self.request_range(peer_id, end_slot, transport);
+                } else {
+                    info!(self.log, "Finished syncing from peer"; "peer_id" => peer_id, "head_slot" => self.chain.head_slot().as_u64());
+                }
+            }
+            Request::Roots(block_roots) => {
+                if blocks
+                    .iter()
+                    .any(|block| !block_roots.contains(&block.canonical_root()))
+                {
+                    return Err(Error::InvalidResponse);
+                }
+
+                for block in blocks {
+                    self.process_block(peer_id, block, transport)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns `true` if the peer's head is later than ours and unknown to us.
+    fn is_ahead(&self, status: &Status) -> bool {
+        status.head_slot > self.chain.head_slot() && !self.is_known_block(&status.head_root)
+    }
+
+    fn is_known_block(&self, block_root: &Hash256) -> bool {
+        match self.chain.is_known_block(block_root) {
+            Ok(is_known) => is_known,
+            Err(e) => {
+                error!(self.log, "Unable to read block"; "error" => format!("{:?}", e));
+                false
+            }
+        }
+    }
+
+    /// Returns the number of orphans from the peer which remain in the orphan pool, forgetting
+    /// those which have since been imported or dropped.
+    fn orphan_count(&mut self, peer_id: PeerId) -> usize {
+        let chain = &self.chain;
+        self.orphans.get_mut(&peer_id).map_or(0, |orphans| {
+            orphans.retain(|block_root| chain.is_orphan(block_root));
+            orphans.len()
+        })
+    }
+
+    /// Request the block which must be imported before the orphan with `block_root` may be,
+    /// unless it has already been requested.
+    fn request_missing_ancestor(
+        &mut self,
+        peer_id: PeerId,
+        block_root: &Hash256,
+        transport: &dyn Transport,
+    ) {
+        let missing_root = match self.chain.missing_ancestor_root(block_root) {
+            Some(missing_root) => missing_root,
+            None => return,
+        };
+        let requested = self
+            .requests
+            .values()
+            .any(|pending| match &pending.request {
+                Request::Roots(block_roots) => block_roots.contains(&missing_root),
+                Request::Range { .. } => false,
+            });
+        if requested {
+            return;
+        }
+
+        let request_id = self.next_request_id();
+        self.insert_request(peer_id, request_id, Request::Roots(vec![missing_root]));
+        transport.send(
+            peer_id,
+            Message::BlocksByRoot(BlocksByRoot {
+                request_id,
+                block_roots: vec![missing_root],
+            }),
+        );
+    }
+
+    fn request_range(&mut self, peer_id: PeerId, start_slot: Slot, transport: &dyn Transport) {
+        let request_id = self.next_request_id();
+        let count = self.blocks_per_request;
+        self.insert_request(peer_id, request_id, Request::Range { start_slot, count });
+        transport.send(
+            peer_id,
+            Message::BlocksByRange(BlocksByRange {
+                request_id,
+                start_slot,
+                count,
+            }),
+        );
+    }
+
+    fn insert_request(&mut self, peer_id: PeerId, request_id: u64, request: Request) {
+        let pending = PendingRequest {
+            request,
+            sent: Instant::now(),
+        };
+        self.requests.insert((peer_id, request_id), pending);
+    }
+
+    fn next_request_id(&mut self) -> u64 {
+        self.next_request_id += 1;
+        self.next_request_id
+    }
+}
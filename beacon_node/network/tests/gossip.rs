@@ -0,0 +1,211 @@
+use beacon_chain::BlockProcessingOutcome;
+use network::{read_message, write_message, Error, Hello, Message, NetworkConfig, NetworkService};
+use slog::{o, Logger};
+use std::net::{SocketAddr, TcpStream};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use test_harness::{BeaconChainHarness, TestingBeaconChain};
+use types::ChainSpec;
+
+fn start_node(
+    chain: Arc<TestingBeaconChain>,
+    boot_nodes: Vec<SocketAddr>,
+    max_peers: usize,
+) -> Arc<NetworkService> {
+    let config = NetworkConfig {
+        listen_address: SocketAddr::from(([127, 0, 0, 1], 0)),
+        boot_nodes,
+        max_peers,
+    };
+    let log = Logger::root(slog::Discard, o!());
+    NetworkService::start(chain, &config, log).unwrap()
+}
+
+/// Poll `condition` until it holds, panicking if it does not within a few seconds.
+fn wait_until<F: Fn() -> bool>(condition: F) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !condition() {
+        assert!(Instant::now() < deadline, "timed out");
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn it_gossips_blocks_and_attestations() {
+    let mut harness = BeaconChainHarness::new(ChainSpec::few_validators(), 8);
+    let chains = [
+        harness.beacon_chain.clone(),
+        harness.chain_from_genesis(),
+        harness.chain_from_genesis(),
+    ];
+
+    // The nodes form a line, so the last only receives messages forwarded by the middle node.
+    let first = start_node(chains[0].clone(), vec![], 4);
+    let middle = start_node(chains[1].clone(), vec![first.listen_address()], 4);
+    let last = start_node(chains[2].clone(), vec![middle.listen_address()], 4);
+    wait_until(|| first.peer_count() == 1 && middle.peer_count() == 2 && last.peer_count() == 1);
+
+    for _ in 0..3 {
+        let slot = harness.increment_beacon_chain_slot();
+        for chain in &chains[1..] {
+            chain.slot_clock.set_slot(slot.as_u64());
+        }
+
+        let block = harness.produce_block();
+        match harness.beacon_chain.process_block(block.clone()) {
+            Ok(BlockProcessingOutcome::ValidBlock(_)) => {}
+            other => panic!("block processing failed with {:?}", other),
+        };
+        first.publish_block(block);
+        let head_root = harness.beacon_chain.head().beacon_block_root;
+        wait_until(|| {
+            chains[1..]
+                .iter()
+                .all(|chain| chain.head().beacon_block_root == head_root)
+        });
+
+        for free_attestation in harness.gather_free_attesations() {
+            harness
+                .beacon_chain
+                .process_free_attestation(free_attestation.clone())
+                .unwrap();
+            first.publish_attestation(free_attestation);
+        }
+        let pool_size = harness.beacon_chain.attestation_pool_size();
+        assert!(pool_size > 0);
+        wait_until(|| {
+            chains[1..]
+                .iter()
+                .all(|chain| chain.attestation_pool_size() == pool_size)
+        });
+    }
+}
+
+#[test]
+fn it_syncs_a_node_joining_late() {
+    let mut harness = BeaconChainHarness::new(ChainSpec::few_validators(), 8);
+    for _ in 0..4 {
+        harness.advance_chain_with_block();
+    }
+    let node = start_node(harness.beacon_chain.clone(), vec![], 4);
+
+    let late = harness.chain_from_genesis();
+    late.slot_clock
+        .set_slot(harness.beacon_chain.present_slot().as_u64());
+    let _late_node = start_node(late.clone(), vec![node.listen_address()], 4);
+
+    let head_root = harness.beacon_chain.head().beacon_block_root;
+    wait_until(|| late.head().beacon_block_root == head_root);
+}
+
+#[test]
+fn it_manages_peers() {
+    let harness = BeaconChainHarness::new(ChainSpec::few_validators(), 8);
+
+    let node = start_node(harness.beacon_chain.clone(), vec![], 2);
+    let peer = start_node(harness.chain_from_genesis(), vec![], 1);
+
+    // Connections to self are rejected.
+    assert_eq!(
+        node.connect(node.listen_address()),
+        Err(Error::SelfConnection)
+    );
+
+    // Only one connection to a node is permitted.
+    peer.connect(node.listen_address()).unwrap();
+    wait_until(|| node.peer_count() == 1);
+    assert_eq!(
+        peer.connect(node.listen_address()),
+        Err(Error::AlreadyConnected)
+    );
+
+    // At most `max_peers` are connected.
+    node.connect(start_node(harness.chain_from_genesis(), vec![], 1).listen_address())
+        .unwrap();
+    let other = start_node(harness.chain_from_genesis(), vec![], 1);
+    assert_eq!(
+        node.connect(other.listen_address()),
+        Err(Error::TooManyPeers)
+    );
+    wait_until(|| other.peer_count() == 0);
+
+    // Inbound connections are closed without a handshake once `max_peers` are connected.
+    let mut stream = TcpStream::connect(node.listen_address()).unwrap();
+    assert_eq!(read_message(&mut stream), Err(Error::ConnectionClosed));
+
+    // Nodes following another chain are rejected.
+    let other_harness = BeaconChainHarness::new(ChainSpec::few_validators(), 8);
+    let other_chain = start_node(other_harness.beacon_chain.clone(), vec![], 1);
+    assert_eq!(
+        other_chain.connect(other.listen_address()),
+        Err(Error::GenesisMismatch)
+    );
+    assert_eq!(other.peer_count(), 0);
+}
+
+#[test]
+fn it_disconnects_peers_sending_invalid_blocks() {
+    let mut harness = BeaconChainHarness::new(ChainSpec::few_validators(), 8);
+    let node = start_node(harness.beacon_chain.clone(), vec![], 1);
+
+    let mut stream = TcpStream::connect(node.listen_address()).unwrap();
+    let genesis_block_root = harness.beacon_chain.genesis_block_root().unwrap();
+    let hello = Message::Hello(Hello {
+        node_id: 0,
+        genesis_block_root,
+    });
+    write_message(&mut stream, &hello.encode()).unwrap();
+    match Message::decode(&read_message(&mut stream).unwrap()) {
+        Ok(Message::Hello(hello)) => assert_eq!(hello.genesis_block_root, genesis_block_root),
+        other => panic!("expected a hello, got {:?}", other),
+    };
+    match Message::decode(&read_message(&mut stream).unwrap()) {
+        Ok(Message::Status(status)) => assert_eq!(status.head_root, genesis_block_root),
+        other => panic!("expected a status, got {:?}", other),
+    };
+    wait_until(|| node.peer_count() == 1);
+
+    harness.increment_beacon_chain_slot();
+    let mut block = harness.produce_block();
+    block.state_root = genesis_block_root;
+    write_message(&mut stream, &Message::Block(block).encode()).unwrap();
+
+    wait_until(|| node.peer_count() == 0);
+    assert!(read_message(&mut stream).is_err());
+}
+
+#[test]
+fn it_gossips_queued_blocks_once_their_slot_is_reached() {
+    let mut harness = BeaconChainHarness::new(ChainSpec::few_validators(), 8);
+    let chains = [
+        harness.beacon_chain.clone(),
+        harness.chain_from_genesis(),
+        harness.chain_from_genesis(),
+    ];
+
+    let first = start_node(chains[0].clone(), vec![], 4);
+    let middle = start_node(chains[1].clone(), vec![first.listen_address()], 4);
+    let last = start_node(chains[2].clone(), vec![middle.listen_address()], 4);
+    wait_until(|| first.peer_count() == 1 && middle.peer_count() == 2 && last.peer_count() == 1);
+
+    // The block arrives before the middle node reaches its slot, so it is queued rather than
+    // forwarded.
+    let slot = harness.increment_beacon_chain_slot();
+    let block = harness.produce_block();
+    match harness.beacon_chain.process_block(block.clone()) {
+        Ok(BlockProcessingOutcome::ValidBlock(_)) => {}
+        other => panic!("block processing failed with {:?}", other),
+    };
+    first.publish_block(block.clone());
+    wait_until(|| chains[1].future_blocks.read().len() == 1);
+
+    // Once the middle node reaches the slot, the block is imported and gossiped to the last node.
+    for chain in &chains[1..] {
+        chain.slot_clock.set_slot(slot.as_u64());
+    }
+    middle.catch_up_with_slot_clock();
+    let block_root = block.canonical_root();
+    assert_eq!(chains[1].head().beacon_block_root, block_root);
+    wait_until(|| chains[2].head().beacon_block_root == block_root);
+}
@@ -0,0 +1,326 @@
+use network::{
+    Blocks, BlocksByRoot, Error, Message, PeerId, SyncManager, Transport, DEFAULT_MAX_PEERS,
+    MAX_BLOCKS_PER_REQUEST, REQUEST_TIMEOUT,
+};
+use slog::{o, Logger};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Instant;
+use test_harness::{BeaconChainHarness, TestingBeaconChain};
+use types::{ChainSpec, Hash256};
+
+/// Messages in flight between in-process nodes, as `(from, to, message)`.
+type Queue = RefCell<VecDeque<(PeerId, PeerId, Message)>>;
+
+/// Sends messages from one node by queueing them, so they may be delivered by `Network::run`.
+struct LocalTransport<'a> {
+    from: PeerId,
+    queue: &'a Queue,
+}
+
+impl<'a> Transport for LocalTransport<'a> {
+    fn send(&self, peer_id: PeerId, message: Message) {
+        // Round trip through the encoding, as though the message were sent over a connection.
+        let message = Message::decode(&message.encode()).unwrap();
+        self.queue
+            .borrow_mut()
+            .push_back((self.from, peer_id, message));
+    }
+}
+
+/// Two nodes connected over a local transport, each identifying the other by its index.
+struct Network {
+    chains: Vec<Arc<TestingBeaconChain>>,
+    nodes: Vec<SyncManager>,
+    queue: Queue,
+}
+
+impl Network {
+    fn new(chains: Vec<Arc<TestingBeaconChain>>, blocks_per_request: u64) -> Self {
+        let nodes = chains
+            .iter()
+            .map(|chain| {
+                let log = Logger::root(slog::Discard, o!());
+                SyncManager::new(chain.clone(), blocks_per_request, DEFAULT_MAX_PEERS, log)
+            })
+            .collect();
+        Self {
+            chains,
+            nodes,
+            queue: RefCell::new(VecDeque::new()),
+        }
+    }
+
+    fn transport(&self, from: PeerId) -> LocalTransport<'_> {
+        LocalTransport {
+            from,
+            queue: &self.queue,
+        }
+    }
+
+    /// Send the `Status` of each node to the other, as upon connecting.
+    fn connect(&self) {
+        for (from, node) in self.nodes.iter().enumerate() {
+            let to = 1 - from;
+            self.transport(from as PeerId)
+                .send(to as PeerId, Message::Status(node.status()));
+        }
+    }
+
+    /// Deliver a message directly to a node.
+    fn deliver(&mut self, from: PeerId, to: PeerId, message: Message) -> Result<(), Error> {
+        let transport = LocalTransport {
+            from: to,
+            queue: &self.queue,
+        };
+        self.nodes[to as usize].handle(from, message, &transport)
+    }
+
+    /// Deliver queued messages until none remain, returning the messages delivered.
+    fn run(&mut self) -> Vec<Message> {
+        let mut delivered = vec![];
+        loop {
+            let next = self.queue.borrow_mut().pop_front();
+            match next {
+                Some((from, to, message)) => {
+                    self.deliver(from, to, message.clone()).unwrap();
+                    delivered.push(message);
+                }
+                None => return delivered,
+            }
+        }
+    }
+
+    fn head_root(&self, node: usize) -> Hash256 {
+        self.chains[node].head().beacon_block_root
+    }
+}
+
+/// Build a chain of `slots` blocks, returning the harness and a second chain with only its
+/// genesis, whose slot clock and state match the harness.
+fn build_chains(slots: usize) -> (BeaconChainHarness, Arc<TestingBeaconChain>) {
+    let mut harness = BeaconChainHarness::new(ChainSpec::few_validators(), 8);
+    for _ in 0..slots {
+        harness.advance_chain_with_block();
+    }
+
+    let late = harness.chain_from_genesis();
+    late.slot_clock
+        .set_slot(harness.beacon_chain.present_slot().as_u64());
+    late.catch_up_with_slot_clock().unwrap();
+    (harness, late)
+}
+
+#[test]
+fn it_syncs_a_late_joining_node_by_range() {
+    let (harness, late) = build_chains(7);
+    let mut network = Network::new(vec![harness.beacon_chain.clone(), late], 3);
+
+    network.connect();
+    let delivered = network.run();
+
+    assert_eq!(network.head_root(1), network.head_root(0));
+    assert!(!network.nodes[1].is_syncing());
+
+    // The seven blocks are requested in batches of three.
+    let range_requests = delivered
+        .iter()
+        .filter_map(|message| match message {
+            Message::BlocksByRange(request) => Some(request),
+            _ => None,
+        })
+        .count();
+    assert_eq!(range_requests, 3);
+}
+
+#[test]
+fn it_imports_orphans_once_their_ancestors_are_fetched_by_root() {
+    let (harness, late) = build_chains(4);
+    let mut network = Network::new(vec![harness.beacon_chain.clone(), late], 3);
+
+    // The head block is gossiped to the late node, whose parent is unknown.
+    let head_block = harness.beacon_chain.head().beacon_block.clone();
+    let transport = LocalTransport {
+        from: 1,
+        queue: &network.queue,
+    };
+    let imported = network.nodes[1]
+        .process_block(0, head_block, &transport)
+        .unwrap();
+    assert!(!imported);
+    assert_eq!(network.chains[1].orphan_pool.read().len(), 1);
+
+    let delivered = network.run();
+
+    assert_eq!(network.head_root(1), network.head_root(0));
+    assert_eq!(network.chains[1].orphan_pool.read().len(), 0);
+    // Each ancestor after genesis is requested in turn.
+    let root_requests = delivered
+        .iter()
+        .filter_map(|message| match message {
+            Message::BlocksByRoot(request) => Some(request),
+            _ => None,
+        })
+        .count();
+    assert_eq!(root_requests, 3);
+}
+
+#[test]
+fn it_limits_the_orphans_held_for_each_peer() {
+    let (harness, late) = build_chains(1);
+    let mut network = Network::new(vec![harness.beacon_chain.clone(), late], 3);
+    // The pool is shared equally between the peers.
+    network.chains[1]
+        .orphan_pool
+        .write()
+        .set_max_orphans(2 * DEFAULT_MAX_PEERS);
+    let max_orphans_per_peer = network.nodes[1].max_orphans_per_peer();
+    assert_eq!(max_orphans_per_peer, 2);
+    let head_block = harness.beacon_chain.head().beacon_block.clone();
+    let orphan = |i: usize| {
+        let mut block = head_block.clone();
+        block.parent_root = Hash256::from(&[i as u8 + 1; 32][..]);
+        block
+    };
+
+    let transport = LocalTransport {
+        from: 1,
+        queue: &network.queue,
+    };
+    for i in 0..=max_orphans_per_peer {
+        let imported = network.nodes[1].process_block(0, orphan(i), &transport);
+        assert_eq!(imported, Ok(false));
+    }
+    assert_eq!(
+        network.chains[1].orphan_pool.read().len(),
+        max_orphans_per_peer
+    );
+
+    // Only the parent of each stored orphan is requested, from the peer which sent it.
+    let requested_roots: Vec<Hash256> = network
+        .queue
+        .borrow_mut()
+        .drain(..)
+        .flat_map(|message| match message {
+            (1, 0, Message::BlocksByRoot(request)) => request.block_roots,
+            other => panic!("expected a root request, got {:?}", other),
+        })
+        .collect();
+    let parent_roots: Vec<Hash256> = (0..max_orphans_per_peer)
+        .map(|i| orphan(i).parent_root)
+        .collect();
+    assert_eq!(requested_roots, parent_roots);
+
+    // Another peer may fill its own share of the pool, but may not evict the orphans from the
+    // first peer.
+    for i in 0..=max_orphans_per_peer {
+        let imported =
+            network.nodes[1].process_block(2, orphan(max_orphans_per_peer + 1 + i), &transport);
+        assert_eq!(imported, Ok(false));
+    }
+    let orphan_pool = network.chains[1].orphan_pool.read();
+    assert_eq!(orphan_pool.len(), 2 * max_orphans_per_peer);
+    for i in 0..max_orphans_per_peer {
+        assert!(orphan_pool.contains(&orphan(i).canonical_root()));
+    }
+}
+
+#[test]
+fn it_serves_at_most_max_blocks_per_request() {
+    let (harness, late) = build_chains(2);
+    let mut network = Network::new(vec![harness.beacon_chain.clone(), late], 3);
+
+    let block_roots = vec![harness.beacon_chain.head().beacon_block_root; 100];
+    let request = Message::BlocksByRoot(BlocksByRoot {
+        request_id: 1,
+        block_roots,
+    });
+    network.deliver(1, 0, request).unwrap();
+
+    match network.queue.borrow_mut().pop_front() {
+        Some((0, 1, Message::Blocks(response))) => {
+            assert_eq!(response.request_id, 1);
+            assert_eq!(response.blocks.len() as u64, MAX_BLOCKS_PER_REQUEST);
+        }
+        other => panic!("expected blocks, got {:?}", other),
+    };
+}
+
+#[test]
+fn it_rejects_unrequested_blocks() {
+    let (harness, late) = build_chains(5);
+    let mut network = Network::new(vec![harness.beacon_chain.clone(), late], 3);
+    let head_block = harness.beacon_chain.head().beacon_block.clone();
+
+    // Responses are only accepted to outstanding requests.
+    let response = Message::Blocks(Blocks {
+        request_id: 1,
+        blocks: vec![],
+    });
+    assert_eq!(
+        network.deliver(0, 1, response),
+        Err(Error::UnexpectedResponse)
+    );
+
+    // Only sync messages are handled.
+    assert_eq!(
+        network.deliver(0, 1, Message::Block(head_block.clone())),
+        Err(Error::UnexpectedMessage)
+    );
+
+    // The late node requests the first three blocks, but the head block is returned.
+    let status = Message::Status(network.nodes[0].status());
+    network.deliver(0, 1, status).unwrap();
+    let request_id = match network.queue.borrow_mut().pop_front() {
+        Some((1, 0, Message::BlocksByRange(request))) => {
+            assert_eq!(request.start_slot, harness.spec.genesis_slot + 1);
+            assert_eq!(request.count, 3);
+            request.request_id
+        }
+        other => panic!("expected a range request, got {:?}", other),
+    };
+    let response = Message::Blocks(Blocks {
+        request_id,
+        blocks: vec![head_block],
+    });
+    assert_eq!(network.deliver(0, 1, response), Err(Error::InvalidResponse));
+    assert_eq!(
+        network.head_root(1),
+        harness.beacon_chain.genesis_block_root().unwrap()
+    );
+}
+
+#[test]
+fn it_resumes_syncing_once_a_request_expires() {
+    let (harness, late) = build_chains(4);
+    let mut network = Network::new(vec![harness.beacon_chain.clone(), late], 3);
+
+    // The range request of the late node is never answered.
+    let status = Message::Status(network.nodes[0].status());
+    network.deliver(0, 1, status).unwrap();
+    match network.queue.borrow_mut().pop_front() {
+        Some((1, 0, Message::BlocksByRange(_))) => {}
+        other => panic!("expected a range request, got {:?}", other),
+    };
+    assert!(network.nodes[1].is_syncing());
+
+    let transport = LocalTransport {
+        from: 1,
+        queue: &network.queue,
+    };
+    network.nodes[1].expire_requests(Instant::now(), &transport);
+    assert!(network.queue.borrow().is_empty());
+
+    // Once expired, the request is abandoned and the sync resumes from the peer still ahead.
+    network.nodes[1].expire_requests(Instant::now() + REQUEST_TIMEOUT, &transport);
+    match network.queue.borrow().front() {
+        Some((1, 0, Message::BlocksByRange(request))) => {
+            assert_eq!(request.start_slot, harness.spec.genesis_slot + 1)
+        }
+        other => panic!("expected a range request, got {:?}", other),
+    };
+
+    network.run();
+    assert_eq!(network.head_root(1), network.head_root(0));
+}
@@ -1,4 +1,7 @@
+use ::beacon_chain::{DEFAULT_FUTURE_SLOT_TOLERANCE, DEFAULT_MAX_ATTESTATIONS};
+use network::DEFAULT_MAX_PEERS;
 use std::fs;
+use std::net::SocketAddr;
 use std::path::PathBuf;
 
 /// Stores the core configuration for this Lighthouse instance.
@@ -8,6 +11,18 @@ use std::path::PathBuf;
 pub struct LighthouseConfig {
     pub data_dir: PathBuf,
     pub p2p_listen_port: u16,
+    /// The peers connected to on start up.
+    pub boot_nodes: Vec<SocketAddr>,
+    /// The maximum number of connected peers.
+    pub max_peers: usize,
+    /// The maximum number of attestations held in memory awaiting inclusion in a block.
+    pub max_attestations: usize,
+    /// The number of slots ahead of the slot clock a block may be queued until its slot.
+    pub future_slot_tolerance: u64,
+    /// The HTTP JSON-RPC endpoint of an Ethereum 1.0 node, followed for eth1 data and deposits.
+    pub eth1_rpc: Option<SocketAddr>,
+    /// The number of the Ethereum 1.0 block in which the deposit contract was deployed.
+    pub deposit_contract_block: u64,
 }
 
 const DEFAULT_LIGHTHOUSE_DIR: &str = ".lighthouse";
@@ -25,6 +40,12 @@ impl LighthouseConfig {
         Self {
             data_dir,
             p2p_listen_port,
+            boot_nodes: vec![],
+            max_peers: DEFAULT_MAX_PEERS,
+            max_attestations: DEFAULT_MAX_ATTESTATIONS,
+            future_slot_tolerance: DEFAULT_FUTURE_SLOT_TOLERANCE,
+            eth1_rpc: None,
+            deposit_contract_block: 0,
         }
     }
 }
@@ -0,0 +1,130 @@
+use ssz::{ssz_encode, Decodable};
+use std::fs;
+use std::path::Path;
+use types::{BeaconState, BeaconStateError, ChainSpec, Deposit, Eth1Data, Hash256};
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    Io(String),
+    InvalidYaml(String),
+    InvalidSsz,
+    NoDeposits,
+    BeaconStateError(BeaconStateError),
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Error {
+        Error::Io(e.to_string())
+    }
+}
+
+impl From<BeaconStateError> for Error {
+    fn from(e: BeaconStateError) -> Error {
+        Error::BeaconStateError(e)
+    }
+}
+
+/// Read a YAML list of the initial validator deposits.
+pub fn read_deposits(path: &Path) -> Result<Vec<Deposit>, Error> {
+    let contents = fs::read_to_string(path)?;
+    serde_yaml::from_str(&contents).map_err(|e| Error::InvalidYaml(e.to_string()))
+}
+
+/// Build the genesis state for a chain starting at `genesis_time` with the initial validator
+/// `deposits`.
+///
+/// No eth1 data is known at genesis, so it is zeroed.
+pub fn genesis_state(
+    genesis_time: u64,
+    deposits: Vec<Deposit>,
+    spec: &ChainSpec,
+) -> Result<BeaconState, Error> {
+    if deposits.is_empty() {
+        return Err(Error::NoDeposits);
+    }
+
+    let latest_eth1_data = Eth1Data {
+        deposit_root: Hash256::zero(),
+        block_hash: Hash256::zero(),
+    };
+
+    Ok(BeaconState::genesis(
+        genesis_time,
+        deposits,
+        latest_eth1_data,
+        spec,
+    )?)
+}
+
+/// Write the SSZ encoding of `state` to `path`.
+pub fn write_state(path: &Path, state: &BeaconState) -> Result<(), Error> {
+    fs::write(path, ssz_encode(state))?;
+    Ok(())
+}
+
+/// Read an SSZ encoded state, as written by `write_state`.
+pub fn read_state(path: &Path) -> Result<BeaconState, Error> {
+    let bytes = fs::read(path)?;
+    match BeaconState::ssz_decode(&bytes, 0) {
+        Ok((state, i)) if i == bytes.len() => Ok(state),
+        _ => Err(Error::InvalidSsz),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bls::{create_proof_of_possession, Keypair};
+    use std::env;
+    use types::{DepositData, DepositInput};
+
+    fn deposits(count: usize) -> Vec<Deposit> {
+        (0..count)
+            .map(|_| {
+                let keypair = Keypair::random();
+                Deposit {
+                    branch: vec![],
+                    index: 0,
+                    deposit_data: DepositData {
+                        amount: 32_000_000_000,
+                        timestamp: 0,
+                        deposit_input: DepositInput {
+                            pubkey: keypair.pk.clone(),
+                            withdrawal_credentials: Hash256::zero(),
+                            proof_of_possession: create_proof_of_possession(&keypair),
+                        },
+                    },
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_genesis_state_from_deposits_file() {
+        let spec = ChainSpec::few_validators();
+        let original = deposits(4);
+
+        let deposits_path = env::temp_dir().join("lighthouse_genesis_test_deposits.yaml");
+        fs::write(&deposits_path, serde_yaml::to_string(&original).unwrap()).unwrap();
+        let deposits = read_deposits(&deposits_path).unwrap();
+        assert_eq!(deposits, original);
+
+        let state = genesis_state(42, deposits, &spec).unwrap();
+        assert_eq!(state.genesis_time, 42);
+        assert_eq!(state.validator_registry.len(), 4);
+
+        let state_path = env::temp_dir().join("lighthouse_genesis_test_state.ssz");
+        write_state(&state_path, &state).unwrap();
+        assert_eq!(read_state(&state_path).unwrap(), state);
+
+        fs::remove_file(deposits_path).unwrap();
+        fs::remove_file(state_path).unwrap();
+    }
+
+    #[test]
+    fn test_no_deposits() {
+        let spec = ChainSpec::few_validators();
+
+        assert_eq!(genesis_state(42, vec![], &spec), Err(Error::NoDeposits));
+    }
+}
@@ -1,25 +1,37 @@
 extern crate slog;
 
 mod config;
+mod genesis;
 mod rpc;
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::config::LighthouseConfig;
 use crate::rpc::start_server;
-use beacon_chain::BeaconChain;
+use ::beacon_chain::{stored_genesis_time, BeaconChain};
 use bls::create_proof_of_possession;
-use clap::{App, Arg};
+use clap::{App, Arg, ArgMatches, SubCommand};
 use db::{
-    stores::{BeaconBlockStore, BeaconStateStore},
-    MemoryDB,
+    stores::{BeaconBlockStore, BeaconStateStore, ChainStore, COLUMNS},
+    DiskDB,
 };
+use eth1::{Eth1Follower, HttpEth1Rpc, ETH1_FOLLOW_DISTANCE};
 use fork_choice::BitwiseLMDGhost;
-use slog::{error, info, o, Drain};
+use network::{NetworkConfig, NetworkService};
+use slog::{error, info, o, warn, Drain, Logger};
 use slot_clock::SystemTimeSlotClock;
+use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
+use types::chain_spec::DEFAULT_PRESET;
 use types::{ChainSpec, Deposit, DepositData, DepositInput, Eth1Data, Hash256, Keypair};
 
+/// The genesis time of the randomly generated chain used when no genesis state is supplied.
+const DEV_GENESIS_TIME: u64 = 1_549_935_547; // 12th Feb 2018 (arbitrary value in the past).
+
+/// The interval between updates of the eth1 follower, roughly the Ethereum 1.0 block time.
+const ETH1_UPDATE_INTERVAL: Duration = Duration::from_secs(15);
+
 fn main() {
     let decorator = slog_term::TermDecorator::new().build();
     let drain = slog_term::CompactFormat::new(decorator).build().fuse();
@@ -44,6 +56,90 @@ fn main() {
                 .help("Network listen port for p2p connections.")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("boot-nodes")
+                .long("boot-nodes")
+                .value_name("ADDRESSES")
+                .help("A comma-separated list of peer addresses (e.g., 127.0.0.1:9000) to connect to on start up.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("max-peers")
+                .long("max-peers")
+                .value_name("COUNT")
+                .help("The maximum number of connected peers.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("max-attestations")
+                .long("max-attestations")
+                .value_name("COUNT")
+                .help("The maximum number of attestations held in memory awaiting inclusion in a block.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("future-slot-tolerance")
+                .long("future-slot-tolerance")
+                .value_name("SLOTS")
+                .help("The number of slots ahead of the local clock a block may be held until its slot is reached.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("eth1-rpc")
+                .long("eth1-rpc")
+                .value_name("ADDRESS")
+                .help("The HTTP JSON-RPC endpoint of an Ethereum 1.0 node (e.g., 127.0.0.1:8545), followed to vote for eth1 data and include deposits in produced blocks.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("deposit-contract-block")
+                .long("deposit-contract-block")
+                .value_name("NUMBER")
+                .help("The number of the Ethereum 1.0 block in which the deposit contract was deployed. Defaults to 0.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("spec")
+                .long("spec")
+                .value_name("PRESET_OR_FILE")
+                .help("The chain specification: a preset (foundation, few_validators) or the path to a YAML file. Defaults to foundation.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("genesis-state")
+                .long("genesis-state")
+                .value_name("FILE")
+                .help("An SSZ genesis state, as written by the genesis subcommand, used to start a new chain. If not supplied, a chain with random validators is started.")
+                .takes_value(true),
+        )
+        .subcommand(
+            SubCommand::with_name("genesis")
+                .about("Build a genesis state from the initial validator deposits and write it to a file.")
+                .arg(
+                    Arg::with_name("deposits")
+                        .long("deposits")
+                        .value_name("FILE")
+                        .help("A YAML list of the initial validator deposits.")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("genesis-time")
+                        .long("genesis-time")
+                        .value_name("SECONDS")
+                        .help("The genesis time, in seconds since the unix epoch.")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .long("output")
+                        .value_name("FILE")
+                        .help("The file to which the SSZ genesis state is written.")
+                        .takes_value(true)
+                        .required(true),
+                ),
+        )
         .get_matches();
 
     let mut config = LighthouseConfig::default();
@@ -63,72 +159,297 @@ fn main() {
         }
     }
 
+    // Custom boot nodes
+    if let Some(boot_nodes_str) = matches.value_of("boot-nodes") {
+        match boot_nodes_str
+            .split(',')
+            .map(|address| address.trim().parse::<SocketAddr>())
+            .collect()
+        {
+            Ok(boot_nodes) => config.boot_nodes = boot_nodes,
+            Err(_) => {
+                error!(log, "Invalid boot nodes"; "boot_nodes" => boot_nodes_str);
+                return;
+            }
+        }
+    }
+
+    // Custom maximum peers
+    if let Some(max_str) = matches.value_of("max-peers") {
+        if let Ok(max_peers) = max_str.parse::<usize>() {
+            config.max_peers = max_peers;
+        } else {
+            error!(log, "Invalid max peers"; "max_peers" => max_str);
+            return;
+        }
+    }
+
+    // Custom attestation pool size
+    if let Some(max_str) = matches.value_of("max-attestations") {
+        if let Ok(max_attestations) = max_str.parse::<usize>() {
+            config.max_attestations = max_attestations;
+        } else {
+            error!(log, "Invalid max attestations"; "max_attestations" => max_str);
+            return;
+        }
+    }
+
+    // Custom future slot tolerance
+    if let Some(tolerance_str) = matches.value_of("future-slot-tolerance") {
+        if let Ok(tolerance) = tolerance_str.parse::<u64>() {
+            config.future_slot_tolerance = tolerance;
+        } else {
+            error!(log, "Invalid future slot tolerance"; "future_slot_tolerance" => tolerance_str);
+            return;
+        }
+    }
+
+    // Custom Ethereum 1.0 endpoint
+    if let Some(address_str) = matches.value_of("eth1-rpc") {
+        if let Ok(address) = address_str.parse::<SocketAddr>() {
+            config.eth1_rpc = Some(address);
+        } else {
+            error!(log, "Invalid eth1 RPC address"; "eth1_rpc" => address_str);
+            return;
+        }
+    }
+
+    // Custom deposit contract block
+    if let Some(block_str) = matches.value_of("deposit-contract-block") {
+        if let Ok(block_number) = block_str.parse::<u64>() {
+            config.deposit_contract_block = block_number;
+        } else {
+            error!(log, "Invalid deposit contract block"; "deposit_contract_block" => block_str);
+            return;
+        }
+    }
+
     // Log configuration
     info!(log, "";
           "data_dir" => &config.data_dir.to_str(),
-          "port" => &config.p2p_listen_port);
+          "port" => &config.p2p_listen_port,
+          "boot_nodes" => config.boot_nodes.len(),
+          "max_peers" => config.max_peers,
+          "max_attestations" => config.max_attestations,
+          "future_slot_tolerance" => config.future_slot_tolerance,
+          "eth1_rpc" => config.eth1_rpc.map(|address| address.to_string()));
 
-    // Specification (presently fixed to foundation).
-    let spec = ChainSpec::foundation();
+    // Specification
+    let spec_name = matches.value_of("spec").unwrap_or(DEFAULT_PRESET);
+    let spec = match ChainSpec::from_preset_or_file(spec_name) {
+        Ok(spec) => spec,
+        Err(e) => {
+            error!(log, "Unable to load spec"; "spec" => spec_name, "error" => format!("{:?}", e));
+            return;
+        }
+    };
+    info!(log, "Loaded spec"; "spec" => spec_name, "epoch_length" => spec.epoch_length, "shard_count" => spec.shard_count);
+
+    if let Some(genesis_matches) = matches.subcommand_matches("genesis") {
+        write_genesis_state(genesis_matches, &spec, &log);
+        return;
+    }
 
-    // Database (presently in-memory)
-    let db = Arc::new(MemoryDB::open());
+    let genesis_state = match matches.value_of("genesis-state") {
+        Some(path) => match genesis::read_state(Path::new(path)) {
+            Ok(state) => Some(state),
+            Err(e) => {
+                error!(log, "Unable to read genesis state"; "path" => path, "error" => format!("{:?}", e));
+                return;
+            }
+        },
+        None => None,
+    };
+
+    // Database
+    let db = Arc::new(DiskDB::open(&config.data_dir, Some(&COLUMNS)));
     let block_store = Arc::new(BeaconBlockStore::new(db.clone()));
     let state_store = Arc::new(BeaconStateStore::new(db.clone()));
+    let chain_store = Arc::new(ChainStore::new(db.clone()));
 
-    // Slot clock
-    let genesis_time = 1_549_935_547; // 12th Feb 2018 (arbitrary value in the past).
-    let slot_clock = SystemTimeSlotClock::new(genesis_time, spec.slot_duration)
-        .expect("Unable to load SystemTimeSlotClock");
-    // Choose the fork choice
-    let fork_choice = BitwiseLMDGhost::new(block_store.clone(), state_store.clone());
-
-    /*
-     * Generate some random data to start a chain with.
-     *
-     * This is will need to be replace for production usage.
-     */
-    let latest_eth1_data = Eth1Data {
-        deposit_root: Hash256::zero(),
-        block_hash: Hash256::zero(),
+    // Slot clock, using the genesis time of the stored chain if there is one.
+    let stored_genesis_time = stored_genesis_time(&block_store, &state_store, &chain_store)
+        .expect("Unable to read chain from database");
+    let genesis_time = match (stored_genesis_time, &genesis_state) {
+        (Some(genesis_time), _) => genesis_time,
+        (None, Some(state)) => state.genesis_time,
+        (None, None) => DEV_GENESIS_TIME,
     };
-    let keypairs: Vec<Keypair> = (0..10)
-        .collect::<Vec<usize>>()
-        .iter()
-        .map(|_| Keypair::random())
-        .collect();
-    let initial_validator_deposits = keypairs
-        .iter()
-        .map(|keypair| Deposit {
-            branch: vec![], // branch verification is not specified.
-            index: 0,       // index verification is not specified.
-            deposit_data: DepositData {
-                amount: 32_000_000_000, // 32 ETH (in Gwei)
-                timestamp: genesis_time - 1,
-                deposit_input: DepositInput {
-                    pubkey: keypair.pk.clone(),
-                    withdrawal_credentials: Hash256::zero(), // Withdrawal not possible.
-                    proof_of_possession: create_proof_of_possession(&keypair),
-                },
-            },
-        })
-        .collect();
-
-    // Genesis chain
-    let _chain_result = BeaconChain::genesis(
+    info!(log, "Genesis time"; "unix_epoch_seconds" => genesis_time);
+    let slot_clock = || {
+        SystemTimeSlotClock::new(genesis_time, spec.slot_duration)
+            .expect("Unable to load SystemTimeSlotClock")
+    };
+    // Choose the fork choice
+    let fork_choice = || BitwiseLMDGhost::new(block_store.clone(), state_store.clone());
+
+    // Resume the chain stored in the database, if any.
+    let stored_chain = BeaconChain::from_store(
         state_store.clone(),
         block_store.clone(),
-        slot_clock,
-        genesis_time,
-        latest_eth1_data,
-        initial_validator_deposits,
-        spec,
-        fork_choice,
-    );
+        chain_store.clone(),
+        slot_clock(),
+        spec.clone(),
+        fork_choice(),
+    )
+    .expect("Unable to load chain from database");
 
-    let _server = start_server(log.clone());
+    let chain = match (stored_chain, genesis_state) {
+        (Some(chain), genesis_state) => {
+            if genesis_state.is_some() {
+                warn!(
+                    log,
+                    "Ignoring genesis state, a chain is already stored in the database"
+                );
+            }
+            info!(log, "Resumed chain from database";
+                  "head_slot" => chain.head().beacon_block.slot.as_u64(),
+                  "finalized_slot" => chain.finalized_head().beacon_block.slot.as_u64());
+            chain
+        }
+        (None, Some(genesis_state)) => {
+            info!(log, "Starting chain from genesis state");
+            BeaconChain::from_genesis_state(
+                state_store.clone(),
+                block_store.clone(),
+                chain_store.clone(),
+                slot_clock(),
+                genesis_state,
+                spec,
+                fork_choice(),
+            )
+            .expect("Unable to create chain from genesis state")
+        }
+        (None, None) => {
+            /*
+             * Generate some random data to start a chain with.
+             *
+             * This is will need to be replace for production usage.
+             */
+            let latest_eth1_data = Eth1Data {
+                deposit_root: Hash256::zero(),
+                block_hash: Hash256::zero(),
+            };
+            let keypairs: Vec<Keypair> = (0..10)
+                .collect::<Vec<usize>>()
+                .iter()
+                .map(|_| Keypair::random())
+                .collect();
+            let initial_validator_deposits = keypairs
+                .iter()
+                .map(|keypair| Deposit {
+                    branch: vec![], // genesis deposits are not proven.
+                    index: 0,       // index verification is not specified.
+                    deposit_data: DepositData {
+                        amount: 32_000_000_000, // 32 ETH (in Gwei)
+                        timestamp: genesis_time - 1,
+                        deposit_input: DepositInput {
+                            pubkey: keypair.pk.clone(),
+                            withdrawal_credentials: Hash256::zero(), // Withdrawal not possible.
+                            proof_of_possession: create_proof_of_possession(&keypair),
+                        },
+                    },
+                })
+                .collect();
 
+            // Genesis chain
+            info!(log, "Starting chain from random genesis");
+            BeaconChain::genesis(
+                state_store.clone(),
+                block_store.clone(),
+                chain_store.clone(),
+                slot_clock(),
+                genesis_time,
+                latest_eth1_data,
+                initial_validator_deposits,
+                spec,
+                fork_choice(),
+            )
+            .expect("Unable to create genesis chain")
+        }
+    };
+
+    chain
+        .attestation_aggregator
+        .write()
+        .set_max_attestations(config.max_attestations);
+    chain
+        .future_blocks
+        .write()
+        .set_tolerance(config.future_slot_tolerance);
+
+    let chain = Arc::new(chain);
+
+    // Follow the Ethereum 1.0 chain on a separate thread, so block production does not wait on
+    // the endpoint.
+    if let Some(address) = config.eth1_rpc {
+        let rpc = Arc::new(HttpEth1Rpc::new(address, &chain.spec));
+        let follower = Arc::new(Eth1Follower::new(
+            rpc,
+            ETH1_FOLLOW_DISTANCE,
+            config.deposit_contract_block,
+            &chain.spec,
+        ));
+        *chain.eth1_follower.write() = Some(follower.clone());
+
+        let log = log.clone();
+        std::thread::spawn(move || loop {
+            if let Err(e) = follower.update() {
+                warn!(log, "Unable to update eth1 follower"; "error" => format!("{:?}", e));
+            }
+            std::thread::sleep(ETH1_UPDATE_INTERVAL);
+        });
+    }
+
+    let network_config = NetworkConfig {
+        listen_address: SocketAddr::from(([0, 0, 0, 0], config.p2p_listen_port)),
+        boot_nodes: config.boot_nodes.clone(),
+        max_peers: config.max_peers,
+    };
+    let network = match NetworkService::start(chain.clone(), &network_config, log.clone()) {
+        Ok(network) => network,
+        Err(e) => {
+            error!(log, "Unable to start network"; "error" => format!("{:?}", e));
+            return;
+        }
+    };
+
+    let _server = start_server(chain.clone(), network.clone(), log.clone());
+
+    // Keep the state at the present slot, processing and gossiping any queued blocks as their
+    // slot is reached.
     loop {
-        std::thread::sleep(std::time::Duration::from_secs(1));
+        std::thread::sleep(Duration::from_secs(1));
+        network.catch_up_with_slot_clock();
+    }
+}
+
+/// Build a genesis state from the deposits and genesis time supplied to the `genesis` subcommand
+/// and write it to the output file.
+fn write_genesis_state(matches: &ArgMatches, spec: &ChainSpec, log: &Logger) {
+    let deposits_path = matches.value_of("deposits").expect("deposits is required");
+    let output_path = matches.value_of("output").expect("output is required");
+    let genesis_time = matches
+        .value_of("genesis-time")
+        .expect("genesis-time is required");
+    let genesis_time = match genesis_time.parse::<u64>() {
+        Ok(genesis_time) => genesis_time,
+        Err(_) => {
+            error!(log, "Invalid genesis time"; "genesis_time" => genesis_time);
+            return;
+        }
+    };
+
+    let result = genesis::read_deposits(Path::new(deposits_path))
+        .and_then(|deposits| genesis::genesis_state(genesis_time, deposits, spec))
+        .and_then(|state| {
+            genesis::write_state(Path::new(output_path), &state)?;
+            Ok(state)
+        });
+    match result {
+        Ok(state) => {
+            info!(log, "Wrote genesis state"; "path" => output_path, "validators" => state.validator_registry.len())
+        }
+        Err(e) => error!(log, "Unable to build genesis state"; "error" => format!("{:?}", e)),
     }
 }
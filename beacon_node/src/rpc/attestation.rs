@@ -0,0 +1,184 @@
+use beacon_chain::BeaconChainApi;
+use futures::Future;
+use grpcio::{RpcContext, RpcStatus, RpcStatusCode, UnarySink};
+use network::NetworkService;
+use protos::services::{
+    AttestationData as AttestationDataProto, FreeAttestation as FreeAttestationProto,
+    ProduceAttestationDataRequest, ProduceAttestationDataResponse, PublishAttestationRequest,
+    PublishAttestationResponse,
+};
+use protos::services_grpc::AttestationService;
+use slog::{debug, error, warn, Logger};
+use ssz::{ssz_encode, Decodable};
+use std::sync::Arc;
+use types::{AttestationData, FreeAttestation, Signature, Slot};
+
+#[derive(Clone)]
+pub struct AttestationServiceInstance {
+    pub chain: Arc<dyn BeaconChainApi>,
+    pub network: Arc<NetworkService>,
+    pub log: Logger,
+}
+
+impl AttestationService for AttestationServiceInstance {
+    /// Produce some `AttestationData` for signing by a validator.
+    fn produce_attestation_data(
+        &mut self,
+        ctx: RpcContext,
+        req: ProduceAttestationDataRequest,
+        sink: UnarySink<ProduceAttestationDataResponse>,
+    ) {
+        let slot = Slot::new(req.get_slot());
+        let shard = req.get_shard();
+        debug!(self.log, "RPC request"; "endpoint" => "ProduceAttestationData", "slot" => slot.as_u64(), "shard" => shard);
+
+        // Attestations may not be produced for a slot the slot clock has not yet reached.
+        match self.chain.read_slot_clock() {
+            Some(present_slot) if slot <= present_slot => {}
+            _ => {
+                let f = sink
+                    .fail(RpcStatus::new(
+                        RpcStatusCode::InvalidArgument,
+                        Some("Slot is in the future".to_string()),
+                    ))
+                    .map_err(move |e| println!("failed to reply {:?}: {:?}", req, e));
+                return ctx.spawn(f);
+            }
+        }
+
+        self.network.advance_state(slot);
+
+        let mut resp = ProduceAttestationDataResponse::new();
+
+        // Attestations can only be produced for the slot of the present state.
+        if self.chain.present_slot() == slot {
+            match self.chain.produce_attestation_data(shard) {
+                Ok(attestation_data) => {
+                    let mut attestation_data_proto = AttestationDataProto::new();
+                    attestation_data_proto.set_ssz(ssz_encode(&attestation_data));
+
+                    resp.set_attestation_data(attestation_data_proto);
+                }
+                Err(e) => {
+                    error!(self.log, "Unable to produce attestation data"; "error" => format!("{:?}", e))
+                }
+            }
+        }
+
+        let f = sink
+            .success(resp)
+            .map_err(move |e| println!("failed to reply {:?}: {:?}", req, e));
+        ctx.spawn(f)
+    }
+
+    /// Accept some `FreeAttestation`, process and publish it.
+    fn publish_attestation(
+        &mut self,
+        ctx: RpcContext,
+        req: PublishAttestationRequest,
+        sink: UnarySink<PublishAttestationResponse>,
+    ) {
+        let free_attestation = match decode_free_attestation(req.get_free_attestation()) {
+            Some(free_attestation) => free_attestation,
+            None => {
+                let f = sink
+                    .fail(RpcStatus::new(
+                        RpcStatusCode::InvalidArgument,
+                        Some("Invalid attestation data or signature SSZ".to_string()),
+                    ))
+                    .map_err(move |e| println!("failed to reply {:?}: {:?}", req, e));
+                return ctx.spawn(f);
+            }
+        };
+
+        debug!(self.log, "RPC request"; "endpoint" => "PublishAttestation", "slot" => free_attestation.data.slot.as_u64(), "validator_index" => free_attestation.validator_index);
+
+        let mut resp = PublishAttestationResponse::new();
+
+        match self
+            .chain
+            .process_free_attestation(free_attestation.clone())
+        {
+            Ok(outcome) => {
+                if outcome.valid {
+                    self.network.publish_attestation(free_attestation);
+                } else {
+                    warn!(self.log, "Published attestation was invalid"; "reason" => format!("{:?}", outcome.message));
+                }
+                resp.set_success(outcome.valid);
+                resp.set_msg(format!("{:?}", outcome.message).as_bytes().to_vec());
+            }
+            Err(e) => {
+                error!(self.log, "Unable to process published attestation"; "error" => format!("{:?}", e));
+                resp.set_success(false);
+                resp.set_msg(format!("Error: {:?}", e).as_bytes().to_vec());
+            }
+        }
+
+        let f = sink
+            .success(resp)
+            .map_err(move |e| println!("failed to reply {:?}: {:?}", req, e));
+        ctx.spawn(f)
+    }
+}
+
+/// Decodes a `FreeAttestation` from its protobuf representation.
+///
+/// Returns `None` if the attestation data or signature is not valid SSZ.
+fn decode_free_attestation(proto: &FreeAttestationProto) -> Option<FreeAttestation> {
+    let (data, _) = AttestationData::ssz_decode(proto.get_data().get_ssz(), 0).ok()?;
+    let (signature, _) = Signature::ssz_decode(proto.get_signature(), 0).ok()?;
+    Some(FreeAttestation {
+        data,
+        signature,
+        validator_index: proto.get_validator_index(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::test_utils::{SeedableRng, TestRandom, XorShiftRng};
+    use types::Keypair;
+
+    fn free_attestation_proto(data: &AttestationData, signature: Vec<u8>) -> FreeAttestationProto {
+        let mut data_proto = AttestationDataProto::new();
+        data_proto.set_ssz(ssz_encode(data));
+
+        let mut proto = FreeAttestationProto::new();
+        proto.set_data(data_proto);
+        proto.set_signature(signature);
+        proto.set_validator_index(3);
+        proto
+    }
+
+    #[test]
+    fn test_decode_free_attestation() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let data = AttestationData::random_for_test(&mut rng);
+        let signature = Signature::new(&[42], 0, &Keypair::random().sk);
+
+        let proto = free_attestation_proto(&data, ssz_encode(&signature));
+        assert_eq!(
+            decode_free_attestation(&proto),
+            Some(FreeAttestation {
+                data,
+                signature,
+                validator_index: 3,
+            })
+        );
+    }
+
+    #[test]
+    fn test_decode_invalid_free_attestation() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let data = AttestationData::random_for_test(&mut rng);
+
+        let proto = free_attestation_proto(&data, vec![42; 3]);
+        assert_eq!(decode_free_attestation(&proto), None);
+
+        let mut proto = free_attestation_proto(&data, vec![]);
+        proto.mut_data().set_ssz(vec![42; 3]);
+        assert_eq!(decode_free_attestation(&proto), None);
+    }
+}
@@ -1,14 +1,21 @@
+use beacon_chain::{BeaconChainApi, BlockProcessingOutcome};
 use futures::Future;
-use grpcio::{RpcContext, UnarySink};
+use grpcio::{RpcContext, RpcStatus, RpcStatusCode, UnarySink};
+use network::NetworkService;
 use protos::services::{
     BeaconBlock as BeaconBlockProto, ProduceBeaconBlockRequest, ProduceBeaconBlockResponse,
     PublishBeaconBlockRequest, PublishBeaconBlockResponse,
 };
 use protos::services_grpc::BeaconBlockService;
-use slog::Logger;
+use slog::{debug, error, warn, Logger};
+use ssz::{ssz_encode, Decodable};
+use std::sync::Arc;
+use types::{BeaconBlock, Signature, Slot};
 
 #[derive(Clone)]
 pub struct BeaconBlockServiceInstance {
+    pub chain: Arc<dyn BeaconChainApi>,
+    pub network: Arc<NetworkService>,
     pub log: Logger,
 }
 
@@ -20,15 +27,49 @@ impl BeaconBlockService for BeaconBlockServiceInstance {
         req: ProduceBeaconBlockRequest,
         sink: UnarySink<ProduceBeaconBlockResponse>,
     ) {
-        println!("producing at slot {}", req.get_slot());
+        let slot = Slot::new(req.get_slot());
+        debug!(self.log, "RPC request"; "endpoint" => "ProduceBeaconBlock", "slot" => slot.as_u64());
 
-        // TODO: build a legit block.
-        let mut block = BeaconBlockProto::new();
-        block.set_slot(req.get_slot());
-        block.set_block_root(b"cats".to_vec());
+        let randao_reveal = match Signature::ssz_decode(req.get_randao_reveal(), 0) {
+            Ok((randao_reveal, _)) => randao_reveal,
+            Err(_) => {
+                let f = sink
+                    .fail(RpcStatus::new(
+                        RpcStatusCode::InvalidArgument,
+                        Some("Invalid randao_reveal".to_string()),
+                    ))
+                    .map_err(move |e| println!("failed to reply {:?}: {:?}", req, e));
+                return ctx.spawn(f);
+            }
+        };
+
+        // Blocks may not be produced for a slot the slot clock has not yet reached.
+        match self.chain.read_slot_clock() {
+            Some(present_slot) if slot <= present_slot => {}
+            _ => {
+                let f = sink
+                    .fail(RpcStatus::new(
+                        RpcStatusCode::InvalidArgument,
+                        Some("Slot is in the future".to_string()),
+                    ))
+                    .map_err(move |e| println!("failed to reply {:?}: {:?}", req, e));
+                return ctx.spawn(f);
+            }
+        }
+
+        self.network.advance_state(slot);
 
         let mut resp = ProduceBeaconBlockResponse::new();
-        resp.set_block(block);
+
+        // Blocks can only be produced for the slot of the present state.
+        if self.chain.present_slot() == slot {
+            if let Some((block, _state)) = self.chain.produce_block(randao_reveal) {
+                let mut block_proto = BeaconBlockProto::new();
+                block_proto.set_ssz(ssz_encode(&block));
+
+                resp.set_block(block_proto);
+            }
+        }
 
         let f = sink
             .success(resp)
@@ -43,11 +84,38 @@ impl BeaconBlockService for BeaconBlockServiceInstance {
         req: PublishBeaconBlockRequest,
         sink: UnarySink<PublishBeaconBlockResponse>,
     ) {
-        println!("publishing {:?}", req.get_block());
+        let block = match BeaconBlock::ssz_decode(req.get_block().get_ssz(), 0) {
+            Ok((block, _)) => block,
+            Err(_) => {
+                let f = sink
+                    .fail(RpcStatus::new(
+                        RpcStatusCode::InvalidArgument,
+                        Some("Invalid block SSZ".to_string()),
+                    ))
+                    .map_err(move |e| println!("failed to reply {:?}: {:?}", req, e));
+                return ctx.spawn(f);
+            }
+        };
+
+        debug!(self.log, "RPC request"; "endpoint" => "PublishBeaconBlock", "slot" => block.slot.as_u64());
 
-        // TODO: actually process the block.
         let mut resp = PublishBeaconBlockResponse::new();
-        resp.set_success(true);
+        match self.chain.process_block(block.clone()) {
+            Ok(BlockProcessingOutcome::ValidBlock(_)) => {
+                self.network.publish_block(block);
+                resp.set_success(true);
+            }
+            Ok(BlockProcessingOutcome::InvalidBlock(reason)) => {
+                warn!(self.log, "Published block was invalid"; "reason" => format!("{:?}", reason));
+                resp.set_success(false);
+                resp.set_msg(format!("InvalidBlock: {:?}", reason).as_bytes().to_vec());
+            }
+            Err(e) => {
+                error!(self.log, "Unable to process published block"; "error" => format!("{:?}", e));
+                resp.set_success(false);
+                resp.set_msg(format!("Error: {:?}", e).as_bytes().to_vec());
+            }
+        }
 
         let f = sink
             .success(resp)
@@ -0,0 +1,30 @@
+use beacon_chain::BeaconChainApi;
+use futures::Future;
+use grpcio::{RpcContext, UnarySink};
+use protos::services::{Empty, NodeInfoResponse};
+use protos::services_grpc::BeaconNodeService;
+use slog::{debug, Logger};
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct BeaconNodeServiceInstance {
+    pub chain: Arc<dyn BeaconChainApi>,
+    pub log: Logger,
+}
+
+impl BeaconNodeService for BeaconNodeServiceInstance {
+    /// Provides information about the chain, such as its genesis time, and the number of
+    /// attestations awaiting inclusion in a block.
+    fn info(&mut self, ctx: RpcContext, req: Empty, sink: UnarySink<NodeInfoResponse>) {
+        debug!(self.log, "RPC request"; "endpoint" => "Info");
+
+        let mut resp = NodeInfoResponse::new();
+        resp.set_genesis_time(self.chain.genesis_time());
+        resp.set_attestation_pool_size(self.chain.attestation_pool_size() as u64);
+
+        let f = sink
+            .success(resp)
+            .map_err(move |e| println!("failed to reply {:?}: {:?}", req, e));
+        ctx.spawn(f)
+    }
+}
@@ -1,30 +1,68 @@
+mod attestation;
 mod beacon_block;
+mod beacon_node;
 mod validator;
 
+use self::attestation::AttestationServiceInstance;
 use self::beacon_block::BeaconBlockServiceInstance;
+use self::beacon_node::BeaconNodeServiceInstance;
 use self::validator::ValidatorServiceInstance;
+use beacon_chain::BeaconChainApi;
 use grpcio::{Environment, Server, ServerBuilder};
-use protos::services_grpc::{create_beacon_block_service, create_validator_service};
+use network::NetworkService;
+use protos::services_grpc::{
+    create_attestation_service, create_beacon_block_service, create_beacon_node_service,
+    create_validator_service,
+};
 use std::sync::Arc;
 
 use slog::{info, Logger};
 
-pub fn start_server(log: Logger) -> Server {
+pub fn start_server(
+    chain: Arc<dyn BeaconChainApi>,
+    network: Arc<NetworkService>,
+    log: Logger,
+) -> Server {
     let log_clone = log.clone();
     let env = Arc::new(Environment::new(1));
 
+    let beacon_node_service = {
+        let instance = BeaconNodeServiceInstance {
+            chain: chain.clone(),
+            log: log.clone(),
+        };
+        create_beacon_node_service(instance)
+    };
     let beacon_block_service = {
-        let instance = BeaconBlockServiceInstance { log: log.clone() };
+        let instance = BeaconBlockServiceInstance {
+            chain: chain.clone(),
+            network: network.clone(),
+            log: log.clone(),
+        };
         create_beacon_block_service(instance)
     };
     let validator_service = {
-        let instance = ValidatorServiceInstance { log: log.clone() };
+        let instance = ValidatorServiceInstance {
+            chain: chain.clone(),
+            network: network.clone(),
+            log: log.clone(),
+        };
         create_validator_service(instance)
     };
+    let attestation_service = {
+        let instance = AttestationServiceInstance {
+            chain: chain.clone(),
+            network: network.clone(),
+            log: log.clone(),
+        };
+        create_attestation_service(instance)
+    };
 
     let mut server = ServerBuilder::new(env)
+        .register_service(beacon_node_service)
         .register_service(beacon_block_service)
         .register_service(validator_service)
+        .register_service(attestation_service)
         .bind("127.0.0.1", 50_051)
         .build()
         .unwrap();
@@ -1,19 +1,160 @@
+use beacon_chain::BeaconChainApi;
 use bls::PublicKey;
 use futures::Future;
 use grpcio::{RpcContext, RpcStatus, RpcStatusCode, UnarySink};
+use network::NetworkService;
 use protos::services::{
-    IndexResponse, ProposeBlockSlotRequest, ProposeBlockSlotResponse, PublicKey as PublicKeyRequest,
+    ActiveValidator, GetDutiesRequest, GetDutiesResponse, IndexResponse, ProposeBlockSlotRequest,
+    ProposeBlockSlotResponse, PublicKey as PublicKeyRequest, ValidatorDuty,
 };
 use protos::services_grpc::ValidatorService;
 use slog::{debug, Logger};
 use ssz::Decodable;
+use std::sync::Arc;
+use types::{BeaconStateError, Epoch, Slot};
 
 #[derive(Clone)]
 pub struct ValidatorServiceInstance {
+    pub chain: Arc<dyn BeaconChainApi>,
+    pub network: Arc<NetworkService>,
     pub log: Logger,
 }
 
+impl ValidatorServiceInstance {
+    /// Returns the first slot in the `epoch` where the validator is the block proposer, if any.
+    ///
+    /// The shuffling is only known for the present and prior epoch, so other epochs result in an
+    /// error.
+    fn block_production_slot(
+        &self,
+        epoch: Epoch,
+        validator_index: usize,
+    ) -> Result<Option<Slot>, BeaconStateError> {
+        let epoch_length = self.chain.get_spec().epoch_length;
+
+        for slot in epoch.slot_iter(epoch_length) {
+            if self.chain.block_proposer(slot)? == validator_index {
+                return Ok(Some(slot));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns the duties of the validator with `public_key` in the present epoch.
+    ///
+    /// Returns `None` if the validator is unknown or has no attestation duties (i.e., it is not
+    /// active).
+    fn validator_duty(
+        &self,
+        epoch: Epoch,
+        public_key: &PublicKey,
+    ) -> Result<Option<ValidatorDuty>, BeaconStateError> {
+        let validator_index = match self.chain.validator_index(public_key) {
+            Some(index) => index,
+            None => return Ok(None),
+        };
+
+        let (attestation_slot, attestation_shard, committee_index) =
+            match self.chain.validator_attestation_duty(validator_index)? {
+                Some(duty) => duty,
+                None => return Ok(None),
+            };
+
+        let mut duty = ValidatorDuty::new();
+        duty.set_validator_index(validator_index as u64);
+        duty.set_attestation_slot(attestation_slot.as_u64());
+        duty.set_attestation_shard(attestation_shard);
+        duty.set_committee_index(committee_index);
+
+        match self.block_production_slot(epoch, validator_index)? {
+            Some(slot) => duty.set_block_production_slot(slot.as_u64()),
+            None => duty.set_none(true),
+        }
+
+        Ok(Some(duty))
+    }
+}
+
 impl ValidatorService for ValidatorServiceInstance {
+    /// Returns the duties of each of the requested validators for the present epoch.
+    fn get_validator_duties(
+        &mut self,
+        ctx: RpcContext,
+        req: GetDutiesRequest,
+        sink: UnarySink<GetDutiesResponse>,
+    ) {
+        let epoch = Epoch::new(req.get_epoch());
+        debug!(self.log, "RPC request"; "endpoint" => "GetValidatorDuties", "epoch" => epoch.as_u64(), "validators" => req.get_public_keys().len());
+
+        let epoch_length = self.chain.get_spec().epoch_length;
+
+        // Advance the state into the requested epoch, if the slot clock has reached it.
+        if let Some(present_slot) = self.chain.read_slot_clock() {
+            if epoch <= present_slot.epoch(epoch_length)
+                && epoch > self.chain.present_slot().epoch(epoch_length)
+            {
+                self.network.advance_state(epoch.start_slot(epoch_length));
+            }
+        }
+
+        // Attestation duties are only known for the epoch of the present state.
+        if self.chain.present_slot().epoch(epoch_length) != epoch {
+            let f = sink
+                .fail(RpcStatus::new(
+                    RpcStatusCode::FailedPrecondition,
+                    Some("Duties are unknown for the requested epoch".to_string()),
+                ))
+                .map_err(move |e| println!("failed to reply {:?}: {:?}", req, e));
+            return ctx.spawn(f);
+        }
+
+        let public_keys: Result<Vec<PublicKey>, _> = req
+            .get_public_keys()
+            .iter()
+            .map(|bytes| PublicKey::ssz_decode(bytes, 0).map(|(public_key, _)| public_key))
+            .collect();
+        let public_keys = match public_keys {
+            Ok(public_keys) => public_keys,
+            Err(_) => {
+                let f = sink
+                    .fail(RpcStatus::new(
+                        RpcStatusCode::InvalidArgument,
+                        Some("Invalid public_key".to_string()),
+                    ))
+                    .map_err(move |e| println!("failed to reply {:?}: {:?}", req, e));
+                return ctx.spawn(f);
+            }
+        };
+
+        let mut resp = GetDutiesResponse::new();
+
+        for public_key in public_keys {
+            let mut active_validator = ActiveValidator::new();
+
+            match self.validator_duty(epoch, &public_key) {
+                Ok(Some(duty)) => active_validator.set_duty(duty),
+                Ok(None) => active_validator.set_none(true),
+                Err(e) => {
+                    let f = sink
+                        .fail(RpcStatus::new(
+                            RpcStatusCode::FailedPrecondition,
+                            Some(format!("Unable to determine duties: {:?}", e)),
+                        ))
+                        .map_err(move |e| println!("failed to reply {:?}: {:?}", req, e));
+                    return ctx.spawn(f);
+                }
+            }
+
+            resp.mut_active_validators().push(active_validator);
+        }
+
+        let f = sink
+            .success(resp)
+            .map_err(move |e| println!("failed to reply {:?}: {:?}", req, e));
+        ctx.spawn(f)
+    }
+
     fn validator_index(
         &mut self,
         ctx: RpcContext,
@@ -23,15 +164,26 @@ impl ValidatorService for ValidatorServiceInstance {
         if let Ok((public_key, _)) = PublicKey::ssz_decode(req.get_public_key(), 0) {
             debug!(self.log, "RPC request"; "endpoint" => "ValidatorIndex", "public_key" => public_key.concatenated_hex_id());
 
-            let mut resp = IndexResponse::new();
-
-            // TODO: return a legit value.
-            resp.set_index(1);
+            match self.chain.validator_index(&public_key) {
+                Some(index) => {
+                    let mut resp = IndexResponse::new();
+                    resp.set_index(index as u64);
 
-            let f = sink
-                .success(resp)
-                .map_err(move |e| println!("failed to reply {:?}: {:?}", req, e));
-            ctx.spawn(f)
+                    let f = sink
+                        .success(resp)
+                        .map_err(move |e| println!("failed to reply {:?}: {:?}", req, e));
+                    ctx.spawn(f)
+                }
+                None => {
+                    let f = sink
+                        .fail(RpcStatus::new(
+                            RpcStatusCode::NotFound,
+                            Some("Unknown public_key".to_string()),
+                        ))
+                        .map_err(move |e| println!("failed to reply {:?}: {:?}", req, e));
+                    ctx.spawn(f)
+                }
+            }
         } else {
             let f = sink
                 .fail(RpcStatus::new(
@@ -51,10 +203,28 @@ impl ValidatorService for ValidatorServiceInstance {
     ) {
         debug!(self.log, "RPC request"; "endpoint" => "ProposeBlockSlot", "epoch" => req.get_epoch(), "validator_index" => req.get_validator_index());
 
+        let epoch = Epoch::new(req.get_epoch());
+        let validator_index = req.get_validator_index() as usize;
+
+        let proposal_slot = match self.block_production_slot(epoch, validator_index) {
+            Ok(proposal_slot) => proposal_slot,
+            Err(e) => {
+                let f = sink
+                    .fail(RpcStatus::new(
+                        RpcStatusCode::FailedPrecondition,
+                        Some(format!("Unable to determine proposer: {:?}", e)),
+                    ))
+                    .map_err(move |e| println!("failed to reply {:?}: {:?}", req, e));
+                return ctx.spawn(f);
+            }
+        };
+
         let mut resp = ProposeBlockSlotResponse::new();
 
-        // TODO: return a legit value.
-        resp.set_slot(1);
+        match proposal_slot {
+            Some(slot) => resp.set_slot(slot.as_u64()),
+            None => resp.set_none(true),
+        }
 
         let f = sink
             .success(resp)
@@ -1,17 +1,16 @@
 pub mod test_utils;
 mod traits;
 
+use slashing_protection::{SlashingProtection, SlashingProtectionError};
 use slot_clock::SlotClock;
+use ssz::TreeHash;
 use std::sync::Arc;
-use types::{AttestationData, FreeAttestation, Signature, Slot};
+use types::{AttestationData, ChainSpec, FreeAttestation, Hash256, Signature, Slot};
 
 pub use self::traits::{
     BeaconNode, BeaconNodeError, DutiesReader, DutiesReaderError, PublishOutcome, Signer,
 };
 
-const PHASE_0_CUSTODY_BIT: bool = false;
-const DOMAIN_ATTESTATION: u64 = 1;
-
 #[derive(Debug, PartialEq)]
 pub enum PollOutcome {
     AttestationProduced(Slot),
@@ -32,36 +31,54 @@ pub enum Error {
     SlotClockPoisoned,
     EpochLengthIsZero,
     BeaconNodeError(BeaconNodeError),
+    SlashingProtectionError(SlashingProtectionError),
 }
 
 /// A polling state machine which performs block production duties, based upon some epoch duties
 /// (`EpochDutiesMap`) and a concept of time (`SlotClock`).
 ///
-/// Ensures that messages are not slashable.
+/// Ensures that messages are not slashable by recording every signed attestation in a
+/// `SlashingProtection`.
 ///
 /// Relies upon an external service to keep the `EpochDutiesMap` updated.
-pub struct Attester<T: SlotClock, U: BeaconNode, V: DutiesReader, W: Signer> {
+pub struct Attester<T: SlotClock, U: BeaconNode, V: DutiesReader, W: Signer, X: SlashingProtection>
+{
     pub last_processed_slot: Option<Slot>,
+    spec: Arc<ChainSpec>,
     duties: Arc<V>,
     slot_clock: Arc<T>,
     beacon_node: Arc<U>,
     signer: Arc<W>,
+    slashing_protection: Arc<X>,
 }
 
-impl<T: SlotClock, U: BeaconNode, V: DutiesReader, W: Signer> Attester<T, U, V, W> {
+impl<T: SlotClock, U: BeaconNode, V: DutiesReader, W: Signer, X: SlashingProtection>
+    Attester<T, U, V, W, X>
+{
     /// Returns a new instance where `last_processed_slot == 0`.
-    pub fn new(duties: Arc<V>, slot_clock: Arc<T>, beacon_node: Arc<U>, signer: Arc<W>) -> Self {
+    pub fn new(
+        spec: Arc<ChainSpec>,
+        duties: Arc<V>,
+        slot_clock: Arc<T>,
+        beacon_node: Arc<U>,
+        signer: Arc<W>,
+        slashing_protection: Arc<X>,
+    ) -> Self {
         Self {
             last_processed_slot: None,
+            spec,
             duties,
             slot_clock,
             beacon_node,
             signer,
+            slashing_protection,
         }
     }
 }
 
-impl<T: SlotClock, U: BeaconNode, V: DutiesReader, W: Signer> Attester<T, U, V, W> {
+impl<T: SlotClock, U: BeaconNode, V: DutiesReader, W: Signer, X: SlashingProtection>
+    Attester<T, U, V, W, X>
+{
     /// Poll the `BeaconNode` and produce an attestation if required.
     pub fn poll(&mut self) -> Result<PollOutcome, Error> {
         let slot = self
@@ -98,11 +115,11 @@ impl<T: SlotClock, U: BeaconNode, V: DutiesReader, W: Signer> Attester<T, U, V,
             None => return Ok(PollOutcome::BeaconNodeUnableToProduceAttestation(slot)),
         };
 
-        if !self.safe_to_produce(&attestation_data) {
+        if !self.safe_to_produce(&attestation_data)? {
             return Ok(PollOutcome::SlashableAttestationNotProduced(slot));
         }
 
-        let signature = match self.sign_attestation_data(&attestation_data) {
+        let signature = match self.sign_attestation_data(&attestation_data)? {
             Some(signature) => signature,
             None => return Ok(PollOutcome::SignerRejection(slot)),
         };
@@ -134,34 +151,35 @@ impl<T: SlotClock, U: BeaconNode, V: DutiesReader, W: Signer> Attester<T, U, V,
     ///
     /// Important: this function will not check to ensure the block is not slashable. This must be
     /// done upstream.
-    fn sign_attestation_data(&mut self, attestation_data: &AttestationData) -> Option<Signature> {
-        self.store_produce(attestation_data);
+    fn sign_attestation_data(
+        &mut self,
+        attestation_data: &AttestationData,
+    ) -> Result<Option<Signature>, Error> {
+        self.store_produce(attestation_data)?;
 
-        self.signer.sign_attestation_message(
-            &attestation_data.signable_message(PHASE_0_CUSTODY_BIT)[..],
-            DOMAIN_ATTESTATION,
-        )
+        Ok(self.signer.sign_attestation(attestation_data))
     }
 
     /// Returns `true` if signing some attestation_data is safe (non-slashable).
     ///
-    /// !!! UNSAFE !!!
-    ///
-    /// Important: this function is presently stubbed-out. It provides ZERO SAFETY.
-    fn safe_to_produce(&self, _attestation_data: &AttestationData) -> bool {
-        // TODO: ensure the producer doesn't produce slashable blocks.
-        // https://github.com/sigp/lighthouse/issues/160
-        true
+    /// An attestation is unsafe if it is a double vote or surround vote with respect to any
+    /// attestation previously signed by this validator.
+    fn safe_to_produce(&self, attestation_data: &AttestationData) -> Result<bool, Error> {
+        Ok(self.slashing_protection.is_safe_attestation(
+            attestation_data.justified_epoch,
+            attestation_data.slot.epoch(self.spec.epoch_length),
+            &Hash256::from(&attestation_data.hash_tree_root()[..]),
+        )?)
     }
 
-    /// Record that a block was produced so that slashable votes may not be made in the future.
-    ///
-    /// !!! UNSAFE !!!
-    ///
-    /// Important: this function is presently stubbed-out. It provides ZERO SAFETY.
-    fn store_produce(&mut self, _block: &AttestationData) {
-        // TODO: record this block production to prevent future slashings.
-        // https://github.com/sigp/lighthouse/issues/160
+    /// Record that an attestation was produced so that slashable votes may not be made in the
+    /// future.
+    fn store_produce(&mut self, attestation_data: &AttestationData) -> Result<(), Error> {
+        Ok(self.slashing_protection.record_attestation(
+            attestation_data.justified_epoch,
+            attestation_data.slot.epoch(self.spec.epoch_length),
+            &Hash256::from(&attestation_data.hash_tree_root()[..]),
+        )?)
     }
 }
 
@@ -171,14 +189,21 @@ impl From<BeaconNodeError> for Error {
     }
 }
 
+impl From<SlashingProtectionError> for Error {
+    fn from(e: SlashingProtectionError) -> Error {
+        Error::SlashingProtectionError(e)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::test_utils::{EpochMap, LocalSigner, SimulatedBeaconNode};
     use super::*;
+    use slashing_protection::test_utils::MemorySlashingProtection;
     use slot_clock::TestingSlotClock;
     use types::{
         test_utils::{SeedableRng, TestRandom, XorShiftRng},
-        ChainSpec, Keypair,
+        Epoch, Keypair,
     };
 
     // TODO: implement more thorough testing.
@@ -193,7 +218,8 @@ mod tests {
         let spec = Arc::new(ChainSpec::foundation());
         let slot_clock = Arc::new(TestingSlotClock::new(0));
         let beacon_node = Arc::new(SimulatedBeaconNode::default());
-        let signer = Arc::new(LocalSigner::new(Keypair::random()));
+        let signer = Arc::new(LocalSigner::new(Keypair::random(), spec.clone()));
+        let slashing_protection = Arc::new(MemorySlashingProtection::default());
 
         let mut duties = EpochMap::new(spec.epoch_length);
         let attest_slot = Slot::new(100);
@@ -204,10 +230,12 @@ mod tests {
         let duties = Arc::new(duties);
 
         let mut attester = Attester::new(
+            spec.clone(),
             duties.clone(),
             slot_clock.clone(),
             beacon_node.clone(),
             signer.clone(),
+            slashing_protection.clone(),
         );
 
         // Configure responses from the BeaconNode.
@@ -250,4 +278,58 @@ mod tests {
             Ok(PollOutcome::ProducerDutiesUnknown(slot))
         );
     }
+
+    #[test]
+    pub fn refuses_surround_vote() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+
+        let spec = Arc::new(ChainSpec::foundation());
+        let slot_clock = Arc::new(TestingSlotClock::new(0));
+        let beacon_node = Arc::new(SimulatedBeaconNode::default());
+        let signer = Arc::new(LocalSigner::new(Keypair::random(), spec.clone()));
+        let slashing_protection = Arc::new(MemorySlashingProtection::default());
+
+        let first_slot = Slot::new(3 * spec.epoch_length);
+        let second_slot = Slot::new(4 * spec.epoch_length);
+
+        let mut duties = EpochMap::new(spec.epoch_length);
+        duties.insert_attestation_shard(first_slot, 0);
+        duties.insert_attestation_shard(second_slot, 0);
+        duties.set_validator_index(Some(2));
+        let duties = Arc::new(duties);
+
+        let mut attester = Attester::new(
+            spec.clone(),
+            duties.clone(),
+            slot_clock.clone(),
+            beacon_node.clone(),
+            signer.clone(),
+            slashing_protection.clone(),
+        );
+        beacon_node.set_next_publish_result(Ok(PublishOutcome::ValidAttestation));
+
+        // Attest to epoch 3 with a source of epoch 2.
+        let mut first_data = AttestationData::random_for_test(&mut rng);
+        first_data.slot = first_slot;
+        first_data.justified_epoch = Epoch::new(2);
+        beacon_node.set_next_produce_result(Ok(Some(first_data)));
+
+        slot_clock.set_slot(first_slot.as_u64());
+        assert_eq!(
+            attester.poll(),
+            Ok(PollOutcome::AttestationProduced(first_slot))
+        );
+
+        // Attesting to epoch 4 with a source of epoch 1 would surround the first vote.
+        let mut second_data = AttestationData::random_for_test(&mut rng);
+        second_data.slot = second_slot;
+        second_data.justified_epoch = Epoch::new(1);
+        beacon_node.set_next_produce_result(Ok(Some(second_data)));
+
+        slot_clock.set_slot(second_slot.as_u64());
+        assert_eq!(
+            attester.poll(),
+            Ok(PollOutcome::SlashableAttestationNotProduced(second_slot))
+        );
+    }
 }
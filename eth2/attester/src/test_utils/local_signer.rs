@@ -1,18 +1,22 @@
 use crate::traits::Signer;
-use std::sync::RwLock;
-use types::{Keypair, Signature};
+use std::sync::{Arc, RwLock};
+use types::{AttestationData, ChainSpec, Keypair, Signature};
+
+const PHASE_0_CUSTODY_BIT: bool = false;
 
 /// A test-only struct used to simulate a Beacon Node.
 pub struct LocalSigner {
     keypair: Keypair,
+    spec: Arc<ChainSpec>,
     should_sign: RwLock<bool>,
 }
 
 impl LocalSigner {
     /// Produce a new LocalSigner with signing enabled by default.
-    pub fn new(keypair: Keypair) -> Self {
+    pub fn new(keypair: Keypair, spec: Arc<ChainSpec>) -> Self {
         Self {
             keypair,
+            spec,
             should_sign: RwLock::new(true),
         }
     }
@@ -25,7 +29,12 @@ impl LocalSigner {
 }
 
 impl Signer for LocalSigner {
-    fn sign_attestation_message(&self, message: &[u8], domain: u64) -> Option<Signature> {
-        Some(Signature::new(message, domain, &self.keypair.sk))
+    fn sign_attestation(&self, attestation_data: &AttestationData) -> Option<Signature> {
+        let message = attestation_data.signable_message(PHASE_0_CUSTODY_BIT);
+        Some(Signature::new(
+            &message[..],
+            self.spec.domain_attestation,
+            &self.keypair.sk,
+        ))
     }
 }
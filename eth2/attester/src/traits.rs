@@ -45,5 +45,9 @@ pub trait DutiesReader: Send + Sync {
 
 /// Signs message using an internally-maintained private key.
 pub trait Signer {
-    fn sign_attestation_message(&self, message: &[u8], domain: u64) -> Option<Signature>;
+    /// Sign `attestation_data`.
+    ///
+    /// The signer derives the signed message, domain and epochs from `attestation_data` itself, so
+    /// that it may perform its own slashing protection.
+    fn sign_attestation(&self, attestation_data: &AttestationData) -> Option<Signature>;
 }
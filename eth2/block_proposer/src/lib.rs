@@ -1,7 +1,7 @@
 pub mod test_utils;
 mod traits;
 
-use int_to_bytes::int_to_bytes32;
+use slashing_protection::{SlashingProtection, SlashingProtectionError};
 use slot_clock::SlotClock;
 use std::sync::Arc;
 use types::{BeaconBlock, ChainSpec, Slot};
@@ -38,24 +38,35 @@ pub enum Error {
     SlotClockPoisoned,
     EpochLengthIsZero,
     BeaconNodeError(BeaconNodeError),
+    SlashingProtectionError(SlashingProtectionError),
 }
 
 /// A polling state machine which performs block production duties, based upon some epoch duties
 /// (`EpochDutiesMap`) and a concept of time (`SlotClock`).
 ///
-/// Ensures that messages are not slashable.
+/// Ensures that messages are not slashable by recording every signed proposal in a
+/// `SlashingProtection`.
 ///
 /// Relies upon an external service to keep the `EpochDutiesMap` updated.
-pub struct BlockProducer<T: SlotClock, U: BeaconNode, V: DutiesReader, W: Signer> {
+pub struct BlockProducer<
+    T: SlotClock,
+    U: BeaconNode,
+    V: DutiesReader,
+    W: Signer,
+    X: SlashingProtection,
+> {
     pub last_processed_slot: Option<Slot>,
     spec: Arc<ChainSpec>,
     epoch_map: Arc<V>,
     slot_clock: Arc<T>,
     beacon_node: Arc<U>,
     signer: Arc<W>,
+    slashing_protection: Arc<X>,
 }
 
-impl<T: SlotClock, U: BeaconNode, V: DutiesReader, W: Signer> BlockProducer<T, U, V, W> {
+impl<T: SlotClock, U: BeaconNode, V: DutiesReader, W: Signer, X: SlashingProtection>
+    BlockProducer<T, U, V, W, X>
+{
     /// Returns a new instance where `last_processed_slot == 0`.
     pub fn new(
         spec: Arc<ChainSpec>,
@@ -63,6 +74,7 @@ impl<T: SlotClock, U: BeaconNode, V: DutiesReader, W: Signer> BlockProducer<T, U
         slot_clock: Arc<T>,
         beacon_node: Arc<U>,
         signer: Arc<W>,
+        slashing_protection: Arc<X>,
     ) -> Self {
         Self {
             last_processed_slot: None,
@@ -71,11 +83,14 @@ impl<T: SlotClock, U: BeaconNode, V: DutiesReader, W: Signer> BlockProducer<T, U
             slot_clock,
             beacon_node,
             signer,
+            slashing_protection,
         }
     }
 }
 
-impl<T: SlotClock, U: BeaconNode, V: DutiesReader, W: Signer> BlockProducer<T, U, V, W> {
+impl<T: SlotClock, U: BeaconNode, V: DutiesReader, W: Signer, X: SlashingProtection>
+    BlockProducer<T, U, V, W, X>
+{
     /// "Poll" to see if the validator is required to take any action.
     ///
     /// The slot clock will be read and any new actions undertaken.
@@ -124,31 +139,21 @@ impl<T: SlotClock, U: BeaconNode, V: DutiesReader, W: Signer> BlockProducer<T, U
     /// Assumes that a block is required at this slot (does not check the duties).
     ///
     /// Ensures the message is not slashable.
-    ///
-    /// !!! UNSAFE !!!
-    ///
-    /// The slash-protection code is not yet implemented. There is zero protection against
-    /// slashing.
     fn produce_block(&mut self, slot: Slot) -> Result<PollOutcome, Error> {
-        let randao_reveal = {
-            // TODO: add domain, etc to this message. Also ensure result matches `into_to_bytes32`.
-            let message = int_to_bytes32(slot.epoch(self.spec.epoch_length).as_u64());
-
-            match self
-                .signer
-                .sign_randao_reveal(&message, self.spec.domain_randao)
-            {
-                None => return Ok(PollOutcome::SignerRejection(slot)),
-                Some(signature) => signature,
-            }
+        let randao_reveal = match self
+            .signer
+            .sign_randao_reveal(slot.epoch(self.spec.epoch_length))
+        {
+            None => return Ok(PollOutcome::SignerRejection(slot)),
+            Some(signature) => signature,
         };
 
         if let Some(block) = self
             .beacon_node
             .produce_beacon_block(slot, &randao_reveal)?
         {
-            if self.safe_to_produce(&block) {
-                if let Some(block) = self.sign_block(block) {
+            if self.safe_to_produce(&block)? {
+                if let Some(block) = self.sign_block(block)? {
                     self.beacon_node.publish_beacon_block(block)?;
                     Ok(PollOutcome::BlockProduced(slot))
                 } else {
@@ -166,40 +171,34 @@ impl<T: SlotClock, U: BeaconNode, V: DutiesReader, W: Signer> BlockProducer<T, U
     ///
     /// Important: this function will not check to ensure the block is not slashable. This must be
     /// done upstream.
-    fn sign_block(&mut self, mut block: BeaconBlock) -> Option<BeaconBlock> {
-        self.store_produce(&block);
-
-        match self.signer.sign_block_proposal(
-            &block.proposal_root(&self.spec)[..],
-            self.spec.domain_proposal,
-        ) {
-            None => None,
+    fn sign_block(&mut self, mut block: BeaconBlock) -> Result<Option<BeaconBlock>, Error> {
+        self.store_produce(&block)?;
+
+        match self.signer.sign_block_proposal(&block) {
+            None => Ok(None),
             Some(signature) => {
                 block.signature = signature;
-                Some(block)
+                Ok(Some(block))
             }
         }
     }
 
     /// Returns `true` if signing a block is safe (non-slashable).
     ///
-    /// !!! UNSAFE !!!
-    ///
-    /// Important: this function is presently stubbed-out. It provides ZERO SAFETY.
-    fn safe_to_produce(&self, _block: &BeaconBlock) -> bool {
-        // TODO: ensure the producer doesn't produce slashable blocks.
-        // https://github.com/sigp/lighthouse/issues/160
-        true
+    /// A block is unsafe if a different block has previously been signed for the same slot.
+    fn safe_to_produce(&self, block: &BeaconBlock) -> Result<bool, Error> {
+        let proposal_root = block.proposal_root(&self.spec);
+        Ok(self
+            .slashing_protection
+            .is_safe_proposal(block.slot, &proposal_root)?)
     }
 
     /// Record that a block was produced so that slashable votes may not be made in the future.
-    ///
-    /// !!! UNSAFE !!!
-    ///
-    /// Important: this function is presently stubbed-out. It provides ZERO SAFETY.
-    fn store_produce(&mut self, _block: &BeaconBlock) {
-        // TODO: record this block production to prevent future slashings.
-        // https://github.com/sigp/lighthouse/issues/160
+    fn store_produce(&mut self, block: &BeaconBlock) -> Result<(), Error> {
+        let proposal_root = block.proposal_root(&self.spec);
+        Ok(self
+            .slashing_protection
+            .record_proposal(block.slot, &proposal_root)?)
     }
 }
 
@@ -209,14 +208,21 @@ impl From<BeaconNodeError> for Error {
     }
 }
 
+impl From<SlashingProtectionError> for Error {
+    fn from(e: SlashingProtectionError) -> Error {
+        Error::SlashingProtectionError(e)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::test_utils::{EpochMap, LocalSigner, SimulatedBeaconNode};
     use super::*;
+    use slashing_protection::test_utils::MemorySlashingProtection;
     use slot_clock::TestingSlotClock;
     use types::{
         test_utils::{SeedableRng, TestRandom, XorShiftRng},
-        Keypair,
+        Hash256, Keypair,
     };
 
     // TODO: implement more thorough testing.
@@ -231,7 +237,8 @@ mod tests {
         let spec = Arc::new(ChainSpec::foundation());
         let slot_clock = Arc::new(TestingSlotClock::new(0));
         let beacon_node = Arc::new(SimulatedBeaconNode::default());
-        let signer = Arc::new(LocalSigner::new(Keypair::random()));
+        let signer = Arc::new(LocalSigner::new(Keypair::random(), spec.clone()));
+        let slashing_protection = Arc::new(MemorySlashingProtection::default());
 
         let mut epoch_map = EpochMap::new(spec.epoch_length);
         let produce_slot = Slot::new(100);
@@ -245,6 +252,7 @@ mod tests {
             slot_clock.clone(),
             beacon_node.clone(),
             signer.clone(),
+            slashing_protection.clone(),
         );
 
         // Configure responses from the BeaconNode.
@@ -287,4 +295,62 @@ mod tests {
             Ok(PollOutcome::ProducerDutiesUnknown(Slot::new(slot)))
         );
     }
+
+    #[test]
+    pub fn refuses_double_proposal() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+
+        let spec = Arc::new(ChainSpec::foundation());
+        let slot_clock = Arc::new(TestingSlotClock::new(0));
+        let beacon_node = Arc::new(SimulatedBeaconNode::default());
+        let signer = Arc::new(LocalSigner::new(Keypair::random(), spec.clone()));
+        let slashing_protection = Arc::new(MemorySlashingProtection::default());
+
+        let mut epoch_map = EpochMap::new(spec.epoch_length);
+        let produce_slot = Slot::new(100);
+        epoch_map
+            .map
+            .insert(produce_slot.epoch(spec.epoch_length), produce_slot);
+        let epoch_map = Arc::new(epoch_map);
+
+        let mut block = BeaconBlock::random_for_test(&mut rng);
+        block.slot = produce_slot;
+        beacon_node.set_next_produce_result(Ok(Some(block.clone())));
+        beacon_node.set_next_publish_result(Ok(PublishOutcome::ValidBlock));
+
+        // Simulate a different block having been signed for this slot by a previous instance.
+        slashing_protection
+            .record_proposal(produce_slot, &Hash256::from("other block".as_bytes()))
+            .unwrap();
+
+        let mut block_proposer = BlockProducer::new(
+            spec.clone(),
+            epoch_map.clone(),
+            slot_clock.clone(),
+            beacon_node.clone(),
+            signer.clone(),
+            slashing_protection.clone(),
+        );
+
+        slot_clock.set_slot(produce_slot.as_u64());
+        assert_eq!(
+            block_proposer.poll(),
+            Ok(PollOutcome::SlashableBlockNotProduced(produce_slot))
+        );
+        assert_eq!(*beacon_node.publish_input.read().unwrap(), None);
+
+        // A fresh instance sharing the same slashing protection must not sign the block either.
+        let mut block_proposer = BlockProducer::new(
+            spec.clone(),
+            epoch_map.clone(),
+            slot_clock.clone(),
+            beacon_node.clone(),
+            signer.clone(),
+            slashing_protection.clone(),
+        );
+        assert_eq!(
+            block_proposer.poll(),
+            Ok(PollOutcome::SlashableBlockNotProduced(produce_slot))
+        );
+    }
 }
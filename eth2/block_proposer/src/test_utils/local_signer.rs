@@ -1,18 +1,21 @@
 use crate::traits::Signer;
-use std::sync::RwLock;
-use types::{Keypair, Signature};
+use int_to_bytes::int_to_bytes32;
+use std::sync::{Arc, RwLock};
+use types::{BeaconBlock, ChainSpec, Epoch, Keypair, Signature};
 
 /// A test-only struct used to simulate a Beacon Node.
 pub struct LocalSigner {
     keypair: Keypair,
+    spec: Arc<ChainSpec>,
     should_sign: RwLock<bool>,
 }
 
 impl LocalSigner {
     /// Produce a new LocalSigner with signing enabled by default.
-    pub fn new(keypair: Keypair) -> Self {
+    pub fn new(keypair: Keypair, spec: Arc<ChainSpec>) -> Self {
         Self {
             keypair,
+            spec,
             should_sign: RwLock::new(true),
         }
     }
@@ -25,11 +28,21 @@ impl LocalSigner {
 }
 
 impl Signer for LocalSigner {
-    fn sign_block_proposal(&self, message: &[u8], domain: u64) -> Option<Signature> {
-        Some(Signature::new(message, domain, &self.keypair.sk))
+    fn sign_block_proposal(&self, block: &BeaconBlock) -> Option<Signature> {
+        let message = block.proposal_root(&self.spec);
+        Some(Signature::new(
+            &message[..],
+            self.spec.domain_proposal,
+            &self.keypair.sk,
+        ))
     }
 
-    fn sign_randao_reveal(&self, message: &[u8], domain: u64) -> Option<Signature> {
-        Some(Signature::new(message, domain, &self.keypair.sk))
+    fn sign_randao_reveal(&self, epoch: Epoch) -> Option<Signature> {
+        let message = int_to_bytes32(epoch.as_u64());
+        Some(Signature::new(
+            &message,
+            self.spec.domain_randao,
+            &self.keypair.sk,
+        ))
     }
 }
@@ -1,4 +1,4 @@
-use types::{BeaconBlock, Signature, Slot};
+use types::{BeaconBlock, Epoch, Signature, Slot};
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum BeaconNodeError {
@@ -43,7 +43,12 @@ pub trait DutiesReader: Send + Sync {
 }
 
 /// Signs message using an internally-maintained private key.
+///
+/// The signer derives the signed message and its domain from the object being signed, so that it
+/// may perform its own slashing protection.
 pub trait Signer {
-    fn sign_block_proposal(&self, message: &[u8], domain: u64) -> Option<Signature>;
-    fn sign_randao_reveal(&self, message: &[u8], domain: u64) -> Option<Signature>;
+    /// Sign the proposal of `block`.
+    fn sign_block_proposal(&self, block: &BeaconBlock) -> Option<Signature>;
+    /// Sign the randao reveal for `epoch`.
+    fn sign_randao_reveal(&self, epoch: Epoch) -> Option<Signature>;
 }
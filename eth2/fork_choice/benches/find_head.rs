@@ -0,0 +1,242 @@
+use bls::{PublicKey, Signature};
+use criterion::Criterion;
+use criterion::{black_box, criterion_group, criterion_main, Bencher, Benchmark};
+use db::stores::{BeaconBlockStore, BeaconStateStore};
+use db::MemoryDB;
+use fork_choice::{BitwiseLMDGhost, ForkChoice, ProtolambdaLMDGhost};
+use ssz::ssz_encode;
+use std::sync::Arc;
+use types::{
+    BeaconBlock, BeaconBlockBody, BeaconState, ChainSpec, Epoch, Eth1Data, Hash256, Validator,
+};
+
+/// The number of blocks in the tree, after genesis.
+const BLOCK_COUNT: usize = 64;
+
+/// The number of blocks in the deep tree, after genesis. Its head is more than 2^16 slots after
+/// genesis.
+const DEEP_BLOCK_COUNT: usize = 100_000;
+
+/// The root of each block with the block, starting with genesis.
+type Blocks = Vec<(Hash256, BeaconBlock)>;
+
+/// Store a genesis state with `validator_count` active validators and a tree of `block_count`
+/// blocks, where every third block forks from its grandparent.
+fn build_block_tree(
+    validator_count: usize,
+    block_count: usize,
+    spec: &ChainSpec,
+) -> (
+    Arc<BeaconBlockStore<MemoryDB>>,
+    Arc<BeaconStateStore<MemoryDB>>,
+    Blocks,
+) {
+    let db = Arc::new(MemoryDB::open());
+    let block_store = Arc::new(BeaconBlockStore::new(db.clone()));
+    let state_store = Arc::new(BeaconStateStore::new(db.clone()));
+    let zero_hash = Hash256::zero();
+    let eth1_data = Eth1Data {
+        deposit_root: zero_hash,
+        block_hash: zero_hash,
+    };
+
+    let mut state = BeaconState::genesis(1_550_381_159, vec![], eth1_data.clone(), spec).unwrap();
+    let validator = Validator {
+        pubkey: PublicKey::default(),
+        withdrawal_credentials: zero_hash,
+        activation_epoch: Epoch::from(0u64),
+        exit_epoch: spec.far_future_epoch,
+        withdrawal_epoch: spec.far_future_epoch,
+        penalized_epoch: spec.far_future_epoch,
+        status_flags: None,
+    };
+    state.validator_registry = vec![validator; validator_count];
+    state.validator_balances = vec![spec.max_deposit_amount; validator_count];
+    let state_root = state.canonical_root();
+    state_store
+        .put(&state_root, &ssz_encode(&state)[..])
+        .unwrap();
+
+    let mut blocks: Blocks = vec![];
+    for i in 0..=block_count {
+        let parent_root = match i {
+            0 => zero_hash,
+            1 => blocks[0].0,
+            i if i % 3 == 0 => blocks[i - 2].0,
+            i => blocks[i - 1].0,
+        };
+        let slot = match i {
+            0 => spec.genesis_slot,
+            i => spec.genesis_slot + i as u64,
+        };
+        let block = BeaconBlock {
+            slot,
+            parent_root,
+            state_root,
+            randao_reveal: Signature::empty_signature(),
+            eth1_data: eth1_data.clone(),
+            signature: Signature::empty_signature(),
+            body: BeaconBlockBody {
+                proposer_slashings: vec![],
+                attester_slashings: vec![],
+                attestations: vec![],
+                deposits: vec![],
+                exits: vec![],
+            },
+        };
+        let block_root = block.canonical_root();
+        block_store
+            .put(&block_root, &ssz_encode(&block)[..])
+            .unwrap();
+        blocks.push((block_root, block));
+    }
+
+    (block_store, state_store, blocks)
+}
+
+/// Add the blocks after genesis and a vote from each validator, spread across the first
+/// `BLOCK_COUNT` blocks.
+fn add_blocks_and_votes<F: ForkChoice>(
+    fork_choice: &mut F,
+    blocks: &[(Hash256, BeaconBlock)],
+    validator_count: usize,
+    spec: &ChainSpec,
+) {
+    for (block_root, block) in &blocks[1..] {
+        fork_choice.add_block(block, block_root, spec).unwrap();
+    }
+    for validator_index in 0..validator_count {
+        let (block_root, _) = &blocks[1 + validator_index % BLOCK_COUNT];
+        fork_choice
+            .add_attestation(validator_index as u64, block_root, spec)
+            .unwrap();
+    }
+}
+
+/// Move the votes of one committee to the block at `index`, which is later than any block they
+/// have voted for.
+fn revote<F: ForkChoice>(
+    fork_choice: &mut F,
+    blocks: &[(Hash256, BeaconBlock)],
+    committee: u64,
+    index: usize,
+    validator_count: usize,
+    spec: &ChainSpec,
+) {
+    let committee_size = validator_count as u64 / spec.epoch_length;
+    let (block_root, _) = &blocks[index];
+    for validator_index in committee * committee_size..(committee + 1) * committee_size {
+        fork_choice
+            .add_attestation(validator_index, block_root, spec)
+            .unwrap();
+    }
+}
+
+/// Returns a routine which moves the votes of one committee to the next block, then finds the
+/// head. Once the last block is reached the votes are left unchanged.
+fn find_head_after_revote<F: ForkChoice>(
+    mut fork_choice: F,
+    blocks: Arc<Blocks>,
+    validator_count: usize,
+    spec: &ChainSpec,
+) -> impl FnMut(&mut Bencher) {
+    let spec = spec.clone();
+    let genesis_root = blocks[0].0;
+    // The re-votes start after the blocks holding the initial votes.
+    let mut index = BLOCK_COUNT;
+    move |b| {
+        b.iter(|| {
+            if index + 1 < blocks.len() {
+                index += 1;
+                let committee = index as u64 % spec.epoch_length;
+                revote(
+                    &mut fork_choice,
+                    &blocks,
+                    committee,
+                    index,
+                    validator_count,
+                    &spec,
+                );
+            }
+            black_box(fork_choice.find_head(&genesis_root, &spec).unwrap())
+        })
+    }
+}
+
+fn find_head(c: &mut Criterion) {
+    let spec = ChainSpec::foundation();
+
+    for validator_count in &[16_384, 65_536] {
+        let validator_count = *validator_count;
+        let (block_store, state_store, blocks) =
+            build_block_tree(validator_count, BLOCK_COUNT, &spec);
+        let genesis_root = blocks[0].0;
+
+        let mut bitwise = BitwiseLMDGhost::new(block_store.clone(), state_store.clone());
+        add_blocks_and_votes(&mut bitwise, &blocks, validator_count, &spec);
+        let mut protolambda = ProtolambdaLMDGhost::new(block_store, state_store);
+        add_blocks_and_votes(&mut protolambda, &blocks, validator_count, &spec);
+
+        // Both rules must agree on the head.
+        assert_eq!(
+            bitwise.find_head(&genesis_root, &spec).unwrap(),
+            protolambda.find_head(&genesis_root, &spec).unwrap()
+        );
+
+        let bitwise_spec = spec.clone();
+        let protolambda_spec = spec.clone();
+        c.bench(
+            &format!("find_head {} validators", validator_count),
+            Benchmark::new("bitwise", move |b| {
+                b.iter(|| black_box(bitwise.find_head(&genesis_root, &bitwise_spec).unwrap()))
+            })
+            .with_function("protolambda", move |b| {
+                b.iter(|| {
+                    black_box(
+                        protolambda
+                            .find_head(&genesis_root, &protolambda_spec)
+                            .unwrap(),
+                    )
+                })
+            })
+            .sample_size(10),
+        );
+    }
+}
+
+fn find_head_revoting(c: &mut Criterion) {
+    let spec = ChainSpec::foundation();
+    let validator_count = 16_384;
+
+    let (block_store, state_store, blocks) =
+        build_block_tree(validator_count, DEEP_BLOCK_COUNT, &spec);
+    let blocks = Arc::new(blocks);
+    let genesis_root = blocks[0].0;
+
+    let mut bitwise = BitwiseLMDGhost::new(block_store.clone(), state_store.clone());
+    add_blocks_and_votes(&mut bitwise, &blocks, validator_count, &spec);
+    let mut protolambda = ProtolambdaLMDGhost::new(block_store, state_store);
+    add_blocks_and_votes(&mut protolambda, &blocks, validator_count, &spec);
+
+    // Both rules must agree on the head.
+    assert_eq!(
+        bitwise.find_head(&genesis_root, &spec).unwrap(),
+        protolambda.find_head(&genesis_root, &spec).unwrap()
+    );
+
+    c.bench(
+        &format!("find_head re-voting {} blocks", DEEP_BLOCK_COUNT),
+        Benchmark::new(
+            "bitwise",
+            find_head_after_revote(bitwise, blocks.clone(), validator_count, &spec),
+        )
+        .with_function(
+            "protolambda",
+            find_head_after_revote(protolambda, blocks, validator_count, &spec),
+        )
+        .sample_size(10),
+    );
+}
+
+criterion_group!(benches, find_head, find_head_revoting);
+criterion_main!(benches);
@@ -1,7 +1,7 @@
 extern crate bit_vec;
 extern crate fast_math;
 
-use crate::{ForkChoice, ForkChoiceError};
+use crate::{descendants, ForkChoice, ForkChoiceError};
 use bit_vec::BitVec;
 use db::{
     stores::{BeaconBlockStore, BeaconStateStore},
@@ -16,7 +16,6 @@ use types::{
     ChainSpec, Hash256, Slot, SlotHeight,
 };
 
-//TODO: Pruning - Children
 //TODO: Handle Syncing
 
 /// The optimised bitwise LMD-GHOST fork choice rule.
@@ -154,12 +153,24 @@ where
 
         // not in the cache recursively search for ancestors using a log-lookup
         if let Some(ancestor) = {
-            let ancestor_lookup = self.ancestors
+            let ancestor_lookup = *self.ancestors
                 [log2_int((block_height - target_height - 1u64).as_u32()) as usize]
                 .get(&block_hash)
                 //TODO: Panic if we can't lookup and fork choice fails
                 .expect("All blocks should be added to the ancestor log lookup table");
-            self.get_ancestor(*ancestor_lookup, target_height, &spec)
+            // the finalized block may be recorded as its own ancestor, in which case no earlier
+            // ancestor is known.
+            if ancestor_lookup == block_hash {
+                return None;
+            }
+            // with skipped slots the log-lookup may pass the target height, in which case step
+            // to the parent instead.
+            let ancestor_lookup = if self.get_height(&ancestor_lookup, spec)? >= target_height {
+                ancestor_lookup
+            } else {
+                self.ancestors[0][&block_hash]
+            };
+            self.get_ancestor(ancestor_lookup, target_height, &spec)
         } {
             // add the result to the cache
             self.cache.insert(cache_key, ancestor);
@@ -169,6 +180,12 @@ where
         None
     }
 
+    /// Gets the height of the block specified by `block_hash`, if it is known.
+    fn get_height(&self, block_hash: &Hash256, spec: &ChainSpec) -> Option<SlotHeight> {
+        let block = self.block_store.get_deserialized(block_hash).ok()??;
+        Some(block.slot.height(spec.genesis_slot))
+    }
+
     // looks for an obvious block winner given the latest votes for a specific height
     fn get_clear_winner(
         &mut self,
@@ -188,6 +205,10 @@ where
                 let current_vote_value = current_votes.get(&ancestor).unwrap_or_else(|| &0);
                 current_votes.insert(ancestor, current_vote_value + *votes);
                 total_vote_count += votes;
+            } else if self.get_height(hash, spec)? > block_height {
+                // the vote is for a chain which skipped this height, so it cannot be counted
+                // towards a winner at this height.
+                return None;
             }
         }
         // Check if there is a clear block winner at this height. If so return it.
@@ -268,6 +289,17 @@ impl<T: ClientDB + Sized> ForkChoice for BitwiseLMDGhost<T> {
 
         let parent_hash = &block.parent_root;
 
+        // find the ancestors of the new block before making any changes, so a block whose parent
+        // is unknown is rejected without effect.
+        let mut block_ancestors = [*parent_hash; 16];
+        for (index, ancestor) in block_ancestors.iter_mut().enumerate() {
+            if parent_height % (1 << index) != 0 {
+                *ancestor = *self.ancestors[index]
+                    .get(parent_hash)
+                    .ok_or_else(|| ForkChoiceError::UnknownParent(*parent_hash))?;
+            }
+        }
+
         // add the new block to the children of parent
         (*self
             .children
@@ -276,14 +308,8 @@ impl<T: ClientDB + Sized> ForkChoice for BitwiseLMDGhost<T> {
         .push(block_hash.clone());
 
         // build the ancestor data structure
-        for index in 0..16 {
-            if parent_height % (1 << index) == 0 {
-                self.ancestors[index].insert(*block_hash, *parent_hash);
-            } else {
-                // TODO: This is unsafe. Will panic if parent_hash doesn't exist. Using it for debugging
-                let parent_ancestor = self.ancestors[index][parent_hash];
-                self.ancestors[index].insert(*block_hash, parent_ancestor);
-            }
+        for (index, ancestor) in block_ancestors.iter().enumerate() {
+            self.ancestors[index].insert(*block_hash, *ancestor);
         }
         // update the max height
         self.max_known_height = std::cmp::max(self.max_known_height, parent_height + 1);
@@ -405,16 +431,24 @@ impl<T: ClientDB + Sized> ForkChoice for BitwiseLMDGhost<T> {
             else {
                 trace!("Searching for best child");
                 let mut child_votes = HashMap::new();
-                for (voted_hash, vote) in latest_votes.iter() {
-                    // if the latest votes correspond to a child
-                    if let Some(child) = self.get_ancestor(*voted_hash, block_height + 1, spec) {
-                        // add up the votes for each child
-                        *child_votes.entry(child).or_insert_with(|| 0) += vote;
+                for child in children.iter() {
+                    // children may not be at the next height if slots were skipped
+                    let child_height = self
+                        .get_height(child, spec)
+                        .ok_or_else(|| ForkChoiceError::MissingBeaconBlock(*child))?;
+                    for (voted_hash, vote) in latest_votes.iter() {
+                        // if the latest votes correspond to the child
+                        if self.get_ancestor(*voted_hash, child_height, spec) == Some(*child) {
+                            // add up the votes for each child
+                            *child_votes.entry(*child).or_insert_with(|| 0) += vote;
+                        }
                     }
                 }
-                // given the votes on the children, find the best child
+                // given the votes on the children, find the best child. If no child has any
+                // votes, the tie is broken in favour of the highest block hash.
                 current_head = self
                     .choose_best_child(&child_votes)
+                    .or_else(|| children.iter().max().cloned())
                     .ok_or(ForkChoiceError::CannotFindBestChild)?;
                 trace!("Best child found: {}", current_head);
             }
@@ -442,6 +476,30 @@ impl<T: ClientDB + Sized> ForkChoice for BitwiseLMDGhost<T> {
             });
         }
     }
+
+    fn prune(&mut self, finalized_block: &Hash256, _: &ChainSpec) -> Result<(), ForkChoiceError> {
+        let descendants = descendants(&self.children, finalized_block);
+
+        self.children
+            .retain(|block_hash, _| descendants.contains(block_hash));
+        for ancestors in self.ancestors.iter_mut() {
+            ancestors.retain(|block_hash, _| descendants.contains(block_hash));
+        }
+        self.cache
+            .retain(|key, _| descendants.contains(&key.block_hash));
+        self.latest_attestation_targets
+            .retain(|_, target| descendants.contains(target));
+
+        // the finalized block is not known if it was finalized before the fork choice was built
+        // (e.g., after a restart). No ancestor prior to it is ever required, so it is recorded as
+        // its own ancestor to allow its children to be added.
+        if !self.ancestors[0].contains_key(finalized_block) {
+            for ancestors in self.ancestors.iter_mut() {
+                ancestors.insert(*finalized_block, *finalized_block);
+            }
+        }
+        Ok(())
+    }
 }
 
 /// Type for storing blocks in a memory cache. Key is comprised of block-hash plus the height.
@@ -1,8 +1,8 @@
 //! This crate stores the various implementations of fork-choice rules that can be used for the
 //! beacon blockchain.
 //!
-//! There are three implementations. One is the naive longest chain rule (primarily for testing
-//! purposes). The other two are proposed implementations of the LMD-GHOST fork-choice rule with various forms of optimisation.
+//! There are four implementations. One is the naive longest chain rule (primarily for testing
+//! purposes). The other three are proposed implementations of the LMD-GHOST fork-choice rule with various forms of optimisation.
 //!
 //! The current implementations are:
 //! - [`longest-chain`]: Simplistic longest-chain fork choice - primarily for testing, **not for
@@ -11,10 +11,13 @@
 //! specifications (https://github.com/ethereum/eth2.0-specs/blob/v0.1/specs/core/0_beacon-chain.md#get_block_root).
 //! - [`bitwise_lmd_ghost`]: This is an optimised version of bitwise LMD-GHOST as proposed
 //! by Vitalik. The reference implementation can be found at: https://github.com/ethereum/research/blob/master/ghost/ghost.py
+//! - [`protolambda_lmd_ghost`]: This is the proto-array implementation of LMD-GHOST as proposed by
+//! protolambda, storing the block tree in a flat vector and applying only the changes in votes.
 //!
 //! [`longest-chain`]: struct.LongestChain.html
 //! [`slow_lmd_ghost`]: struct.SlowLmdGhost.html
 //! [`bitwise_lmd_ghost`]: struct.OptimisedLmdGhost.html
+//! [`protolambda_lmd_ghost`]: struct.ProtolambdaLMDGhost.html
 
 extern crate db;
 extern crate ssz;
@@ -22,14 +25,17 @@ extern crate types;
 
 pub mod bitwise_lmd_ghost;
 pub mod longest_chain;
+pub mod protolambda_lmd_ghost;
 pub mod slow_lmd_ghost;
 
 use db::stores::BeaconBlockAtSlotError;
 use db::DBError;
+use std::collections::{HashMap, HashSet};
 use types::{BeaconBlock, ChainSpec, Hash256};
 
 pub use bitwise_lmd_ghost::BitwiseLMDGhost;
 pub use longest_chain::LongestChain;
+pub use protolambda_lmd_ghost::ProtolambdaLMDGhost;
 pub use slow_lmd_ghost::SlowLMDGhost;
 
 /// Defines the interface for Fork Choices. Each Fork choice will define their own data structures
@@ -59,6 +65,23 @@ pub trait ForkChoice: Send + Sync {
         justified_start_block: &Hash256,
         spec: &ChainSpec,
     ) -> Result<Hash256, ForkChoiceError>;
+    /// Called when the finalized block has changed. Removes all data relating to blocks which
+    /// do not descend from `finalized_block`, as they may never become the head.
+    fn prune(&mut self, finalized_block: &Hash256, spec: &ChainSpec)
+        -> Result<(), ForkChoiceError>;
+}
+
+/// Returns `root` and all of its descendants in the `children` tree.
+fn descendants(children: &HashMap<Hash256, Vec<Hash256>>, root: &Hash256) -> HashSet<Hash256> {
+    let mut descendants = HashSet::new();
+    let mut queue = vec![*root];
+    while let Some(block_hash) = queue.pop() {
+        if let Some(block_children) = children.get(&block_hash) {
+            queue.extend(block_children.iter().cloned());
+        }
+        descendants.insert(block_hash);
+    }
+    descendants
 }
 
 /// Possible fork choice errors that can occur.
@@ -71,6 +94,11 @@ pub enum ForkChoiceError {
     ChildrenNotFound,
     StorageError(String),
     HeadNotFound,
+    /// The parent of a block is neither a block supplied to the fork choice, the genesis block nor
+    /// the finalized block.
+    UnknownParent(Hash256),
+    /// Applying the change in votes would make the weight of the block negative.
+    InvalidDelta(Hash256),
 }
 
 impl From<DBError> for ForkChoiceError {
@@ -101,4 +129,33 @@ pub enum ForkChoiceAlgorithm {
     SlowLMDGhost,
     /// An optimised version of bitwise LMD-GHOST by Vitalik.
     BitwiseLMDGhost,
+    /// The proto-array version of LMD-GHOST by protolambda.
+    ProtolambdaLMDGhost,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_descendants() {
+        let hash = |i: u8| Hash256::from(&[i; 32][..]);
+        // 1 -> 2 -> 4
+        //   -> 3
+        // 5 -> 6
+        let mut children = HashMap::new();
+        children.insert(hash(1), vec![hash(2), hash(3)]);
+        children.insert(hash(2), vec![hash(4)]);
+        children.insert(hash(5), vec![hash(6)]);
+
+        let expected: HashSet<Hash256> = [1, 2, 3, 4].iter().map(|i| hash(*i)).collect();
+        assert_eq!(descendants(&children, &hash(1)), expected);
+
+        let expected: HashSet<Hash256> = [2, 4].iter().map(|i| hash(*i)).collect();
+        assert_eq!(descendants(&children, &hash(2)), expected);
+
+        // A block without children is its only descendant.
+        let expected: HashSet<Hash256> = [4].iter().map(|i| hash(*i)).collect();
+        assert_eq!(descendants(&children, &hash(4)), expected);
+    }
 }
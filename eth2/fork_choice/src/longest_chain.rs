@@ -105,4 +105,34 @@ impl<T: ClientDB + Sized> ForkChoice for LongestChain<T> {
 
         Ok(self.head_block_hashes[head_index.unwrap()])
     }
+
+    fn prune(&mut self, finalized_block: &Hash256, _: &ChainSpec) -> Result<(), ForkChoiceError> {
+        let finalized_slot = self
+            .block_store
+            .get_deserialized(finalized_block)?
+            .ok_or(ForkChoiceError::MissingBeaconBlock(*finalized_block))?
+            .slot;
+
+        // Keep only the heads which descend from the finalized block.
+        let mut head_block_hashes = vec![];
+        for head_hash in &self.head_block_hashes {
+            let mut block_hash = *head_hash;
+            loop {
+                if block_hash == *finalized_block {
+                    head_block_hashes.push(*head_hash);
+                    break;
+                }
+                let block = self
+                    .block_store
+                    .get_deserialized(&block_hash)?
+                    .ok_or(ForkChoiceError::MissingBeaconBlock(block_hash))?;
+                if block.slot <= finalized_slot {
+                    break;
+                }
+                block_hash = block.parent_root;
+            }
+        }
+        self.head_block_hashes = head_block_hashes;
+        Ok(())
+    }
 }
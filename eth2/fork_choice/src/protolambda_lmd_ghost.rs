@@ -1 +1,381 @@
+use crate::{ForkChoice, ForkChoiceError};
+use db::{
+    stores::{BeaconBlockStore, BeaconStateStore},
+    ClientDB,
+};
+use log::{debug, trace};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use types::{
+    readers::BeaconBlockReader, validator_registry::get_active_validator_indices, BeaconBlock,
+    ChainSpec, Hash256, Slot,
+};
 
+/// A block in the `ProtolambdaLMDGhost` tree.
+struct ProtoNode {
+    root: Hash256,
+    slot: Slot,
+    /// The index of the parent, or `None` if the parent is not in the tree.
+    parent: Option<usize>,
+    /// The total balance of the validators voting for this block or its descendants.
+    weight: u64,
+    /// The index of the child leading to the head, if any.
+    best_child: Option<usize>,
+    /// The index of the head, if this block has any children.
+    best_descendant: Option<usize>,
+}
+
+/// The latest vote of a validator.
+#[derive(Clone, Default)]
+struct Vote {
+    /// The block root whose weight includes this vote.
+    current_root: Hash256,
+    /// The block root of the latest attestation, applied upon the next `find_head`.
+    next_root: Hash256,
+}
+
+/// The proto-array LMD-GHOST fork choice rule, as proposed by protolambda.
+///
+/// Blocks are stored in a flat vector, each after its parent, with the weight of its subtree.
+/// Rather than tallying every vote upon each `find_head`, only the change in the balance voting for
+/// each block since the last run is applied, by a single pass from the leaves to the root which
+/// also finds the best child and descendant of each block. The head is then the best descendant
+/// of the justified block.
+///
+/// Ties between blocks of equal weight are broken in favour of the highest block root.
+pub struct ProtolambdaLMDGhost<T: ClientDB + Sized> {
+    /// The blocks of the tree, each after its parent.
+    nodes: Vec<ProtoNode>,
+    /// The index of each block in `nodes`.
+    indices: HashMap<Hash256, usize>,
+    /// The latest vote of each validator, by validator index.
+    votes: Vec<Vote>,
+    /// The balance of each validator applied by the last `find_head`.
+    balances: Vec<u64>,
+    /// Block storage access.
+    block_store: Arc<BeaconBlockStore<T>>,
+    /// State storage access.
+    state_store: Arc<BeaconStateStore<T>>,
+}
+
+impl<T> ProtolambdaLMDGhost<T>
+where
+    T: ClientDB + Sized,
+{
+    pub fn new(
+        block_store: Arc<BeaconBlockStore<T>>,
+        state_store: Arc<BeaconStateStore<T>>,
+    ) -> Self {
+        ProtolambdaLMDGhost {
+            nodes: vec![],
+            indices: HashMap::new(),
+            votes: vec![],
+            balances: vec![],
+            block_store,
+            state_store,
+        }
+    }
+
+    /// Returns the balance of each validator in the state with `state_root`, weighted by
+    /// min(balance, MAX_DEPOSIT_AMOUNT) // FORK_CHOICE_BALANCE_INCREMENT. Validators which are not
+    /// active at `block_slot` have no balance.
+    pub fn get_latest_balances(
+        &self,
+        state_root: &Hash256,
+        block_slot: Slot,
+        spec: &ChainSpec,
+    ) -> Result<Vec<u64>, ForkChoiceError> {
+        let current_state = self
+            .state_store
+            .get_deserialized(state_root)?
+            .ok_or(ForkChoiceError::MissingBeaconState(*state_root))?;
+
+        let active_validator_indices = get_active_validator_indices(
+            &current_state.validator_registry[..],
+            block_slot.epoch(spec.epoch_length),
+        );
+
+        let mut balances = vec![0; current_state.validator_registry.len()];
+        for index in active_validator_indices {
+            balances[index] = std::cmp::min(
+                current_state.validator_balances[index],
+                spec.max_deposit_amount,
+            ) / spec.fork_choice_balance_increment;
+        }
+        Ok(balances)
+    }
+
+    /// Returns the index of the block with `block_hash`, adding it to the tree as a root if it is
+    /// not already present (e.g., the genesis block, or the finalized block after a restart).
+    fn get_or_insert_root(&mut self, block_hash: &Hash256) -> Result<usize, ForkChoiceError> {
+        if let Some(index) = self.indices.get(block_hash) {
+            return Ok(*index);
+        }
+        let slot = self.get_slot(block_hash)?;
+        Ok(self.insert_node(*block_hash, slot, None))
+    }
+
+    fn insert_node(&mut self, root: Hash256, slot: Slot, parent: Option<usize>) -> usize {
+        let index = self.nodes.len();
+        self.nodes.push(ProtoNode {
+            root,
+            slot,
+            parent,
+            weight: 0,
+            best_child: None,
+            best_descendant: None,
+        });
+        self.indices.insert(root, index);
+        index
+    }
+
+    /// Gets the slot of the block specified by `block_hash`, from the tree if it is present.
+    fn get_slot(&self, block_hash: &Hash256) -> Result<Slot, ForkChoiceError> {
+        if let Some(index) = self.indices.get(block_hash) {
+            return Ok(self.nodes[*index].slot);
+        }
+        Ok(self
+            .block_store
+            .get_deserialized(block_hash)?
+            .ok_or(ForkChoiceError::MissingBeaconBlock(*block_hash))?
+            .slot())
+    }
+
+    /// Returns the block root whose weight includes `vote` once the next votes are applied. A vote
+    /// for a block which is not yet in the tree is applied once the block is added.
+    fn next_applied_root(&self, vote: &Vote) -> Hash256 {
+        if self.indices.contains_key(&vote.next_root) {
+            vote.next_root
+        } else {
+            vote.current_root
+        }
+    }
+
+    /// Returns the change in the balance voting for each block if the next votes and
+    /// `new_balances` were applied.
+    fn compute_deltas(&self, new_balances: &[u64]) -> Vec<i64> {
+        let mut deltas = vec![0; self.nodes.len()];
+        for (validator_index, vote) in self.votes.iter().enumerate() {
+            let old_balance = self.balances.get(validator_index).cloned().unwrap_or(0);
+            let new_balance = new_balances.get(validator_index).cloned().unwrap_or(0);
+            let next_root = self.next_applied_root(vote);
+
+            if vote.current_root != next_root || old_balance != new_balance {
+                if let Some(index) = self.indices.get(&vote.current_root) {
+                    deltas[*index] -= old_balance as i64;
+                }
+                if let Some(index) = self.indices.get(&next_root) {
+                    deltas[*index] += new_balance as i64;
+                }
+            }
+        }
+        deltas
+    }
+
+    /// Record the next votes and `new_balances` as applied, once their deltas have been.
+    fn commit_votes(&mut self, new_balances: Vec<u64>) {
+        for index in 0..self.votes.len() {
+            self.votes[index].current_root = self.next_applied_root(&self.votes[index]);
+        }
+        self.balances = new_balances;
+    }
+
+    /// Apply `deltas` to the weight of each block and its ancestors, then find the best child and
+    /// descendant of each block.
+    ///
+    /// The new weights are computed into a scratch buffer, so the tree is unchanged if any weight
+    /// would become negative.
+    fn apply_score_changes(&mut self, mut deltas: Vec<i64>) -> Result<(), ForkChoiceError> {
+        // Children are always after their parent, so iterating backwards visits each block after
+        // all of its descendants.
+        let mut weights = vec![0; self.nodes.len()];
+        for index in (0..self.nodes.len()).rev() {
+            let node = &self.nodes[index];
+            let weight = node.weight as i64 + deltas[index];
+            if weight < 0 {
+                return Err(ForkChoiceError::InvalidDelta(node.root));
+            }
+            weights[index] = weight as u64;
+            if let Some(parent) = node.parent {
+                deltas[parent] += deltas[index];
+            }
+        }
+        for (node, weight) in self.nodes.iter_mut().zip(weights) {
+            node.weight = weight;
+            node.best_child = None;
+            node.best_descendant = None;
+        }
+
+        for index in (0..self.nodes.len()).rev() {
+            let parent = match self.nodes[index].parent {
+                Some(parent) => parent,
+                None => continue,
+            };
+            let is_best = match self.nodes[parent].best_child {
+                Some(best_child) => {
+                    let (child, best) = (&self.nodes[index], &self.nodes[best_child]);
+                    (child.weight, child.root) > (best.weight, best.root)
+                }
+                None => true,
+            };
+            if is_best {
+                let best_descendant = self.nodes[index].best_descendant.unwrap_or(index);
+                self.nodes[parent].best_child = Some(index);
+                self.nodes[parent].best_descendant = Some(best_descendant);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: ClientDB + Sized> ForkChoice for ProtolambdaLMDGhost<T> {
+    fn add_block(
+        &mut self,
+        block: &BeaconBlock,
+        block_hash: &Hash256,
+        _: &ChainSpec,
+    ) -> Result<(), ForkChoiceError> {
+        if self.indices.contains_key(block_hash) {
+            return Ok(());
+        }
+        let parent = self.get_or_insert_root(&block.parent_root)?;
+        self.insert_node(*block_hash, block.slot, Some(parent));
+        Ok(())
+    }
+
+    fn add_attestation(
+        &mut self,
+        validator_index: u64,
+        target_block_root: &Hash256,
+        _: &ChainSpec,
+    ) -> Result<(), ForkChoiceError> {
+        trace!(
+            "Adding attestation of validator: {:?} for block: {}",
+            validator_index,
+            target_block_root
+        );
+        let validator_index = validator_index as usize;
+        if validator_index >= self.votes.len() {
+            self.votes.resize(validator_index + 1, Vote::default());
+        }
+
+        // update the attestation only if the new target is later than the previous one
+        let previous_root = self.votes[validator_index].next_root;
+        if previous_root == *target_block_root {
+            return Ok(());
+        }
+        if previous_root == Hash256::zero()
+            || self.get_slot(&previous_root)? < self.get_slot(target_block_root)?
+        {
+            trace!("Updating attestation");
+            self.votes[validator_index].next_root = *target_block_root;
+        }
+        Ok(())
+    }
+
+    /// Apply the votes since the last call, then return the best descendant of the justified
+    /// block.
+    fn find_head(
+        &mut self,
+        justified_block_start: &Hash256,
+        spec: &ChainSpec,
+    ) -> Result<Hash256, ForkChoiceError> {
+        debug!(
+            "Starting proto-array fork choice at block: {}",
+            justified_block_start
+        );
+        let block = self
+            .block_store
+            .get_deserialized(justified_block_start)?
+            .ok_or(ForkChoiceError::MissingBeaconBlock(*justified_block_start))?;
+
+        let balances = self.get_latest_balances(&block.state_root(), block.slot(), spec)?;
+        let deltas = self.compute_deltas(&balances);
+        self.apply_score_changes(deltas)?;
+        self.commit_votes(balances);
+
+        let justified_index = self.get_or_insert_root(justified_block_start)?;
+        let head_index = self.nodes[justified_index]
+            .best_descendant
+            .unwrap_or(justified_index);
+        Ok(self.nodes[head_index].root)
+    }
+
+    fn prune(&mut self, finalized_block: &Hash256, _: &ChainSpec) -> Result<(), ForkChoiceError> {
+        let finalized_index = match self.indices.get(finalized_block) {
+            Some(index) => *index,
+            None => return Ok(()),
+        };
+
+        // Parents are always before their children, so each descendant of the finalized block
+        // may be found in a single pass.
+        let mut new_indices: Vec<Option<usize>> = vec![None; self.nodes.len()];
+        let mut nodes = vec![];
+        let mut discarded = HashSet::new();
+        for (index, node) in self.nodes.drain(..).enumerate() {
+            let parent = node.parent.and_then(|parent| new_indices[parent]);
+            if index == finalized_index || parent.is_some() {
+                new_indices[index] = Some(nodes.len());
+                nodes.push(ProtoNode { parent, ..node });
+            } else {
+                discarded.insert(node.root);
+            }
+        }
+
+        self.indices = nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| (node.root, index))
+            .collect();
+        self.nodes = nodes;
+
+        // Votes for discarded blocks are forgotten, as their blocks may be deleted from the store.
+        // The balance of a vote for a discarded block is not in the weight of any remaining block.
+        let indices = &self.indices;
+        for vote in self.votes.iter_mut() {
+            if !indices.contains_key(&vote.current_root) {
+                vote.current_root = Hash256::zero();
+            }
+            if discarded.contains(&vote.next_root) {
+                vote.next_root = Hash256::zero();
+            }
+        }
+
+        // Find the best children and descendants again, without changing any weights.
+        self.apply_score_changes(vec![0; self.nodes.len()])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use db::MemoryDB;
+
+    #[test]
+    pub fn test_apply_score_changes_is_atomic() {
+        let db = Arc::new(MemoryDB::open());
+        let mut fork_choice = ProtolambdaLMDGhost::new(
+            Arc::new(BeaconBlockStore::new(db.clone())),
+            Arc::new(BeaconStateStore::new(db)),
+        );
+        let roots: Vec<Hash256> = (1..4).map(|i| Hash256::from(&[i; 32][..])).collect();
+        let root = fork_choice.insert_node(roots[0], Slot::new(0), None);
+        fork_choice.insert_node(roots[1], Slot::new(1), Some(root));
+        fork_choice.insert_node(roots[2], Slot::new(1), Some(root));
+
+        fork_choice.apply_score_changes(vec![0, 5, 3]).unwrap();
+        let weights = |fork_choice: &ProtolambdaLMDGhost<MemoryDB>| -> Vec<u64> {
+            fork_choice.nodes.iter().map(|node| node.weight).collect()
+        };
+        assert_eq!(weights(&fork_choice), vec![8, 5, 3]);
+        assert_eq!(fork_choice.nodes[root].best_descendant, Some(1));
+
+        // The weight of the first child would become negative, so no weight is changed.
+        assert_eq!(
+            fork_choice.apply_score_changes(vec![0, -6, 2]),
+            Err(ForkChoiceError::InvalidDelta(roots[1]))
+        );
+        assert_eq!(weights(&fork_choice), vec![8, 5, 3]);
+        assert_eq!(fork_choice.nodes[root].best_descendant, Some(1));
+    }
+}
@@ -1,6 +1,6 @@
 extern crate db;
 
-use crate::{ForkChoice, ForkChoiceError};
+use crate::{descendants, ForkChoice, ForkChoiceError};
 use db::{
     stores::{BeaconBlockStore, BeaconStateStore},
     ClientDB,
@@ -13,7 +13,7 @@ use types::{
     ChainSpec, Hash256, Slot,
 };
 
-//TODO: Pruning and syncing
+//TODO: Syncing
 
 pub struct SlowLMDGhost<T: ClientDB + Sized> {
     /// The latest attestation targets as a map of validator index to block hash.
@@ -222,4 +222,14 @@ impl<T: ClientDB + Sized> ForkChoice for SlowLMDGhost<T> {
         }
         Ok(head_hash)
     }
+
+    fn prune(&mut self, finalized_block: &Hash256, _: &ChainSpec) -> Result<(), ForkChoiceError> {
+        let descendants = descendants(&self.children, finalized_block);
+
+        self.children
+            .retain(|block_hash, _| descendants.contains(block_hash));
+        self.latest_attestation_targets
+            .retain(|_, target| descendants.contains(target));
+        Ok(())
+    }
 }
@@ -16,7 +16,10 @@ use bls::{PublicKey, Signature};
 use db::stores::{BeaconBlockStore, BeaconStateStore};
 use db::MemoryDB;
 //use env_logger::{Builder, Env};
-use fork_choice::{BitwiseLMDGhost, ForkChoice, ForkChoiceAlgorithm, LongestChain, SlowLMDGhost};
+use fork_choice::{
+    BitwiseLMDGhost, ForkChoice, ForkChoiceAlgorithm, ForkChoiceError, LongestChain,
+    ProtolambdaLMDGhost, SlowLMDGhost,
+};
 use ssz::ssz_encode;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -49,6 +52,15 @@ fn test_slow_lmd_ghost() {
     );
 }
 
+#[test]
+fn test_protolambda_lmd_ghost() {
+    test_yaml_vectors(
+        ForkChoiceAlgorithm::ProtolambdaLMDGhost,
+        "tests/lmd_ghost_test_vectors.yaml",
+        100,
+    );
+}
+
 #[test]
 fn test_longest_chain() {
     test_yaml_vectors(
@@ -58,6 +70,108 @@ fn test_longest_chain() {
     );
 }
 
+#[test]
+fn test_prune() {
+    for fork_choice_algo in &[
+        ForkChoiceAlgorithm::BitwiseLMDGhost,
+        ForkChoiceAlgorithm::SlowLMDGhost,
+        ForkChoiceAlgorithm::ProtolambdaLMDGhost,
+    ] {
+        let spec = ChainSpec::foundation();
+        let (mut fork_choice, block_store, state_root) = setup_inital_state(fork_choice_algo, 10);
+        let mut add_block = |fork_choice: &mut Box<ForkChoice>, id: u8, parent_id: u8, height| {
+            let block_hash = store_test_block(&block_store, state_root, id, parent_id, height);
+            fork_choice.add_block(
+                &block_store.get_deserialized(&block_hash).unwrap().unwrap(),
+                &block_hash,
+                &spec,
+            )
+        };
+
+        // 1 -> 2 -> 3
+        //        -> 4
+        //   -> 5
+        store_test_block(&block_store, state_root, 1, 0, 0);
+        add_block(&mut fork_choice, 2, 1, 1).unwrap();
+        add_block(&mut fork_choice, 3, 2, 2).unwrap();
+        add_block(&mut fork_choice, 4, 2, 2).unwrap();
+        add_block(&mut fork_choice, 5, 1, 1).unwrap();
+        for validator_index in 0..3 {
+            fork_choice
+                .add_attestation(validator_index, &test_hash(5), &spec)
+                .unwrap();
+        }
+        fork_choice
+            .add_attestation(3, &test_hash(3), &spec)
+            .unwrap();
+        assert_eq!(
+            fork_choice.find_head(&test_hash(1), &spec),
+            Ok(test_hash(5))
+        );
+
+        // Finalizing block 2 discards block 5 and the votes for it, and the beacon chain then
+        // deletes block 5 from the store.
+        fork_choice.prune(&test_hash(2), &spec).unwrap();
+        block_store.delete(&test_hash(5)).unwrap();
+        assert_eq!(
+            fork_choice.find_head(&test_hash(2), &spec),
+            Ok(test_hash(3))
+        );
+
+        // Blocks and votes may still be added to the descendants of the finalized block, including
+        // by the validators which voted for block 5.
+        add_block(&mut fork_choice, 6, 4, 3).unwrap();
+        for validator_index in 0..2 {
+            fork_choice
+                .add_attestation(validator_index, &test_hash(6), &spec)
+                .unwrap();
+        }
+        assert_eq!(
+            fork_choice.find_head(&test_hash(2), &spec),
+            Ok(test_hash(6))
+        );
+    }
+}
+
+#[test]
+fn test_bitwise_lmd_ghost_requires_known_parents() {
+    let spec = ChainSpec::foundation();
+    let (mut fork_choice, block_store, state_root) =
+        setup_inital_state(&ForkChoiceAlgorithm::BitwiseLMDGhost, 10);
+    let get_block = |block_hash| block_store.get_deserialized(&block_hash).unwrap().unwrap();
+
+    // 1 -> 2 -> 3 -> 4
+    store_test_block(&block_store, state_root, 1, 0, 0);
+    for id in 2..5 {
+        store_test_block(&block_store, state_root, id, id - 1, u64::from(id) - 1);
+    }
+
+    // Block 3 is unknown to the fork choice, so block 4 may not be added.
+    fork_choice
+        .add_block(&get_block(test_hash(2)), &test_hash(2), &spec)
+        .unwrap();
+    assert_eq!(
+        fork_choice.add_block(&get_block(test_hash(4)), &test_hash(4), &spec),
+        Err(ForkChoiceError::UnknownParent(test_hash(3)))
+    );
+
+    // A fork choice rebuilt from a finalized block accepts its children.
+    let (mut fork_choice, block_store, state_root) =
+        setup_inital_state(&ForkChoiceAlgorithm::BitwiseLMDGhost, 10);
+    store_test_block(&block_store, state_root, 3, 2, 2);
+    store_test_block(&block_store, state_root, 4, 3, 3);
+    fork_choice.prune(&test_hash(3), &spec).unwrap();
+    let block = block_store
+        .get_deserialized(&test_hash(4))
+        .unwrap()
+        .unwrap();
+    fork_choice.add_block(&block, &test_hash(4), &spec).unwrap();
+    assert_eq!(
+        fork_choice.find_head(&test_hash(3), &spec),
+        Ok(test_hash(4))
+    );
+}
+
 // run a generic test over given YAML test vectors
 fn test_yaml_vectors(
     fork_choice_algo: ForkChoiceAlgorithm,
@@ -221,6 +335,10 @@ fn setup_inital_state(
         ForkChoiceAlgorithm::SlowLMDGhost => {
             Box::new(SlowLMDGhost::new(block_store.clone(), state_store.clone()))
         }
+        ForkChoiceAlgorithm::ProtolambdaLMDGhost => Box::new(ProtolambdaLMDGhost::new(
+            block_store.clone(),
+            state_store.clone(),
+        )),
         ForkChoiceAlgorithm::LongestChain => Box::new(LongestChain::new(block_store.clone())),
     };
 
@@ -268,6 +386,45 @@ fn setup_inital_state(
     (fork_choice, block_store, state_root)
 }
 
+// the hash used for the block with some numeric id in tests which do not use YAML vectors.
+fn test_hash(id: u8) -> Hash256 {
+    Hash256::from(&[id; 32][..])
+}
+
+// store a block with the given id and parent id, at `height` slots after genesis.
+fn store_test_block(
+    block_store: &BeaconBlockStore<MemoryDB>,
+    state_root: Hash256,
+    id: u8,
+    parent_id: u8,
+    height: u64,
+) -> Hash256 {
+    let spec = ChainSpec::foundation();
+    let block = BeaconBlock {
+        slot: spec.genesis_slot + height,
+        parent_root: test_hash(parent_id),
+        state_root,
+        randao_reveal: Signature::empty_signature(),
+        eth1_data: Eth1Data {
+            deposit_root: Hash256::zero(),
+            block_hash: Hash256::zero(),
+        },
+        signature: Signature::empty_signature(),
+        body: BeaconBlockBody {
+            proposer_slashings: vec![],
+            attester_slashings: vec![],
+            attestations: vec![],
+            deposits: vec![],
+            exits: vec![],
+        },
+    };
+    let block_hash = test_hash(id);
+    block_store
+        .put(&block_hash, &ssz_encode(&block)[..])
+        .unwrap();
+    block_hash
+}
+
 // convert a block_id into a Hash256 -- assume input is hex encoded;
 fn id_to_hash(id: &String) -> Hash256 {
     let bytes = hex::decode(id).expect("Block ID should be hex");
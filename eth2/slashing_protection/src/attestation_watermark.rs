@@ -0,0 +1,128 @@
+use ssz::{Decodable, DecodeError, Encodable, SszStream};
+use types::{Epoch, Hash256};
+
+/// The highest source and target epochs of the attestations signed by a validator, with the
+/// signing root of the attestation with the highest target.
+///
+/// Rather than storing every signed attestation, only attestations which do not lower either
+/// watermark are permitted. Such an attestation can neither surround nor be surrounded by a
+/// previously signed attestation, and it may only share a target with the attestation recorded
+/// here. This refuses some attestations which would not be slashable (e.g., those with an earlier
+/// target than one already signed), which an honest validator never needs to sign.
+#[derive(Debug, PartialEq, Clone)]
+pub struct AttestationWatermark {
+    pub source_epoch: Epoch,
+    pub target_epoch: Epoch,
+    pub signing_root: Hash256,
+}
+
+impl AttestationWatermark {
+    pub fn new(source_epoch: Epoch, target_epoch: Epoch, signing_root: Hash256) -> Self {
+        Self {
+            source_epoch,
+            target_epoch,
+            signing_root,
+        }
+    }
+
+    /// Returns `true` if signing the given attestation cannot be a double vote or surround vote
+    /// with respect to any previously signed attestation.
+    ///
+    /// Signing the exact same message twice is not slashable.
+    pub fn is_safe(
+        &self,
+        source_epoch: Epoch,
+        target_epoch: Epoch,
+        signing_root: &Hash256,
+    ) -> bool {
+        let is_later_target = (target_epoch > self.target_epoch)
+            || ((target_epoch == self.target_epoch) && (*signing_root == self.signing_root));
+        (source_epoch >= self.source_epoch) && is_later_target
+    }
+
+    /// Raise the watermarks to include an attestation which has been signed.
+    pub fn update(&mut self, source_epoch: Epoch, target_epoch: Epoch, signing_root: &Hash256) {
+        self.source_epoch = std::cmp::max(self.source_epoch, source_epoch);
+        if target_epoch > self.target_epoch {
+            self.target_epoch = target_epoch;
+            self.signing_root = *signing_root;
+        }
+    }
+}
+
+impl Encodable for AttestationWatermark {
+    fn ssz_append(&self, s: &mut SszStream) {
+        s.append(&self.source_epoch);
+        s.append(&self.target_epoch);
+        s.append(&self.signing_root);
+    }
+}
+
+impl Decodable for AttestationWatermark {
+    fn ssz_decode(bytes: &[u8], i: usize) -> Result<(Self, usize), DecodeError> {
+        let (source_epoch, i) = <_>::ssz_decode(bytes, i)?;
+        let (target_epoch, i) = <_>::ssz_decode(bytes, i)?;
+        let (signing_root, i) = <_>::ssz_decode(bytes, i)?;
+        Ok((
+            AttestationWatermark {
+                source_epoch,
+                target_epoch,
+                signing_root,
+            },
+            i,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ssz::ssz_encode;
+
+    fn root(byte: u8) -> Hash256 {
+        Hash256::from(&[byte; 32][..])
+    }
+
+    #[test]
+    fn test_is_safe() {
+        let watermark = AttestationWatermark::new(Epoch::new(2), Epoch::new(3), root(1));
+
+        // Re-signing the same attestation is safe.
+        assert!(watermark.is_safe(Epoch::new(2), Epoch::new(3), &root(1)));
+        // A double vote is refused.
+        assert!(!watermark.is_safe(Epoch::new(2), Epoch::new(3), &root(2)));
+        // Surrounding the previous attestation is refused.
+        assert!(!watermark.is_safe(Epoch::new(1), Epoch::new(4), &root(2)));
+        // An earlier target is refused, even though it is not slashable.
+        assert!(!watermark.is_safe(Epoch::new(2), Epoch::new(2), &root(2)));
+        assert!(watermark.is_safe(Epoch::new(2), Epoch::new(4), &root(2)));
+    }
+
+    #[test]
+    fn test_update() {
+        let mut watermark = AttestationWatermark::new(Epoch::new(2), Epoch::new(3), root(1));
+
+        watermark.update(Epoch::new(0), Epoch::new(10), &root(2));
+        assert_eq!(
+            watermark,
+            AttestationWatermark::new(Epoch::new(2), Epoch::new(10), root(2))
+        );
+
+        // Being surrounded by a previous attestation is refused.
+        assert!(!watermark.is_safe(Epoch::new(4), Epoch::new(5), &root(3)));
+
+        watermark.update(Epoch::new(4), Epoch::new(5), &root(3));
+        assert_eq!(
+            watermark,
+            AttestationWatermark::new(Epoch::new(4), Epoch::new(10), root(2))
+        );
+    }
+
+    #[test]
+    fn test_ssz_round_trip() {
+        let watermark = AttestationWatermark::new(Epoch::new(2), Epoch::new(3), root(1));
+        let bytes = ssz_encode(&watermark);
+        let (decoded, _) = AttestationWatermark::ssz_decode(&bytes, 0).unwrap();
+        assert_eq!(decoded, watermark);
+    }
+}
@@ -0,0 +1,54 @@
+mod attestation_watermark;
+pub mod test_utils;
+
+use types::{Epoch, Hash256, Slot};
+
+pub use self::attestation_watermark::AttestationWatermark;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum SlashingProtectionError {
+    DBError(String),
+    DecodeError,
+}
+
+/// Records the block proposals and attestations signed by a single validator, refusing to permit
+/// messages which would be slashable when considered alongside a previously signed message.
+pub trait SlashingProtection: Send + Sync {
+    /// Returns `true` if signing a proposal with `proposal_root` at `slot` is not slashable.
+    ///
+    /// A proposal is slashable if a proposal with a different root has already been signed for the
+    /// same slot.
+    fn is_safe_proposal(
+        &self,
+        slot: Slot,
+        proposal_root: &Hash256,
+    ) -> Result<bool, SlashingProtectionError>;
+
+    /// Record that a proposal with `proposal_root` at `slot` has been signed.
+    ///
+    /// Does not check that the proposal is safe, use `is_safe_proposal` first.
+    fn record_proposal(
+        &self,
+        slot: Slot,
+        proposal_root: &Hash256,
+    ) -> Result<(), SlashingProtectionError>;
+
+    /// Returns `true` if signing an attestation with the given source and target epochs is not
+    /// slashable, as determined by `AttestationWatermark::is_safe`.
+    fn is_safe_attestation(
+        &self,
+        source_epoch: Epoch,
+        target_epoch: Epoch,
+        signing_root: &Hash256,
+    ) -> Result<bool, SlashingProtectionError>;
+
+    /// Record that an attestation with the given source and target epochs has been signed.
+    ///
+    /// Does not check that the attestation is safe, use `is_safe_attestation` first.
+    fn record_attestation(
+        &self,
+        source_epoch: Epoch,
+        target_epoch: Epoch,
+        signing_root: &Hash256,
+    ) -> Result<(), SlashingProtectionError>;
+}
@@ -0,0 +1,65 @@
+use crate::{AttestationWatermark, SlashingProtection, SlashingProtectionError};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use types::{Epoch, Hash256, Slot};
+
+/// A test-only implementation of `SlashingProtection` which stores signed messages in memory.
+#[derive(Default)]
+pub struct MemorySlashingProtection {
+    proposals: RwLock<HashMap<Slot, Hash256>>,
+    attestations: RwLock<Option<AttestationWatermark>>,
+}
+
+impl SlashingProtection for MemorySlashingProtection {
+    fn is_safe_proposal(
+        &self,
+        slot: Slot,
+        proposal_root: &Hash256,
+    ) -> Result<bool, SlashingProtectionError> {
+        Ok(match self.proposals.read().unwrap().get(&slot) {
+            Some(signed_root) => signed_root == proposal_root,
+            None => true,
+        })
+    }
+
+    fn record_proposal(
+        &self,
+        slot: Slot,
+        proposal_root: &Hash256,
+    ) -> Result<(), SlashingProtectionError> {
+        self.proposals.write().unwrap().insert(slot, *proposal_root);
+        Ok(())
+    }
+
+    fn is_safe_attestation(
+        &self,
+        source_epoch: Epoch,
+        target_epoch: Epoch,
+        signing_root: &Hash256,
+    ) -> Result<bool, SlashingProtectionError> {
+        Ok(match *self.attestations.read().unwrap() {
+            Some(ref watermark) => watermark.is_safe(source_epoch, target_epoch, signing_root),
+            None => true,
+        })
+    }
+
+    fn record_attestation(
+        &self,
+        source_epoch: Epoch,
+        target_epoch: Epoch,
+        signing_root: &Hash256,
+    ) -> Result<(), SlashingProtectionError> {
+        let mut attestations = self.attestations.write().unwrap();
+        match *attestations {
+            Some(ref mut watermark) => watermark.update(source_epoch, target_epoch, signing_root),
+            None => {
+                *attestations = Some(AttestationWatermark::new(
+                    source_epoch,
+                    target_epoch,
+                    *signing_root,
+                ))
+            }
+        }
+        Ok(())
+    }
+}
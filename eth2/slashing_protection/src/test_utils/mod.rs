@@ -0,0 +1,3 @@
+mod memory_slashing_protection;
+
+pub use self::memory_slashing_protection::MemorySlashingProtection;
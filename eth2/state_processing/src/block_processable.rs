@@ -1,11 +1,13 @@
-use crate::SlotProcessingError;
+use crate::{verify_merkle_branch, SlotProcessingError};
+use bls::verify_proof_of_possession;
 use hashing::hash;
 use int_to_bytes::int_to_bytes32;
 use log::{debug, trace};
 use ssz::{ssz_encode, TreeHash};
 use types::{
-    AggregatePublicKey, Attestation, BeaconBlock, BeaconState, BeaconStateError, ChainSpec,
-    Crosslink, Epoch, Exit, Fork, Hash256, PendingAttestation, PublicKey, RelativeEpoch, Signature,
+    AggregatePublicKey, Attestation, AttesterSlashing, BeaconBlock, BeaconState, BeaconStateError,
+    ChainSpec, Crosslink, Deposit, Epoch, Eth1DataVote, Exit, Fork, Hash256, PendingAttestation,
+    ProposerSlashing, PublicKey, RelativeEpoch, Signature, SlashableAttestation,
 };
 
 // TODO: define elsehwere.
@@ -31,10 +33,15 @@ pub enum Error {
     BadRandaoSignature,
     MaxProposerSlashingsExceeded,
     BadProposerSlashing,
+    MaxAttesterSlashingsExceeded,
+    BadAttesterSlashing,
     MaxAttestationsExceeded,
     InvalidAttestation(AttestationValidationError),
     NoBlockRoot,
     MaxDepositsExceeded,
+    BadDeposit,
+    BadDepositBranch,
+    BadDepositIndex,
     MaxExitsExceeded,
     BadExit,
     BadCustodyReseeds,
@@ -146,7 +153,17 @@ fn per_block_processing_signature_optional(
     /*
      * Eth1 data
      */
-    // TODO: Eth1 data processing.
+    match state
+        .eth1_data_votes
+        .iter_mut()
+        .find(|vote| vote.eth1_data == block.eth1_data)
+    {
+        Some(vote) => vote.vote_count += 1,
+        None => state.eth1_data_votes.push(Eth1DataVote {
+            eth1_data: block.eth1_data.clone(),
+            vote_count: 1,
+        }),
+    }
 
     /*
      * Proposer slashings
@@ -156,60 +173,18 @@ fn per_block_processing_signature_optional(
         Error::MaxProposerSlashingsExceeded
     );
     for proposer_slashing in &block.body.proposer_slashings {
-        let proposer = state
-            .validator_registry
-            .get(proposer_slashing.proposer_index as usize)
-            .ok_or(Error::BadProposerSlashing)?;
-        ensure!(
-            proposer_slashing.proposal_data_1.slot == proposer_slashing.proposal_data_2.slot,
-            Error::BadProposerSlashing
-        );
-        ensure!(
-            proposer_slashing.proposal_data_1.shard == proposer_slashing.proposal_data_2.shard,
-            Error::BadProposerSlashing
-        );
-        ensure!(
-            proposer_slashing.proposal_data_1.block_root
-                != proposer_slashing.proposal_data_2.block_root,
-            Error::BadProposerSlashing
-        );
-        ensure!(
-            proposer.penalized_epoch > state.current_epoch(spec),
-            Error::BadProposerSlashing
-        );
-        ensure!(
-            bls_verify(
-                &proposer.pubkey,
-                &proposer_slashing.proposal_data_1.hash_tree_root(),
-                &proposer_slashing.proposal_signature_1,
-                get_domain(
-                    &state.fork,
-                    proposer_slashing
-                        .proposal_data_1
-                        .slot
-                        .epoch(spec.epoch_length),
-                    DOMAIN_PROPOSAL
-                )
-            ),
-            Error::BadProposerSlashing
-        );
-        ensure!(
-            bls_verify(
-                &proposer.pubkey,
-                &proposer_slashing.proposal_data_2.hash_tree_root(),
-                &proposer_slashing.proposal_signature_2,
-                get_domain(
-                    &state.fork,
-                    proposer_slashing
-                        .proposal_data_2
-                        .slot
-                        .epoch(spec.epoch_length),
-                    DOMAIN_PROPOSAL
-                )
-            ),
-            Error::BadProposerSlashing
-        );
-        state.penalize_validator(proposer_slashing.proposer_index as usize, spec)?;
+        process_proposer_slashing(state, proposer_slashing, spec)?;
+    }
+
+    /*
+     * Attester slashings
+     */
+    ensure!(
+        block.body.attester_slashings.len() as u64 <= spec.max_attester_slashings,
+        Error::MaxAttesterSlashingsExceeded
+    );
+    for attester_slashing in &block.body.attester_slashings {
+        process_attester_slashing(state, attester_slashing, spec)?;
     }
 
     /*
@@ -242,7 +217,9 @@ fn per_block_processing_signature_optional(
         Error::MaxDepositsExceeded
     );
 
-    // TODO: process deposits.
+    for deposit in &block.body.deposits {
+        process_deposit(state, deposit, spec)?;
+    }
 
     /*
      * Exits
@@ -253,34 +230,7 @@ fn per_block_processing_signature_optional(
     );
 
     for exit in &block.body.exits {
-        let validator = state
-            .validator_registry
-            .get(exit.validator_index as usize)
-            .ok_or(Error::BadExit)?;
-        ensure!(
-            validator.exit_epoch
-                > state.get_entry_exit_effect_epoch(state.current_epoch(spec), spec),
-            Error::BadExit
-        );
-        ensure!(state.current_epoch(spec) >= exit.epoch, Error::BadExit);
-        let exit_message = {
-            let exit_struct = Exit {
-                epoch: exit.epoch,
-                validator_index: exit.validator_index,
-                signature: spec.empty_signature.clone(),
-            };
-            exit_struct.hash_tree_root()
-        };
-        ensure!(
-            bls_verify(
-                &validator.pubkey,
-                &exit_message,
-                &exit.signature,
-                get_domain(&state.fork, exit.epoch, DOMAIN_EXIT)
-            ),
-            Error::BadProposerSlashing
-        );
-        state.initiate_validator_exit(exit.validator_index as usize);
+        process_exit(state, exit, spec)?;
     }
 
     debug!("State transition complete.");
@@ -288,6 +238,292 @@ fn per_block_processing_signature_optional(
     Ok(())
 }
 
+/// Validate a `ProposerSlashing` and penalize the proposer.
+///
+/// Spec v0.2.0
+pub fn process_proposer_slashing(
+    state: &mut BeaconState,
+    proposer_slashing: &ProposerSlashing,
+    spec: &ChainSpec,
+) -> Result<(), Error> {
+    validate_proposer_slashing(state, proposer_slashing, spec)?;
+    state.penalize_validator(proposer_slashing.proposer_index as usize, spec)?;
+    Ok(())
+}
+
+/// Validate an `AttesterSlashing` and penalize the slashable validators.
+///
+/// Spec v0.2.0
+pub fn process_attester_slashing(
+    state: &mut BeaconState,
+    attester_slashing: &AttesterSlashing,
+    spec: &ChainSpec,
+) -> Result<(), Error> {
+    for validator_index in validate_attester_slashing(state, attester_slashing, spec)? {
+        state.penalize_validator(validator_index as usize, spec)?;
+    }
+    Ok(())
+}
+
+/// Validate a `Deposit` and apply it to the validator registry.
+///
+/// Deposits must be processed in the order of the deposit contract, so `deposit.index` must be
+/// `state.deposit_index`.
+pub fn process_deposit(
+    state: &mut BeaconState,
+    deposit: &Deposit,
+    spec: &ChainSpec,
+) -> Result<(), Error> {
+    ensure!(deposit.index == state.deposit_index, Error::BadDepositIndex);
+    validate_deposit(state, deposit, spec)?;
+    let deposit_input = &deposit.deposit_data.deposit_input;
+    state
+        .process_deposit(
+            deposit_input.pubkey.clone(),
+            deposit.deposit_data.amount,
+            deposit_input.proof_of_possession.clone(),
+            deposit_input.withdrawal_credentials,
+            None,
+            spec,
+        )
+        .map_err(|_| Error::BadDeposit)?;
+    state.deposit_index += 1;
+    Ok(())
+}
+
+/// Validate an `Exit` and initiate the exit of the validator.
+///
+/// Spec v0.2.0
+pub fn process_exit(state: &mut BeaconState, exit: &Exit, spec: &ChainSpec) -> Result<(), Error> {
+    validate_exit(state, exit, spec)?;
+    state.initiate_validator_exit(exit.validator_index as usize);
+    Ok(())
+}
+
+/// Validate a `ProposerSlashing` for inclusion in a block upon `state`.
+///
+/// Spec v0.2.0
+pub fn validate_proposer_slashing(
+    state: &BeaconState,
+    proposer_slashing: &ProposerSlashing,
+    spec: &ChainSpec,
+) -> Result<(), Error> {
+    let proposer = state
+        .validator_registry
+        .get(proposer_slashing.proposer_index as usize)
+        .ok_or(Error::BadProposerSlashing)?;
+    ensure!(
+        proposer_slashing.proposal_data_1.slot == proposer_slashing.proposal_data_2.slot,
+        Error::BadProposerSlashing
+    );
+    ensure!(
+        proposer_slashing.proposal_data_1.shard == proposer_slashing.proposal_data_2.shard,
+        Error::BadProposerSlashing
+    );
+    ensure!(
+        proposer_slashing.proposal_data_1.block_root
+            != proposer_slashing.proposal_data_2.block_root,
+        Error::BadProposerSlashing
+    );
+    ensure!(
+        proposer.penalized_epoch > state.current_epoch(spec),
+        Error::BadProposerSlashing
+    );
+    ensure!(
+        bls_verify(
+            &proposer.pubkey,
+            &proposer_slashing.proposal_data_1.hash_tree_root(),
+            &proposer_slashing.proposal_signature_1,
+            get_domain(
+                &state.fork,
+                proposer_slashing
+                    .proposal_data_1
+                    .slot
+                    .epoch(spec.epoch_length),
+                DOMAIN_PROPOSAL
+            )
+        ),
+        Error::BadProposerSlashing
+    );
+    ensure!(
+        bls_verify(
+            &proposer.pubkey,
+            &proposer_slashing.proposal_data_2.hash_tree_root(),
+            &proposer_slashing.proposal_signature_2,
+            get_domain(
+                &state.fork,
+                proposer_slashing
+                    .proposal_data_2
+                    .slot
+                    .epoch(spec.epoch_length),
+                DOMAIN_PROPOSAL
+            )
+        ),
+        Error::BadProposerSlashing
+    );
+    Ok(())
+}
+
+/// Validate an `AttesterSlashing` for inclusion in a block upon `state`, returning the indices
+/// of the validators which are penalized by it.
+///
+/// Spec v0.2.0
+pub fn validate_attester_slashing(
+    state: &BeaconState,
+    attester_slashing: &AttesterSlashing,
+    spec: &ChainSpec,
+) -> Result<Vec<u64>, Error> {
+    let slashable_attestation_1 = &attester_slashing.slashable_attestation_1;
+    let slashable_attestation_2 = &attester_slashing.slashable_attestation_2;
+
+    ensure!(
+        slashable_attestation_1.data != slashable_attestation_2.data,
+        Error::BadAttesterSlashing
+    );
+    ensure!(
+        slashable_attestation_1.is_double_vote(slashable_attestation_2, spec)
+            || slashable_attestation_1.is_surround_vote(slashable_attestation_2, spec),
+        Error::BadAttesterSlashing
+    );
+    ensure!(
+        verify_slashable_attestation(state, slashable_attestation_1, spec),
+        Error::BadAttesterSlashing
+    );
+    ensure!(
+        verify_slashable_attestation(state, slashable_attestation_2, spec),
+        Error::BadAttesterSlashing
+    );
+
+    let slashable_indices: Vec<u64> = slashable_attestation_1
+        .validator_indices
+        .iter()
+        .filter(|index| {
+            slashable_attestation_2.validator_indices.contains(index)
+                && state.validator_registry[**index as usize].penalized_epoch
+                    > state.current_epoch(spec)
+        })
+        .cloned()
+        .collect();
+    ensure!(!slashable_indices.is_empty(), Error::BadAttesterSlashing);
+
+    Ok(slashable_indices)
+}
+
+/// Verify the indices and aggregate signature of a `SlashableAttestation`.
+///
+/// Only phase 0 attestations are valid, so all custody bits must be zero.
+///
+/// Spec v0.2.0
+fn verify_slashable_attestation(
+    state: &BeaconState,
+    slashable_attestation: &SlashableAttestation,
+    spec: &ChainSpec,
+) -> bool {
+    let validator_indices = &slashable_attestation.validator_indices;
+
+    if slashable_attestation.custody_bitfield.num_set_bits() > 0
+        || validator_indices.is_empty()
+        || validator_indices.len() as u64 > spec.max_indices_per_slashable_vote
+        || validator_indices.windows(2).any(|pair| pair[0] >= pair[1])
+    {
+        return false;
+    }
+
+    let mut aggregate_public_key = AggregatePublicKey::new();
+    for index in validator_indices {
+        match state.validator_registry.get(*index as usize) {
+            Some(validator) => aggregate_public_key.add(validator.pubkey.as_raw()),
+            None => return false,
+        }
+    }
+
+    slashable_attestation.aggregate_signature.verify(
+        &slashable_attestation
+            .data
+            .signable_message(PHASE_0_CUSTODY_BIT),
+        get_domain(
+            &state.fork,
+            slashable_attestation.data.slot.epoch(spec.epoch_length),
+            DOMAIN_ATTESTATION,
+        ),
+        &aggregate_public_key,
+    )
+}
+
+/// Validate a `Deposit` for inclusion in a block upon `state`.
+///
+/// The deposit must be proven against `state.latest_eth1_data.deposit_root`. Its index is only
+/// checked by `process_deposit`, so a deposit may be validated before the deposits preceding it
+/// have been processed.
+pub fn validate_deposit(
+    state: &BeaconState,
+    deposit: &Deposit,
+    spec: &ChainSpec,
+) -> Result<(), Error> {
+    let leaf = Hash256::from(&hash(&ssz_encode(&deposit.deposit_data))[..]);
+    ensure!(
+        verify_merkle_branch(
+            leaf,
+            &deposit.branch,
+            spec.deposit_contract_tree_depth,
+            deposit.index,
+            state.latest_eth1_data.deposit_root
+        ),
+        Error::BadDepositBranch
+    );
+
+    let deposit_input = &deposit.deposit_data.deposit_input;
+    ensure!(
+        verify_proof_of_possession(&deposit_input.proof_of_possession, &deposit_input.pubkey),
+        Error::BadDeposit
+    );
+    // A deposit for an existing validator must have matching withdrawal credentials.
+    if let Some(validator) = state
+        .validator_registry
+        .iter()
+        .find(|validator| validator.pubkey == deposit_input.pubkey)
+    {
+        ensure!(
+            validator.withdrawal_credentials == deposit_input.withdrawal_credentials,
+            Error::BadDeposit
+        );
+    }
+    Ok(())
+}
+
+/// Validate an `Exit` for inclusion in a block upon `state`.
+///
+/// Spec v0.2.0
+pub fn validate_exit(state: &BeaconState, exit: &Exit, spec: &ChainSpec) -> Result<(), Error> {
+    let validator = state
+        .validator_registry
+        .get(exit.validator_index as usize)
+        .ok_or(Error::BadExit)?;
+    ensure!(
+        validator.exit_epoch > state.get_entry_exit_effect_epoch(state.current_epoch(spec), spec),
+        Error::BadExit
+    );
+    ensure!(state.current_epoch(spec) >= exit.epoch, Error::BadExit);
+    let exit_message = {
+        let exit_struct = Exit {
+            epoch: exit.epoch,
+            validator_index: exit.validator_index,
+            signature: spec.empty_signature.clone(),
+        };
+        exit_struct.hash_tree_root()
+    };
+    ensure!(
+        bls_verify(
+            &validator.pubkey,
+            &exit_message,
+            &exit.signature,
+            get_domain(&state.fork, exit.epoch, DOMAIN_EXIT)
+        ),
+        Error::BadExit
+    );
+    Ok(())
+}
+
 pub fn validate_attestation(
     state: &BeaconState,
     attestation: &Attestation,
@@ -0,0 +1,185 @@
+use hashing::hash;
+use ssz::ssz_encode;
+use types::{DepositData, Hash256};
+
+/// The Merkle tree of `DepositData` built by the deposit contract.
+///
+/// Each leaf is the hash of a serialized `DepositData`. As in the contract, each node above the
+/// leaves is the hash of its two children, with the nodes of empty subtrees being zero.
+///
+/// The tree may be read as it was after any number of deposits, so proofs may be produced against
+/// a deposit root which has since been superseded.
+#[derive(Debug, Clone)]
+pub struct DepositTree {
+    depth: usize,
+    leaves: Vec<Hash256>,
+}
+
+impl DepositTree {
+    /// Create an empty tree of `depth` levels above the leaves, i.e.,
+    /// `spec.deposit_contract_tree_depth`.
+    pub fn new(depth: u64) -> Self {
+        Self {
+            depth: depth as usize,
+            leaves: vec![],
+        }
+    }
+
+    /// Append the leaf for `deposit_data`.
+    pub fn push(&mut self, deposit_data: &DepositData) {
+        self.leaves
+            .push(Hash256::from(&hash(&ssz_encode(deposit_data))[..]));
+    }
+
+    /// Returns the number of deposits in the tree.
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// Returns the root of the tree, as it was after the first `deposit_count` deposits.
+    ///
+    /// Returns `None` if there have not been `deposit_count` deposits.
+    pub fn root(&self, deposit_count: usize) -> Option<Hash256> {
+        let levels = self.levels(deposit_count)?;
+        Some(
+            levels[self.depth]
+                .first()
+                .cloned()
+                .unwrap_or_else(Hash256::zero),
+        )
+    }
+
+    /// Returns the branch proving the deposit with `index` against the root of the tree after the
+    /// first `deposit_count` deposits, ordered from the leaf up.
+    ///
+    /// Returns `None` if `index` is not less than `deposit_count`, or there have not been
+    /// `deposit_count` deposits.
+    pub fn branch(&self, index: usize, deposit_count: usize) -> Option<Vec<Hash256>> {
+        if index >= deposit_count {
+            return None;
+        }
+        let levels = self.levels(deposit_count)?;
+        Some(
+            (0..self.depth)
+                .map(|level| {
+                    levels[level]
+                        .get((index >> level) ^ 1)
+                        .cloned()
+                        .unwrap_or_else(Hash256::zero)
+                })
+                .collect(),
+        )
+    }
+
+    /// Returns the non-empty nodes at each level of the tree after the first `deposit_count`
+    /// deposits, from the leaves up to the root.
+    fn levels(&self, deposit_count: usize) -> Option<Vec<Vec<Hash256>>> {
+        let mut levels = vec![self.leaves.get(0..deposit_count)?.to_vec()];
+        for level in 0..self.depth {
+            let parents = levels[level]
+                .chunks(2)
+                .map(|children| {
+                    let mut preimage = children[0].to_vec();
+                    match children.get(1) {
+                        Some(right) => preimage.extend_from_slice(&right[..]),
+                        None => preimage.extend_from_slice(&Hash256::zero()[..]),
+                    }
+                    Hash256::from(&hash(&preimage)[..])
+                })
+                .collect();
+            levels.push(parents);
+        }
+        Some(levels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verify_merkle_branch;
+    use types::test_utils::{SeedableRng, TestRandom, XorShiftRng};
+
+    fn hash_pair(left: &Hash256, right: &Hash256) -> Hash256 {
+        let mut preimage = left.to_vec();
+        preimage.extend_from_slice(&right[..]);
+        Hash256::from(&hash(&preimage)[..])
+    }
+
+    fn leaf(deposit_data: &DepositData) -> Hash256 {
+        Hash256::from(&hash(&ssz_encode(deposit_data))[..])
+    }
+
+    #[test]
+    fn test_root() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let deposits: Vec<DepositData> = (0..3)
+            .map(|_| DepositData::random_for_test(&mut rng))
+            .collect();
+        let mut tree = DepositTree::new(2);
+        assert_eq!(tree.root(0), Some(Hash256::zero()));
+
+        for deposit_data in &deposits {
+            tree.push(deposit_data);
+        }
+        assert_eq!(tree.len(), 3);
+
+        let zero = Hash256::zero();
+        let (a, b, c) = (leaf(&deposits[0]), leaf(&deposits[1]), leaf(&deposits[2]));
+        assert_eq!(tree.root(1), Some(hash_pair(&hash_pair(&a, &zero), &zero)));
+        assert_eq!(
+            tree.root(3),
+            Some(hash_pair(&hash_pair(&a, &b), &hash_pair(&c, &zero)))
+        );
+        assert_eq!(tree.root(4), None);
+    }
+
+    #[test]
+    fn test_branch() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let deposits: Vec<DepositData> = (0..3)
+            .map(|_| DepositData::random_for_test(&mut rng))
+            .collect();
+        let mut tree = DepositTree::new(2);
+        for deposit_data in &deposits {
+            tree.push(deposit_data);
+        }
+
+        let zero = Hash256::zero();
+        let (a, b, c) = (leaf(&deposits[0]), leaf(&deposits[1]), leaf(&deposits[2]));
+        assert_eq!(tree.branch(0, 1), Some(vec![zero, zero]));
+        assert_eq!(tree.branch(0, 3), Some(vec![b, hash_pair(&c, &zero)]));
+        assert_eq!(tree.branch(2, 3), Some(vec![zero, hash_pair(&a, &b)]));
+        assert_eq!(tree.branch(3, 3), None);
+    }
+
+    #[test]
+    fn test_branches_verify() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let depth = 32;
+        let mut tree = DepositTree::new(depth);
+        let deposits: Vec<DepositData> = (0..5)
+            .map(|_| DepositData::random_for_test(&mut rng))
+            .collect();
+        for deposit_data in &deposits {
+            tree.push(deposit_data);
+        }
+
+        for deposit_count in 1..=deposits.len() {
+            let root = tree.root(deposit_count).unwrap();
+            for (index, deposit_data) in deposits[0..deposit_count].iter().enumerate() {
+                let branch = tree.branch(index, deposit_count).unwrap();
+                assert!(verify_merkle_branch(
+                    leaf(deposit_data),
+                    &branch,
+                    depth,
+                    index as u64,
+                    root
+                ));
+            }
+        }
+    }
+}
@@ -6,7 +6,8 @@ use std::collections::{HashMap, HashSet};
 use std::iter::FromIterator;
 use types::{
     validator_registry::get_active_validator_indices, BeaconState, BeaconStateError, ChainSpec,
-    Crosslink, Epoch, Hash256, InclusionError, PendingAttestation, RelativeEpoch,
+    Crosslink, Epoch, Eth1Data, Eth1DataVote, Hash256, InclusionError, PendingAttestation,
+    RelativeEpoch,
 };
 
 macro_rules! safe_add_assign {
@@ -84,14 +85,8 @@ impl EpochProcessable for BeaconState {
             current_total_balance
         );
 
-        let current_epoch_attestations: Vec<&PendingAttestation> = self
-            .latest_attestations
-            .par_iter()
-            .filter(|a| {
-                (a.data.slot / spec.epoch_length).epoch(spec.epoch_length)
-                    == self.current_epoch(spec)
-            })
-            .collect();
+        let current_epoch_attestations =
+            attestations_in_epoch(&self.latest_attestations, current_epoch, spec);
 
         trace!(
             "Current epoch attestations: {}",
@@ -130,15 +125,8 @@ impl EpochProcessable for BeaconState {
         /*
          * Validators that made an attestation during the previous epoch
          */
-        let previous_epoch_attestations: Vec<&PendingAttestation> = self
-            .latest_attestations
-            .par_iter()
-            .filter(|a| {
-                //TODO: ensure these saturating subs are correct.
-                (a.data.slot / spec.epoch_length).epoch(spec.epoch_length)
-                    == self.previous_epoch(spec)
-            })
-            .collect();
+        let previous_epoch_attestations =
+            attestations_in_epoch(&self.latest_attestations, previous_epoch, spec);
 
         debug!(
             "previous epoch attestations: {}",
@@ -222,10 +210,8 @@ impl EpochProcessable for BeaconState {
          * Eth1 Data
          */
         if self.next_epoch(spec) % spec.eth1_data_voting_period == 0 {
-            for eth1_data_vote in &self.eth1_data_votes {
-                if eth1_data_vote.vote_count * 2 > spec.eth1_data_voting_period {
-                    self.latest_eth1_data = eth1_data_vote.eth1_data.clone();
-                }
+            if let Some(eth1_data) = winning_eth1_data(&self.eth1_data_votes, spec) {
+                self.latest_eth1_data = eth1_data.clone();
             }
             self.eth1_data_votes = vec![];
         }
@@ -708,6 +694,31 @@ fn winning_root(
         .clone())
 }
 
+/// Returns the attestations for a slot within `epoch`.
+fn attestations_in_epoch<'a>(
+    attestations: &'a [PendingAttestation],
+    epoch: Epoch,
+    spec: &ChainSpec,
+) -> Vec<&'a PendingAttestation> {
+    attestations
+        .par_iter()
+        .filter(|a| a.data.slot.epoch(spec.epoch_length) == epoch)
+        .collect()
+}
+
+/// Returns the `Eth1Data` voted for in a majority of the slots of a voting period, if any.
+///
+/// Spec v0.2.0
+fn winning_eth1_data<'a>(
+    eth1_data_votes: &'a [Eth1DataVote],
+    spec: &ChainSpec,
+) -> Option<&'a Eth1Data> {
+    eth1_data_votes
+        .iter()
+        .find(|vote| vote.vote_count * 2 > spec.eth1_data_voting_period * spec.epoch_length)
+        .map(|vote| &vote.eth1_data)
+}
+
 impl From<InclusionError> for Error {
     fn from(e: InclusionError) -> Error {
         Error::InclusionError(e)
@@ -728,8 +739,53 @@ impl From<BeaconStateError> for WinningRootError {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use types::test_utils::{SeedableRng, TestRandom, XorShiftRng};
+    use types::Slot;
+
+    #[test]
+    fn test_attestations_in_epoch() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let spec = ChainSpec::few_validators();
+        let epoch = spec.genesis_epoch + 2;
+        let start_slot = epoch.start_slot(spec.epoch_length);
+
+        let attestations: Vec<PendingAttestation> = vec![
+            start_slot - 1,
+            start_slot,
+            start_slot + spec.epoch_length - 1,
+            start_slot + spec.epoch_length,
+        ]
+        .into_iter()
+        .map(|slot| {
+            let mut attestation = PendingAttestation::random_for_test(&mut rng);
+            attestation.data.slot = slot;
+            attestation
+        })
+        .collect();
+
+        let slots: Vec<Slot> = attestations_in_epoch(&attestations, epoch, &spec)
+            .iter()
+            .map(|a| a.data.slot)
+            .collect();
+        assert_eq!(slots, vec![start_slot, start_slot + spec.epoch_length - 1]);
+    }
+
     #[test]
-    fn it_works() {
-        assert_eq!(2 + 2, 4);
+    fn test_winning_eth1_data() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let spec = ChainSpec::few_validators();
+        let period_slots = spec.eth1_data_voting_period * spec.epoch_length;
+
+        let mut vote = |vote_count| Eth1DataVote {
+            eth1_data: Eth1Data::random_for_test(&mut rng),
+            vote_count,
+        };
+        let minority = vote(period_slots / 2);
+        let majority = vote(period_slots / 2 + 1);
+
+        let votes = vec![minority, majority];
+        assert_eq!(winning_eth1_data(&votes[..1], &spec), None);
+        assert_eq!(winning_eth1_data(&votes, &spec), Some(&votes[1].eth1_data));
     }
 }
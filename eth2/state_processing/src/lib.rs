@@ -1,10 +1,16 @@
 mod block_processable;
+mod deposit_tree;
 mod epoch_processable;
+mod merkle_proof;
 mod slot_processable;
 
 pub use block_processable::{
-    validate_attestation, validate_attestation_without_signature, BlockProcessable,
+    process_attester_slashing, process_deposit, process_exit, process_proposer_slashing,
+    validate_attestation, validate_attestation_without_signature, validate_attester_slashing,
+    validate_deposit, validate_exit, validate_proposer_slashing, BlockProcessable,
     Error as BlockProcessingError,
 };
+pub use deposit_tree::DepositTree;
 pub use epoch_processable::{EpochProcessable, Error as EpochProcessingError};
+pub use merkle_proof::verify_merkle_branch;
 pub use slot_processable::{Error as SlotProcessingError, SlotProcessable};
@@ -0,0 +1,99 @@
+use hashing::hash;
+use types::Hash256;
+
+/// Verify that `branch` proves `leaf` is at `index` in the Merkle tree of `depth` with `root`.
+///
+/// `branch` is ordered from the leaf up, as produced by `DepositTree::branch`.
+///
+/// Spec v0.3.0
+pub fn verify_merkle_branch(
+    leaf: Hash256,
+    branch: &[Hash256],
+    depth: u64,
+    index: u64,
+    root: Hash256,
+) -> bool {
+    if branch.len() as u64 != depth {
+        return false;
+    }
+
+    let mut value = leaf;
+    for (i, sibling) in branch.iter().enumerate() {
+        let mut preimage = vec![];
+        if (index >> i) & 1 == 1 {
+            preimage.extend_from_slice(&sibling[..]);
+            preimage.extend_from_slice(&value[..]);
+        } else {
+            preimage.extend_from_slice(&value[..]);
+            preimage.extend_from_slice(&sibling[..]);
+        }
+        value = Hash256::from(&hash(&preimage)[..]);
+    }
+
+    value == root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash_pair(left: Hash256, right: Hash256) -> Hash256 {
+        let mut preimage = left.to_vec();
+        preimage.extend_from_slice(&right[..]);
+        Hash256::from(&hash(&preimage)[..])
+    }
+
+    #[test]
+    fn test_verify_merkle_branch() {
+        let leaves: Vec<Hash256> = (1..5).map(|i| Hash256::from(&[i; 32][..])).collect();
+        let left = hash_pair(leaves[0], leaves[1]);
+        let right = hash_pair(leaves[2], leaves[3]);
+        let root = hash_pair(left, right);
+
+        assert!(verify_merkle_branch(
+            leaves[2],
+            &[leaves[3], left],
+            2,
+            2,
+            root
+        ));
+        assert!(verify_merkle_branch(
+            leaves[1],
+            &[leaves[0], right],
+            2,
+            1,
+            root
+        ));
+
+        // Wrong index, leaf, depth or root.
+        assert!(!verify_merkle_branch(
+            leaves[2],
+            &[leaves[3], left],
+            2,
+            3,
+            root
+        ));
+        assert!(!verify_merkle_branch(
+            leaves[3],
+            &[leaves[3], left],
+            2,
+            2,
+            root
+        ));
+        assert!(!verify_merkle_branch(leaves[2], &[leaves[3]], 1, 1, right));
+        assert!(!verify_merkle_branch(
+            leaves[2],
+            &[leaves[3], left],
+            3,
+            2,
+            root
+        ));
+        assert!(!verify_merkle_branch(
+            leaves[2],
+            &[leaves[3], left],
+            2,
+            2,
+            left
+        ));
+    }
+}
@@ -45,18 +45,22 @@ impl BeaconBlock {
     }
 
     pub fn proposal_root(&self, spec: &ChainSpec) -> Hash256 {
+        Hash256::from(&self.proposal_signed_data(spec).hash_tree_root()[..])
+    }
+
+    /// Returns the `ProposalSignedData` which is signed by the proposer of this block.
+    pub fn proposal_signed_data(&self, spec: &ChainSpec) -> ProposalSignedData {
         let block_without_signature_root = {
             let mut block_without_signature = self.clone();
             block_without_signature.signature = spec.empty_signature.clone();
             block_without_signature.canonical_root()
         };
 
-        let proposal = ProposalSignedData {
+        ProposalSignedData {
             slot: self.slot,
             shard: spec.beacon_chain_shard_number,
             block_root: block_without_signature_root,
-        };
-        Hash256::from(&proposal.hash_tree_root()[..])
+        }
     }
 }
 
@@ -106,6 +106,8 @@ pub struct BeaconState {
     // Ethereum 1.0 chain data
     pub latest_eth1_data: Eth1Data,
     pub eth1_data_votes: Vec<Eth1DataVote>,
+    /// The index of the next deposit to be processed, i.e., the number of processed deposits.
+    pub deposit_index: u64,
 
     // Caching
     pub cache_index_offset: usize,
@@ -179,6 +181,7 @@ impl BeaconState {
              */
             latest_eth1_data,
             eth1_data_votes: vec![],
+            deposit_index: initial_validator_deposits.len() as u64,
 
             /*
              * Caching (not in spec)
@@ -1221,6 +1224,7 @@ impl Encodable for BeaconState {
         s.append(&self.batched_block_roots);
         s.append(&self.latest_eth1_data);
         s.append(&self.eth1_data_votes);
+        s.append(&self.deposit_index);
     }
 }
 
@@ -1251,6 +1255,7 @@ impl Decodable for BeaconState {
         let (batched_block_roots, i) = <_>::ssz_decode(bytes, i)?;
         let (latest_eth1_data, i) = <_>::ssz_decode(bytes, i)?;
         let (eth1_data_votes, i) = <_>::ssz_decode(bytes, i)?;
+        let (deposit_index, i) = <_>::ssz_decode(bytes, i)?;
 
         Ok((
             Self {
@@ -1279,6 +1284,7 @@ impl Decodable for BeaconState {
                 batched_block_roots,
                 latest_eth1_data,
                 eth1_data_votes,
+                deposit_index,
                 cache_index_offset: 0,
                 caches: vec![EpochCache::empty(); CACHED_EPOCHS],
             },
@@ -1319,6 +1325,7 @@ impl TreeHash for BeaconState {
         result.append(&mut self.batched_block_roots.hash_tree_root_internal());
         result.append(&mut self.latest_eth1_data.hash_tree_root_internal());
         result.append(&mut self.eth1_data_votes.hash_tree_root_internal());
+        result.append(&mut self.deposit_index.hash_tree_root_internal());
         hash(&result)
     }
 }
@@ -1351,6 +1358,7 @@ impl<T: RngCore> TestRandom<T> for BeaconState {
             batched_block_roots: <_>::random_for_test(rng),
             latest_eth1_data: <_>::random_for_test(rng),
             eth1_data_votes: <_>::random_for_test(rng),
+            deposit_index: <_>::random_for_test(rng),
             cache_index_offset: 0,
             caches: vec![EpochCache::empty(); CACHED_EPOCHS],
         }
@@ -28,7 +28,7 @@ impl BeaconStateTestBuilder {
         let initial_validator_deposits = keypairs
             .iter()
             .map(|keypair| Deposit {
-                branch: vec![], // branch verification is not specified.
+                branch: vec![], // genesis deposits are not proven.
                 index: 0,       // index verification is not specified.
                 deposit_data: DepositData {
                     amount: 32_000_000_000, // 32 ETH (in Gwei)
@@ -1,12 +1,26 @@
 use crate::{Address, Epoch, Hash256, Slot};
 use bls::Signature;
+use serde_derive::{Deserialize, Serialize};
+use std::fs::File;
+use std::path::Path;
 
 const GWEI: u64 = 1_000_000_000;
 
+/// The names of the presets which may be passed to `ChainSpec::from_preset`.
+pub const PRESETS: [&str; 2] = ["foundation", "few_validators"];
+/// The preset used when no chain specification is supplied.
+pub const DEFAULT_PRESET: &str = "foundation";
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    Io(String),
+    InvalidYaml(String),
+}
+
 /// Holds all the "constants" for a BeaconChain.
 ///
 /// Spec v0.2.0
-#[derive(PartialEq, Debug, Clone)]
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
 pub struct ChainSpec {
     /*
      * Misc
@@ -209,12 +223,82 @@ impl ChainSpec {
     }
 }
 
+impl ChainSpec {
+    /// Returns the preset with the given `name`, if it exists. See `PRESETS`.
+    pub fn from_preset(name: &str) -> Option<Self> {
+        match name {
+            "foundation" => Some(ChainSpec::foundation()),
+            "few_validators" => Some(ChainSpec::few_validators()),
+            _ => None,
+        }
+    }
+
+    /// Load a `ChainSpec` from the YAML file at `path`.
+    ///
+    /// The file must specify every field.
+    pub fn from_yaml_file(path: &Path) -> Result<Self, Error> {
+        let file = File::open(path).map_err(|e| Error::Io(e.to_string()))?;
+        serde_yaml::from_reader(file).map_err(|e| Error::InvalidYaml(e.to_string()))
+    }
+
+    /// Returns the preset named `preset_or_path` or, if there is no such preset, loads the YAML
+    /// file at that path.
+    pub fn from_preset_or_file(preset_or_path: &str) -> Result<Self, Error> {
+        match ChainSpec::from_preset(preset_or_path) {
+            Some(spec) => Ok(spec),
+            None => ChainSpec::from_yaml_file(Path::new(preset_or_path)),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::env;
+    use std::fs;
 
     #[test]
     fn test_foundation_spec_can_be_constructed() {
         let _ = ChainSpec::foundation();
     }
+
+    #[test]
+    fn test_presets() {
+        for name in PRESETS.iter() {
+            assert!(ChainSpec::from_preset(name).is_some());
+        }
+        assert_eq!(ChainSpec::from_preset("unknown"), None);
+    }
+
+    #[test]
+    fn test_yaml_round_trip() {
+        for spec in &[ChainSpec::foundation(), ChainSpec::few_validators()] {
+            let yaml = serde_yaml::to_string(spec).unwrap();
+            let decoded: ChainSpec = serde_yaml::from_str(&yaml).unwrap();
+
+            assert_eq!(decoded, *spec);
+        }
+    }
+
+    #[test]
+    fn test_from_preset_or_file() {
+        let path = env::temp_dir().join("lighthouse_chain_spec_test.yaml");
+        let spec = ChainSpec {
+            epoch_length: 4,
+            shard_count: 2,
+            ..ChainSpec::few_validators()
+        };
+        fs::write(&path, serde_yaml::to_string(&spec).unwrap()).unwrap();
+
+        assert_eq!(
+            ChainSpec::from_preset_or_file(path.to_str().unwrap()),
+            Ok(spec)
+        );
+        assert_eq!(
+            ChainSpec::from_preset_or_file("few_validators"),
+            Ok(ChainSpec::few_validators())
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
 }
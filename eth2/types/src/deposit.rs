@@ -1,11 +1,11 @@
 use super::{DepositData, Hash256};
 use crate::test_utils::TestRandom;
 use rand::RngCore;
-use serde_derive::Serialize;
+use serde_derive::{Deserialize, Serialize};
 use ssz::{hash, TreeHash};
 use ssz_derive::{Decode, Encode};
 
-#[derive(Debug, PartialEq, Clone, Serialize, Encode, Decode)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, Encode, Decode)]
 pub struct Deposit {
     pub branch: Vec<Hash256>,
     pub index: u64,
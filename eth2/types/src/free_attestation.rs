@@ -3,8 +3,9 @@
 /// We use it for managing attestations that have not been aggregated.
 use super::{AttestationData, Signature};
 use serde_derive::Serialize;
+use ssz_derive::{Decode, Encode};
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Encode, Decode)]
 pub struct FreeAttestation {
     pub data: AttestationData,
     pub signature: Signature,
@@ -46,7 +46,7 @@ pub use crate::beacon_state::{
     BeaconState, Error as BeaconStateError, InclusionError, RelativeEpoch,
 };
 pub use crate::casper_slashing::CasperSlashing;
-pub use crate::chain_spec::ChainSpec;
+pub use crate::chain_spec::{ChainSpec, Error as ChainSpecError};
 pub use crate::crosslink::Crosslink;
 pub use crate::deposit::Deposit;
 pub use crate::deposit_data::DepositData;
@@ -1,4 +1,4 @@
-use crate::{test_utils::TestRandom, AggregateSignature, AttestationData, Bitfield};
+use crate::{test_utils::TestRandom, AggregateSignature, AttestationData, Bitfield, ChainSpec};
 use rand::RngCore;
 use serde_derive::Serialize;
 use ssz::{hash, TreeHash};
@@ -12,6 +12,27 @@ pub struct SlashableAttestation {
     pub aggregate_signature: AggregateSignature,
 }
 
+impl SlashableAttestation {
+    /// Check if ``attestation_data_1`` and ``attestation_data_2`` have the same target.
+    ///
+    /// Spec v0.3.0
+    pub fn is_double_vote(&self, other: &SlashableAttestation, spec: &ChainSpec) -> bool {
+        self.data.slot.epoch(spec.epoch_length) == other.data.slot.epoch(spec.epoch_length)
+    }
+
+    /// Check if ``attestation_data_1`` surrounds ``attestation_data_2``.
+    ///
+    /// Spec v0.3.0
+    pub fn is_surround_vote(&self, other: &SlashableAttestation, spec: &ChainSpec) -> bool {
+        let source_epoch_1 = self.data.justified_epoch;
+        let source_epoch_2 = other.data.justified_epoch;
+        let target_epoch_1 = self.data.slot.epoch(spec.epoch_length);
+        let target_epoch_2 = other.data.slot.epoch(spec.epoch_length);
+
+        (source_epoch_1 < source_epoch_2) && (target_epoch_2 < target_epoch_1)
+    }
+}
+
 impl TreeHash for SlashableAttestation {
     fn hash_tree_root_internal(&self) -> Vec<u8> {
         let mut result: Vec<u8> = vec![];
@@ -37,9 +58,30 @@ impl<T: RngCore> TestRandom<T> for SlashableAttestation {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::chain_spec::ChainSpec;
+    use crate::slot_epoch::{Epoch, Slot};
     use crate::test_utils::{SeedableRng, TestRandom, XorShiftRng};
     use ssz::{ssz_encode, Decodable};
 
+    #[test]
+    pub fn test_is_double_vote() {
+        let spec = ChainSpec::foundation();
+        let first = create_slashable_attestation(1, 1, &spec);
+
+        assert!(first.is_double_vote(&create_slashable_attestation(1, 1, &spec), &spec));
+        assert!(!first.is_double_vote(&create_slashable_attestation(2, 1, &spec), &spec));
+    }
+
+    #[test]
+    pub fn test_is_surround_vote() {
+        let spec = ChainSpec::foundation();
+        let first = create_slashable_attestation(4, 1, &spec);
+
+        assert!(first.is_surround_vote(&create_slashable_attestation(3, 2, &spec), &spec));
+        assert!(!first.is_surround_vote(&create_slashable_attestation(3, 1, &spec), &spec));
+        assert!(!first.is_surround_vote(&create_slashable_attestation(5, 2, &spec), &spec));
+    }
+
     #[test]
     pub fn test_ssz_round_trip() {
         let mut rng = XorShiftRng::from_seed([42; 16]);
@@ -62,4 +104,17 @@ mod tests {
         // TODO: Add further tests
         // https://github.com/sigp/lighthouse/issues/170
     }
+
+    fn create_slashable_attestation(
+        slot_factor: u64,
+        justified_epoch: u64,
+        spec: &ChainSpec,
+    ) -> SlashableAttestation {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let mut slashable_attestation = SlashableAttestation::random_for_test(&mut rng);
+
+        slashable_attestation.data.slot = Slot::new(slot_factor * spec.epoch_length);
+        slashable_attestation.data.justified_epoch = Epoch::new(justified_epoch);
+        slashable_attestation
+    }
 }
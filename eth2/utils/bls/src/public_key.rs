@@ -1,6 +1,7 @@
 use super::SecretKey;
 use bls_aggregates::PublicKey as RawPublicKey;
 use hex::encode as hex_encode;
+use serde::de::{Deserialize, Deserializer, Error as DeError};
 use serde::ser::{Serialize, Serializer};
 use ssz::{
     decode_ssz_list, hash, ssz_encode, Decodable, DecodeError, Encodable, SszStream, TreeHash,
@@ -56,12 +57,28 @@ impl Decodable for PublicKey {
     }
 }
 
+/// Serializes as a hex string of the public key bytes, so public keys may be written in
+/// human-readable formats (e.g., YAML).
 impl Serialize for PublicKey {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        serializer.serialize_bytes(&ssz_encode(self))
+        serializer.serialize_str(&hex_encode(self.0.as_bytes()))
+    }
+}
+
+impl<'de> Deserialize<'de> for PublicKey {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let string = String::deserialize(deserializer)?;
+        let bytes = hex::decode(string.trim_start_matches("0x"))
+            .map_err(|e| DeError::custom(format!("invalid hex ({:?})", e)))?;
+        let raw_pk = RawPublicKey::from_bytes(&bytes)
+            .map_err(|e| DeError::custom(format!("invalid public key ({:?})", e)))?;
+        Ok(PublicKey(raw_pk))
     }
 }
 
@@ -98,4 +115,15 @@ mod tests {
 
         assert_eq!(original, decoded);
     }
+
+    #[test]
+    pub fn test_serde_round_trip() {
+        let sk = SecretKey::random();
+        let original = PublicKey::from_secret_key(&sk);
+
+        let json = serde_json::to_string(&original).unwrap();
+        let decoded: PublicKey = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(original, decoded);
+    }
 }
@@ -1,9 +1,9 @@
 use super::{PublicKey, SecretKey};
 use bls_aggregates::Signature as RawSignature;
+use hex::encode as hex_encode;
+use serde::de::{Deserialize, Deserializer, Error as DeError};
 use serde::ser::{Serialize, Serializer};
-use ssz::{
-    decode_ssz_list, hash, ssz_encode, Decodable, DecodeError, Encodable, SszStream, TreeHash,
-};
+use ssz::{decode_ssz_list, hash, Decodable, DecodeError, Encodable, SszStream, TreeHash};
 
 /// A single BLS signature.
 ///
@@ -78,12 +78,28 @@ impl TreeHash for Signature {
     }
 }
 
+/// Serializes as a hex string of the signature bytes, so signatures may be written in
+/// human-readable formats (e.g., YAML).
 impl Serialize for Signature {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        serializer.serialize_bytes(&ssz_encode(self))
+        serializer.serialize_str(&hex_encode(self.0.as_bytes()))
+    }
+}
+
+impl<'de> Deserialize<'de> for Signature {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let string = String::deserialize(deserializer)?;
+        let bytes = hex::decode(string.trim_start_matches("0x"))
+            .map_err(|e| DeError::custom(format!("invalid hex ({:?})", e)))?;
+        let raw_sig = RawSignature::from_bytes(&bytes)
+            .map_err(|e| DeError::custom(format!("invalid signature ({:?})", e)))?;
+        Ok(Signature(raw_sig))
     }
 }
 
@@ -105,6 +121,18 @@ mod tests {
         assert_eq!(original, decoded);
     }
 
+    #[test]
+    pub fn test_serde_round_trip() {
+        let keypair = Keypair::random();
+
+        let original = Signature::new(&[42, 42], 0, &keypair.sk);
+
+        let json = serde_json::to_string(&original).unwrap();
+        let decoded: Signature = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(original, decoded);
+    }
+
     #[test]
     pub fn test_empty_signature() {
         let sig = Signature::empty_signature();
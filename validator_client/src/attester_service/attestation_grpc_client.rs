@@ -0,0 +1,82 @@
+use attester::{BeaconNode, BeaconNodeError, PublishOutcome};
+use protos::services::{
+    AttestationData as GrpcAttestationData, FreeAttestation as GrpcFreeAttestation,
+    ProduceAttestationDataRequest, PublishAttestationRequest,
+};
+use protos::services_grpc::AttestationServiceClient;
+use ssz::{ssz_encode, Decodable};
+use std::sync::Arc;
+use types::{AttestationData, FreeAttestation, Slot};
+
+/// A newtype designed to wrap the gRPC-generated service so the `BeaconNode` trait may be
+/// implemented upon it.
+pub struct AttestationGrpcClient {
+    client: Arc<AttestationServiceClient>,
+}
+
+impl AttestationGrpcClient {
+    pub fn new(client: Arc<AttestationServiceClient>) -> Self {
+        Self { client }
+    }
+}
+
+impl BeaconNode for AttestationGrpcClient {
+    /// Request a Beacon Node (BN) to produce some `AttestationData` for the supplied slot and
+    /// shard.
+    ///
+    /// Returns `None` if it is not possible to produce at the supplied slot.
+    fn produce_attestation_data(
+        &self,
+        slot: Slot,
+        shard: u64,
+    ) -> Result<Option<AttestationData>, BeaconNodeError> {
+        let mut req = ProduceAttestationDataRequest::new();
+        req.set_slot(slot.as_u64());
+        req.set_shard(shard);
+
+        let reply = self
+            .client
+            .produce_attestation_data(&req)
+            .map_err(|err| BeaconNodeError::RemoteFailure(format!("{:?}", err)))?;
+
+        if reply.has_attestation_data() {
+            let (attestation_data, _) =
+                AttestationData::ssz_decode(reply.get_attestation_data().get_ssz(), 0)
+                    .map_err(|_| BeaconNodeError::DecodeFailure)?;
+
+            Ok(Some(attestation_data))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Request a Beacon Node (BN) to publish a `FreeAttestation`.
+    fn publish_attestation_data(
+        &self,
+        free_attestation: FreeAttestation,
+    ) -> Result<PublishOutcome, BeaconNodeError> {
+        let mut req = PublishAttestationRequest::new();
+
+        let mut grpc_attestation_data = GrpcAttestationData::new();
+        grpc_attestation_data.set_ssz(ssz_encode(&free_attestation.data));
+
+        let mut grpc_free_attestation = GrpcFreeAttestation::new();
+        grpc_free_attestation.set_data(grpc_attestation_data);
+        grpc_free_attestation.set_signature(ssz_encode(&free_attestation.signature));
+        grpc_free_attestation.set_validator_index(free_attestation.validator_index);
+
+        req.set_free_attestation(grpc_free_attestation);
+
+        let reply = self
+            .client
+            .publish_attestation(&req)
+            .map_err(|err| BeaconNodeError::RemoteFailure(format!("{:?}", err)))?;
+
+        if reply.get_success() {
+            Ok(PublishOutcome::ValidAttestation)
+        } else {
+            let msg = String::from_utf8_lossy(reply.get_msg()).to_string();
+            Ok(PublishOutcome::InvalidAttestation(msg))
+        }
+    }
+}
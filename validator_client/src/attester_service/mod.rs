@@ -0,0 +1,159 @@
+mod attestation_grpc_client;
+
+use attester::{
+    Attester, BeaconNode, DutiesReader, Error as AttesterError, PollOutcome as AttesterPollOutcome,
+    Signer,
+};
+use slashing_protection::SlashingProtection;
+use slog::{error, info, warn, Logger};
+use slot_clock::SlotClock;
+use std::time::Duration;
+
+pub use self::attestation_grpc_client::AttestationGrpcClient;
+
+pub struct AttesterService<
+    T: SlotClock,
+    U: BeaconNode,
+    V: DutiesReader,
+    W: Signer,
+    X: SlashingProtection,
+> {
+    pub attester: Attester<T, U, V, W, X>,
+    pub poll_interval_millis: u64,
+    pub log: Logger,
+}
+
+impl<T: SlotClock, U: BeaconNode, V: DutiesReader, W: Signer, X: SlashingProtection>
+    AttesterService<T, U, V, W, X>
+{
+    /// Run a loop which polls the attester each `poll_interval_millis` millseconds.
+    pub fn run(&mut self) {
+        loop {
+            // The outcome has already been logged.
+            let _ = self.poll();
+
+            std::thread::sleep(Duration::from_millis(self.poll_interval_millis));
+        }
+    }
+
+    /// Poll the attester once, logging the result.
+    pub fn poll(&mut self) -> Result<AttesterPollOutcome, AttesterError> {
+        let result = self.attester.poll();
+        match result {
+            Err(ref error) => {
+                error!(self.log, "Attester poll error"; "error" => format!("{:?}", error))
+            }
+            Ok(AttesterPollOutcome::AttestationProduced(slot)) => {
+                info!(self.log, "Produced attestation"; "slot" => slot)
+            }
+            Ok(AttesterPollOutcome::SlashableAttestationNotProduced(slot)) => {
+                warn!(self.log, "Slashable attestation was not signed"; "slot" => slot)
+            }
+            Ok(AttesterPollOutcome::AttestationNotRequired(slot)) => {
+                info!(self.log, "Attestation not required"; "slot" => slot)
+            }
+            Ok(AttesterPollOutcome::ProducerDutiesUnknown(slot)) => {
+                error!(self.log, "Attestation duties unknown"; "slot" => slot)
+            }
+            Ok(AttesterPollOutcome::SlotAlreadyProcessed(slot)) => {
+                warn!(self.log, "Attempted to re-process slot"; "slot" => slot)
+            }
+            Ok(AttesterPollOutcome::BeaconNodeUnableToProduceAttestation(slot)) => {
+                error!(self.log, "Beacon node unable to produce attestation"; "slot" => slot)
+            }
+            Ok(AttesterPollOutcome::SignerRejection(slot)) => {
+                error!(self.log, "The cryptographic signer refused to sign the attestation"; "slot" => slot)
+            }
+            Ok(AttesterPollOutcome::ValidatorIsUnknown(slot)) => {
+                error!(self.log, "The Beacon Node does not recognise the validator"; "slot" => slot)
+            }
+        };
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::duties::{EpochDuties, EpochDutiesMap};
+    use attester::test_utils::{LocalSigner, SimulatedBeaconNode};
+    use attester::PublishOutcome;
+    use slashing_protection::test_utils::MemorySlashingProtection;
+    use slog::o;
+    use slot_clock::TestingSlotClock;
+    use std::sync::Arc;
+    use types::test_utils::{SeedableRng, TestRandom, XorShiftRng};
+    use types::{AttestationData, ChainSpec, Keypair, Slot};
+
+    #[test]
+    pub fn attests_at_the_attestation_slot_of_its_duties() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let spec = Arc::new(ChainSpec::foundation());
+        let slot_clock = Arc::new(TestingSlotClock::new(0));
+        let beacon_node = Arc::new(SimulatedBeaconNode::default());
+
+        let attestation_slot = Slot::new(3 * spec.epoch_length + 2);
+        let duties = Arc::new(EpochDutiesMap::new(spec.epoch_length));
+        duties
+            .insert(
+                attestation_slot.epoch(spec.epoch_length),
+                EpochDuties {
+                    validator_index: 7,
+                    block_production_slot: None,
+                    attestation_slot,
+                    attestation_shard: 4,
+                    committee_index: 1,
+                },
+            )
+            .unwrap();
+
+        let attester = Attester::new(
+            spec.clone(),
+            duties,
+            slot_clock.clone(),
+            beacon_node.clone(),
+            Arc::new(LocalSigner::new(Keypair::random(), spec.clone())),
+            Arc::new(MemorySlashingProtection::default()),
+        );
+        let mut service = AttesterService {
+            attester,
+            poll_interval_millis: 0,
+            log: Logger::root(slog::Discard, o!()),
+        };
+
+        let mut attestation_data = AttestationData::random_for_test(&mut rng);
+        attestation_data.slot = attestation_slot;
+        beacon_node.set_next_produce_result(Ok(Some(attestation_data.clone())));
+        beacon_node.set_next_publish_result(Ok(PublishOutcome::ValidAttestation));
+
+        slot_clock.set_slot(attestation_slot.as_u64() - 1);
+        assert_eq!(
+            service.poll(),
+            Ok(AttesterPollOutcome::AttestationNotRequired(
+                attestation_slot - 1
+            ))
+        );
+
+        slot_clock.set_slot(attestation_slot.as_u64());
+        assert_eq!(
+            service.poll(),
+            Ok(AttesterPollOutcome::AttestationProduced(attestation_slot))
+        );
+        assert_eq!(
+            *beacon_node.produce_input.read().unwrap(),
+            Some((attestation_slot, 4))
+        );
+        let published = beacon_node.publish_input.read().unwrap().clone().unwrap();
+        assert_eq!(published.data, attestation_data);
+        assert_eq!(published.validator_index, 7);
+
+        // Duties for the next epoch are not yet known.
+        let next_epoch_slot =
+            (attestation_slot.epoch(spec.epoch_length) + 1).start_slot(spec.epoch_length);
+        slot_clock.set_slot(next_epoch_slot.as_u64());
+        assert_eq!(
+            service.poll(),
+            Ok(AttesterPollOutcome::ProducerDutiesUnknown(next_epoch_slot))
+        );
+    }
+}
@@ -5,7 +5,7 @@ use protos::services::{
 use protos::services_grpc::BeaconBlockServiceClient;
 use ssz::{ssz_encode, Decodable};
 use std::sync::Arc;
-use types::{BeaconBlock, BeaconBlockBody, Eth1Data, Hash256, Signature, Slot};
+use types::{BeaconBlock, Signature, Slot};
 
 /// A newtype designed to wrap the gRPC-generated service so the `BeaconNode` trait may be
 /// implemented upon it.
@@ -27,11 +27,11 @@ impl BeaconNode for BeaconBlockGrpcClient {
     fn produce_beacon_block(
         &self,
         slot: Slot,
-        // TODO: use randao_reveal, when proto APIs have been updated.
-        _randao_reveal: &Signature,
+        randao_reveal: &Signature,
     ) -> Result<Option<BeaconBlock>, BeaconNodeError> {
         let mut req = ProduceBeaconBlockRequest::new();
         req.set_slot(slot.as_u64());
+        req.set_randao_reveal(ssz_encode(randao_reveal));
 
         let reply = self
             .client
@@ -39,33 +39,10 @@ impl BeaconNode for BeaconBlockGrpcClient {
             .map_err(|err| BeaconNodeError::RemoteFailure(format!("{:?}", err)))?;
 
         if reply.has_block() {
-            let block = reply.get_block();
-
-            let (signature, _) = Signature::ssz_decode(block.get_signature(), 0)
-                .map_err(|_| BeaconNodeError::DecodeFailure)?;
-
-            let (randao_reveal, _) = Signature::ssz_decode(block.get_randao_reveal(), 0)
+            let (block, _) = BeaconBlock::ssz_decode(reply.get_block().get_ssz(), 0)
                 .map_err(|_| BeaconNodeError::DecodeFailure)?;
 
-            // TODO: this conversion is incomplete; fix it.
-            Ok(Some(BeaconBlock {
-                slot: Slot::new(block.get_slot()),
-                parent_root: Hash256::zero(),
-                state_root: Hash256::zero(),
-                randao_reveal,
-                eth1_data: Eth1Data {
-                    deposit_root: Hash256::zero(),
-                    block_hash: Hash256::zero(),
-                },
-                signature,
-                body: BeaconBlockBody {
-                    proposer_slashings: vec![],
-                    attester_slashings: vec![],
-                    attestations: vec![],
-                    deposits: vec![],
-                    exits: vec![],
-                },
-            }))
+            Ok(Some(block))
         } else {
             Ok(None)
         }
@@ -78,12 +55,8 @@ impl BeaconNode for BeaconBlockGrpcClient {
     fn publish_beacon_block(&self, block: BeaconBlock) -> Result<PublishOutcome, BeaconNodeError> {
         let mut req = PublishBeaconBlockRequest::new();
 
-        // TODO: this conversion is incomplete; fix it.
         let mut grpc_block = GrpcBeaconBlock::new();
-        grpc_block.set_slot(block.slot.as_u64());
-        grpc_block.set_block_root(vec![0]);
-        grpc_block.set_randao_reveal(ssz_encode(&block.randao_reveal));
-        grpc_block.set_signature(ssz_encode(&block.signature));
+        grpc_block.set_ssz(ssz_encode(&block));
 
         req.set_block(grpc_block);
 
@@ -95,8 +68,8 @@ impl BeaconNode for BeaconBlockGrpcClient {
         if reply.get_success() {
             Ok(PublishOutcome::ValidBlock)
         } else {
-            // TODO: distinguish between different errors
-            Ok(PublishOutcome::InvalidBlock("Publish failed".to_string()))
+            let msg = String::from_utf8_lossy(reply.get_msg()).to_string();
+            Ok(PublishOutcome::InvalidBlock(msg))
         }
     }
 }
@@ -4,19 +4,28 @@ mod beacon_block_grpc_client;
 use block_proposer::{
     BeaconNode, BlockProducer, DutiesReader, PollOutcome as BlockProducerPollOutcome, Signer,
 };
+use slashing_protection::SlashingProtection;
 use slog::{error, info, warn, Logger};
 use slot_clock::SlotClock;
 use std::time::Duration;
 
 pub use self::beacon_block_grpc_client::BeaconBlockGrpcClient;
 
-pub struct BlockProducerService<T: SlotClock, U: BeaconNode, V: DutiesReader, W: Signer> {
-    pub block_producer: BlockProducer<T, U, V, W>,
+pub struct BlockProducerService<
+    T: SlotClock,
+    U: BeaconNode,
+    V: DutiesReader,
+    W: Signer,
+    X: SlashingProtection,
+> {
+    pub block_producer: BlockProducer<T, U, V, W, X>,
     pub poll_interval_millis: u64,
     pub log: Logger,
 }
 
-impl<T: SlotClock, U: BeaconNode, V: DutiesReader, W: Signer> BlockProducerService<T, U, V, W> {
+impl<T: SlotClock, U: BeaconNode, V: DutiesReader, W: Signer, X: SlashingProtection>
+    BlockProducerService<T, U, V, W, X>
+{
     /// Run a loop which polls the block producer each `poll_interval_millis` millseconds.
     ///
     /// Logs the results of the polls.
@@ -1,3 +1,6 @@
+use attester::{
+    DutiesReader as AttesterDutiesReader, DutiesReaderError as AttesterDutiesReaderError,
+};
 use block_proposer::{DutiesReader, DutiesReaderError};
 use std::collections::HashMap;
 use std::sync::RwLock;
@@ -12,7 +15,9 @@ use types::{Epoch, Slot};
 pub struct EpochDuties {
     pub validator_index: u64,
     pub block_production_slot: Option<Slot>,
-    // Future shard info
+    pub attestation_slot: Slot,
+    pub attestation_shard: u64,
+    pub committee_index: u64,
 }
 
 impl EpochDuties {
@@ -24,8 +29,19 @@ impl EpochDuties {
             _ => false,
         }
     }
+
+    /// Returns `Some(shard)` if the supplied `slot` is a slot in which the validator should
+    /// attest. Otherwise, returns `None`.
+    pub fn attestation_shard(&self, slot: Slot) -> Option<u64> {
+        if self.attestation_slot == slot {
+            Some(self.attestation_shard)
+        } else {
+            None
+        }
+    }
 }
 
+#[derive(Debug, PartialEq)]
 pub enum EpochDutiesMapError {
     Poisoned,
 }
@@ -77,4 +93,62 @@ impl DutiesReader for EpochDutiesMap {
     }
 }
 
-// TODO: add tests.
+impl AttesterDutiesReader for EpochDutiesMap {
+    fn attestation_shard(&self, slot: Slot) -> Result<Option<u64>, AttesterDutiesReaderError> {
+        let epoch = slot.epoch(self.epoch_length);
+
+        let map = self
+            .map
+            .read()
+            .map_err(|_| AttesterDutiesReaderError::Poisoned)?;
+        let duties = map
+            .get(&epoch)
+            .ok_or(AttesterDutiesReaderError::UnknownEpoch)?;
+        Ok(duties.attestation_shard(slot))
+    }
+
+    fn validator_index(&self) -> Option<u64> {
+        let map = self.map.read().ok()?;
+        map.values().next().map(|duties| duties.validator_index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn reads_duties_for_known_epochs() {
+        let epoch_length = 8;
+        let duties_map = EpochDutiesMap::new(epoch_length);
+
+        let duties = EpochDuties {
+            validator_index: 3,
+            block_production_slot: Some(Slot::new(10)),
+            attestation_slot: Slot::new(12),
+            attestation_shard: 5,
+            committee_index: 1,
+        };
+        duties_map.insert(Epoch::new(1), duties).unwrap();
+
+        assert_eq!(duties_map.is_block_production_slot(Slot::new(10)), Ok(true));
+        assert_eq!(
+            duties_map.is_block_production_slot(Slot::new(11)),
+            Ok(false)
+        );
+
+        assert_eq!(duties_map.attestation_shard(Slot::new(12)), Ok(Some(5)));
+        assert_eq!(duties_map.attestation_shard(Slot::new(13)), Ok(None));
+        assert_eq!(AttesterDutiesReader::validator_index(&duties_map), Some(3));
+
+        // Epoch 2 is unknown.
+        assert_eq!(
+            duties_map.is_block_production_slot(Slot::new(16)),
+            Err(DutiesReaderError::UnknownEpoch)
+        );
+        assert_eq!(
+            duties_map.attestation_shard(Slot::new(16)),
+            Err(AttesterDutiesReaderError::UnknownEpoch)
+        );
+    }
+}
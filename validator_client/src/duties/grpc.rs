@@ -1,54 +1,62 @@
 use super::traits::{BeaconNode, BeaconNodeError};
 use super::EpochDuties;
-use protos::services::{ProposeBlockSlotRequest, PublicKey as IndexRequest};
+use protos::services::GetDutiesRequest;
 use protos::services_grpc::ValidatorServiceClient;
 use ssz::ssz_encode;
+use std::collections::HashMap;
 use types::{Epoch, PublicKey, Slot};
 
 impl BeaconNode for ValidatorServiceClient {
-    /// Request the shuffling from the Beacon Node (BN).
+    /// Request the duties for the given epoch and public keys from the Beacon Node (BN).
     ///
-    /// As this function takes a `PublicKey`, it will first attempt to resolve the public key into
-    /// a validator index, then call the BN for production/attestation duties.
-    ///
-    /// Note: presently only block production information is returned.
+    /// The BN returns the duties of each public key in the order they were requested.
     fn request_shuffling(
         &self,
         epoch: Epoch,
-        public_key: &PublicKey,
-    ) -> Result<Option<EpochDuties>, BeaconNodeError> {
-        // Lookup the validator index for the supplied public key.
-        let validator_index = {
-            let mut req = IndexRequest::new();
-            req.set_public_key(ssz_encode(public_key).to_vec());
-            let resp = self
-                .validator_index(&req)
-                .map_err(|err| BeaconNodeError::RemoteFailure(format!("{:?}", err)))?;
-            resp.get_index()
-        };
-
-        let mut req = ProposeBlockSlotRequest::new();
-        req.set_validator_index(validator_index);
+        public_keys: &[PublicKey],
+    ) -> Result<HashMap<PublicKey, EpochDuties>, BeaconNodeError> {
+        let mut req = GetDutiesRequest::new();
         req.set_epoch(epoch.as_u64());
+        for public_key in public_keys {
+            req.mut_public_keys().push(ssz_encode(public_key));
+        }
 
         let reply = self
-            .propose_block_slot(&req)
+            .get_validator_duties(&req)
             .map_err(|err| BeaconNodeError::RemoteFailure(format!("{:?}", err)))?;
 
-        let block_production_slot = if reply.has_slot() {
-            Some(reply.get_slot())
-        } else {
-            None
-        };
+        let active_validators = reply.get_active_validators();
+        if active_validators.len() != public_keys.len() {
+            return Err(BeaconNodeError::RemoteFailure(format!(
+                "Duties response contained {} validators, expected {}",
+                active_validators.len(),
+                public_keys.len()
+            )));
+        }
+
+        let mut all_duties = HashMap::new();
+        for (public_key, active_validator) in public_keys.iter().zip(active_validators) {
+            if !active_validator.has_duty() {
+                continue;
+            }
+
+            let duty = active_validator.get_duty();
 
-        let block_production_slot = match block_production_slot {
-            Some(slot) => Some(Slot::new(slot)),
-            None => None,
-        };
+            let block_production_slot = if duty.has_block_production_slot() {
+                Some(Slot::new(duty.get_block_production_slot()))
+            } else {
+                None
+            };
 
-        Ok(Some(EpochDuties {
-            validator_index,
-            block_production_slot,
-        }))
+            let duties = EpochDuties {
+                validator_index: duty.get_validator_index(),
+                block_production_slot,
+                attestation_slot: Slot::new(duty.get_attestation_slot()),
+                attestation_shard: duty.get_attestation_shard(),
+                committee_index: duty.get_committee_index(),
+            };
+            all_duties.insert(public_key.clone(), duties);
+        }
+        Ok(all_duties)
     }
 }
@@ -5,8 +5,8 @@ mod service;
 mod test_node;
 mod traits;
 
-pub use self::epoch_duties::EpochDutiesMap;
-use self::epoch_duties::{EpochDuties, EpochDutiesMapError};
+use self::epoch_duties::EpochDutiesMapError;
+pub use self::epoch_duties::{EpochDuties, EpochDutiesMap};
 pub use self::service::DutiesManagerService;
 use self::traits::{BeaconNode, BeaconNodeError};
 use bls::PublicKey;
@@ -39,22 +39,24 @@ pub enum Error {
 /// A polling state machine which ensures the latest `EpochDuties` are obtained from the Beacon
 /// Node.
 ///
-/// There is a single `DutiesManager` per validator instance.
+/// There is a single `DutiesManager` per validator client instance, which requests the duties of
+/// all of its validators at once.
 pub struct DutiesManager<T: SlotClock, U: BeaconNode> {
-    pub duties_map: Arc<EpochDutiesMap>,
-    /// The validator's public key.
-    pub pubkey: PublicKey,
+    /// The public key and `EpochDutiesMap` of each validator.
+    pub duties_maps: Vec<(PublicKey, Arc<EpochDutiesMap>)>,
     pub spec: Arc<ChainSpec>,
     pub slot_clock: Arc<T>,
     pub beacon_node: Arc<U>,
 }
 
 impl<T: SlotClock, U: BeaconNode> DutiesManager<T, U> {
-    /// Poll the Beacon Node for `EpochDuties`.
+    /// Poll the Beacon Node for the `EpochDuties` of every validator.
     ///
     /// The present `epoch` will be learned from the supplied `SlotClock`. In production this will
     /// be a wall-clock (e.g., system time, remote server time, etc.).
-    pub fn poll(&self) -> Result<PollOutcome, Error> {
+    ///
+    /// Returns the outcome for each validator, in the order of `duties_maps`.
+    pub fn poll(&self) -> Result<Vec<(PublicKey, PollOutcome)>, Error> {
         let slot = self
             .slot_clock
             .present_slot()
@@ -63,22 +65,33 @@ impl<T: SlotClock, U: BeaconNode> DutiesManager<T, U> {
 
         let epoch = slot.epoch(self.spec.epoch_length);
 
-        if let Some(duties) = self.beacon_node.request_shuffling(epoch, &self.pubkey)? {
-            // If these duties were known, check to see if they're updates or identical.
-            let result = if let Some(known_duties) = self.duties_map.get(epoch)? {
-                if known_duties == duties {
-                    Ok(PollOutcome::NoChange(epoch))
-                } else {
-                    Ok(PollOutcome::DutiesChanged(epoch, duties))
+        let public_keys: Vec<PublicKey> = self
+            .duties_maps
+            .iter()
+            .map(|(pubkey, _)| pubkey.clone())
+            .collect();
+        let all_duties = self.beacon_node.request_shuffling(epoch, &public_keys)?;
+
+        let mut outcomes = Vec::with_capacity(self.duties_maps.len());
+        for (pubkey, duties_map) in &self.duties_maps {
+            let outcome = match all_duties.get(pubkey) {
+                Some(duties) => {
+                    // If these duties were known, check to see if they're updates or identical.
+                    let outcome = match duties_map.get(epoch)? {
+                        Some(ref known_duties) if known_duties == duties => {
+                            PollOutcome::NoChange(epoch)
+                        }
+                        Some(_) => PollOutcome::DutiesChanged(epoch, *duties),
+                        None => PollOutcome::NewDuties(epoch, *duties),
+                    };
+                    duties_map.insert(epoch, *duties)?;
+                    outcome
                 }
-            } else {
-                Ok(PollOutcome::NewDuties(epoch, duties))
+                None => PollOutcome::UnknownValidatorOrEpoch(epoch),
             };
-            self.duties_map.insert(epoch, duties)?;
-            result
-        } else {
-            Ok(PollOutcome::UnknownValidatorOrEpoch(epoch))
+            outcomes.push((pubkey.clone(), outcome));
         }
+        Ok(outcomes)
     }
 }
 
@@ -113,14 +126,13 @@ mod tests {
     pub fn polling() {
         let spec = Arc::new(ChainSpec::foundation());
         let duties_map = Arc::new(EpochDutiesMap::new(spec.epoch_length));
-        let keypair = Keypair::random();
+        let pubkey = Keypair::random().pk;
         let slot_clock = Arc::new(TestingSlotClock::new(0));
         let beacon_node = Arc::new(TestBeaconNode::default());
 
         let manager = DutiesManager {
             spec: spec.clone(),
-            pubkey: keypair.pk.clone(),
-            duties_map: duties_map.clone(),
+            duties_maps: vec![(pubkey.clone(), duties_map.clone())],
             slot_clock: slot_clock.clone(),
             beacon_node: beacon_node.clone(),
         };
@@ -129,33 +141,110 @@ mod tests {
         let duties = EpochDuties {
             validator_index: 0,
             block_production_slot: Some(Slot::new(10)),
+            attestation_slot: Slot::new(12),
+            attestation_shard: 1,
+            committee_index: 2,
         };
-        beacon_node.set_next_shuffling_result(Ok(Some(duties)));
+        beacon_node.set_next_shuffling_result(Ok(vec![(pubkey.clone(), duties)]));
 
         // Get the duties for the first time...
         assert_eq!(
             manager.poll(),
-            Ok(PollOutcome::NewDuties(Epoch::new(0), duties))
+            Ok(vec![(
+                pubkey.clone(),
+                PollOutcome::NewDuties(Epoch::new(0), duties)
+            )])
         );
         // Get the same duties again...
-        assert_eq!(manager.poll(), Ok(PollOutcome::NoChange(Epoch::new(0))));
+        assert_eq!(
+            manager.poll(),
+            Ok(vec![(pubkey.clone(), PollOutcome::NoChange(Epoch::new(0)))])
+        );
 
         // Return new duties.
         let duties = EpochDuties {
             validator_index: 0,
             block_production_slot: Some(Slot::new(11)),
+            attestation_slot: Slot::new(12),
+            attestation_shard: 1,
+            committee_index: 2,
         };
-        beacon_node.set_next_shuffling_result(Ok(Some(duties)));
+        beacon_node.set_next_shuffling_result(Ok(vec![(pubkey.clone(), duties)]));
         assert_eq!(
             manager.poll(),
-            Ok(PollOutcome::DutiesChanged(Epoch::new(0), duties))
+            Ok(vec![(
+                pubkey.clone(),
+                PollOutcome::DutiesChanged(Epoch::new(0), duties)
+            )])
         );
 
         // Return no duties.
-        beacon_node.set_next_shuffling_result(Ok(None));
+        beacon_node.set_next_shuffling_result(Ok(vec![]));
+        assert_eq!(
+            manager.poll(),
+            Ok(vec![(
+                pubkey.clone(),
+                PollOutcome::UnknownValidatorOrEpoch(Epoch::new(0))
+            )])
+        );
+    }
+
+    #[test]
+    pub fn requests_all_validators_at_once() {
+        let spec = Arc::new(ChainSpec::foundation());
+        let slot_clock = Arc::new(TestingSlotClock::new(0));
+        let beacon_node = Arc::new(TestBeaconNode::default());
+        let pubkeys: Vec<PublicKey> = (0..3).map(|_| Keypair::random().pk).collect();
+        let duties_maps: Vec<(PublicKey, Arc<EpochDutiesMap>)> = pubkeys
+            .iter()
+            .map(|pubkey| {
+                let duties_map = Arc::new(EpochDutiesMap::new(spec.epoch_length));
+                (pubkey.clone(), duties_map)
+            })
+            .collect();
+
+        let manager = DutiesManager {
+            spec: spec.clone(),
+            duties_maps: duties_maps.clone(),
+            slot_clock: slot_clock.clone(),
+            beacon_node: beacon_node.clone(),
+        };
+
+        // The second validator is unknown to the Beacon Node.
+        let duties = |validator_index| EpochDuties {
+            validator_index,
+            block_production_slot: None,
+            attestation_slot: Slot::new(validator_index),
+            attestation_shard: validator_index,
+            committee_index: 0,
+        };
+        beacon_node.set_next_shuffling_result(Ok(vec![
+            (pubkeys[2].clone(), duties(2)),
+            (pubkeys[0].clone(), duties(0)),
+        ]));
+
         assert_eq!(
             manager.poll(),
-            Ok(PollOutcome::UnknownValidatorOrEpoch(Epoch::new(0)))
+            Ok(vec![
+                (
+                    pubkeys[0].clone(),
+                    PollOutcome::NewDuties(Epoch::new(0), duties(0))
+                ),
+                (
+                    pubkeys[1].clone(),
+                    PollOutcome::UnknownValidatorOrEpoch(Epoch::new(0))
+                ),
+                (
+                    pubkeys[2].clone(),
+                    PollOutcome::NewDuties(Epoch::new(0), duties(2))
+                ),
+            ])
+        );
+        assert_eq!(
+            *beacon_node.request_shuffling_input.read().unwrap(),
+            Some((Epoch::new(0), pubkeys))
         );
+        assert_eq!(duties_maps[2].1.get(Epoch::new(0)), Ok(Some(duties(2))));
+        assert_eq!(duties_maps[1].1.get(Epoch::new(0)), Ok(None));
     }
 }
@@ -1,5 +1,6 @@
 use super::traits::BeaconNode;
 use super::{DutiesManager, PollOutcome};
+use bls::PublicKey;
 use slog::{debug, error, info, Logger};
 use slot_clock::SlotClock;
 use std::time::Duration;
@@ -20,21 +21,32 @@ impl<T: SlotClock, U: BeaconNode> DutiesManagerService<T, U> {
                 Err(error) => {
                     error!(self.log, "Epoch duties poll error"; "error" => format!("{:?}", error))
                 }
-                Ok(PollOutcome::NoChange(epoch)) => {
-                    debug!(self.log, "No change in duties"; "epoch" => epoch)
-                }
-                Ok(PollOutcome::DutiesChanged(epoch, duties)) => {
-                    info!(self.log, "Duties changed (potential re-org)"; "epoch" => epoch, "duties" => format!("{:?}", duties))
-                }
-                Ok(PollOutcome::NewDuties(epoch, duties)) => {
-                    info!(self.log, "New duties obtained"; "epoch" => epoch, "duties" => format!("{:?}", duties))
-                }
-                Ok(PollOutcome::UnknownValidatorOrEpoch(epoch)) => {
-                    error!(self.log, "Epoch or validator unknown"; "epoch" => epoch)
+                Ok(outcomes) => {
+                    for (pubkey, outcome) in outcomes {
+                        self.log_outcome(&pubkey, outcome)
+                    }
                 }
             };
 
             std::thread::sleep(Duration::from_millis(self.poll_interval_millis));
         }
     }
+
+    fn log_outcome(&self, pubkey: &PublicKey, outcome: PollOutcome) {
+        let validator = pubkey.concatenated_hex_id();
+        match outcome {
+            PollOutcome::NoChange(epoch) => {
+                debug!(self.log, "No change in duties"; "epoch" => epoch, "validator" => validator)
+            }
+            PollOutcome::DutiesChanged(epoch, duties) => {
+                info!(self.log, "Duties changed (potential re-org)"; "epoch" => epoch, "validator" => validator, "duties" => format!("{:?}", duties))
+            }
+            PollOutcome::NewDuties(epoch, duties) => {
+                info!(self.log, "New duties obtained"; "epoch" => epoch, "validator" => validator, "duties" => format!("{:?}", duties))
+            }
+            PollOutcome::UnknownValidatorOrEpoch(epoch) => {
+                error!(self.log, "Epoch or validator unknown"; "epoch" => epoch, "validator" => validator)
+            }
+        };
+    }
 }
@@ -1,15 +1,16 @@
 use super::traits::{BeaconNode, BeaconNodeError};
 use super::EpochDuties;
 use bls::PublicKey;
+use std::collections::HashMap;
 use std::sync::RwLock;
 use types::Epoch;
 
-type ShufflingResult = Result<Option<EpochDuties>, BeaconNodeError>;
+type ShufflingResult = Result<Vec<(PublicKey, EpochDuties)>, BeaconNodeError>;
 
 /// A test-only struct used to simulate a Beacon Node.
 #[derive(Default)]
 pub struct TestBeaconNode {
-    pub request_shuffling_input: RwLock<Option<(Epoch, PublicKey)>>,
+    pub request_shuffling_input: RwLock<Option<(Epoch, Vec<PublicKey>)>>,
     pub request_shuffling_result: RwLock<Option<ShufflingResult>>,
 }
 
@@ -22,10 +23,14 @@ impl TestBeaconNode {
 
 impl BeaconNode for TestBeaconNode {
     /// Returns the value specified by the `set_next_shuffling_result`.
-    fn request_shuffling(&self, epoch: Epoch, public_key: &PublicKey) -> ShufflingResult {
-        *self.request_shuffling_input.write().unwrap() = Some((epoch, public_key.clone()));
+    fn request_shuffling(
+        &self,
+        epoch: Epoch,
+        public_keys: &[PublicKey],
+    ) -> Result<HashMap<PublicKey, EpochDuties>, BeaconNodeError> {
+        *self.request_shuffling_input.write().unwrap() = Some((epoch, public_keys.to_vec()));
         match *self.request_shuffling_result.read().unwrap() {
-            Some(ref r) => r.clone(),
+            Some(ref r) => r.clone().map(|duties| duties.into_iter().collect()),
             None => panic!("TestBeaconNode: produce_result == None"),
         }
     }
@@ -1,5 +1,6 @@
 use super::EpochDuties;
 use bls::PublicKey;
+use std::collections::HashMap;
 use types::Epoch;
 
 #[derive(Debug, PartialEq, Clone)]
@@ -9,12 +10,13 @@ pub enum BeaconNodeError {
 
 /// Defines the methods required to obtain a validators shuffling from a Beacon Node.
 pub trait BeaconNode: Send + Sync {
-    /// Get the shuffling for the given epoch and public key.
+    /// Get the shuffling for the given epoch and public keys in a single request.
     ///
-    /// Returns Ok(None) if the public key is unknown, or the shuffling for that epoch is unknown.
+    /// Returns the duties of each public key, omitting any public key which is unknown or whose
+    /// shuffling for that epoch is unknown.
     fn request_shuffling(
         &self,
         epoch: Epoch,
-        public_key: &PublicKey,
-    ) -> Result<Option<EpochDuties>, BeaconNodeError>;
+        public_keys: &[PublicKey],
+    ) -> Result<HashMap<PublicKey, EpochDuties>, BeaconNodeError>;
 }
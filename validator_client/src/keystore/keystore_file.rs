@@ -0,0 +1,168 @@
+use super::Error;
+use aes_ctr::cipher::{NewStreamCipher, SyncStreamCipher};
+use aes_ctr::Aes128Ctr;
+use bls::{Keypair, PublicKey, SecretKey};
+use hmac::Hmac;
+use rand::{thread_rng, Rng};
+use serde_derive::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// The number of PBKDF2 rounds used when encrypting a new keystore.
+pub const DEFAULT_PBKDF2_ROUNDS: u32 = 262_144;
+const KDF: &str = "pbkdf2-hmac-sha256";
+const CIPHER: &str = "aes-128-ctr";
+const DERIVED_KEY_LEN: usize = 32;
+const SALT_LEN: usize = 32;
+const IV_LEN: usize = 16;
+
+/// The parameters required to decrypt the secret key of a `Keystore`.
+///
+/// All byte values are hex encoded.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Crypto {
+    pub kdf: String,
+    pub rounds: u32,
+    pub salt: String,
+    pub cipher: String,
+    pub iv: String,
+    pub ciphertext: String,
+    /// `sha256(derived_key[16..32] ++ ciphertext)`, used to detect an incorrect password.
+    pub checksum: String,
+}
+
+/// A BLS secret key, encrypted with a password and stored as JSON.
+///
+/// The secret key is encrypted with AES-128-CTR, using a key derived from the password with
+/// PBKDF2-HMAC-SHA256.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Keystore {
+    /// The hex encoded bytes of the public key, stored in the clear so keystores may be listed
+    /// without a password.
+    pub pubkey: String,
+    pub crypto: Crypto,
+}
+
+impl Keystore {
+    /// Encrypt the secret key of `keypair` with `password`, using `rounds` of PBKDF2.
+    pub fn encrypt(keypair: &Keypair, password: &str, rounds: u32) -> Self {
+        let mut rng = thread_rng();
+        let mut salt = [0; SALT_LEN];
+        rng.fill(&mut salt[..]);
+        let mut iv = [0; IV_LEN];
+        rng.fill(&mut iv[..]);
+
+        let derived_key = derive_key(password, &salt, rounds);
+
+        let mut ciphertext = keypair.sk.as_raw().as_bytes();
+        apply_cipher(&derived_key, &iv, &mut ciphertext);
+
+        Self {
+            pubkey: pubkey_hex(&keypair.pk),
+            crypto: Crypto {
+                kdf: KDF.to_string(),
+                rounds,
+                salt: hex::encode(salt),
+                cipher: CIPHER.to_string(),
+                iv: hex::encode(iv),
+                checksum: hex::encode(checksum(&derived_key, &ciphertext)),
+                ciphertext: hex::encode(ciphertext),
+            },
+        }
+    }
+
+    /// Decrypt the secret key with `password`, returning the keypair.
+    ///
+    /// Returns `Error::InvalidPassword` if the checksum does not match the derived key.
+    pub fn decrypt(&self, password: &str) -> Result<Keypair, Error> {
+        if self.crypto.kdf != KDF || self.crypto.cipher != CIPHER {
+            return Err(Error::UnsupportedCrypto);
+        }
+
+        let salt = decode_hex(&self.crypto.salt)?;
+        let iv = decode_hex(&self.crypto.iv)?;
+        let mut ciphertext = decode_hex(&self.crypto.ciphertext)?;
+        let expected_checksum = decode_hex(&self.crypto.checksum)?;
+
+        if iv.len() != IV_LEN {
+            return Err(Error::InvalidHex);
+        }
+
+        let derived_key = derive_key(password, &salt, self.crypto.rounds);
+        if checksum(&derived_key, &ciphertext) != expected_checksum {
+            return Err(Error::InvalidPassword);
+        }
+
+        apply_cipher(&derived_key, &iv, &mut ciphertext);
+
+        let sk = SecretKey::from_bytes(&ciphertext).map_err(|_| Error::InvalidSecretKey)?;
+        let pk = PublicKey::from_secret_key(&sk);
+
+        if pubkey_hex(&pk) != self.pubkey {
+            return Err(Error::PublicKeyMismatch);
+        }
+
+        Ok(Keypair { sk, pk })
+    }
+}
+
+/// Returns the hex encoding of the bytes of `pubkey`.
+pub fn pubkey_hex(pubkey: &PublicKey) -> String {
+    hex::encode(pubkey.as_raw().as_bytes())
+}
+
+fn derive_key(password: &str, salt: &[u8], rounds: u32) -> [u8; DERIVED_KEY_LEN] {
+    let mut derived_key = [0; DERIVED_KEY_LEN];
+    pbkdf2::pbkdf2::<Hmac<Sha256>>(password.as_bytes(), salt, rounds, &mut derived_key);
+    derived_key
+}
+
+fn apply_cipher(derived_key: &[u8], iv: &[u8], bytes: &mut [u8]) {
+    // Key and IV lengths are fixed, so construction cannot fail.
+    let mut cipher = Aes128Ctr::new_var(&derived_key[0..16], iv).expect("Invalid key or IV length");
+    cipher.apply_keystream(bytes);
+}
+
+fn checksum(derived_key: &[u8], ciphertext: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(&derived_key[16..32]);
+    hasher.update(ciphertext);
+    hasher.finalize().to_vec()
+}
+
+fn decode_hex(string: &str) -> Result<Vec<u8>, Error> {
+    hex::decode(string).map_err(|_| Error::InvalidHex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn encrypt_decrypt_round_trip() {
+        let keypair = Keypair::random();
+        let keystore = Keystore::encrypt(&keypair, "cats", 2);
+
+        assert_eq!(keystore.pubkey, pubkey_hex(&keypair.pk));
+        assert_eq!(keystore.decrypt("cats"), Ok(keypair));
+    }
+
+    #[test]
+    pub fn wrong_password_is_rejected() {
+        let keypair = Keypair::random();
+        let keystore = Keystore::encrypt(&keypair, "cats", 2);
+
+        assert_eq!(keystore.decrypt("dogs"), Err(Error::InvalidPassword));
+    }
+
+    #[test]
+    pub fn json_round_trip() {
+        let keypair = Keypair::random();
+        let keystore = Keystore::encrypt(&keypair, "cats", 2);
+
+        let json = serde_json::to_string(&keystore).unwrap();
+        let decoded: Keystore = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(decoded, keystore);
+        assert_eq!(decoded.decrypt("cats"), Ok(keypair));
+    }
+}
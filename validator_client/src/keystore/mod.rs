@@ -0,0 +1,209 @@
+mod keystore_file;
+
+use bls::{Keypair, PublicKey, SecretKey};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+use self::keystore_file::{Keystore, DEFAULT_PBKDF2_ROUNDS};
+
+pub use self::keystore_file::pubkey_hex;
+
+const KEYSTORES_DIR: &str = "keystores";
+const KEYSTORE_EXTENSION: &str = "json";
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    Io(String),
+    InvalidJson(String),
+    InvalidHex,
+    InvalidPassword,
+    InvalidSecretKey,
+    PublicKeyMismatch,
+    UnsupportedCrypto,
+    KeystoreExists(String),
+    KeystoreNotFound(String),
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Error {
+        Error::Io(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Error {
+        Error::InvalidJson(e.to_string())
+    }
+}
+
+/// Manages a directory of password-encrypted `Keystore` files, one per validator.
+///
+/// Each keystore is stored as `<pubkey_hex>.json`.
+pub struct KeystoreDirectory {
+    dir: PathBuf,
+    /// The number of PBKDF2 rounds used when encrypting new keystores.
+    pbkdf2_rounds: u32,
+}
+
+impl KeystoreDirectory {
+    /// Open (or create) the keystore directory within `data_dir`.
+    pub fn open(data_dir: &Path) -> Result<Self, Error> {
+        let dir = data_dir.join(KEYSTORES_DIR);
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            pbkdf2_rounds: DEFAULT_PBKDF2_ROUNDS,
+        })
+    }
+
+    /// Generate a new random keypair and store it, encrypted with `password`.
+    pub fn generate(&self, password: &str) -> Result<Keypair, Error> {
+        let keypair = Keypair::random();
+        self.store(&keypair, password)?;
+        Ok(keypair)
+    }
+
+    /// Import an existing secret key and store it, encrypted with `password`.
+    pub fn import(&self, secret_key_bytes: &[u8], password: &str) -> Result<Keypair, Error> {
+        let sk = SecretKey::from_bytes(secret_key_bytes).map_err(|_| Error::InvalidSecretKey)?;
+        let pk = PublicKey::from_secret_key(&sk);
+        let keypair = Keypair { sk, pk };
+        self.store(&keypair, password)?;
+        Ok(keypair)
+    }
+
+    /// Decrypt the keystore for the public key with the hex encoding `pubkey_hex`.
+    pub fn export(&self, pubkey_hex: &str, password: &str) -> Result<Keypair, Error> {
+        let path = self.keystore_path(pubkey_hex);
+        if !path.exists() {
+            return Err(Error::KeystoreNotFound(pubkey_hex.to_string()));
+        }
+        read_keystore(&path)?.decrypt(password)
+    }
+
+    /// Returns the hex encoded public keys of all stored keystores, in lexicographical order.
+    pub fn list(&self) -> Result<Vec<String>, Error> {
+        let mut pubkeys = self
+            .keystore_paths()?
+            .iter()
+            .map(|path| Ok(read_keystore(path)?.pubkey))
+            .collect::<Result<Vec<String>, Error>>()?;
+        pubkeys.sort();
+        Ok(pubkeys)
+    }
+
+    /// Decrypt every stored keystore with `password`.
+    pub fn load_all(&self, password: &str) -> Result<Vec<Keypair>, Error> {
+        self.list()?
+            .iter()
+            .map(|pubkey_hex| self.export(pubkey_hex, password))
+            .collect()
+    }
+
+    fn store(&self, keypair: &Keypair, password: &str) -> Result<(), Error> {
+        let pubkey_hex = pubkey_hex(&keypair.pk);
+        let path = self.keystore_path(&pubkey_hex);
+        if path.exists() {
+            return Err(Error::KeystoreExists(pubkey_hex));
+        }
+
+        let keystore = Keystore::encrypt(keypair, password, self.pbkdf2_rounds);
+        let mut file = create_private_file(&path)?;
+        file.write_all(serde_json::to_string_pretty(&keystore)?.as_bytes())?;
+        Ok(())
+    }
+
+    fn keystore_path(&self, pubkey_hex: &str) -> PathBuf {
+        self.dir.join(pubkey_hex).with_extension(KEYSTORE_EXTENSION)
+    }
+
+    fn keystore_paths(&self) -> Result<Vec<PathBuf>, Error> {
+        let mut paths = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) == Some(KEYSTORE_EXTENSION) {
+                paths.push(path);
+            }
+        }
+        Ok(paths)
+    }
+}
+
+/// Create a new file which only the owner may read or write (i.e., mode `0600`).
+fn create_private_file(path: &Path) -> Result<File, Error> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    Ok(options.open(path)?)
+}
+
+fn read_keystore(path: &Path) -> Result<Keystore, Error> {
+    let json = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    pub fn generate_list_export() {
+        let data_dir = TempDir::new("lighthouse_keystore_test").unwrap();
+        let mut keystores = KeystoreDirectory::open(data_dir.path()).unwrap();
+        keystores.pbkdf2_rounds = 2;
+
+        let a = keystores.generate("cats").unwrap();
+        let b = keystores.generate("cats").unwrap();
+
+        let mut expected = vec![pubkey_hex(&a.pk), pubkey_hex(&b.pk)];
+        expected.sort();
+        assert_eq!(keystores.list(), Ok(expected));
+
+        assert_eq!(keystores.export(&pubkey_hex(&a.pk), "cats"), Ok(a));
+        assert_eq!(
+            keystores.export(&pubkey_hex(&b.pk), "dogs"),
+            Err(Error::InvalidPassword)
+        );
+        assert_eq!(keystores.load_all("cats").unwrap().len(), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    pub fn keystores_are_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let data_dir = TempDir::new("lighthouse_keystore_test").unwrap();
+        let mut keystores = KeystoreDirectory::open(data_dir.path()).unwrap();
+        keystores.pbkdf2_rounds = 2;
+
+        let keypair = keystores.generate("cats").unwrap();
+        let path = keystores.keystore_path(&pubkey_hex(&keypair.pk));
+        let mode = fs::metadata(path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    pub fn import_existing_key() {
+        let data_dir = TempDir::new("lighthouse_keystore_test").unwrap();
+        let mut keystores = KeystoreDirectory::open(data_dir.path()).unwrap();
+        keystores.pbkdf2_rounds = 2;
+
+        let keypair = Keypair::random();
+        let secret_key_bytes = keypair.sk.as_raw().as_bytes();
+
+        assert_eq!(
+            keystores.import(&secret_key_bytes, "cats"),
+            Ok(keypair.clone())
+        );
+        assert_eq!(
+            keystores.import(&secret_key_bytes, "cats"),
+            Err(Error::KeystoreExists(pubkey_hex(&keypair.pk)))
+        );
+        assert_eq!(keystores.load_all("cats"), Ok(vec![keypair]));
+    }
+}
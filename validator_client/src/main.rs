@@ -1,21 +1,42 @@
+use self::attester_service::{AttestationGrpcClient, AttesterService};
 use self::block_producer_service::{BeaconBlockGrpcClient, BlockProducerService};
 use self::duties::{DutiesManager, DutiesManagerService, EpochDutiesMap};
 use crate::config::ClientConfig;
-use block_proposer::{test_utils::LocalSigner, BlockProducer};
-use bls::Keypair;
-use clap::{App, Arg};
+use crate::keystore::{pubkey_hex, KeystoreDirectory};
+use crate::signer::{RemoteSigner, SignerServer, ValidatorSigner};
+use attester::Attester;
+use block_proposer::BlockProducer;
+use bls::create_proof_of_possession;
+use clap::{App, Arg, ArgMatches, SubCommand};
+use db::{
+    stores::{SlashingProtectionStore, COLUMNS},
+    DiskDB,
+};
 use grpcio::{ChannelBuilder, EnvBuilder};
-use protos::services_grpc::{BeaconBlockServiceClient, ValidatorServiceClient};
-use slog::{error, info, o, Drain};
+use protos::services::Empty;
+use protos::services_grpc::{
+    AttestationServiceClient, BeaconBlockServiceClient, BeaconNodeServiceClient,
+    ValidatorServiceClient,
+};
+use slog::{error, info, o, Drain, Logger};
 use slot_clock::SystemTimeSlotClock;
-use std::path::PathBuf;
+use std::fs;
+use std::net::{SocketAddr, TcpListener};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::thread;
-use types::ChainSpec;
+use types::chain_spec::DEFAULT_PRESET;
+use types::{ChainSpec, Deposit, DepositData, DepositInput, Hash256, Keypair};
 
+mod attester_service;
 mod block_producer_service;
 mod config;
 mod duties;
+mod keystore;
+mod signer;
+
+const DEFAULT_SIGNER_LISTEN_ADDRESS: &str = "127.0.0.1:5053";
+const DEFAULT_SIGNER_DB_DIR: &str = ".lighthouse-signer";
 
 fn main() {
     // Logging
@@ -43,6 +64,97 @@ fn main() {
                 .help("Address to connect to BeaconNode.")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("spec")
+                .long("spec")
+                .value_name("PRESET_OR_FILE")
+                .help("The chain specification: a preset (foundation, few_validators) or the path to a YAML file. Defaults to foundation.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("password-file")
+                .long("password-file")
+                .value_name("FILE")
+                .help("File containing the password used to encrypt and decrypt keystores.")
+                .takes_value(true)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("signer")
+                .long("signer")
+                .value_name("ADDRESS")
+                .help("Address of a remote signer holding the validator keys. If not supplied, keys are loaded from keystores.")
+                .takes_value(true)
+                .requires("signer-token-file"),
+        )
+        .arg(
+            Arg::with_name("signer-token-file")
+                .long("signer-token-file")
+                .value_name("FILE")
+                .help("File containing the secret token with which validator clients authenticate to the signer.")
+                .takes_value(true)
+                .global(true),
+        )
+        .subcommand(
+            SubCommand::with_name("keys")
+                .about("Manage the password-encrypted validator keystores in the data directory.")
+                .subcommand(SubCommand::with_name("generate").about("Generate a new keypair."))
+                .subcommand(SubCommand::with_name("list").about("List the stored public keys."))
+                .subcommand(
+                    SubCommand::with_name("import")
+                        .about("Import an existing secret key.")
+                        .arg(
+                            Arg::with_name("secret-key")
+                                .long("secret-key")
+                                .value_name("HEX")
+                                .help("The hex encoded bytes of the secret key.")
+                                .takes_value(true)
+                                .required(true),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("deposits")
+                        .about("Write a genesis deposit for each stored keypair, for use with the beacon node genesis subcommand.")
+                        .arg(
+                            Arg::with_name("output")
+                                .long("output")
+                                .value_name("FILE")
+                                .help("The file to which the YAML list of deposits is written.")
+                                .takes_value(true)
+                                .required(true),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("export")
+                        .about("Print the secret key for some public key.")
+                        .arg(
+                            Arg::with_name("pubkey")
+                                .long("pubkey")
+                                .value_name("HEX")
+                                .help("The hex encoded bytes of the public key.")
+                                .takes_value(true)
+                                .required(true),
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("signer")
+                .about("Run a signer, holding the keystores in the data directory on behalf of remote validator clients.")
+                .arg(
+                    Arg::with_name("listen-address")
+                        .long("listen-address")
+                        .value_name("ADDRESS")
+                        .help("Address on which to accept signing requests.")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("db-dir")
+                        .long("db-dir")
+                        .value_name("DIR")
+                        .help("Directory of the signer's slashing protection database. Defaults to ~/.lighthouse-signer, separate from the validator client data directory.")
+                        .takes_value(true),
+                ),
+        )
         .get_matches();
 
     let mut config = ClientConfig::default();
@@ -67,6 +179,112 @@ fn main() {
           "data_dir" => &config.data_dir.to_str(),
           "server" => &config.server);
 
+    // Ethereum
+    let spec_name = matches.value_of("spec").unwrap_or(DEFAULT_PRESET);
+    let spec = match ChainSpec::from_preset_or_file(spec_name) {
+        Ok(spec) => Arc::new(spec),
+        Err(e) => {
+            error!(log, "Unable to load spec"; "spec" => spec_name, "error" => format!("{:?}", e));
+            return;
+        }
+    };
+    info!(log, "Loaded spec"; "spec" => spec_name, "epoch_length" => spec.epoch_length, "shard_count" => spec.shard_count);
+
+    // Keystores
+    let keystores = match KeystoreDirectory::open(&config.data_dir) {
+        Ok(keystores) => keystores,
+        Err(e) => {
+            error!(log, "Unable to open keystore directory"; "error" => format!("{:?}", e));
+            return;
+        }
+    };
+    let password = match matches.value_of("password-file") {
+        Some(path) => match read_first_line(Path::new(path)) {
+            Ok(password) => Some(password),
+            Err(e) => {
+                error!(log, "Unable to read password file"; "path" => path, "error" => format!("{:?}", e));
+                return;
+            }
+        },
+        None => None,
+    };
+
+    if let Some(keys_matches) = matches.subcommand_matches("keys") {
+        manage_keys(&keystores, keys_matches, password, &spec, &log);
+        return;
+    }
+
+    let signer_token = match matches.value_of("signer-token-file") {
+        Some(path) => match read_first_line(Path::new(path)) {
+            Ok(ref token) if token.is_empty() => {
+                error!(log, "Signer token file is empty"; "path" => path);
+                return;
+            }
+            Ok(token) => Some(token),
+            Err(e) => {
+                error!(log, "Unable to read signer token file"; "path" => path, "error" => format!("{:?}", e));
+                return;
+            }
+        },
+        None => None,
+    };
+
+    if let Some(signer_matches) = matches.subcommand_matches("signer") {
+        run_signer(
+            &keystores,
+            signer_matches,
+            password,
+            signer_token,
+            spec.clone(),
+            &log,
+        );
+        return;
+    }
+
+    let signers = match (matches.value_of("signer"), signer_token) {
+        (Some(address), Some(token)) => {
+            let address: SocketAddr = match address.parse() {
+                Ok(address) => address,
+                Err(_) => {
+                    error!(log, "Invalid signer address"; "signer" => address);
+                    return;
+                }
+            };
+            match RemoteSigner::public_keys(&address, &token) {
+                Ok(public_keys) => public_keys
+                    .into_iter()
+                    .map(|public_key| {
+                        ValidatorSigner::Remote(RemoteSigner::new(
+                            address,
+                            token.clone(),
+                            public_key,
+                            log.clone(),
+                        ))
+                    })
+                    .collect(),
+                Err(e) => {
+                    error!(log, "Unable to reach signer"; "signer" => address.to_string(), "error" => format!("{:?}", e));
+                    return;
+                }
+            }
+        }
+        _ => match load_keypairs(&keystores, password, &log) {
+            Some(keypairs) => keypairs
+                .into_iter()
+                .map(|keypair| ValidatorSigner::Local {
+                    keypair,
+                    spec: spec.clone(),
+                })
+                .collect::<Vec<_>>(),
+            None => return,
+        },
+    };
+    if signers.is_empty() {
+        error!(log, "No validator keys found");
+        return;
+    }
+    info!(log, "Loaded validators"; "count" => signers.len());
+
     // Beacon node gRPC beacon block endpoints.
     let beacon_block_grpc_client = {
         let env = Arc::new(EnvBuilder::new().build());
@@ -81,16 +299,28 @@ fn main() {
         Arc::new(ValidatorServiceClient::new(ch))
     };
 
-    // Ethereum
-    //
-    // TODO: Permit loading a custom spec from file.
-    // https://github.com/sigp/lighthouse/issues/160
-    let spec = Arc::new(ChainSpec::foundation());
+    // Beacon node gRPC attester endpoints.
+    let attester_grpc_client = {
+        let env = Arc::new(EnvBuilder::new().build());
+        let ch = ChannelBuilder::new(env).connect(&config.server);
+        Arc::new(AttestationServiceClient::new(ch))
+    };
+
+    // Beacon node gRPC info endpoint, used to obtain the genesis time.
+    let beacon_node_grpc_client = {
+        let env = Arc::new(EnvBuilder::new().build());
+        let ch = ChannelBuilder::new(env).connect(&config.server);
+        BeaconNodeServiceClient::new(ch)
+    };
 
     // Clock for determining the present slot.
-    // TODO: this shouldn't be a static time, instead it should be pulled from the beacon node.
-    // https://github.com/sigp/lighthouse/issues/160
-    let genesis_time = 1_549_935_547;
+    let genesis_time = match beacon_node_grpc_client.info(&Empty::new()) {
+        Ok(info) => info.get_genesis_time(),
+        Err(e) => {
+            error!(log, "Unable to fetch genesis time from beacon node"; "server" => &config.server, "error" => format!("{:?}", e));
+            return;
+        }
+    };
     let slot_clock = {
         info!(log, "Genesis time"; "unix_epoch_seconds" => genesis_time);
         let clock = SystemTimeSlotClock::new(genesis_time, spec.slot_duration)
@@ -98,6 +328,9 @@ fn main() {
         Arc::new(clock)
     };
 
+    // Database for slashing protection, shared between all validators.
+    let db = Arc::new(DiskDB::open(&config.data_dir, Some(&COLUMNS)));
+
     let poll_interval_millis = spec.slot_duration * 1000 / 10; // 10% epoch time precision.
     info!(log, "Starting block producer service"; "polls_per_epoch" => spec.slot_duration * 1000 / poll_interval_millis);
 
@@ -105,68 +338,297 @@ fn main() {
      * Start threads.
      */
     let mut threads = vec![];
-    // TODO: keypairs are randomly generated; they should be loaded from a file or generated.
-    // https://github.com/sigp/lighthouse/issues/160
-    let keypairs = vec![Keypair::random()];
 
-    for keypair in keypairs {
-        info!(log, "Starting validator services"; "validator" => keypair.pk.concatenated_hex_id());
-        let duties_map = Arc::new(EpochDutiesMap::new(spec.epoch_length));
+    let signers: Vec<(Arc<ValidatorSigner>, Arc<EpochDutiesMap>)> = signers
+        .into_iter()
+        .map(|signer| {
+            let duties_map = Arc::new(EpochDutiesMap::new(spec.epoch_length));
+            (Arc::new(signer), duties_map)
+        })
+        .collect();
 
-        // Spawn a new thread to maintain the validator's `EpochDuties`.
-        let duties_manager_thread = {
+    // Spawn a new thread to maintain the `EpochDuties` of every validator.
+    let duties_manager_thread = {
+        let duties_maps = signers
+            .iter()
+            .map(|(signer, duties_map)| (signer.public_key().clone(), duties_map.clone()))
+            .collect();
+        let spec = spec.clone();
+        let slot_clock = slot_clock.clone();
+        let log = log.clone();
+        let beacon_node = validator_grpc_client.clone();
+        thread::spawn(move || {
+            let manager = DutiesManager {
+                duties_maps,
+                spec,
+                slot_clock,
+                beacon_node,
+            };
+            let mut duties_manager_service = DutiesManagerService {
+                manager,
+                poll_interval_millis,
+                log,
+            };
+
+            duties_manager_service.run();
+        })
+    };
+
+    for (signer, duties_map) in signers {
+        let pubkey = signer.public_key().clone();
+        info!(log, "Starting validator services"; "validator" => pubkey.concatenated_hex_id());
+        let slashing_protection = Arc::new(SlashingProtectionStore::new(db.clone(), &pubkey));
+
+        // Spawn a new thread to perform block production for the validator.
+        let producer_thread = {
             let spec = spec.clone();
+            let signer = signer.clone();
             let duties_map = duties_map.clone();
             let slot_clock = slot_clock.clone();
             let log = log.clone();
-            let beacon_node = validator_grpc_client.clone();
-            let pubkey = keypair.pk.clone();
+            let client = Arc::new(BeaconBlockGrpcClient::new(beacon_block_grpc_client.clone()));
+            let slashing_protection = slashing_protection.clone();
             thread::spawn(move || {
-                let manager = DutiesManager {
-                    duties_map,
-                    pubkey,
+                let block_producer = BlockProducer::new(
                     spec,
+                    duties_map,
                     slot_clock,
-                    beacon_node,
-                };
-                let mut duties_manager_service = DutiesManagerService {
-                    manager,
+                    client,
+                    signer,
+                    slashing_protection,
+                );
+                let mut block_producer_service = BlockProducerService {
+                    block_producer,
                     poll_interval_millis,
                     log,
                 };
 
-                duties_manager_service.run();
+                block_producer_service.run();
             })
         };
 
-        // Spawn a new thread to perform block production for the validator.
-        let producer_thread = {
+        // Spawn a new thread for attestation for the validator.
+        let attester_thread = {
             let spec = spec.clone();
-            let signer = Arc::new(LocalSigner::new(keypair.clone()));
+            let signer = signer.clone();
             let duties_map = duties_map.clone();
             let slot_clock = slot_clock.clone();
             let log = log.clone();
-            let client = Arc::new(BeaconBlockGrpcClient::new(beacon_block_grpc_client.clone()));
+            let client = Arc::new(AttestationGrpcClient::new(attester_grpc_client.clone()));
+            let slashing_protection = slashing_protection.clone();
             thread::spawn(move || {
-                let block_producer =
-                    BlockProducer::new(spec, duties_map, slot_clock, client, signer);
-                let mut block_producer_service = BlockProducerService {
-                    block_producer,
+                let attester = Attester::new(
+                    spec,
+                    duties_map,
+                    slot_clock,
+                    client,
+                    signer,
+                    slashing_protection,
+                );
+                let mut attester_service = AttesterService {
+                    attester,
                     poll_interval_millis,
                     log,
                 };
 
-                block_producer_service.run();
+                attester_service.run();
             })
         };
 
-        threads.push((duties_manager_thread, producer_thread));
+        threads.push((producer_thread, attester_thread));
     }
 
     // Naively wait for all the threads to complete.
-    for tuple in threads {
-        let (manager, producer) = tuple;
+    for (producer, attester) in threads {
         let _ = producer.join();
-        let _ = manager.join();
+        let _ = attester.join();
+    }
+    let _ = duties_manager_thread.join();
+}
+
+/// Decrypt all keystores with `password`, logging an error and returning `None` on failure.
+fn load_keypairs(
+    keystores: &KeystoreDirectory,
+    password: Option<String>,
+    log: &Logger,
+) -> Option<Vec<Keypair>> {
+    let password = match password {
+        Some(password) => password,
+        None => {
+            error!(log, "A --password-file is required to load keystores");
+            return None;
+        }
+    };
+
+    match keystores.load_all(&password) {
+        Ok(ref keypairs) if keypairs.is_empty() => {
+            error!(
+                log,
+                "No keystores found, generate one with the `keys generate` subcommand"
+            );
+            None
+        }
+        Ok(keypairs) => {
+            info!(log, "Loaded keystores"; "count" => keypairs.len());
+            Some(keypairs)
+        }
+        Err(e) => {
+            error!(log, "Unable to load keystores"; "error" => format!("{:?}", e));
+            None
+        }
+    }
+}
+
+/// Run a signer for all keystores, blocking until the listener fails.
+///
+/// The signer keeps its own slashing protection database, separate from that of the validator
+/// client, and only serves clients presenting `token`.
+fn run_signer(
+    keystores: &KeystoreDirectory,
+    matches: &ArgMatches,
+    password: Option<String>,
+    token: Option<String>,
+    spec: Arc<ChainSpec>,
+    log: &Logger,
+) {
+    let token = match token {
+        Some(token) => token,
+        None => {
+            error!(log, "A --signer-token-file is required to run a signer");
+            return;
+        }
+    };
+    let keypairs = match load_keypairs(keystores, password, log) {
+        Some(keypairs) => keypairs,
+        None => return,
+    };
+
+    let address = matches
+        .value_of("listen-address")
+        .unwrap_or(DEFAULT_SIGNER_LISTEN_ADDRESS);
+    let listener = match TcpListener::bind(address) {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!(log, "Unable to bind signer"; "address" => address, "error" => format!("{:?}", e));
+            return;
+        }
+    };
+
+    let db_dir = match matches.value_of("db-dir") {
+        Some(dir) => PathBuf::from(dir),
+        None => dirs::home_dir()
+            .expect("Unable to determine home dir.")
+            .join(DEFAULT_SIGNER_DB_DIR),
+    };
+    if let Err(e) = fs::create_dir_all(&db_dir) {
+        error!(log, "Unable to create signer database directory"; "dir" => format!("{:?}", db_dir), "error" => format!("{:?}", e));
+        return;
+    }
+    info!(log, "Signer database"; "dir" => format!("{:?}", db_dir));
+
+    let db = Arc::new(DiskDB::open(&db_dir, Some(&COLUMNS)));
+    Arc::new(SignerServer::new(keypairs, token, db, spec, log.clone())).serve(listener);
+}
+
+/// Read a password or token from the first line of the file at `path`.
+fn read_first_line(path: &Path) -> Result<String, std::io::Error> {
+    let contents = fs::read_to_string(path)?;
+    Ok(contents.lines().next().unwrap_or("").to_string())
+}
+
+/// Run a `keys` subcommand against the `keystores`.
+fn manage_keys(
+    keystores: &KeystoreDirectory,
+    matches: &ArgMatches,
+    password: Option<String>,
+    spec: &ChainSpec,
+    log: &Logger,
+) {
+    if matches.subcommand_matches("list").is_some() {
+        match keystores.list() {
+            Ok(pubkeys) => {
+                for pubkey in pubkeys {
+                    println!("{}", pubkey);
+                }
+            }
+            Err(e) => error!(log, "Unable to list keystores"; "error" => format!("{:?}", e)),
+        }
+        return;
+    }
+
+    let password = match password {
+        Some(password) => password,
+        None => {
+            error!(log, "A --password-file is required");
+            return;
+        }
+    };
+
+    if matches.subcommand_matches("generate").is_some() {
+        match keystores.generate(&password) {
+            Ok(keypair) => info!(log, "Generated keystore"; "pubkey" => pubkey_hex(&keypair.pk)),
+            Err(e) => error!(log, "Unable to generate keystore"; "error" => format!("{:?}", e)),
+        }
+    } else if let Some(import_matches) = matches.subcommand_matches("import") {
+        let secret_key_hex = import_matches
+            .value_of("secret-key")
+            .expect("secret-key is required");
+        let result = hex::decode(secret_key_hex.trim_start_matches("0x"))
+            .map_err(|_| keystore::Error::InvalidHex)
+            .and_then(|bytes| keystores.import(&bytes, &password));
+        match result {
+            Ok(keypair) => info!(log, "Imported keystore"; "pubkey" => pubkey_hex(&keypair.pk)),
+            Err(e) => error!(log, "Unable to import keystore"; "error" => format!("{:?}", e)),
+        }
+    } else if let Some(deposits_matches) = matches.subcommand_matches("deposits") {
+        let output = deposits_matches
+            .value_of("output")
+            .expect("output is required");
+        let keypairs = match keystores.load_all(&password) {
+            Ok(keypairs) => keypairs,
+            Err(e) => {
+                error!(log, "Unable to load keystores"; "error" => format!("{:?}", e));
+                return;
+            }
+        };
+        let deposits: Vec<Deposit> = keypairs
+            .iter()
+            .map(|keypair| genesis_deposit(keypair, spec))
+            .collect();
+        let yaml = serde_yaml::to_string(&deposits).expect("Deposits are serializable");
+        match fs::write(output, yaml) {
+            Ok(()) => info!(log, "Wrote deposits"; "path" => output, "count" => deposits.len()),
+            Err(e) => error!(log, "Unable to write deposits"; "error" => format!("{:?}", e)),
+        }
+    } else if let Some(export_matches) = matches.subcommand_matches("export") {
+        let pubkey = export_matches
+            .value_of("pubkey")
+            .expect("pubkey is required");
+        match keystores.export(pubkey.trim_start_matches("0x"), &password) {
+            Ok(keypair) => println!("{}", hex::encode(keypair.sk.as_raw().as_bytes())),
+            Err(e) => error!(log, "Unable to export keystore"; "error" => format!("{:?}", e)),
+        }
+    } else {
+        error!(log, "No keys subcommand supplied, see --help");
+    }
+}
+
+/// A deposit of the maximum amount for `keypair`, for inclusion in a genesis state.
+///
+/// Genesis deposits are not made on the eth1 chain, so have no branch, index or timestamp.
+/// Withdrawals are not yet specified, so the withdrawal credentials are zeroed.
+fn genesis_deposit(keypair: &Keypair, spec: &ChainSpec) -> Deposit {
+    Deposit {
+        branch: vec![],
+        index: 0,
+        deposit_data: DepositData {
+            amount: spec.max_deposit_amount,
+            timestamp: 0,
+            deposit_input: DepositInput {
+                pubkey: keypair.pk.clone(),
+                withdrawal_credentials: Hash256::zero(),
+                proof_of_possession: create_proof_of_possession(keypair),
+            },
+        },
     }
 }
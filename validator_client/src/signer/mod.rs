@@ -0,0 +1,115 @@
+mod protocol;
+mod remote_signer;
+mod signer_server;
+
+use attester::Signer as AttesterSigner;
+use block_proposer::Signer as BlockProposerSigner;
+use bls::{Keypair, PublicKey, Signature};
+use int_to_bytes::int_to_bytes32;
+use std::sync::Arc;
+use types::{AttestationData, BeaconBlock, ChainSpec, Epoch};
+
+pub use self::remote_signer::RemoteSigner;
+pub use self::signer_server::SignerServer;
+
+const PHASE_0_CUSTODY_BIT: bool = false;
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    Io(String),
+    InvalidJson(String),
+    InvalidHex,
+    InvalidPublicKey,
+    InvalidSignature,
+    InvalidMessage,
+    ConnectionClosed,
+    LineTooLong,
+    Rejected(String),
+    UnexpectedResponse,
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Error {
+        Error::Io(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Error {
+        Error::InvalidJson(e.to_string())
+    }
+}
+
+/// An object which a validator may be asked to sign.
+///
+/// Signers are given the object itself rather than its root, so the signed message, its domain
+/// and any slashing protection information are derived by the signer.
+#[derive(Debug, PartialEq, Clone)]
+pub enum SigningMessage {
+    BlockProposal(BeaconBlock),
+    RandaoReveal(Epoch),
+    Attestation(AttestationData),
+}
+
+impl SigningMessage {
+    /// Returns the bytes to be signed and the domain in which they are signed.
+    pub fn signable_message(&self, spec: &ChainSpec) -> (Vec<u8>, u64) {
+        match self {
+            SigningMessage::BlockProposal(block) => {
+                (block.proposal_root(spec)[..].to_vec(), spec.domain_proposal)
+            }
+            SigningMessage::RandaoReveal(epoch) => {
+                (int_to_bytes32(epoch.as_u64()), spec.domain_randao)
+            }
+            SigningMessage::Attestation(attestation_data) => (
+                attestation_data.signable_message(PHASE_0_CUSTODY_BIT),
+                spec.domain_attestation,
+            ),
+        }
+    }
+}
+
+/// Signs messages for a single validator, either with a secret key held in this process or by
+/// forwarding messages to a remote signer.
+pub enum ValidatorSigner {
+    Local {
+        keypair: Keypair,
+        spec: Arc<ChainSpec>,
+    },
+    Remote(RemoteSigner),
+}
+
+impl ValidatorSigner {
+    pub fn public_key(&self) -> &PublicKey {
+        match self {
+            ValidatorSigner::Local { keypair, .. } => &keypair.pk,
+            ValidatorSigner::Remote(signer) => signer.public_key(),
+        }
+    }
+
+    fn sign(&self, message: SigningMessage) -> Option<Signature> {
+        match self {
+            ValidatorSigner::Local { keypair, spec } => {
+                let (message, domain) = message.signable_message(spec);
+                Some(Signature::new(&message, domain, &keypair.sk))
+            }
+            ValidatorSigner::Remote(signer) => signer.sign(&message),
+        }
+    }
+}
+
+impl BlockProposerSigner for ValidatorSigner {
+    fn sign_block_proposal(&self, block: &BeaconBlock) -> Option<Signature> {
+        self.sign(SigningMessage::BlockProposal(block.clone()))
+    }
+
+    fn sign_randao_reveal(&self, epoch: Epoch) -> Option<Signature> {
+        self.sign(SigningMessage::RandaoReveal(epoch))
+    }
+}
+
+impl AttesterSigner for ValidatorSigner {
+    fn sign_attestation(&self, attestation_data: &AttestationData) -> Option<Signature> {
+        self.sign(SigningMessage::Attestation(attestation_data.clone()))
+    }
+}
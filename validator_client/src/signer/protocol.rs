@@ -0,0 +1,120 @@
+use super::{Error, SigningMessage};
+use bls::{PublicKey, Signature};
+use serde_derive::{Deserialize, Serialize};
+use ssz::{ssz_encode, Decodable};
+use std::io::{BufRead, Read, Write};
+use types::Epoch;
+
+/// The maximum length of a request or response, including the newline.
+pub const MAX_LINE_LENGTH: u64 = 1 << 20;
+
+/// The object to be signed, as hex encoded SSZ.
+///
+/// The signer derives the signed message and its domain from the object, so a client is unable to
+/// have it sign anything other than a block, attestation or randao reveal.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum EncodedMessage {
+    BlockProposal(String),
+    RandaoReveal(u64),
+    Attestation(String),
+}
+
+/// A request sent from a `RemoteSigner` to a `SignerServer`.
+///
+/// Public keys and signatures are hex encoded.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum Request {
+    PublicKeys,
+    Sign {
+        public_key: String,
+        message: EncodedMessage,
+    },
+}
+
+/// A `Request` along with the secret token which authenticates the client to the signer.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct AuthenticatedRequest {
+    pub token: String,
+    pub request: Request,
+}
+
+/// A reply sent from a `SignerServer` to a `RemoteSigner`.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum Response {
+    PublicKeys(Vec<String>),
+    Signature(String),
+    Rejected(String),
+}
+
+/// Write `value` as a single line of JSON.
+pub fn write_line<W: Write, T: serde::Serialize>(writer: &mut W, value: &T) -> Result<(), Error> {
+    let mut line = serde_json::to_vec(value)?;
+    line.push(b'\n');
+    writer.write_all(&line)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Read a single line of JSON, of at most `MAX_LINE_LENGTH` bytes.
+pub fn read_line<R: BufRead, T: serde::de::DeserializeOwned>(reader: &mut R) -> Result<T, Error> {
+    let mut line = String::new();
+    let length = reader.by_ref().take(MAX_LINE_LENGTH).read_line(&mut line)?;
+    if length == 0 {
+        return Err(Error::ConnectionClosed);
+    }
+    if !line.ends_with('\n') && length as u64 == MAX_LINE_LENGTH {
+        return Err(Error::LineTooLong);
+    }
+    Ok(serde_json::from_str(&line)?)
+}
+
+pub fn encode_public_key(public_key: &PublicKey) -> String {
+    hex::encode(ssz_encode(public_key))
+}
+
+pub fn decode_public_key(string: &str) -> Result<PublicKey, Error> {
+    let bytes = hex::decode(string).map_err(|_| Error::InvalidHex)?;
+    let (public_key, _) = PublicKey::ssz_decode(&bytes, 0).map_err(|_| Error::InvalidPublicKey)?;
+    Ok(public_key)
+}
+
+pub fn encode_signature(signature: &Signature) -> String {
+    hex::encode(ssz_encode(signature))
+}
+
+pub fn decode_signature(string: &str) -> Result<Signature, Error> {
+    let bytes = hex::decode(string).map_err(|_| Error::InvalidHex)?;
+    let (signature, _) = Signature::ssz_decode(&bytes, 0).map_err(|_| Error::InvalidSignature)?;
+    Ok(signature)
+}
+
+pub fn encode_message(message: &SigningMessage) -> EncodedMessage {
+    match message {
+        SigningMessage::BlockProposal(block) => {
+            EncodedMessage::BlockProposal(hex::encode(ssz_encode(block)))
+        }
+        SigningMessage::RandaoReveal(epoch) => EncodedMessage::RandaoReveal(epoch.as_u64()),
+        SigningMessage::Attestation(attestation_data) => {
+            EncodedMessage::Attestation(hex::encode(ssz_encode(attestation_data)))
+        }
+    }
+}
+
+pub fn decode_message(message: &EncodedMessage) -> Result<SigningMessage, Error> {
+    match message {
+        EncodedMessage::BlockProposal(string) => {
+            Ok(SigningMessage::BlockProposal(decode_ssz(string)?))
+        }
+        EncodedMessage::RandaoReveal(epoch) => Ok(SigningMessage::RandaoReveal(Epoch::new(*epoch))),
+        EncodedMessage::Attestation(string) => Ok(SigningMessage::Attestation(decode_ssz(string)?)),
+    }
+}
+
+/// Decode some hex encoded SSZ, rejecting any trailing bytes.
+fn decode_ssz<T: Decodable>(string: &str) -> Result<T, Error> {
+    let bytes = hex::decode(string).map_err(|_| Error::InvalidHex)?;
+    match T::ssz_decode(&bytes, 0) {
+        Ok((value, index)) if index == bytes.len() => Ok(value),
+        _ => Err(Error::InvalidMessage),
+    }
+}
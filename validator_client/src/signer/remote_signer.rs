@@ -0,0 +1,93 @@
+use super::protocol::{
+    decode_public_key, decode_signature, encode_message, encode_public_key, read_line, write_line,
+    AuthenticatedRequest, Request, Response,
+};
+use super::{Error, SigningMessage};
+use bls::{PublicKey, Signature};
+use slog::{warn, Logger};
+use std::io::BufReader;
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+
+/// The maximum time to wait for a signer to connect or reply.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Signs messages by forwarding them to a `SignerServer`, which holds the secret key.
+///
+/// The signer is expected to perform its own slashing protection. Any failure to obtain a
+/// signature is logged and treated as a refusal to sign.
+pub struct RemoteSigner {
+    address: SocketAddr,
+    token: String,
+    public_key: PublicKey,
+    log: Logger,
+}
+
+impl RemoteSigner {
+    /// Create a signer for the validator with `public_key`, held by the signer at `address` which
+    /// accepts requests bearing `token`.
+    pub fn new(address: SocketAddr, token: String, public_key: PublicKey, log: Logger) -> Self {
+        Self {
+            address,
+            token,
+            public_key,
+            log,
+        }
+    }
+
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    /// Returns the public keys of all validators held by the signer at `address`.
+    pub fn public_keys(address: &SocketAddr, token: &str) -> Result<Vec<PublicKey>, Error> {
+        match request(address, token, Request::PublicKeys)? {
+            Response::PublicKeys(public_keys) => public_keys
+                .iter()
+                .map(|public_key| decode_public_key(public_key))
+                .collect(),
+            Response::Rejected(reason) => Err(Error::Rejected(reason)),
+            Response::Signature(_) => Err(Error::UnexpectedResponse),
+        }
+    }
+
+    /// Request a signature for `message`, returning `None` if one could not be obtained.
+    pub fn sign(&self, message: &SigningMessage) -> Option<Signature> {
+        let req = Request::Sign {
+            public_key: encode_public_key(&self.public_key),
+            message: encode_message(message),
+        };
+
+        let result = request(&self.address, &self.token, req).and_then(|response| match response {
+            Response::Signature(signature) => decode_signature(&signature).map(Some),
+            Response::Rejected(reason) => {
+                warn!(self.log, "Remote signer rejected message"; "reason" => reason);
+                Ok(None)
+            }
+            Response::PublicKeys(_) => Err(Error::UnexpectedResponse),
+        });
+
+        match result {
+            Ok(signature) => signature,
+            Err(e) => {
+                warn!(self.log, "Remote signer failure"; "address" => self.address.to_string(), "error" => format!("{:?}", e));
+                None
+            }
+        }
+    }
+}
+
+/// Send a single request, authenticated by `token`, to the signer at `address` and wait for the
+/// response.
+fn request(address: &SocketAddr, token: &str, request: Request) -> Result<Response, Error> {
+    let mut stream = TcpStream::connect_timeout(address, TIMEOUT)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+
+    let request = AuthenticatedRequest {
+        token: token.to_string(),
+        request,
+    };
+    write_line(&mut stream, &request)?;
+    read_line(&mut BufReader::new(stream))
+}
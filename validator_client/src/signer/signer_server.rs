@@ -0,0 +1,400 @@
+use super::protocol::{
+    decode_message, encode_public_key, encode_signature, read_line, write_line,
+    AuthenticatedRequest, EncodedMessage, Request, Response,
+};
+use super::{Error, SigningMessage};
+use bls::{Keypair, Signature};
+use db::stores::SlashingProtectionStore;
+use db::ClientDB;
+use slashing_protection::SlashingProtection;
+use slog::{debug, error, info, warn, Logger};
+use ssz::TreeHash;
+use std::collections::HashMap;
+use std::io::BufReader;
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use types::{ChainSpec, Hash256};
+
+/// The maximum number of connections served at once.
+pub const MAX_CONNECTIONS: usize = 16;
+
+/// The maximum time to wait for each request, or for a response to be sent.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A validator whose secret key is held by the `SignerServer`.
+struct Validator<T: ClientDB> {
+    keypair: Keypair,
+    slashing_protection: SlashingProtectionStore<T>,
+}
+
+/// Holds the secret keys of some validators and signs messages on behalf of `RemoteSigner`s.
+///
+/// Clients send the block, attestation or epoch to be signed, from which the server derives the
+/// signed message, its domain and the slot or epochs checked against a slashing protection
+/// database which is independent of any validator client. A compromised validator client is
+/// therefore unable to obtain slashable signatures.
+pub struct SignerServer<T: ClientDB> {
+    /// Validators, keyed by their hex encoded public key.
+    validators: HashMap<String, Validator<T>>,
+    /// The secret which clients must present with each request.
+    token: String,
+    spec: Arc<ChainSpec>,
+    /// Held whilst checking, recording and signing a message so concurrent requests may not both
+    /// pass the slashing checks.
+    signing_lock: Mutex<()>,
+    /// The number of connections being served.
+    connections: AtomicUsize,
+    log: Logger,
+}
+
+impl<T: ClientDB + 'static> SignerServer<T> {
+    /// Create a server which signs for each of `keypairs` on behalf of clients presenting `token`,
+    /// recording signed messages in `db`.
+    pub fn new(
+        keypairs: Vec<Keypair>,
+        token: String,
+        db: Arc<T>,
+        spec: Arc<ChainSpec>,
+        log: Logger,
+    ) -> Self {
+        let validators = keypairs
+            .into_iter()
+            .map(|keypair| {
+                let slashing_protection = SlashingProtectionStore::new(db.clone(), &keypair.pk);
+                let validator = Validator {
+                    keypair,
+                    slashing_protection,
+                };
+                (encode_public_key(&validator.keypair.pk), validator)
+            })
+            .collect();
+
+        Self {
+            validators,
+            token,
+            spec,
+            signing_lock: Mutex::new(()),
+            connections: AtomicUsize::new(0),
+            log,
+        }
+    }
+
+    /// Serve connections from `listener` until it fails, handling each connection in a new
+    /// thread.
+    ///
+    /// Connections are closed without being read whilst `MAX_CONNECTIONS` are being served.
+    pub fn serve(self: Arc<Self>, listener: TcpListener) {
+        if let Ok(address) = listener.local_addr() {
+            info!(self.log, "Signer listening"; "address" => address.to_string(), "validators" => self.validators.len());
+        }
+
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let connections = self.connections.fetch_add(1, Ordering::SeqCst);
+                    if connections >= MAX_CONNECTIONS {
+                        self.connections.fetch_sub(1, Ordering::SeqCst);
+                        warn!(self.log, "Rejected signer connection"; "connections" => connections);
+                        continue;
+                    }
+                    let server = self.clone();
+                    thread::spawn(move || {
+                        let handled = server.handle_connection(stream);
+                        server.connections.fetch_sub(1, Ordering::SeqCst);
+                        if let Err(e) = handled {
+                            warn!(server.log, "Signer connection failed"; "error" => format!("{:?}", e));
+                        }
+                    });
+                }
+                Err(e) => {
+                    error!(self.log, "Signer listener failed"; "error" => format!("{:?}", e));
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Reply to each request on `stream` until it is closed.
+    ///
+    /// The connection is closed after the first request which does not carry the server's token,
+    /// or once no request arrives within `TIMEOUT`.
+    fn handle_connection(&self, stream: TcpStream) -> Result<(), Error> {
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut writer = stream;
+
+        loop {
+            let request: AuthenticatedRequest = match read_line(&mut reader) {
+                Ok(request) => request,
+                Err(Error::ConnectionClosed) => return Ok(()),
+                Err(e) => return Err(e),
+            };
+            if !tokens_match(&request.token, &self.token) {
+                let address = writer
+                    .peer_addr()
+                    .map(|a| a.to_string())
+                    .unwrap_or_default();
+                warn!(self.log, "Rejected unauthenticated request"; "address" => address);
+                write_line(
+                    &mut writer,
+                    &Response::Rejected("Invalid token".to_string()),
+                )?;
+                return Ok(());
+            }
+            write_line(&mut writer, &self.process(request.request))?;
+        }
+    }
+
+    /// Produce the response to some authenticated `request`.
+    pub fn process(&self, request: Request) -> Response {
+        match request {
+            Request::PublicKeys => {
+                let mut public_keys: Vec<String> = self.validators.keys().cloned().collect();
+                public_keys.sort();
+                Response::PublicKeys(public_keys)
+            }
+            Request::Sign {
+                public_key,
+                message,
+            } => match self.sign(&public_key, &message) {
+                Ok(signature) => Response::Signature(encode_signature(&signature)),
+                Err(reason) => {
+                    warn!(self.log, "Refused to sign"; "public_key" => public_key, "reason" => &reason);
+                    Response::Rejected(reason)
+                }
+            },
+        }
+    }
+
+    /// Sign the encoded `message`, if doing so is not slashable.
+    ///
+    /// The message is recorded as signed before the signature is returned.
+    fn sign(&self, public_key: &str, message: &EncodedMessage) -> Result<Signature, String> {
+        let validator = self.validators.get(public_key).ok_or("Unknown validator")?;
+        let message = decode_message(message).map_err(|e| format!("Invalid message: {:?}", e))?;
+
+        let _guard = self
+            .signing_lock
+            .lock()
+            .map_err(|_| "Signing lock poisoned")?;
+        let store = &validator.slashing_protection;
+
+        match &message {
+            SigningMessage::BlockProposal(block) => {
+                let root = block.proposal_root(&self.spec);
+                if !store
+                    .is_safe_proposal(block.slot, &root)
+                    .map_err(db_error)?
+                {
+                    return Err(format!("Slashable block proposal at slot {}", block.slot));
+                }
+                store.record_proposal(block.slot, &root).map_err(db_error)?;
+            }
+            // The randao reveal for an epoch is unique, so signing it is never slashable.
+            SigningMessage::RandaoReveal(_) => (),
+            SigningMessage::Attestation(attestation_data) => {
+                let source_epoch = attestation_data.justified_epoch;
+                let target_epoch = attestation_data.slot.epoch(self.spec.epoch_length);
+                let root = Hash256::from(&attestation_data.hash_tree_root()[..]);
+                if !store
+                    .is_safe_attestation(source_epoch, target_epoch, &root)
+                    .map_err(db_error)?
+                {
+                    return Err(format!(
+                        "Slashable attestation from epoch {} to {}",
+                        source_epoch, target_epoch
+                    ));
+                }
+                store
+                    .record_attestation(source_epoch, target_epoch, &root)
+                    .map_err(db_error)?;
+            }
+        }
+
+        let (message, domain) = message.signable_message(&self.spec);
+        debug!(self.log, "Signed message"; "public_key" => public_key, "domain" => domain);
+        Ok(Signature::new(&message, domain, &validator.keypair.sk))
+    }
+}
+
+/// Compare tokens in constant time, so that response times do not reveal a valid prefix.
+fn tokens_match(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+fn db_error<E: std::fmt::Debug>(e: E) -> String {
+    format!("Slashing protection error: {:?}", e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::protocol::MAX_LINE_LENGTH;
+    use super::super::RemoteSigner;
+    use super::*;
+    use db::MemoryDB;
+    use slog::o;
+    use std::io::{Read, Write};
+    use std::net::SocketAddr;
+    use types::test_utils::{SeedableRng, TestRandom, XorShiftRng};
+    use types::{AttestationData, BeaconBlock, Epoch, Slot};
+
+    const TOKEN: &str = "secret";
+
+    /// Start a server signing for `keypairs` on some free local port.
+    fn start_server(keypairs: Vec<Keypair>) -> SocketAddr {
+        let log = Logger::root(slog::Discard, o!());
+        let spec = Arc::new(ChainSpec::foundation());
+        let db = Arc::new(MemoryDB::open());
+        let server = Arc::new(SignerServer::new(
+            keypairs,
+            TOKEN.to_string(),
+            db,
+            spec,
+            log,
+        ));
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || server.serve(listener));
+        address
+    }
+
+    fn remote_signer(address: SocketAddr, keypair: &Keypair) -> RemoteSigner {
+        let log = Logger::root(slog::Discard, o!());
+        RemoteSigner::new(address, TOKEN.to_string(), keypair.pk.clone(), log)
+    }
+
+    fn block(rng: &mut XorShiftRng, slot: u64) -> SigningMessage {
+        let mut block = BeaconBlock::random_for_test(rng);
+        block.slot = Slot::new(slot);
+        SigningMessage::BlockProposal(block)
+    }
+
+    fn attestation(rng: &mut XorShiftRng, source_epoch: u64, target_epoch: u64) -> SigningMessage {
+        let spec = ChainSpec::foundation();
+        let mut attestation_data = AttestationData::random_for_test(rng);
+        attestation_data.justified_epoch = Epoch::new(source_epoch);
+        attestation_data.slot = Epoch::new(target_epoch).start_slot(spec.epoch_length);
+        SigningMessage::Attestation(attestation_data)
+    }
+
+    #[test]
+    pub fn lists_public_keys() {
+        let keypair = Keypair::random();
+        let address = start_server(vec![keypair.clone()]);
+
+        assert_eq!(
+            RemoteSigner::public_keys(&address, TOKEN),
+            Ok(vec![keypair.pk])
+        );
+    }
+
+    #[test]
+    pub fn signs_the_message_derived_from_the_block() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let keypair = Keypair::random();
+        let signer = remote_signer(start_server(vec![keypair.clone()]), &keypair);
+        let message = block(&mut rng, 5);
+
+        let (bytes, domain) = message.signable_message(&ChainSpec::foundation());
+        assert_eq!(
+            signer.sign(&message),
+            Some(Signature::new(&bytes, domain, &keypair.sk))
+        );
+    }
+
+    #[test]
+    pub fn rejects_double_proposal() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let keypair = Keypair::random();
+        let signer = remote_signer(start_server(vec![keypair.clone()]), &keypair);
+        let proposal = block(&mut rng, 5);
+
+        assert!(signer.sign(&proposal).is_some());
+
+        // Signing the same proposal again is permitted, a different proposal is not.
+        assert!(signer.sign(&proposal).is_some());
+        assert_eq!(signer.sign(&block(&mut rng, 5)), None);
+        assert!(signer.sign(&block(&mut rng, 6)).is_some());
+    }
+
+    #[test]
+    pub fn rejects_surround_vote() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let keypair = Keypair::random();
+        let signer = remote_signer(start_server(vec![keypair.clone()]), &keypair);
+
+        assert!(signer.sign(&attestation(&mut rng, 2, 3)).is_some());
+        assert_eq!(signer.sign(&attestation(&mut rng, 1, 4)), None);
+        assert_eq!(signer.sign(&attestation(&mut rng, 0, 3)), None);
+        assert!(signer.sign(&attestation(&mut rng, 3, 4)).is_some());
+    }
+
+    #[test]
+    pub fn rejects_unknown_validator() {
+        let address = start_server(vec![Keypair::random()]);
+        let signer = remote_signer(address, &Keypair::random());
+
+        assert_eq!(
+            signer.sign(&SigningMessage::RandaoReveal(Epoch::new(3))),
+            None
+        );
+    }
+
+    #[test]
+    pub fn rejects_over_long_lines() {
+        let address = start_server(vec![Keypair::random()]);
+        let mut stream = TcpStream::connect(address).unwrap();
+
+        stream.set_read_timeout(Some(TIMEOUT / 2)).unwrap();
+
+        // The server closes the connection, without a response, as soon as the line is too long
+        // rather than waiting for the rest of it. The write may fail once it does so.
+        let _ = stream.write_all(&vec![b' '; MAX_LINE_LENGTH as usize + 1]);
+        let mut response = vec![];
+        if let Err(e) = stream.read_to_end(&mut response) {
+            assert_eq!(e.kind(), std::io::ErrorKind::ConnectionReset);
+        }
+        assert!(response.is_empty());
+    }
+
+    #[test]
+    pub fn limits_concurrent_connections() {
+        let address = start_server(vec![Keypair::random()]);
+        let idle: Vec<TcpStream> = (0..MAX_CONNECTIONS)
+            .map(|_| TcpStream::connect(address).unwrap())
+            .collect();
+        // Ensure the idle connections have been accepted.
+        thread::sleep(Duration::from_millis(100));
+        assert!(RemoteSigner::public_keys(&address, TOKEN).is_err());
+
+        // Connections are accepted once the idle connections are closed.
+        drop(idle);
+        thread::sleep(Duration::from_millis(100));
+        assert!(RemoteSigner::public_keys(&address, TOKEN).is_ok());
+    }
+
+    #[test]
+    pub fn rejects_invalid_token() {
+        let keypair = Keypair::random();
+        let address = start_server(vec![keypair.clone()]);
+        let log = Logger::root(slog::Discard, o!());
+        let signer = RemoteSigner::new(address, "guess".to_string(), keypair.pk.clone(), log);
+
+        assert_eq!(
+            RemoteSigner::public_keys(&address, "guess"),
+            Err(Error::Rejected("Invalid token".to_string()))
+        );
+        assert_eq!(
+            signer.sign(&SigningMessage::RandaoReveal(Epoch::new(3))),
+            None
+        );
+    }
+}